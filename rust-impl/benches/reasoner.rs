@@ -0,0 +1,173 @@
+//! `cargo bench`: parsing, saturation and taxonomy construction on the
+//! generated ontologies of [`el_reasoner::generate`], and on ChEBI and GO
+//! when `EL_BENCH_CHEBI` and `EL_BENCH_GO` name copies of `chebi.obo` and
+//! `go.obo`. Saturation runs once per [`WorklistOrder`], and the fastest
+//! order of each ontology is named after them.
+//!
+//! Each benchmark runs once to warm up, then until it has ten samples or
+//! three seconds; the minimum, median and maximum are printed with the
+//! change in median from the previous run, whose medians are kept in
+//! `target/el-bench.tsv`. Medians more than 10% slower are flagged.
+//! `cargo bench -- <filter>` runs only the benchmarks whose name contains
+//! the filter.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use el_reasoner::generate::{generate, Shape};
+use el_reasoner::obo::{build_axiom_store, parse_obo, ParseResult};
+use el_reasoner::{saturate, saturate_with, SaturationOptions, Taxonomy, WorklistOrder};
+
+const SAMPLES: usize = 10;
+const BUDGET: Duration = Duration::from_secs(3);
+const REGRESSION: f64 = 0.10;
+
+struct Harness {
+    filter: Option<String>,
+    previous: HashMap<String, f64>,
+    medians: Vec<(String, f64)>,
+}
+
+impl Harness {
+    fn new() -> Self {
+        // Cargo passes `--bench`; anything else is a name filter.
+        let filter = env::args().skip(1).find(|arg| !arg.starts_with('-'));
+        let previous = fs::read_to_string(baseline_path())
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let (name, secs) = line.split_once('\t')?;
+                Some((name.to_string(), secs.parse().ok()?))
+            })
+            .collect();
+        Self { filter, previous, medians: Vec::new() }
+    }
+
+    fn wants(&self, name: &str) -> bool {
+        self.filter.as_ref().is_none_or(|filter| name.contains(filter.as_str()))
+    }
+
+    /// Returns the median, unless filtered out.
+    fn bench<T>(&mut self, name: &str, mut f: impl FnMut() -> T) -> Option<f64> {
+        if !self.wants(name) {
+            return None;
+        }
+        black_box(f());
+        let started = Instant::now();
+        let mut samples = Vec::with_capacity(SAMPLES);
+        while samples.len() < SAMPLES && (samples.len() < 3 || started.elapsed() < BUDGET) {
+            let start = Instant::now();
+            black_box(f());
+            samples.push(start.elapsed().as_secs_f64());
+        }
+        samples.sort_by(f64::total_cmp);
+        let median = samples[samples.len() / 2];
+        let change = match self.previous.get(name) {
+            Some(&before) => {
+                let change = median / before - 1.0;
+                let flag = if change > REGRESSION { "  regressed" } else { "" };
+                format!("  change: {:+.1}%{}", change * 100.0, flag)
+            }
+            None => String::new(),
+        };
+        println!(
+            "{:<36} time: [{} {} {}]{}",
+            name,
+            shown(samples[0]),
+            shown(median),
+            shown(samples[samples.len() - 1]),
+            change
+        );
+        self.medians.push((name.to_string(), median));
+        Some(median)
+    }
+
+    /// Saves this run's medians over the previous ones.
+    fn finish(mut self) {
+        for (name, median) in self.medians {
+            self.previous.insert(name, median);
+        }
+        let mut lines: Vec<String> =
+            self.previous.iter().map(|(name, secs)| format!("{}\t{}\n", name, secs)).collect();
+        lines.sort();
+        let _ = fs::write(baseline_path(), lines.concat());
+    }
+}
+
+fn baseline_path() -> PathBuf {
+    let target = env::var_os("CARGO_TARGET_DIR").unwrap_or_else(|| "target".into());
+    PathBuf::from(target).join("el-bench.tsv")
+}
+
+fn shown(secs: f64) -> String {
+    match secs {
+        s if s < 1e-3 => format!("{:.1} µs", s * 1e6),
+        s if s < 1.0 => format!("{:.2} ms", s * 1e3),
+        s => format!("{:.3} s", s),
+    }
+}
+
+fn worklist_bench(name: &str, order: WorklistOrder) -> String {
+    format!("{}/saturate-{}", name, order.name())
+}
+
+/// Parsing, saturation and taxonomy benchmarks over one document.
+fn bench_document(harness: &mut Harness, name: &str, document: &[u8]) {
+    let parse = || parse_obo(document).expect("generated OBO parses").0;
+    harness.bench(&format!("{}/parse", name), parse);
+
+    let wanted = ["saturate", "taxonomy"].iter().any(|phase| harness.wants(&format!("{}/{}", name, phase)));
+    let wanted = wanted || WorklistOrder::ALL.iter().any(|order| harness.wants(&worklist_bench(name, *order)));
+    if !wanted {
+        return;
+    }
+    let parsed: ParseResult = parse();
+    let store = build_axiom_store(&parsed);
+    let n = parsed.concepts.len();
+    let saturated = || saturate(&store, store.num_concepts(), parsed.roles.len());
+    harness.bench(&format!("{}/saturate", name), saturated);
+
+    let mut fastest: Option<(WorklistOrder, f64)> = None;
+    for order in WorklistOrder::ALL {
+        let options = SaturationOptions { worklist: order, ..SaturationOptions::default() };
+        let run = || saturate_with(&store, store.num_concepts(), parsed.roles.len(), &options);
+        if let Some(median) = harness.bench(&worklist_bench(name, order), run) {
+            if fastest.is_none_or(|(_, best)| median < best) {
+                fastest = Some((order, median));
+            }
+        }
+    }
+    if let Some((order, median)) = fastest {
+        println!("{:<36} fastest worklist: {} ({})", name, order.name(), shown(median));
+    }
+
+    let mut contexts = saturated();
+    contexts.truncate(n);
+    harness.bench(&format!("{}/taxonomy", name), || Taxonomy::new(&contexts, n));
+}
+
+fn main() {
+    let mut harness = Harness::new();
+    let shapes = [
+        // Subsumer sets grow with depth, so the chain stays short.
+        (Shape::Chain, 2_000),
+        (Shape::Conjunctions { width: 4 }, 20_000),
+        (Shape::Existentials { degree: 3, roles: 4 }, 20_000),
+    ];
+    for (shape, terms) in shapes {
+        let document = generate(shape, terms, 1);
+        bench_document(&mut harness, &format!("{}-{}", shape.name(), terms), document.as_bytes());
+    }
+    for (name, var) in [("chebi", "EL_BENCH_CHEBI"), ("go", "EL_BENCH_GO")] {
+        let Some(path) = env::var_os(var) else { continue };
+        match fs::read(&path) {
+            Ok(document) => bench_document(&mut harness, name, &document),
+            Err(e) => eprintln!("{}: {}: {}", var, PathBuf::from(path).display(), e),
+        }
+    }
+    harness.finish();
+}
@@ -0,0 +1,69 @@
+/*
+ * C API of el-reasoner, exported by the el-reasoner-ffi library in this
+ * directory (`cargo build --release` gives libel_reasoner.so/.a).
+ * Kept in step with src/ffi.rs, where each function is documented.
+ *
+ *     ElOntology *o = el_ontology_new();
+ *     uint32_t a = el_concept(o, "A"), b = el_concept(o, "B");
+ *     el_add_subclass_of(o, a, b);
+ *     el_classify(o);
+ *     size_t n = el_superclasses(o, a, 1, NULL, 0);
+ *     ...
+ *     el_ontology_free(o);
+ *
+ * Concepts 0 and 1 are owl:Thing and owl:Nothing. Queries classify on
+ * first use, again after any axiom is added. Strings returned are freed
+ * with el_string_free; a failing call returns NULL and, when `error` isn't
+ * NULL, stores a message there.
+ */
+#ifndef EL_REASONER_H
+#define EL_REASONER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct ElOntology ElOntology;
+
+#define EL_THING 0u
+#define EL_NOTHING 1u
+/* What el_concept and el_role return for a NULL, empty or non-UTF-8 name. */
+#define EL_NONE UINT32_MAX
+
+ElOntology *el_ontology_new(void);
+ElOntology *el_ontology_read(const char *path, char **error);
+void el_ontology_free(ElOntology *o);
+void el_string_free(char *s);
+
+uint32_t el_concept(ElOntology *o, const char *name);
+uint32_t el_role(ElOntology *o, const char *name);
+int64_t el_find_concept(const ElOntology *o, const char *name);
+char *el_concept_name(const ElOntology *o, uint32_t c);
+size_t el_num_concepts(const ElOntology *o);
+
+void el_add_subclass_of(ElOntology *o, uint32_t sub, uint32_t sup);
+void el_add_existential(ElOntology *o, uint32_t sub, uint32_t role, uint32_t filler);
+void el_add_equivalent(ElOntology *o, uint32_t c, const uint32_t *conjuncts, size_t n,
+                       const uint32_t *roles, const uint32_t *fillers, size_t m);
+void el_add_disjoint(ElOntology *o, uint32_t a, uint32_t b);
+void el_add_sub_role(ElOntology *o, uint32_t sub, uint32_t sup);
+void el_add_role_chain(ElOntology *o, uint32_t first, uint32_t second, uint32_t sup);
+void el_add_transitive(ElOntology *o, uint32_t role);
+void el_add_reflexive(ElOntology *o, uint32_t role);
+void el_add_domain(ElOntology *o, uint32_t role, uint32_t domain);
+void el_add_range(ElOntology *o, uint32_t role, uint32_t range);
+
+int el_classify(ElOntology *o);
+size_t el_superclasses(ElOntology *o, uint32_t c, int direct, uint32_t *out, size_t cap);
+size_t el_subclasses(ElOntology *o, uint32_t c, int direct, uint32_t *out, size_t cap);
+size_t el_equivalents(ElOntology *o, uint32_t c, uint32_t *out, size_t cap);
+int el_is_subsumed_by(const ElOntology *o, uint32_t sub, uint32_t sup);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
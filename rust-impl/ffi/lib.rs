@@ -0,0 +1 @@
+pub use el_reasoner::ffi::*;
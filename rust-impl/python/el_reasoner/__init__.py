@@ -0,0 +1,144 @@
+"""Python bindings for el-reasoner, over its C API through ctypes.
+
+    from el_reasoner import Ontology
+
+    go = Ontology("go.obo")
+    parents = go.classify()          # {term: [direct parents]}
+    go.ancestors("GO:0005634")
+    go.is_subsumed_by("GO:0005634", "GO:0043226")
+
+The functions are the C API of ffi/el_reasoner.h, in the el-reasoner-ffi
+shared library (built with `cargo build --release` in ../ffi), looked up in
+$EL_REASONER_LIB, next to this file, then in ../ffi/target/release.
+"""
+
+import ctypes
+import os
+import sys
+
+__all__ = ["Ontology", "ReasonerError"]
+
+_HERE = os.path.dirname(os.path.abspath(__file__))
+
+
+class ReasonerError(Exception):
+    """A failed read or an unknown term."""
+
+
+def _library_path():
+    name = {"darwin": "libel_reasoner.dylib", "win32": "el_reasoner.dll"}.get(
+        sys.platform, "libel_reasoner.so"
+    )
+    candidates = [
+        os.environ.get("EL_REASONER_LIB"),
+        os.path.join(_HERE, name),
+        os.path.join(_HERE, "..", "..", "ffi", "target", "release", name),
+    ]
+    for path in candidates:
+        if path and os.path.exists(path):
+            return path
+    raise ImportError("el-reasoner shared library not found; set EL_REASONER_LIB")
+
+
+def _load():
+    lib = ctypes.CDLL(_library_path())
+    handle, text, error = ctypes.c_void_p, ctypes.c_char_p, ctypes.POINTER(ctypes.c_void_p)
+    ids = ctypes.POINTER(ctypes.c_uint32)
+    # Strings the library allocates come back as void pointers so they can
+    # be freed with el_string_free after copying.
+    signatures = {
+        "el_ontology_read": ([text, error], handle),
+        "el_ontology_free": ([handle], None),
+        "el_string_free": ([ctypes.c_void_p], None),
+        "el_find_concept": ([handle, text], ctypes.c_int64),
+        "el_concept_name": ([handle, ctypes.c_uint32], ctypes.c_void_p),
+        "el_num_concepts": ([handle], ctypes.c_size_t),
+        "el_superclasses": ([handle, ctypes.c_uint32, ctypes.c_int, ids, ctypes.c_size_t], ctypes.c_size_t),
+        "el_subclasses": ([handle, ctypes.c_uint32, ctypes.c_int, ids, ctypes.c_size_t], ctypes.c_size_t),
+        "el_is_subsumed_by": ([handle, ctypes.c_uint32, ctypes.c_uint32], ctypes.c_int),
+    }
+    for name, (args, result) in signatures.items():
+        function = getattr(lib, name)
+        function.argtypes = args
+        function.restype = result
+    return lib
+
+
+_lib = _load()
+
+
+def _take(pointer):
+    """The library string at `pointer`, freed."""
+    if not pointer:
+        return None
+    try:
+        return ctypes.string_at(pointer).decode("utf-8")
+    finally:
+        _lib.el_string_free(pointer)
+
+
+def _call(function, *args):
+    error = ctypes.c_void_p()
+    result = function(*args, ctypes.byref(error))
+    if error.value:
+        raise ReasonerError(_take(error.value))
+    return result
+
+
+def _ids(function, handle, c, direct):
+    """The concept IDs `function` (el_superclasses or el_subclasses) gives."""
+    n = function(handle, c, direct, None, 0)
+    out = (ctypes.c_uint32 * n)()
+    function(handle, c, direct, out, n)
+    return list(out)
+
+
+class Ontology:
+    """An ontology read from OBO, OWL functional syntax, RDF/XML or Turtle,
+    with its imports."""
+
+    def __init__(self, path):
+        self._handle = _call(_lib.el_ontology_read, os.fsencode(path))
+
+    def __del__(self):
+        handle, self._handle = getattr(self, "_handle", None), None
+        if handle:
+            _lib.el_ontology_free(handle)
+
+    def __len__(self):
+        """Named concepts, owl:Thing and owl:Nothing included."""
+        return _lib.el_num_concepts(self._handle)
+
+    def _concept(self, name):
+        c = _lib.el_find_concept(self._handle, name.encode())
+        if c < 0:
+            raise ReasonerError("unknown term '%s'" % name)
+        return c
+
+    def _name(self, c):
+        return _take(_lib.el_concept_name(self._handle, c))
+
+    def _names(self, function, name, direct):
+        ids = _ids(function, self._handle, self._concept(name), direct)
+        return [self._name(c) for c in ids]
+
+    def classify(self):
+        """Direct parents of every named concept, by name."""
+        parents = {}
+        for c in range(2, len(self)):
+            ids = _ids(_lib.el_superclasses, self._handle, c, 1)
+            if ids:
+                parents[self._name(c)] = [self._name(p) for p in ids]
+        return parents
+
+    def ancestors(self, name):
+        """Every concept above `name`, owl:Thing included."""
+        return self._names(_lib.el_superclasses, name, 0)
+
+    def descendants(self, name):
+        """Every concept below `name`."""
+        return self._names(_lib.el_subclasses, name, 0)
+
+    def is_subsumed_by(self, sub, sup):
+        """Whether `sub` is a subclass of `sup`, without classifying."""
+        return bool(_lib.el_is_subsumed_by(self._handle, self._concept(sub), self._concept(sup)))
@@ -0,0 +1,129 @@
+//! Individuals and their assertions. Each individual `a` is reasoned about
+//! as a concept `{a}` of its own, numbered after every concept of the store:
+//! `a : C` becomes `{a} ⊑ C` and `r(a, b)` becomes `{a} ⊑ ∃r.{b}`, with
+//! `{b}` under the ranges of `r`. No TBox axiom mentions these concepts, so
+//! they are saturated on top of an existing classification without changing
+//! it, and with no nominals in the TBox their super sets are exactly the
+//! entailed types. With nominals, `{a}` is put under the TBox nominal of
+//! `a` and every individual counts as a nominal, so nominal merging carries
+//! the assertions over to the TBox contexts holding it.
+
+use crate::kernel::{resaturate, AxiomStore, ConceptId, Context, RoleId, BOTTOM, TOP};
+use crate::normalize::role_ranges;
+use crate::obo::ParseResult;
+
+/// The types of every individual of a [`ParseResult`], and the reverse.
+pub struct Realization {
+    /// Named types of each individual other than TOP, ascending.
+    types: Vec<Vec<ConceptId>>,
+    /// The most specific of `types`.
+    direct_types: Vec<Vec<ConceptId>>,
+    /// Individuals of each named concept, ascending.
+    instances: Vec<Vec<usize>>,
+    /// Individuals whose assertions contradict the TBox.
+    inconsistent: Vec<usize>,
+}
+
+impl Realization {
+    pub fn types_of(&self, individual: usize) -> &[ConceptId] {
+        &self.types[individual]
+    }
+
+    pub fn direct_types_of(&self, individual: usize) -> &[ConceptId] {
+        &self.direct_types[individual]
+    }
+
+    /// Every individual of `c`, directly asserted or inferred.
+    pub fn instances_of(&self, c: ConceptId) -> &[usize] {
+        self.instances.get(c as usize).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn inconsistent(&self) -> &[usize] {
+        &self.inconsistent
+    }
+}
+
+/// Adds the assertions of `parsed` to `store` and saturates the individuals
+/// on top of `contexts`, the saturation of `store` so far. Individual `i`
+/// gets concept `first + i`, `first` being the store's concept count
+/// beforehand; its context is appended to `contexts`.
+pub fn realize(
+    parsed: &ParseResult,
+    store: &mut AxiomStore,
+    contexts: &mut Vec<Context>,
+) -> Realization {
+    let num_named = parsed.concepts.len();
+    let num_roles = parsed.roles.len();
+    let first = store.num_concepts();
+    let individual = |i: usize| (first + i) as ConceptId;
+
+    store.grow(first + parsed.individuals.len());
+    if !store.nominals.is_empty() {
+        for &(c, a) in &parsed.nominals {
+            store.add_subsumption(individual(a), c as ConceptId);
+        }
+        for i in 0..parsed.individuals.len() {
+            store.add_nominal(individual(i));
+        }
+    }
+    for &(a, c) in &parsed.class_assertions {
+        store.add_subsumption(individual(a), c as ConceptId);
+    }
+    let ranges: Vec<(RoleId, ConceptId)> =
+        parsed.role_ranges.iter().map(|&(r, c)| (r as RoleId, c as ConceptId)).collect();
+    let ranges = role_ranges(store, &ranges);
+    for &(a, r, b) in &parsed.role_assertions {
+        store.add_exist_right(individual(a), r as ConceptId, individual(b));
+        // `{b}` is a single individual, so it can hold the range itself.
+        for &c in ranges.get(r).map_or(&[][..], Vec::as_slice) {
+            store.add_subsumption(individual(b), c);
+        }
+    }
+
+    let mut saturated = std::mem::take(contexts);
+    saturated.extend((0..parsed.individuals.len()).map(|i| Context::new(individual(i), num_roles)));
+    let dirty = (0..parsed.individuals.len()).map(individual);
+    *contexts = resaturate(store, saturated, num_roles, dirty);
+
+    let mut realization = Realization {
+        types: Vec::with_capacity(parsed.individuals.len()),
+        direct_types: Vec::with_capacity(parsed.individuals.len()),
+        instances: vec![Vec::new(); num_named],
+        inconsistent: Vec::new(),
+    };
+    for i in 0..parsed.individuals.len() {
+        let supers = &contexts[first + i].super_set;
+        if supers.contains(&BOTTOM) {
+            realization.inconsistent.push(i);
+        }
+        let mut types: Vec<ConceptId> = supers
+            .iter()
+            .copied()
+            .filter(|&c| c != TOP && c != BOTTOM && (c as usize) < num_named)
+            .collect();
+        types.sort_unstable();
+
+        // A type is direct unless it strictly subsumes another one.
+        let mut subsuming: Vec<ConceptId> = Vec::new();
+        for &t in &types {
+            let strictly_above = contexts[t as usize]
+                .super_set
+                .iter()
+                .filter(|&&s| s != t && !contexts[s as usize].super_set.contains(&t));
+            subsuming.extend(strictly_above);
+        }
+        subsuming.sort_unstable();
+        let direct = types
+            .iter()
+            .copied()
+            .filter(|t| subsuming.binary_search(t).is_err())
+            .collect();
+
+        for &t in &types {
+            realization.instances[t as usize].push(i);
+        }
+        realization.types.push(types);
+        realization.direct_types.push(direct);
+    }
+    realization
+}
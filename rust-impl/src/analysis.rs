@@ -0,0 +1,180 @@
+//! Structural metrics of an inferred taxonomy (`el-reasoner analyze`):
+//! depth distribution, branching, multiple inheritance, equivalences and
+//! existential links. Compared across releases, a jump in any of them
+//! usually points at a modeling change worth a look.
+
+use std::fmt;
+
+use crate::json::Value;
+use crate::kernel::{ConceptId, Context, BOTTOM, TOP};
+use crate::taxonomy::Taxonomy;
+
+/// Concepts listed under the largest existential fan-outs.
+const FAN_OUTS_SHOWN: usize = 10;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TaxonomyAnalysis {
+    /// Named concepts, unsatisfiable ones included.
+    pub concepts: usize,
+    pub unsatisfiable: usize,
+    /// Satisfiable concepts directly under `owl:Thing`.
+    pub roots: usize,
+    /// Satisfiable concepts without children.
+    pub leaves: usize,
+    /// Satisfiable concepts by their longest path from `owl:Thing`: entry
+    /// `d` counts those at depth `d`, roots being at depth 1.
+    pub depths: Vec<usize>,
+    /// Concepts with children and the children they have between them.
+    pub parents: usize,
+    pub children: usize,
+    /// The concept with the most children, and how many.
+    pub widest: Option<(String, usize)>,
+    /// Concepts with two or more direct parents.
+    pub multi_parent: usize,
+    pub max_parents: usize,
+    pub equivalence_classes: usize,
+    /// Concepts in an equivalence class.
+    pub equivalent_concepts: usize,
+    /// Concepts with the most inferred existential links, most first.
+    pub fan_outs: Vec<(String, usize)>,
+    /// Inferred existential links from satisfiable concepts, per role.
+    pub role_links: Vec<(String, usize)>,
+}
+
+impl TaxonomyAnalysis {
+    pub fn max_depth(&self) -> usize {
+        self.depths.len().saturating_sub(1)
+    }
+
+    /// Mean longest depth of the satisfiable concepts.
+    pub fn mean_depth(&self) -> f64 {
+        let (total, weighted) = self.depths.iter().enumerate().fold((0, 0), |(n, sum), (d, &k)| (n + k, sum + d * k));
+        if total == 0 {
+            0.0
+        } else {
+            weighted as f64 / total as f64
+        }
+    }
+
+    /// Mean number of children of the concepts that have any.
+    pub fn mean_branching(&self) -> f64 {
+        if self.parents == 0 {
+            0.0
+        } else {
+            self.children as f64 / self.parents as f64
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let counts = |pairs: &[(String, usize)]| {
+            Value::Object(pairs.iter().map(|(name, n)| (name.clone(), Value::from(*n))).collect())
+        };
+        let widest = match &self.widest {
+            Some((name, n)) => Value::Object(vec![
+                ("concept".to_string(), Value::from(name.as_str())),
+                ("children".to_string(), Value::from(*n)),
+            ]),
+            None => Value::Null,
+        };
+        Value::Object(vec![
+            ("concepts".to_string(), Value::from(self.concepts)),
+            ("unsatisfiable".to_string(), Value::from(self.unsatisfiable)),
+            ("roots".to_string(), Value::from(self.roots)),
+            ("leaves".to_string(), Value::from(self.leaves)),
+            ("max_depth".to_string(), Value::from(self.max_depth())),
+            ("mean_depth".to_string(), Value::from(self.mean_depth())),
+            ("depths".to_string(), Value::Array(self.depths.iter().map(|&n| Value::from(n)).collect())),
+            ("mean_branching".to_string(), Value::from(self.mean_branching())),
+            ("widest".to_string(), widest),
+            ("multi_parent".to_string(), Value::from(self.multi_parent)),
+            ("max_parents".to_string(), Value::from(self.max_parents)),
+            ("equivalence_classes".to_string(), Value::from(self.equivalence_classes)),
+            ("equivalent_concepts".to_string(), Value::from(self.equivalent_concepts)),
+            ("fan_outs".to_string(), counts(&self.fan_outs)),
+            ("role_links".to_string(), counts(&self.role_links)),
+        ])
+    }
+}
+
+/// Measures `taxonomy`, classified from `contexts`, over the concepts with
+/// a name in `names`; `roles` names the roles of the links.
+pub fn analyze(contexts: &[Context], taxonomy: &Taxonomy, names: &[String], roles: &[String]) -> TaxonomyAnalysis {
+    let mut analysis = TaxonomyAnalysis::default();
+    let n = names.len().min(contexts.len()).min(taxonomy.len());
+    let depths = taxonomy.depths();
+    let mut role_links = vec![0; roles.len()];
+    let mut fan_outs: Vec<(ConceptId, usize)> = Vec::new();
+    for c in (BOTTOM as usize + 1..n).filter(|&c| !names[c].is_empty()) {
+        analysis.concepts += 1;
+        let context = &contexts[c];
+        if context.super_set.contains(&BOTTOM) {
+            analysis.unsatisfiable += 1;
+            continue;
+        }
+        let c = c as ConceptId;
+        let parents = taxonomy.parents(c);
+        let children = taxonomy.children(c).len();
+        analysis.roots += (parents == [TOP]) as usize;
+        analysis.leaves += (children == 0) as usize;
+        let depth = depths[c as usize].max as usize;
+        if analysis.depths.len() <= depth {
+            analysis.depths.resize(depth + 1, 0);
+        }
+        analysis.depths[depth] += 1;
+        if children > 0 {
+            analysis.parents += 1;
+            analysis.children += children;
+            if analysis.widest.as_ref().is_none_or(|&(_, most)| children > most) {
+                analysis.widest = Some((names[c as usize].clone(), children));
+            }
+        }
+        analysis.multi_parent += (parents.len() > 1) as usize;
+        analysis.max_parents = analysis.max_parents.max(parents.len());
+
+        let mut links = 0;
+        for (r, targets) in context.link_map.iter().enumerate() {
+            role_links[r] += targets.len();
+            links += targets.len();
+        }
+        if links > 0 {
+            fan_outs.push((c, links));
+        }
+    }
+    let equivalences = taxonomy.equivalences();
+    analysis.equivalence_classes = equivalences.len();
+    analysis.equivalent_concepts = equivalences.iter().map(Vec::len).sum();
+    fan_outs.sort_by_key(|&(c, links)| (std::cmp::Reverse(links), c));
+    fan_outs.truncate(FAN_OUTS_SHOWN);
+    analysis.fan_outs = fan_outs.into_iter().map(|(c, links)| (names[c as usize].clone(), links)).collect();
+    analysis.role_links = roles.iter().cloned().zip(role_links).filter(|&(_, links)| links > 0).collect();
+    analysis
+}
+
+impl fmt::Display for TaxonomyAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== Taxonomy Analysis ===")?;
+        writeln!(f, "Concepts: {} ({} unsatisfiable)", self.concepts, self.unsatisfiable)?;
+        writeln!(f, "Roots: {}", self.roots)?;
+        writeln!(f, "Leaves: {}", self.leaves)?;
+        writeln!(f, "Max depth: {} (mean {:.2})", self.max_depth(), self.mean_depth())?;
+        writeln!(f, "Branching: {:.2} children on average over {} concepts", self.mean_branching(), self.parents)?;
+        if let Some((name, children)) = &self.widest {
+            writeln!(f, "Widest: {} ({} children)", name, children)?;
+        }
+        writeln!(f, "Multiple parents: {} concepts (at most {})", self.multi_parent, self.max_parents)?;
+        writeln!(f, "Equivalence classes: {} ({} concepts)", self.equivalence_classes, self.equivalent_concepts)?;
+        writeln!(f, "\nConcepts by depth:")?;
+        for (depth, count) in self.depths.iter().enumerate().skip(1) {
+            writeln!(f, "  {}: {}", depth, count)?;
+        }
+        writeln!(f, "\nLargest existential fan-outs:")?;
+        for (name, links) in &self.fan_outs {
+            writeln!(f, "  {}: {}", name, links)?;
+        }
+        write!(f, "\nLinks per role:")?;
+        for (role, links) in &self.role_links {
+            write!(f, "\n  {}: {}", role, links)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! Request and response bodies of the reasoning service, shared by the
+//! server and [`crate::client`]. The contract is documented in
+//! `openapi.yaml`, available here as [`OPENAPI`].
+
+use crate::json::Value;
+
+/// The service's OpenAPI 3 document.
+pub const OPENAPI: &str = include_str!("../openapi.yaml");
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConceptRef {
+    pub id: String,
+    pub label: Option<String>,
+}
+
+/// Body of `/subclasses`, `/superclasses` and `/equivalents`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConceptList {
+    pub concept: ConceptRef,
+    pub concepts: Vec<ConceptRef>,
+}
+
+/// Body of `/satisfiable`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Satisfiability {
+    pub concept: ConceptRef,
+    pub satisfiable: bool,
+}
+
+/// Body of `/classify`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassifySummary {
+    pub concepts: usize,
+    pub inferred_subsumptions: usize,
+    pub unsatisfiable: Vec<ConceptRef>,
+}
+
+/// Body of every non-2xx response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiError {
+    pub error: String,
+}
+
+fn count(value: &Value) -> Option<usize> {
+    value.as_f64().filter(|n| *n >= 0.0 && n.fract() == 0.0).map(|n| n as usize)
+}
+
+fn concepts_to_json(concepts: &[ConceptRef]) -> Value {
+    Value::Array(concepts.iter().map(ConceptRef::to_json).collect())
+}
+
+fn concepts_from_json(value: &Value) -> Option<Vec<ConceptRef>> {
+    value.as_array()?.iter().map(ConceptRef::from_json).collect()
+}
+
+impl ConceptRef {
+    pub fn to_json(&self) -> Value {
+        let mut members = vec![("id".to_string(), Value::from(self.id.as_str()))];
+        if let Some(label) = &self.label {
+            members.push(("label".to_string(), Value::from(label.as_str())));
+        }
+        Value::Object(members)
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            id: value.get("id")?.as_str()?.to_string(),
+            label: value.get("label").and_then(Value::as_str).map(str::to_string),
+        })
+    }
+}
+
+impl ConceptList {
+    pub fn to_json(&self) -> Value {
+        Value::Object(vec![
+            ("concept".to_string(), self.concept.to_json()),
+            ("concepts".to_string(), concepts_to_json(&self.concepts)),
+        ])
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            concept: ConceptRef::from_json(value.get("concept")?)?,
+            concepts: concepts_from_json(value.get("concepts")?)?,
+        })
+    }
+}
+
+impl Satisfiability {
+    pub fn to_json(&self) -> Value {
+        Value::Object(vec![
+            ("concept".to_string(), self.concept.to_json()),
+            ("satisfiable".to_string(), Value::from(self.satisfiable)),
+        ])
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            concept: ConceptRef::from_json(value.get("concept")?)?,
+            satisfiable: value.get("satisfiable")?.as_bool()?,
+        })
+    }
+}
+
+impl ClassifySummary {
+    pub fn to_json(&self) -> Value {
+        Value::Object(vec![
+            ("concepts".to_string(), Value::from(self.concepts)),
+            ("inferred_subsumptions".to_string(), Value::from(self.inferred_subsumptions)),
+            ("unsatisfiable".to_string(), concepts_to_json(&self.unsatisfiable)),
+        ])
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            concepts: count(value.get("concepts")?)?,
+            inferred_subsumptions: count(value.get("inferred_subsumptions")?)?,
+            unsatisfiable: concepts_from_json(value.get("unsatisfiable")?)?,
+        })
+    }
+}
+
+impl ApiError {
+    pub fn to_json(&self) -> Value {
+        Value::Object(vec![("error".to_string(), Value::from(self.error.as_str()))])
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self { error: value.get("error")?.as_str()?.to_string() })
+    }
+}
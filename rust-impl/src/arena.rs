@@ -0,0 +1,139 @@
+//! A bump allocator for the `el-reasoner` binary (`--features arena`).
+//!
+//! Saturation makes millions of small allocations that live until the end
+//! of the run: a super set and two per-role list vectors for every context,
+//! and the lists themselves as links are derived. Here they are carved
+//! front to back out of large chunks instead of going through the system
+//! allocator one by one. Memory is only handed back when it is the most
+//! recent allocation, which covers a vector growing at the end of a chunk;
+//! anything else freed stays abandoned until exit, and [`ArenaStats`] says
+//! how much. Large allocations go to the system allocator as usual.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ptr;
+use std::sync::{Mutex, PoisonError};
+
+/// Size of the chunks small allocations are carved from.
+const CHUNK: usize = 64 << 20;
+/// Allocations from this size up bypass the arena.
+const LARGE: usize = CHUNK / 16;
+const CHUNK_ALIGN: usize = 4096;
+
+/// What the arena has done so far, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArenaStats {
+    /// Chunks taken from the system allocator.
+    pub reserved: usize,
+    /// Handed out and not yet freed.
+    pub live: usize,
+    /// Freed, but not reusable before exit.
+    pub abandoned: usize,
+}
+
+struct State {
+    chunk: *mut u8,
+    /// Offset of the free space in `chunk`.
+    used: usize,
+    stats: ArenaStats,
+}
+
+// The chunk pointer is only dereferenced under the lock.
+unsafe impl Send for State {}
+
+pub struct Arena {
+    state: Mutex<State>,
+}
+
+impl Arena {
+    pub const fn new() -> Self {
+        let stats = ArenaStats { reserved: 0, live: 0, abandoned: 0 };
+        Self { state: Mutex::new(State { chunk: ptr::null_mut(), used: 0, stats }) }
+    }
+
+    pub fn stats(&self) -> ArenaStats {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).stats
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn large(layout: Layout) -> bool {
+    layout.size() >= LARGE || layout.align() > CHUNK_ALIGN
+}
+
+impl State {
+    /// Whether `ptr` of `size` bytes ends where the free space starts.
+    fn is_last(&self, ptr: *mut u8, size: usize) -> bool {
+        !self.chunk.is_null() && ptr as usize + size == self.chunk as usize + self.used
+    }
+}
+
+unsafe impl GlobalAlloc for Arena {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if large(layout) {
+            return System.alloc(layout);
+        }
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let base = state.chunk as usize;
+        let mut start = (base + state.used).next_multiple_of(layout.align()) - base;
+        if state.chunk.is_null() || start + layout.size() > CHUNK {
+            let chunk = System.alloc(Layout::from_size_align_unchecked(CHUNK, CHUNK_ALIGN));
+            if chunk.is_null() {
+                return chunk;
+            }
+            // The rest of the old chunk is never used.
+            if !state.chunk.is_null() {
+                state.stats.abandoned += CHUNK - state.used;
+            }
+            state.chunk = chunk;
+            state.stats.reserved += CHUNK;
+            start = 0;
+        }
+        state.used = start + layout.size();
+        state.stats.live += layout.size();
+        state.chunk.add(start)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if large(layout) {
+            return System.dealloc(ptr, layout);
+        }
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.stats.live -= layout.size();
+        if state.is_last(ptr, layout.size()) {
+            state.used -= layout.size();
+        } else {
+            state.stats.abandoned += layout.size();
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        match (large(layout), large(new_layout)) {
+            (true, true) => return System.realloc(ptr, layout, new_size),
+            (false, false) => {
+                // The last allocation grows or shrinks in place.
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+                if state.is_last(ptr, layout.size()) {
+                    let offset = ptr as usize - state.chunk as usize;
+                    if offset + new_size <= CHUNK {
+                        state.used = offset + new_size;
+                        state.stats.live = state.stats.live - layout.size() + new_size;
+                        return ptr;
+                    }
+                }
+            }
+            _ => {}
+        }
+        let moved = self.alloc(new_layout);
+        if !moved.is_null() {
+            ptr::copy_nonoverlapping(ptr, moved, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        moved
+    }
+}
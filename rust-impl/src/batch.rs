@@ -0,0 +1,155 @@
+//! Hierarchy questions in bulk (`el-reasoner batch`), for pipelines asking
+//! millions of them against one classification.
+//!
+//! Each input line is two tab-separated columns. `sub<TAB>sup` asks whether
+//! `sub ⊑ sup`; `term<TAB>kind`, with a kind of `sub`, `sup`, `ancestors`,
+//! `descendants` or `equivalents`, asks for those concepts (direct
+//! subclasses and superclasses for `sub` and `sup`). A second column naming
+//! a concept is always a superclass. Answers stream out a line at a time
+//! after the question's own two columns: `true` or `false`, or the names
+//! found separated by spaces.
+
+use std::io::{self, BufRead, Write};
+
+use crate::kernel::{ConceptId, Context, BOTTOM};
+use crate::query::Symbols;
+use crate::taxonomy::Taxonomy;
+
+/// Rejected lines kept in a [`BatchSummary`]; the rest are only counted.
+const REJECTED_KEPT: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    /// Direct subclasses.
+    Sub,
+    /// Direct superclasses.
+    Sup,
+    Ancestors,
+    Descendants,
+    Equivalents,
+}
+
+impl Relation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sub" => Some(Relation::Sub),
+            "sup" => Some(Relation::Sup),
+            "ancestors" => Some(Relation::Ancestors),
+            "descendants" => Some(Relation::Descendants),
+            "equivalents" => Some(Relation::Equivalents),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Question {
+    /// Whether the first concept is subsumed by the second.
+    Subsumed(ConceptId, ConceptId),
+    Related(ConceptId, Relation),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub answered: usize,
+    pub rejected: usize,
+    /// The first rejected lines, 1-based, and why.
+    pub first_rejected: Vec<(usize, String)>,
+}
+
+/// Answers [`Question`]s from the contexts and hierarchy of a
+/// classification, naming concepts after `names`.
+pub struct Batch<'a> {
+    contexts: &'a [Context],
+    taxonomy: &'a Taxonomy,
+    names: &'a [String],
+}
+
+impl<'a> Batch<'a> {
+    pub fn new(contexts: &'a [Context], taxonomy: &'a Taxonomy, names: &'a [String]) -> Self {
+        Self { contexts, taxonomy, names }
+    }
+
+    /// The question on one input line.
+    pub fn question(&self, line: &str, symbols: &dyn Symbols) -> Result<Question, String> {
+        let mut fields = line.split('\t').map(str::trim);
+        let (Some(first), Some(second), None) = (fields.next(), fields.next(), fields.next()) else {
+            return Err("expected two tab-separated columns".to_string());
+        };
+        let term = symbols.concept(first).ok_or_else(|| format!("unknown term '{}'", first))?;
+        match (symbols.concept(second), Relation::from_name(second)) {
+            (Some(sup), _) => Ok(Question::Subsumed(term, sup)),
+            (None, Some(relation)) => Ok(Question::Related(term, relation)),
+            (None, None) => Err(format!("unknown term or query kind '{}'", second)),
+        }
+    }
+
+    /// Whether `sub ⊑ sup`; an unsatisfiable `sub` is below everything.
+    pub fn is_subsumed(&self, sub: ConceptId, sup: ConceptId) -> bool {
+        let Some(context) = self.contexts.get(sub as usize) else {
+            return sub == sup;
+        };
+        sub == sup || context.super_set.contains(&sup) || context.super_set.contains(&BOTTOM)
+    }
+
+    /// The concepts `relation` of `c` finds, in hierarchy order.
+    pub fn related(&self, c: ConceptId, relation: Relation) -> Vec<ConceptId> {
+        match relation {
+            Relation::Sub => self.taxonomy.children(c).to_vec(),
+            Relation::Sup => self.taxonomy.parents(c).to_vec(),
+            Relation::Ancestors => self.taxonomy.ancestors(c),
+            Relation::Descendants => self.taxonomy.descendants(c),
+            Relation::Equivalents => {
+                let representative = self.taxonomy.representative(c);
+                let classes = self.taxonomy.equivalences();
+                match classes.binary_search_by_key(&representative, |class| class[0]) {
+                    Ok(i) => classes[i].iter().copied().filter(|&m| m != c).collect(),
+                    Err(_) => Vec::new(),
+                }
+            }
+        }
+    }
+
+    /// Writes the answer column for `question`, without a line break.
+    pub fn write_answer(&self, out: &mut dyn Write, question: Question) -> io::Result<()> {
+        match question {
+            Question::Subsumed(sub, sup) => write!(out, "{}", self.is_subsumed(sub, sup)),
+            Question::Related(c, relation) => {
+                for (i, found) in self.related(c, relation).into_iter().enumerate() {
+                    let separator = if i == 0 { "" } else { " " };
+                    write!(out, "{}{}", separator, self.names[found as usize])?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Answers every line of `input` on `out`, skipping blank lines and
+    /// `#` comments. Lines that aren't a question get no answer and are
+    /// counted in the summary.
+    pub fn run(&self, input: impl BufRead, symbols: &dyn Symbols, out: &mut dyn Write) -> io::Result<BatchSummary> {
+        let mut summary = BatchSummary::default();
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match self.question(line, symbols) {
+                Ok(question) => {
+                    write!(out, "{}\t", line)?;
+                    self.write_answer(out, question)?;
+                    writeln!(out)?;
+                    summary.answered += 1;
+                }
+                Err(message) => {
+                    summary.rejected += 1;
+                    if summary.first_rejected.len() < REJECTED_KEPT {
+                        summary.first_rejected.push((i + 1, message));
+                    }
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
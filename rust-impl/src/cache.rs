@@ -0,0 +1,220 @@
+//! On-disk cache of saturated contexts, keyed by a hash of everything that
+//! determines them (input bytes plus the options that change the axiom
+//! store), so reclassifying an unchanged ontology is a file read.
+//!
+//! Layout: the magic `ELCACHE1`, `num_concepts` and `num_roles`, then per
+//! concept its super set and, per role, its link targets; all counts and ids
+//! are little-endian `u32`s. Predecessor maps are rebuilt on load.
+//!
+//! Taxonomies are saved the same way, for tools that only need the inferred
+//! hierarchy: the magic `ELTAXON1`, `num_concepts`, per concept its direct
+//! parents, then the equivalence classes.
+
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use crate::kernel::{ConceptId, Context};
+use crate::taxonomy::Taxonomy;
+
+const MAGIC: &[u8; 8] = b"ELCACHE1";
+const TAXONOMY_MAGIC: &[u8; 8] = b"ELTAXON1";
+
+/// Incremental cache key: feed it the input document and any option that
+/// affects the axiom store.
+pub struct CacheKey(fxhash::FxHasher64);
+
+impl CacheKey {
+    pub fn new() -> Self {
+        let mut hasher = fxhash::FxHasher64::default();
+        // Results are only reusable by the same reasoner version.
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        Self(hasher)
+    }
+
+    pub fn add_bytes(&mut self, bytes: &[u8]) {
+        self.0.write_usize(bytes.len());
+        self.0.write(bytes);
+    }
+
+    pub fn add_reader<R: Read>(&mut self, mut reader: R) -> io::Result<()> {
+        let mut buf = vec![0u8; 1024 * 1024];
+        let mut total = 0usize;
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            self.0.write(&buf[..n]);
+            total += n;
+        }
+        self.0.write_usize(total);
+        Ok(())
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+impl Default for CacheKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn write_contexts<W: Write>(contexts: &[Context], num_roles: usize, mut out: W) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    write_u32(&mut out, contexts.len())?;
+    write_u32(&mut out, num_roles)?;
+    for ctx in contexts {
+        write_u32(&mut out, ctx.super_set.len())?;
+        for &s in &ctx.super_set {
+            out.write_all(&s.to_le_bytes())?;
+        }
+        for targets in &ctx.link_map {
+            write_u32(&mut out, targets.len())?;
+            for &t in targets {
+                out.write_all(&t.to_le_bytes())?;
+            }
+        }
+    }
+    out.flush()
+}
+
+pub fn read_contexts<R: Read>(mut input: R) -> io::Result<Vec<Context>> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a classification cache file".to_string()));
+    }
+    let num_concepts = read_u32(&mut input)? as usize;
+    let num_roles = read_u32(&mut input)? as usize;
+
+    let mut contexts: Vec<Context> =
+        (0..num_concepts).map(|c| Context::new(c as ConceptId, num_roles)).collect();
+    for c in 0..num_concepts {
+        let supers = read_u32(&mut input)? as usize;
+        contexts[c].super_set.reserve(supers);
+        for _ in 0..supers {
+            let s = read_id(&mut input, num_concepts)?;
+            contexts[c].super_set.insert(s);
+        }
+        for r in 0..num_roles {
+            let links = read_u32(&mut input)? as usize;
+            for _ in 0..links {
+                let t = read_id(&mut input, num_concepts)?;
+                contexts[c].link_map[r].push(t);
+                contexts[t as usize].pred_map[r].push(c as ConceptId);
+            }
+        }
+    }
+    Ok(contexts)
+}
+
+pub fn write_taxonomy<W: Write>(taxonomy: &Taxonomy, mut out: W) -> io::Result<()> {
+    out.write_all(TAXONOMY_MAGIC)?;
+    write_u32(&mut out, taxonomy.len())?;
+    for parents in taxonomy.direct_parents() {
+        write_list(&mut out, parents)?;
+    }
+    write_u32(&mut out, taxonomy.equivalences().len())?;
+    for class in taxonomy.equivalences() {
+        write_list(&mut out, class)?;
+    }
+    out.flush()
+}
+
+pub fn read_taxonomy<R: Read>(mut input: R) -> io::Result<Taxonomy> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != TAXONOMY_MAGIC {
+        return Err(invalid("not a taxonomy file".to_string()));
+    }
+    let num_concepts = read_u32(&mut input)? as usize;
+    let direct_parents = (0..num_concepts)
+        .map(|_| read_list(&mut input, num_concepts))
+        .collect::<io::Result<_>>()?;
+    let num_classes = read_u32(&mut input)? as usize;
+    let equivalences = (0..num_classes)
+        .map(|_| read_list(&mut input, num_concepts))
+        .collect::<io::Result<_>>()?;
+    Ok(Taxonomy::from_parents(direct_parents).with_equivalences(equivalences))
+}
+
+/// A directory of `<key>.elc` files.
+pub struct ClassificationCache {
+    dir: PathBuf,
+}
+
+impl ClassificationCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.elc", key))
+    }
+
+    /// The cached contexts for `key`, or `None` on a miss.
+    pub fn load(&self, key: u64) -> io::Result<Option<Vec<Context>>> {
+        match File::open(self.path(key)) {
+            Ok(f) => read_contexts(BufReader::with_capacity(1024 * 1024, f)).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes through a temporary file and renames it into place, so a
+    /// concurrent or interrupted run never sees a partial entry.
+    pub fn store(&self, key: u64, contexts: &[Context], num_roles: usize) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        let written = File::create(&tmp).and_then(|f| {
+            write_contexts(contexts, num_roles, BufWriter::with_capacity(1024 * 1024, f))
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&tmp, &path)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+        Ok(path)
+    }
+}
+
+fn write_u32<W: Write>(out: &mut W, n: usize) -> io::Result<()> {
+    let n = u32::try_from(n).map_err(|_| invalid(format!("count {} exceeds u32", n)))?;
+    out.write_all(&n.to_le_bytes())
+}
+
+fn read_u32<R: Read>(input: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn write_list<W: Write>(out: &mut W, ids: &[ConceptId]) -> io::Result<()> {
+    write_u32(out, ids.len())?;
+    for &c in ids {
+        out.write_all(&c.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_list<R: Read>(input: &mut R, num_concepts: usize) -> io::Result<Vec<ConceptId>> {
+    let len = read_u32(input)? as usize;
+    (0..len).map(|_| read_id(input, num_concepts)).collect()
+}
+
+fn read_id<R: Read>(input: &mut R, num_concepts: usize) -> io::Result<ConceptId> {
+    let id = read_u32(input)?;
+    if id as usize >= num_concepts {
+        return Err(invalid(format!("concept id {} out of range", id)));
+    }
+    Ok(id)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,280 @@
+//! Release-to-release term change report between two classified OBO versions.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::obo::{build_axiom_store, ParseResult};
+use crate::{saturate, unsatisfiable_concepts, ConceptId, Taxonomy, TOP};
+
+/// One side of a comparison: a parsed release and its classification.
+pub struct Release<'a> {
+    pub parsed: &'a ParseResult,
+    pub direct_parents: &'a [Vec<ConceptId>],
+    /// Equivalence classes of named concepts, as [`Taxonomy::equivalences`].
+    pub equivalences: &'a [Vec<ConceptId>],
+    pub unsatisfiable: &'a [ConceptId],
+}
+
+impl Release<'_> {
+    fn label(&self, idx: usize) -> Option<&str> {
+        self.parsed.labels.get(&idx).map(String::as_str)
+    }
+
+    fn parent_ids(&self, idx: usize) -> Vec<&str> {
+        let mut parents: Vec<&str> = self.direct_parents[idx]
+            .iter()
+            .filter(|&&p| p != TOP)
+            .map(|&p| self.parsed.concepts[p as usize].as_str())
+            .collect();
+        parents.sort_unstable();
+        parents
+    }
+
+    fn term(&self, idx: usize) -> TermRef {
+        TermRef {
+            id: self.parsed.concepts[idx].clone(),
+            label: self.label(idx).map(str::to_string),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermRef {
+    pub id: String,
+    pub label: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParentChange {
+    pub term: TermRef,
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LabelChange {
+    pub id: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TermChanges {
+    pub new_terms: Vec<TermRef>,
+    /// Terms declared in the old release only, deleted rather than
+    /// obsoleted.
+    pub removed_terms: Vec<TermRef>,
+    pub newly_obsolete: Vec<TermRef>,
+    pub parent_changes: Vec<ParentChange>,
+    pub label_changes: Vec<LabelChange>,
+    /// Classes unsatisfiable in the new release only (or new and
+    /// unsatisfiable).
+    pub newly_unsatisfiable: Vec<TermRef>,
+    /// Equivalence classes of the new release with a member that wasn't
+    /// equivalent to the others before, members sorted by ID.
+    pub new_equivalences: Vec<Vec<TermRef>>,
+}
+
+impl TermChanges {
+    pub fn is_empty(&self) -> bool {
+        self.new_terms.is_empty()
+            && self.removed_terms.is_empty()
+            && self.newly_obsolete.is_empty()
+            && self.parent_changes.is_empty()
+            && self.label_changes.is_empty()
+            && self.newly_unsatisfiable.is_empty()
+            && self.new_equivalences.is_empty()
+    }
+}
+
+/// Compares the declared terms of `new` against `old`. Parent changes are
+/// reported on the inferred direct parents of terms live in both releases,
+/// and of live terms since removed, whose parents all count as removed;
+/// unsatisfiable classes and equivalences cover every named concept.
+pub fn term_changes(old: &Release, new: &Release) -> TermChanges {
+    let mut changes = TermChanges::default();
+
+    let mut declared: Vec<usize> = new.parsed.declared.iter().copied().collect();
+    declared.sort_unstable_by(|&a, &b| new.parsed.concepts[a].cmp(&new.parsed.concepts[b]));
+
+    for idx in declared {
+        let id = &new.parsed.concepts[idx];
+        let term = TermRef {
+            id: id.clone(),
+            label: new.label(idx).map(str::to_string),
+        };
+        let old_idx = match old.parsed.concept_idx.get(id) {
+            Some(&i) if old.parsed.declared.contains(&i) => i,
+            _ => {
+                changes.new_terms.push(term);
+                continue;
+            }
+        };
+
+        let was_obsolete = old.parsed.obsolete.contains(&old_idx);
+        let is_obsolete = new.parsed.obsolete.contains(&idx);
+        if is_obsolete && !was_obsolete {
+            changes.newly_obsolete.push(term.clone());
+        }
+
+        if let (Some(before), Some(after)) = (old.label(old_idx), new.label(idx)) {
+            if before != after {
+                changes.label_changes.push(LabelChange {
+                    id: id.clone(),
+                    old: before.to_string(),
+                    new: after.to_string(),
+                });
+            }
+        }
+
+        if is_obsolete || was_obsolete {
+            continue;
+        }
+        let before = old.parent_ids(old_idx);
+        let after = new.parent_ids(idx);
+        if before != after {
+            changes.parent_changes.push(ParentChange {
+                term,
+                removed: before.iter().filter(|p| !after.contains(p)).map(|p| p.to_string()).collect(),
+                added: after.iter().filter(|p| !before.contains(p)).map(|p| p.to_string()).collect(),
+            });
+        }
+    }
+
+    let mut removed: Vec<usize> = old
+        .parsed
+        .declared
+        .iter()
+        .copied()
+        .filter(|&i| {
+            let id = &old.parsed.concepts[i];
+            new.parsed.concept_idx.get(id).is_none_or(|j| !new.parsed.declared.contains(j))
+        })
+        .collect();
+    removed.sort_unstable_by(|&a, &b| old.parsed.concepts[a].cmp(&old.parsed.concepts[b]));
+    for idx in removed {
+        let term = old.term(idx);
+        changes.removed_terms.push(term.clone());
+        let before = old.parent_ids(idx);
+        if !old.parsed.obsolete.contains(&idx) && !before.is_empty() {
+            let removed = before.iter().map(|p| p.to_string()).collect();
+            changes.parent_changes.push(ParentChange { term, removed, added: Vec::new() });
+        }
+    }
+    changes.parent_changes.sort_unstable_by(|a, b| a.term.id.cmp(&b.term.id));
+
+    let old_idx = |c: ConceptId| old.parsed.concept_idx.get(&new.parsed.concepts[c as usize]);
+    let was_unsat: HashSet<usize> = old.unsatisfiable.iter().map(|&c| c as usize).collect();
+    changes.newly_unsatisfiable = new
+        .unsatisfiable
+        .iter()
+        .filter(|&&c| old_idx(c).is_none_or(|i| !was_unsat.contains(i)))
+        .map(|&c| new.term(c as usize))
+        .collect();
+    changes.newly_unsatisfiable.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+
+    let old_class: HashMap<usize, usize> = old
+        .equivalences
+        .iter()
+        .enumerate()
+        .flat_map(|(i, class)| class.iter().map(move |&c| (c as usize, i)))
+        .collect();
+    for class in new.equivalences {
+        let before = old_idx(class[0]).and_then(|i| old_class.get(i));
+        let kept = before.is_some()
+            && class[1..].iter().all(|&c| old_idx(c).and_then(|i| old_class.get(i)) == before);
+        if !kept {
+            let mut members: Vec<TermRef> = class.iter().map(|&c| new.term(c as usize)).collect();
+            members.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+            changes.new_equivalences.push(members);
+        }
+    }
+    changes.new_equivalences.sort_unstable_by(|a, b| a[0].id.cmp(&b[0].id));
+
+    changes
+}
+
+/// Classifies both releases and compares them with [`term_changes`].
+pub fn diff(old: &ParseResult, new: &ParseResult) -> TermChanges {
+    let (before, after) = (Classified::new(old), Classified::new(new));
+    term_changes(&before.release(old), &after.release(new))
+}
+
+struct Classified {
+    taxonomy: Taxonomy,
+    unsatisfiable: Vec<ConceptId>,
+}
+
+impl Classified {
+    fn new(parsed: &ParseResult) -> Self {
+        let store = build_axiom_store(parsed);
+        let mut contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+        contexts.truncate(parsed.concepts.len());
+        let unsatisfiable = unsatisfiable_concepts(&contexts);
+        Self { taxonomy: Taxonomy::new(&contexts, parsed.concepts.len()), unsatisfiable }
+    }
+
+    fn release<'a>(&'a self, parsed: &'a ParseResult) -> Release<'a> {
+        Release {
+            parsed,
+            direct_parents: self.taxonomy.direct_parents(),
+            equivalences: self.taxonomy.equivalences(),
+            unsatisfiable: &self.unsatisfiable,
+        }
+    }
+}
+
+impl fmt::Display for TermRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} ({})", self.id, label),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+/// Markdown, ready to paste into release notes.
+impl fmt::Display for TermChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "## New terms ({})\n", self.new_terms.len())?;
+        for t in &self.new_terms {
+            writeln!(f, "- {}", t)?;
+        }
+        writeln!(f, "\n## Removed terms ({})\n", self.removed_terms.len())?;
+        for t in &self.removed_terms {
+            writeln!(f, "- {}", t)?;
+        }
+        writeln!(f, "\n## Newly obsoleted terms ({})\n", self.newly_obsolete.len())?;
+        for t in &self.newly_obsolete {
+            writeln!(f, "- {}", t)?;
+        }
+        writeln!(f, "\n## Changed inferred parents ({})\n", self.parent_changes.len())?;
+        for c in &self.parent_changes {
+            write!(f, "- {}:", c.term)?;
+            if !c.added.is_empty() {
+                write!(f, " added {}", c.added.join(", "))?;
+            }
+            if !c.removed.is_empty() {
+                if !c.added.is_empty() {
+                    write!(f, ";")?;
+                }
+                write!(f, " removed {}", c.removed.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "\n## Label changes ({})\n", self.label_changes.len())?;
+        for c in &self.label_changes {
+            writeln!(f, "- {}: \"{}\" → \"{}\"", c.id, c.old, c.new)?;
+        }
+        writeln!(f, "\n## Newly unsatisfiable classes ({})\n", self.newly_unsatisfiable.len())?;
+        for t in &self.newly_unsatisfiable {
+            writeln!(f, "- {}", t)?;
+        }
+        writeln!(f, "\n## New equivalences ({})\n", self.new_equivalences.len())?;
+        for class in &self.new_equivalences {
+            let members: Vec<String> = class.iter().map(|t| t.to_string()).collect();
+            writeln!(f, "- {}", members.join(" ≡ "))?;
+        }
+        Ok(())
+    }
+}
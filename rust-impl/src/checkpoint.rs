@@ -0,0 +1,184 @@
+//! Saturation checkpoints, so a classification that runs past a batch
+//! job's wall-clock limit can pick up where the preempted run stopped.
+//!
+//! Layout: the magic `ELCKPT01`, a summary of the axiom store (its sizes
+//! and axiom counts, checked on load so a checkpoint is only resumed
+//! against the store it was taken from), the contexts in the
+//! [`crate::cache`] layout, then the number of pending worklist items and
+//! per item a tag byte (0 for a subsumption, 1 for a link) followed by its
+//! ids; all counts and ids are little-endian `u32`s.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::cache::{read_contexts, write_contexts};
+use crate::kernel::{
+    saturate_checkpointed, AxiomStore, ConceptId, ConceptMap, Context, PartialSaturation, SaturationOptions,
+    TraceEvent,
+};
+
+const MAGIC: &[u8; 8] = b"ELCKPT01";
+
+/// Worklist items between two looks at the clock.
+const CLOCK_STEP: usize = 1 << 16;
+
+fn store_summary(store: &AxiomStore) -> [usize; 10] {
+    let maps = |maps: &[ConceptMap<Vec<ConceptId>>]| -> usize {
+        maps.iter().flat_map(|m| m.values()).map(Vec::len).sum()
+    };
+    [
+        store.num_concepts(),
+        store.exist_left.len(),
+        store.sub_to_sups.iter().map(Vec::len).sum(),
+        maps(&store.conj_index),
+        store.exist_right.iter().map(Vec::len).sum(),
+        maps(&store.exist_left),
+        store.role_sub_to_sups.iter().map(Vec::len).sum(),
+        store.role_chains().count(),
+        store.reflexive_roles.len(),
+        store.nominals.len(),
+    ]
+}
+
+pub fn write_checkpoint<W: Write>(
+    store: &AxiomStore,
+    contexts: &[Context],
+    pending: &[TraceEvent],
+    mut out: W,
+) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    for n in store_summary(store) {
+        write_u32(&mut out, n)?;
+    }
+    write_contexts(contexts, store.exist_left.len(), &mut out)?;
+    write_u32(&mut out, pending.len())?;
+    for &event in pending {
+        let (tag, ids) = match event {
+            TraceEvent::Concept { concept, added } => (0u8, vec![concept, added]),
+            TraceEvent::Link { source, role, target } => (1u8, vec![source, role, target]),
+        };
+        out.write_all(&[tag])?;
+        for id in ids {
+            out.write_all(&id.to_le_bytes())?;
+        }
+    }
+    out.flush()
+}
+
+/// Reads a checkpoint taken while saturating `store`.
+pub fn read_checkpoint<R: Read>(store: &AxiomStore, mut input: R) -> io::Result<PartialSaturation> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a saturation checkpoint".to_string()));
+    }
+    for expected in store_summary(store) {
+        if read_u32(&mut input)? as usize != expected {
+            return Err(invalid("checkpoint was taken from a different axiom store".to_string()));
+        }
+    }
+    let contexts = read_contexts(&mut input)?;
+    let num_concepts = contexts.len();
+    let num_roles = store.exist_left.len();
+    let n = read_u32(&mut input)? as usize;
+    let mut pending = Vec::with_capacity(n);
+    for _ in 0..n {
+        let mut tag = [0u8; 1];
+        input.read_exact(&mut tag)?;
+        let mut id = |bound: usize| {
+            let id = read_u32(&mut input)?;
+            match (id as usize) < bound {
+                true => Ok(id),
+                false => Err(invalid(format!("id {} out of range", id))),
+            }
+        };
+        pending.push(match tag[0] {
+            0 => TraceEvent::Concept { concept: id(num_concepts)?, added: id(num_concepts)? },
+            1 => TraceEvent::Link { source: id(num_concepts)?, role: id(num_roles)?, target: id(num_concepts)? },
+            tag => return Err(invalid(format!("unknown pending item tag {}", tag))),
+        });
+    }
+    Ok(PartialSaturation { contexts, pending })
+}
+
+/// Writes through a temporary file and renames it into place, so a run
+/// killed mid-write leaves the previous checkpoint intact.
+pub fn save_checkpoint(
+    path: &Path,
+    store: &AxiomStore,
+    contexts: &[Context],
+    pending: &[TraceEvent],
+) -> io::Result<()> {
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    let written = File::create(&tmp).and_then(|f| {
+        write_checkpoint(store, contexts, pending, BufWriter::with_capacity(1024 * 1024, f))
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// The checkpoint at `path`, or `None` if there is none.
+pub fn load_checkpoint(path: &Path, store: &AxiomStore) -> io::Result<Option<PartialSaturation>> {
+    match File::open(path) {
+        Ok(f) => read_checkpoint(store, BufReader::with_capacity(1024 * 1024, f)).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// [`saturate_with`](crate::kernel::saturate_with), going on from the
+/// checkpoint at `path` if there is one and writing one there every
+/// `interval`. The last checkpoint is left in place.
+pub fn saturate_resumable(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+    path: &Path,
+    interval: Duration,
+) -> io::Result<Vec<Context>> {
+    let from = load_checkpoint(path, store)?;
+    if from.as_ref().is_some_and(|from| from.contexts.len() != num_concepts) {
+        return Err(invalid(format!("checkpoint {} does not cover {} concepts", path.display(), num_concepts)));
+    }
+    let mut last = Instant::now();
+    let mut failed = None;
+    let checkpoint = |contexts: &[Context], pending: &[TraceEvent]| {
+        if last.elapsed() < interval {
+            return ControlFlow::Continue(());
+        }
+        if let Err(e) = save_checkpoint(path, store, contexts, pending) {
+            failed = Some(e);
+            return ControlFlow::Break(());
+        }
+        last = Instant::now();
+        ControlFlow::Continue(())
+    };
+    let result = saturate_checkpointed(store, num_concepts, num_roles, options, from, CLOCK_STEP, checkpoint);
+    match (result, failed) {
+        (Ok(contexts), _) => Ok(contexts),
+        (Err(_), Some(e)) => Err(e),
+        (Err(_), None) => unreachable!("saturation only stops when a checkpoint fails"),
+    }
+}
+
+fn write_u32<W: Write>(out: &mut W, n: usize) -> io::Result<()> {
+    let n = u32::try_from(n).map_err(|_| invalid(format!("count {} exceeds u32", n)))?;
+    out.write_all(&n.to_le_bytes())
+}
+
+fn read_u32<R: Read>(input: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
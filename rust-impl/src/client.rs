@@ -0,0 +1,165 @@
+//! Blocking client for the reasoning service (see `openapi.yaml`).
+//!
+//! Plain HTTP/1.1 over a fresh `TcpStream` per request; the service closes
+//! the connection after each response, so no pooling is attempted.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::api::{ApiError, ClassifySummary, ConceptList, Satisfiability};
+use crate::json::{self, Value};
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    /// The service answered with a non-2xx status.
+    Status { code: u16, message: String },
+    /// The response was not a well-formed body of the expected shape.
+    Malformed(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "{}", e),
+            ClientError::Status { code, message } => write!(f, "HTTP {}: {}", code, message),
+            ClientError::Malformed(what) => write!(f, "malformed response: {}", what),
+        }
+    }
+}
+
+impl Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+pub struct Client {
+    /// `host:port`.
+    authority: String,
+    timeout: Option<Duration>,
+}
+
+impl Client {
+    /// `base` is `host:port`, optionally prefixed with `http://`.
+    pub fn new(base: &str) -> Self {
+        let authority = base.strip_prefix("http://").unwrap_or(base).trim_end_matches('/');
+        Self { authority: authority.to_string(), timeout: None }
+    }
+
+    /// Read/write timeout per request; none by default, since `/classify`
+    /// on a large upload can take a while.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn subclasses(&self, id: &str, direct: bool) -> Result<ConceptList, ClientError> {
+        let body = self.get(&hierarchy_path("subclasses", id, direct))?;
+        ConceptList::from_json(&body).ok_or_else(|| malformed("ConceptList"))
+    }
+
+    pub fn superclasses(&self, id: &str, direct: bool) -> Result<ConceptList, ClientError> {
+        let body = self.get(&hierarchy_path("superclasses", id, direct))?;
+        ConceptList::from_json(&body).ok_or_else(|| malformed("ConceptList"))
+    }
+
+    pub fn equivalents(&self, id: &str) -> Result<ConceptList, ClientError> {
+        let body = self.get(&format!("/equivalents/{}", percent_encode(id)))?;
+        ConceptList::from_json(&body).ok_or_else(|| malformed("ConceptList"))
+    }
+
+    pub fn satisfiable(&self, id: &str) -> Result<Satisfiability, ClientError> {
+        let body = self.get(&format!("/satisfiable/{}", percent_encode(id)))?;
+        Satisfiability::from_json(&body).ok_or_else(|| malformed("Satisfiability"))
+    }
+
+    /// Uploads an OBO document; the service serves it from then on.
+    pub fn classify(&self, obo: &str) -> Result<ClassifySummary, ClientError> {
+        let body = self.request("POST", "/classify", Some(obo))?;
+        ClassifySummary::from_json(&body).ok_or_else(|| malformed("ClassifySummary"))
+    }
+
+    fn get(&self, path: &str) -> Result<Value, ClientError> {
+        self.request("GET", path, None)
+    }
+
+    fn request(&self, method: &str, path: &str, body: Option<&str>) -> Result<Value, ClientError> {
+        let mut stream = TcpStream::connect(&self.authority)?;
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n",
+            method, path, self.authority
+        );
+        if let Some(body) = body {
+            head.push_str(&format!(
+                "Content-Type: text/plain\r\nContent-Length: {}\r\n",
+                body.len()
+            ));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        if let Some(body) = body {
+            stream.write_all(body.as_bytes())?;
+        }
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        let (code, body) = split_response(&response)?;
+        let value = json::parse(body).map_err(|e| ClientError::Malformed(e.to_string()))?;
+        if !(200..300).contains(&code) {
+            let message = ApiError::from_json(&value).map_or_else(|| body.to_string(), |e| e.error);
+            return Err(ClientError::Status { code, message });
+        }
+        Ok(value)
+    }
+}
+
+fn malformed(expected: &str) -> ClientError {
+    ClientError::Malformed(format!("expected a {} body", expected))
+}
+
+fn hierarchy_path(endpoint: &str, id: &str, direct: bool) -> String {
+    let mut path = format!("/{}/{}", endpoint, percent_encode(id));
+    if direct {
+        path.push_str("?direct=true");
+    }
+    path
+}
+
+/// Status code and body of a `Connection: close` response. The service
+/// always sends `Content-Length`, so chunked bodies are not handled.
+fn split_response(response: &[u8]) -> Result<(u16, &str), ClientError> {
+    let text = std::str::from_utf8(response).map_err(|_| malformed("UTF-8"))?;
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| ClientError::Malformed("no header terminator".to_string()))?;
+    let code = head
+        .lines()
+        .next()
+        .and_then(|status| status.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| ClientError::Malformed("bad status line".to_string()))?;
+    Ok((code, body))
+}
+
+/// Encodes everything but RFC 3986 unreserved characters, so IRIs
+/// (`/`, `#`, `:`) survive as a single path segment.
+pub fn percent_encode(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
@@ -0,0 +1,342 @@
+//! The materialized closure of a classification as a flat on-disk index:
+//! every named concept's inferred ancestors and descendants and its
+//! relation links, readable in place without the contexts or a reasoner.
+//!
+//! Layout: the magic `ELCLOSE1`, `num_concepts` and `num_roles`, then
+//! these sections of little-endian `u32`s, the tables as `n + 1` offsets
+//! followed by the entries they delimit:
+//!
+//! - concept names, byte offsets into UTF-8, padded to 4 bytes;
+//! - role names, likewise;
+//! - concept IDs sorted by name, for [`ClosureIndex::find`];
+//! - ancestors per concept, sorted IDs;
+//! - descendants per concept, likewise;
+//! - relation links per concept, sorted `(role, target)` pairs.
+//!
+//! Every section starts 4-byte aligned and nothing needs decoding up
+//! front, so [`ClosureIndex`] reads a memory-mapped file as well as a
+//! buffer; opening it only checks the offset tables. owl:Thing is nobody's
+//! listed ancestor, so it has no descendants. An unsatisfiable concept has
+//! owl:Nothing as its only ancestor and is left out of other concepts'
+//! descendants.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::export::relation_closure;
+use crate::kernel::{ConceptId, Context, RoleId, BOTTOM, TOP};
+
+const MAGIC: &[u8; 8] = b"ELCLOSE1";
+
+/// Writes the closure of `contexts` over the named concepts `concepts`
+/// (fresh normalization concepts after them are left out) and `roles`.
+pub fn write_closure_index<W: Write>(
+    contexts: &[Context],
+    concepts: &[String],
+    roles: &[String],
+    mut out: W,
+) -> io::Result<()> {
+    let n = concepts.len();
+    let named = |d: ConceptId| (d as usize) < n && d != TOP && !concepts[d as usize].is_empty();
+    let unsatisfiable = |c: usize| contexts[c].super_set.contains(&BOTTOM);
+
+    let mut ancestors: Vec<Vec<ConceptId>> = vec![Vec::new(); n];
+    let mut descendants: Vec<Vec<ConceptId>> = vec![Vec::new(); n];
+    for c in (0..n).filter(|&c| named(c as ConceptId)) {
+        if unsatisfiable(c) && c != BOTTOM as usize {
+            ancestors[c].push(BOTTOM);
+            descendants[BOTTOM as usize].push(c as ConceptId);
+            continue;
+        }
+        for &d in contexts[c].super_set.iter().filter(|&&d| d as usize != c && named(d)) {
+            ancestors[c].push(d);
+            descendants[d as usize].push(c as ConceptId);
+        }
+    }
+    for ids in ancestors.iter_mut().chain(&mut descendants) {
+        ids.sort_unstable();
+    }
+    let mut relations: Vec<Vec<(RoleId, ConceptId)>> = vec![Vec::new(); n];
+    for r in 0..roles.len() as RoleId {
+        for (c, d) in relation_closure(contexts, n, r).into_iter().filter(|&(c, d)| named(c) && named(d)) {
+            relations[c as usize].push((r, d));
+        }
+    }
+
+    out.write_all(MAGIC)?;
+    write_u32(&mut out, n)?;
+    write_u32(&mut out, roles.len())?;
+    write_names(&mut out, concepts)?;
+    write_names(&mut out, roles)?;
+    let mut by_name: Vec<ConceptId> = (0..n as ConceptId).collect();
+    by_name.sort_by(|&a, &b| concepts[a as usize].cmp(&concepts[b as usize]));
+    for c in by_name {
+        out.write_all(&c.to_le_bytes())?;
+    }
+    write_lists(&mut out, &ancestors, |ids| ids.len(), |out, ids| write_ids(out, ids))?;
+    write_lists(&mut out, &descendants, |ids| ids.len(), |out, ids| write_ids(out, ids))?;
+    write_lists(&mut out, &relations, |links| links.len(), |out, links| {
+        links.iter().try_for_each(|&(r, d)| write_ids(out, &[r, d]))
+    })?;
+    out.flush()
+}
+
+fn write_names<W: Write>(out: &mut W, names: &[String]) -> io::Result<()> {
+    let mut offset = 0;
+    write_u32(out, offset)?;
+    for name in names {
+        offset += name.len();
+        write_u32(out, offset)?;
+    }
+    for name in names {
+        out.write_all(name.as_bytes())?;
+    }
+    out.write_all(&[0; 3][..(4 - offset % 4) % 4])
+}
+
+/// `n + 1` offsets counting the entries of each list, then the lists.
+fn write_lists<W: Write, T>(
+    out: &mut W,
+    lists: &[T],
+    len: impl Fn(&T) -> usize,
+    write: impl Fn(&mut W, &T) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut offset = 0;
+    write_u32(out, offset)?;
+    for list in lists {
+        offset += len(list);
+        write_u32(out, offset)?;
+    }
+    lists.iter().try_for_each(|list| write(out, list))
+}
+
+fn write_ids<W: Write>(out: &mut W, ids: &[u32]) -> io::Result<()> {
+    ids.iter().try_for_each(|id| out.write_all(&id.to_le_bytes()))
+}
+
+fn write_u32<W: Write>(out: &mut W, n: usize) -> io::Result<()> {
+    let n = u32::try_from(n).map_err(|_| invalid(format!("count {} exceeds u32", n)))?;
+    out.write_all(&n.to_le_bytes())
+}
+
+/// An offset table: where its offsets start and where the data they index
+/// does, both as byte positions.
+#[derive(Clone, Copy)]
+struct Table {
+    offsets: usize,
+    data: usize,
+}
+
+impl Table {
+    /// The table of `n` lists of `width`-byte entries at `*pos` in `data`,
+    /// moving `*pos` past it and its padding.
+    fn check(data: &[u8], pos: &mut usize, n: usize, width: usize) -> io::Result<Self> {
+        let offsets = *pos;
+        let start = offsets + (n + 1) * 4;
+        if start > data.len() {
+            return Err(invalid("truncated offset table".to_string()));
+        }
+        let mut last = 0;
+        for i in 0..=n {
+            let offset = u32_at(data, offsets + i * 4);
+            if offset < last || (i == 0 && offset != 0) {
+                return Err(invalid("offsets out of order".to_string()));
+            }
+            last = offset;
+        }
+        *pos = start + (last as usize * width).div_ceil(4) * 4;
+        if *pos > data.len() {
+            return Err(invalid("truncated table".to_string()));
+        }
+        Ok(Self { offsets, data: start })
+    }
+}
+
+/// A closure index written by [`write_closure_index`], read in place from
+/// `bytes`: a `Vec<u8>`, a memory map or anything else holding the file.
+pub struct ClosureIndex<B> {
+    bytes: B,
+    num_concepts: usize,
+    num_roles: usize,
+    concept_names: Table,
+    role_names: Table,
+    by_name: usize,
+    ancestors: Table,
+    descendants: Table,
+    relations: Table,
+}
+
+impl ClosureIndex<Vec<u8>> {
+    /// Reads the index at `path` into memory.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        Self::new(fs::read(path)?)
+    }
+}
+
+impl<B: AsRef<[u8]>> ClosureIndex<B> {
+    /// Checks the header and offset tables of `bytes`; the entries
+    /// themselves are read as they are asked for.
+    pub fn new(bytes: B) -> io::Result<Self> {
+        let data = bytes.as_ref();
+        if data.len() < 16 || &data[..8] != MAGIC {
+            return Err(invalid("not a closure index".to_string()));
+        }
+        let num_concepts = u32_at(data, 8) as usize;
+        let num_roles = u32_at(data, 12) as usize;
+        let mut pos = 16;
+        let concept_names = Table::check(data, &mut pos, num_concepts, 1)?;
+        let role_names = Table::check(data, &mut pos, num_roles, 1)?;
+        let by_name = pos;
+        pos += num_concepts * 4;
+        let ancestors = Table::check(data, &mut pos, num_concepts, 4)?;
+        let descendants = Table::check(data, &mut pos, num_concepts, 4)?;
+        let relations = Table::check(data, &mut pos, num_concepts, 8)?;
+        if pos != data.len() {
+            return Err(invalid("trailing bytes after the relations".to_string()));
+        }
+        Ok(Self {
+            bytes,
+            num_concepts,
+            num_roles,
+            concept_names,
+            role_names,
+            by_name,
+            ancestors,
+            descendants,
+            relations,
+        })
+    }
+
+    pub fn num_concepts(&self) -> usize {
+        self.num_concepts
+    }
+
+    pub fn num_roles(&self) -> usize {
+        self.num_roles
+    }
+
+    /// Entry `i`'s bytes in `table`, `width` bytes per entry; empty past
+    /// the end of the table.
+    fn entry(&self, table: Table, n: usize, i: usize, width: usize) -> &[u8] {
+        if i >= n {
+            return &[];
+        }
+        let data = self.bytes.as_ref();
+        let start = u32_at(data, table.offsets + i * 4) as usize * width;
+        let end = u32_at(data, table.offsets + (i + 1) * 4) as usize * width;
+        &data[table.data + start..table.data + end]
+    }
+
+    /// The name of `c`, empty for an unnamed or unknown concept.
+    pub fn name(&self, c: ConceptId) -> &str {
+        core::str::from_utf8(self.entry(self.concept_names, self.num_concepts, c as usize, 1)).unwrap_or("")
+    }
+
+    pub fn role_name(&self, r: RoleId) -> &str {
+        core::str::from_utf8(self.entry(self.role_names, self.num_roles, r as usize, 1)).unwrap_or("")
+    }
+
+    /// The concept named `name`, by binary search.
+    pub fn find(&self, name: &str) -> Option<ConceptId> {
+        if name.is_empty() {
+            return None;
+        }
+        let data = self.bytes.as_ref();
+        let (mut lo, mut hi) = (0, self.num_concepts);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let c = u32_at(data, self.by_name + mid * 4);
+            match self.name(c).cmp(name) {
+                core::cmp::Ordering::Less => lo = mid + 1,
+                core::cmp::Ordering::Greater => hi = mid,
+                core::cmp::Ordering::Equal => return Some(c),
+            }
+        }
+        None
+    }
+
+    pub fn find_role(&self, name: &str) -> Option<RoleId> {
+        (0..self.num_roles as RoleId).find(|&r| self.role_name(r) == name)
+    }
+
+    /// The named concepts subsuming `c`, equivalents included, itself and
+    /// owl:Thing not; sorted by ID.
+    pub fn ancestors(&self, c: ConceptId) -> Ids<'_> {
+        Ids(self.entry(self.ancestors, self.num_concepts, c as usize, 4))
+    }
+
+    /// The satisfiable named concepts `c` subsumes, equivalents included,
+    /// itself not; sorted by ID.
+    pub fn descendants(&self, c: ConceptId) -> Ids<'_> {
+        Ids(self.entry(self.descendants, self.num_concepts, c as usize, 4))
+    }
+
+    /// Whether `sub ⊑ sup`, by binary search in the ancestors of `sub`.
+    pub fn is_subsumed_by(&self, sub: ConceptId, sup: ConceptId) -> bool {
+        let ancestors = self.ancestors(sub);
+        sub == sup || sup == TOP || ancestors.contains(BOTTOM) || ancestors.contains(sup)
+    }
+
+    /// The named concepts `c` is linked to, as `(role, target)` for each
+    /// `c ⊑ ∃role.target`, sorted; none for an unsatisfiable `c`.
+    pub fn relations(&self, c: ConceptId) -> impl Iterator<Item = (RoleId, ConceptId)> + '_ {
+        let links = self.entry(self.relations, self.num_concepts, c as usize, 8);
+        links.chunks_exact(8).map(|pair| (u32_at(pair, 0), u32_at(pair, 4)))
+    }
+}
+
+/// Sorted concept IDs read from an index.
+#[derive(Clone)]
+pub struct Ids<'a>(&'a [u8]);
+
+impl Ids<'_> {
+    pub fn len(&self) -> usize {
+        self.0.len() / 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<ConceptId> {
+        (i < self.len()).then(|| u32_at(self.0, i * 4))
+    }
+
+    pub fn contains(&self, c: ConceptId) -> bool {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match u32_at(self.0, mid * 4).cmp(&c) {
+                core::cmp::Ordering::Less => lo = mid + 1,
+                core::cmp::Ordering::Greater => hi = mid,
+                core::cmp::Ordering::Equal => return true,
+            }
+        }
+        false
+    }
+}
+
+impl Iterator for Ids<'_> {
+    type Item = ConceptId;
+
+    fn next(&mut self) -> Option<ConceptId> {
+        let (first, rest) = self.0.split_first_chunk::<4>()?;
+        self.0 = rest;
+        Some(u32::from_le_bytes(*first))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl ExactSizeIterator for Ids<'_> {}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(bytes[pos..pos + 4].try_into().expect("four bytes"))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,51 @@
+//! Parquet writers for tabular results (the `arrow` feature).
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+
+const SIMILARITY_SCHEMA: &str = "message similarity {
+    required binary term_a (UTF8);
+    required binary term_b (UTF8);
+    required double score;
+}";
+
+/// Writes a row-major `names × names` score matrix in long form, one
+/// `(term_a, term_b, score)` row per cell, as a single row group.
+pub fn write_similarity_parquet(path: &Path, names: &[&str], scores: &[f64]) -> io::Result<()> {
+    let schema = Arc::new(parse_message_type(SIMILARITY_SCHEMA).map_err(io::Error::other)?);
+    let props = Arc::new(WriterProperties::builder().build());
+    let mut writer =
+        SerializedFileWriter::new(File::create(path)?, schema, props).map_err(io::Error::other)?;
+
+    let n = names.len();
+    let column = |pick: fn(usize, usize) -> usize| -> Vec<ByteArray> {
+        (0..n * n).map(|i| ByteArray::from(names[pick(i, n)])).collect()
+    };
+    let term_a = column(|i, n| i / n);
+    let term_b = column(|i, n| i % n);
+
+    let mut group = writer.next_row_group().map_err(io::Error::other)?;
+    for index in 0..3 {
+        let mut col = group
+            .next_column()
+            .map_err(io::Error::other)?
+            .ok_or_else(|| io::Error::other("similarity schema has three columns"))?;
+        let written = match index {
+            0 => col.typed::<ByteArrayType>().write_batch(&term_a, None, None),
+            1 => col.typed::<ByteArrayType>().write_batch(&term_b, None, None),
+            _ => col.typed::<DoubleType>().write_batch(scores, None, None),
+        };
+        written.map_err(io::Error::other)?;
+        col.close().map_err(io::Error::other)?;
+    }
+    group.close().map_err(io::Error::other)?;
+    writer.close().map_err(io::Error::other)?;
+    Ok(())
+}
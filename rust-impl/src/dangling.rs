@@ -0,0 +1,114 @@
+//! Dangling references: classes that axioms point at but no `[Term]`
+//! stanza or OWL declaration introduces, in the document or its imports.
+//! They are interned like any other class and reasoned over as empty
+//! leaves, which usually means an import is missing; grouping them by ID
+//! prefix says which.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::changes::TermRef;
+use crate::obo::ParseResult;
+use crate::BOTTOM;
+
+/// An undeclared class and the terms whose axioms refer to it.
+#[derive(Clone, Debug)]
+pub struct Dangling {
+    pub term: TermRef,
+    pub referrers: Vec<TermRef>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DanglingReport {
+    /// In ID order.
+    pub terms: Vec<Dangling>,
+    /// Undeclared classes per ID prefix, most first.
+    pub by_prefix: Vec<(String, usize)>,
+}
+
+impl DanglingReport {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+/// The prefix of a CURIE, or an IRI up to its last `/` or `#`.
+fn id_prefix(id: &str) -> &str {
+    if id.contains("://") {
+        return id.rfind(['/', '#']).map_or(id, |i| &id[..=i]);
+    }
+    id.split_once(':').map_or("", |(prefix, _)| prefix)
+}
+
+/// Classes of `parsed` that nothing declares, nominals aside.
+pub fn undeclared(parsed: &ParseResult) -> Vec<usize> {
+    let nominals: Vec<usize> = parsed.nominals.iter().map(|&(c, _)| c).collect();
+    (BOTTOM as usize + 1..parsed.concepts.len())
+        .filter(|c| !parsed.declared.contains(c) && !nominals.contains(c))
+        .collect()
+}
+
+pub fn dangling_references(parsed: &ParseResult) -> DanglingReport {
+    let undeclared = undeclared(parsed);
+    let mut referrers: BTreeMap<usize, Vec<usize>> =
+        undeclared.iter().map(|&c| (c, Vec::new())).collect();
+    let mut refer = |from: usize, to: usize| {
+        if let Some(list) = referrers.get_mut(&to) {
+            list.push(from);
+        }
+    };
+    for &(a, b) in &parsed.subsumptions {
+        refer(a, b);
+    }
+    for &(a, _, b) in &parsed.relations {
+        refer(a, b);
+    }
+    for &(a, b) in &parsed.disjoint_pairs {
+        refer(a, b);
+        refer(b, a);
+    }
+    for definition in &parsed.definitions {
+        let fillers = definition.differentia.iter().map(|&(_, f)| f);
+        for c in definition.genus.iter().copied().chain(fillers) {
+            refer(definition.term, c);
+        }
+    }
+
+    let term = |c: usize| TermRef { id: parsed.concepts[c].clone(), label: parsed.labels.get(&c).cloned() };
+    let mut by_prefix: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut terms: Vec<Dangling> = referrers
+        .into_iter()
+        .map(|(c, mut from)| {
+            *by_prefix.entry(id_prefix(&parsed.concepts[c])).or_default() += 1;
+            from.sort_unstable();
+            from.dedup();
+            Dangling { term: term(c), referrers: from.into_iter().map(term).collect() }
+        })
+        .collect();
+    terms.sort_by(|a, b| a.term.id.cmp(&b.term.id));
+    let mut by_prefix: Vec<(String, usize)> =
+        by_prefix.into_iter().map(|(prefix, n)| (prefix.to_string(), n)).collect();
+    by_prefix.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    DanglingReport { terms, by_prefix }
+}
+
+/// Markdown, in the style of the `changes` report.
+impl fmt::Display for DanglingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "## Undeclared classes by prefix ({})\n", self.by_prefix.len())?;
+        for (prefix, n) in &self.by_prefix {
+            let prefix = if prefix.is_empty() { "(none)" } else { prefix };
+            writeln!(f, "- {}: {}", prefix, n)?;
+        }
+        writeln!(f, "\n## Undeclared classes ({})\n", self.terms.len())?;
+        for d in &self.terms {
+            let referrers: Vec<String> = d.referrers.iter().map(ToString::to_string).collect();
+            if referrers.is_empty() {
+                writeln!(f, "- {}", d.term)?;
+            } else {
+                writeln!(f, "- {}, used by {}", d.term, referrers.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Derivation logs (`--derivation-log`): every rule application of a
+//! saturation run, one JSON object per line, so a missing or spurious
+//! inference can be followed back to the rule that made it; and a summary
+//! of how often each rule fired (`el-reasoner derivations <log>`).
+//!
+//! A line reads
+//! `{"rule":"CR1","premise":{"sub":"A","sup":"B"},"derived":{"sub":"A","sup":"C"}}`;
+//! links are `{"source":…,"role":…,"target":…}` and `init` lines have a
+//! null premise. Concepts are named as in the normalized dump, with fresh
+//! normalization concepts as `_:N`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::json::{self, Value};
+use crate::kernel::{ConceptId, Derivation, Rule, TraceEvent};
+
+pub fn write_derivations<W: Write>(
+    out: &mut W,
+    log: &[Derivation],
+    concepts: &[String],
+    roles: &[String],
+) -> io::Result<()> {
+    let name = |c: ConceptId| match concepts.get(c as usize) {
+        Some(n) if !n.is_empty() => n.clone(),
+        _ => format!("_:{}", c),
+    };
+    let fact = |event: TraceEvent| match event {
+        TraceEvent::Concept { concept, added } => Value::Object(vec![
+            ("sub".to_string(), Value::from(name(concept))),
+            ("sup".to_string(), Value::from(name(added))),
+        ]),
+        TraceEvent::Link { source, role, target } => Value::Object(vec![
+            ("source".to_string(), Value::from(name(source))),
+            ("role".to_string(), Value::from(roles[role as usize].as_str())),
+            ("target".to_string(), Value::from(name(target))),
+        ]),
+    };
+    for derivation in log {
+        let line = Value::Object(vec![
+            ("rule".to_string(), Value::from(derivation.rule.name())),
+            ("premise".to_string(), derivation.premise.map_or(Value::Null, fact)),
+            ("derived".to_string(), fact(derivation.derived)),
+        ]);
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()
+}
+
+/// Rule firing counts of a derivation log.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DerivationSummary {
+    pub subsumptions: usize,
+    pub links: usize,
+    /// Firings per rule, in rule order; rules that never fired are left out.
+    pub by_rule: Vec<(Rule, usize)>,
+    /// The contexts (subclass or link source) with the most derivations,
+    /// busiest first.
+    pub busiest: Vec<(String, usize)>,
+}
+
+impl DerivationSummary {
+    pub fn total(&self) -> usize {
+        self.subsumptions + self.links
+    }
+}
+
+/// Reads a log written by [`write_derivations`], keeping the `top` busiest
+/// contexts.
+pub fn summarize<R: BufRead>(input: R, top: usize) -> io::Result<DerivationSummary> {
+    let mut summary = DerivationSummary::default();
+    let mut by_rule: HashMap<Rule, usize> = HashMap::new();
+    let mut by_context: HashMap<String, usize> = HashMap::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |message: &str| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, message))
+        };
+        let value = json::parse(&line).map_err(|e| invalid(&e.to_string()))?;
+        let rule = value.get("rule").and_then(Value::as_str).ok_or_else(|| invalid("no rule"))?;
+        let rule = Rule::from_name(rule).ok_or_else(|| invalid(&format!("unknown rule '{}'", rule)))?;
+        let derived = value.get("derived").ok_or_else(|| invalid("no derived fact"))?;
+        let context = match (derived.get("sub"), derived.get("source")) {
+            (Some(sub), _) => {
+                summary.subsumptions += 1;
+                sub
+            }
+            (None, Some(source)) => {
+                summary.links += 1;
+                source
+            }
+            (None, None) => return Err(invalid("derived fact is neither a subsumption nor a link")),
+        };
+        let context = context.as_str().ok_or_else(|| invalid("concept is not a string"))?;
+        *by_rule.entry(rule).or_default() += 1;
+        *by_context.entry(context.to_string()).or_default() += 1;
+    }
+    summary.by_rule = Rule::ALL.iter().filter_map(|r| Some((*r, *by_rule.get(r)?))).collect();
+    let mut busiest: Vec<(String, usize)> = by_context.into_iter().collect();
+    busiest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    busiest.truncate(top);
+    summary.busiest = busiest;
+    Ok(summary)
+}
+
+impl fmt::Display for DerivationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} derivations ({} subsumptions, {} links)", self.total(), self.subsumptions, self.links)?;
+        writeln!(f, "rule\tcount\tshare")?;
+        let total = self.total().max(1) as f64;
+        for (rule, count) in &self.by_rule {
+            writeln!(f, "{}\t{}\t{:.1}%", rule.name(), count, *count as f64 * 100.0 / total)?;
+        }
+        if !self.busiest.is_empty() {
+            writeln!(f, "busiest contexts:")?;
+            for (context, count) in &self.busiest {
+                writeln!(f, "{}\t{}", context, count)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+//! The error type of the library's fallible entry points.
+
+use std::{fmt, io};
+
+use crate::input::InputFormat;
+use crate::obo::{ParseError, Warning};
+
+#[derive(Debug)]
+pub enum ReasonerError {
+    /// Opening, reading or writing a file.
+    Io(io::Error),
+    /// The reader failed partway through a document.
+    Parse(ParseError),
+    /// Warnings found in a document read in strict mode, in document order.
+    Rejected(Vec<Warning>),
+    /// A concept name or ID the ontology doesn't have.
+    UnknownTerm(String),
+    /// A format without a front-end in this build.
+    UnsupportedFormat(InputFormat),
+}
+
+impl fmt::Display for ReasonerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReasonerError::Io(e) => write!(f, "{}", e),
+            ReasonerError::Parse(e) => write!(f, "{}", e),
+            ReasonerError::Rejected(warnings) => match warnings.first() {
+                Some(first) if warnings.len() > 1 => {
+                    write!(f, "{} (and {} more warnings)", first, warnings.len() - 1)
+                }
+                Some(first) => write!(f, "{}", first),
+                None => f.write_str("rejected"),
+            },
+            ReasonerError::UnknownTerm(term) => write!(f, "unknown term '{}'", term),
+            ReasonerError::UnsupportedFormat(format) => {
+                write!(f, "{} input is not supported in this build", format.name())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReasonerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReasonerError::Io(e) => Some(e),
+            ReasonerError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReasonerError {
+    fn from(e: io::Error) -> Self {
+        ReasonerError::Io(e)
+    }
+}
+
+impl From<ParseError> for ReasonerError {
+    fn from(e: ParseError) -> Self {
+        ReasonerError::Parse(e)
+    }
+}
@@ -0,0 +1,496 @@
+//! Justifications: minimal sets of told axioms that entail a subsumption.
+//!
+//! Nothing is recorded during saturation. The axioms that can matter for
+//! `sub ⊑ sup` are the ⊥-locality module of `sub` (see [`crate::modules`]);
+//! a justification is what is left after dropping module axioms while
+//! the subsumption still follows, and further justifications come from
+//! Reiter's hitting-set tree: each branch removes one axiom of a
+//! justification found so far and looks for one without it.
+//!
+//! An unsatisfiable class is explained by the justifications of `C ⊑ ⊥`;
+//! removing one axiom of each, a hitting set of them, repairs it.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::changes::TermRef;
+use crate::kernel::{AxiomStore, ConceptId, Context, RoleId, BOTTOM, TOP};
+use crate::module::is_subsumed_by;
+use crate::normalize::{ClassAxiom, ClassExpr, Normalizer};
+use crate::obo::ParseResult;
+
+pub use crate::modules::AxiomId;
+use crate::modules::{Locality, ModuleExtractor};
+
+/// Finds justifications over the told axioms of one parse result.
+pub struct Explainer<'p> {
+    parsed: &'p ParseResult,
+    modules: ModuleExtractor<'p>,
+    limit: usize,
+}
+
+impl<'p> Explainer<'p> {
+    pub fn new(parsed: &'p ParseResult) -> Self {
+        Self { parsed, modules: ModuleExtractor::new(parsed), limit: usize::MAX }
+    }
+
+    /// Stops [`explain`](Self::explain) after `limit` justifications.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// The told axioms of the ⊥-locality module of `seed`, in table order.
+    pub fn module(&self, seed: ConceptId) -> Vec<AxiomId> {
+        self.modules.extract(Locality::Bottom, &[seed], &[])
+    }
+
+    /// Dense concept IDs for the signature of `module`, TOP and BOTTOM
+    /// keeping theirs, so entailment checks only allocate for the module.
+    fn dense_ids(&self, module: &[AxiomId], seed: ConceptId) -> HashMap<usize, ConceptId> {
+        let mut ids: HashMap<usize, ConceptId> = HashMap::new();
+        let concepts = module.iter().flat_map(|&a| self.modules.signature(a));
+        for c in [TOP as usize, BOTTOM as usize, seed as usize]
+            .into_iter()
+            .chain(concepts)
+        {
+            if c < self.parsed.concepts.len() {
+                let next = ids.len() as ConceptId;
+                ids.entry(c).or_insert(next);
+            }
+        }
+        ids
+    }
+
+    /// Whether `axioms` alone entail `sub ⊑ sup`, between dense IDs.
+    fn entails(
+        &self,
+        axioms: &[AxiomId],
+        ids: &HashMap<usize, ConceptId>,
+        sub: ConceptId,
+        sup: ConceptId,
+    ) -> bool {
+        let p = self.parsed;
+        let id = |c: usize| ids[&c];
+        let mut store = AxiomStore::new(ids.len(), p.roles.len());
+        let mut normalizer = Normalizer::new(&mut store, ids.len());
+        let mut dense = |c: ConceptId| ids.get(&(c as usize)).copied();
+        for &axiom in axioms {
+            match axiom {
+                AxiomId::IsA(i) => {
+                    let (a, b) = p.subsumptions[i];
+                    normalizer.add_subsumption(id(a), &ClassExpr::Named(id(b)));
+                }
+                AxiomId::Relationship(i) => {
+                    let (a, r, b) = p.relations[i];
+                    let some = ClassExpr::Some(r as RoleId, Box::new(ClassExpr::Named(id(b))));
+                    normalizer.add_subsumption(id(a), &some);
+                }
+                AxiomId::Disjoint(i) => {
+                    let (a, b) = p.disjoint_pairs[i];
+                    let both =
+                        ClassExpr::And(vec![ClassExpr::Named(id(a)), ClassExpr::Named(id(b))]);
+                    normalizer.add_inclusion(&both, &ClassExpr::Named(BOTTOM));
+                }
+                AxiomId::Definition(i) => {
+                    let definition = &p.definitions[i];
+                    let expr = definition
+                        .expression()
+                        .try_map(&mut dense)
+                        .expect("module signature");
+                    normalizer.add_equivalence(id(definition.term), &expr);
+                }
+                AxiomId::ClassAxiom(i) => {
+                    let axiom = p.class_axioms[i]
+                        .try_map(&mut dense)
+                        .expect("module signature");
+                    normalizer.add_axiom(&axiom);
+                }
+                AxiomId::Domain(i) => {
+                    let (r, domain) = p.role_domains[i];
+                    let some = ClassExpr::Some(r as RoleId, Box::new(ClassExpr::Named(TOP)));
+                    normalizer.add_inclusion(&some, &ClassExpr::Named(id(domain)));
+                }
+                AxiomId::SubRole(_)
+                | AxiomId::RoleChain(_)
+                | AxiomId::Reflexive(_)
+                | AxiomId::Range(_) => {}
+            }
+        }
+        for &axiom in axioms {
+            match axiom {
+                AxiomId::SubRole(i) => {
+                    let (r, s) = p.role_subsumptions[i];
+                    store.add_role_subsumption(r as RoleId, s as RoleId);
+                }
+                AxiomId::RoleChain(i) => {
+                    let (r, s, t) = p.role_chains[i];
+                    store.add_role_chain(r as RoleId, s as RoleId, t as RoleId);
+                }
+                AxiomId::Reflexive(i) => store.add_reflexive(p.reflexive_roles[i] as RoleId),
+                _ => {}
+            }
+        }
+        for &(c, _) in &p.nominals {
+            if let Some(&c) = ids.get(&c) {
+                store.add_nominal(c);
+            }
+        }
+        // Ranges last, once every existential and role inclusion is in.
+        let ranges: Vec<(RoleId, ConceptId)> = axioms
+            .iter()
+            .filter_map(|&axiom| match axiom {
+                AxiomId::Range(i) => {
+                    let (r, range) = p.role_ranges[i];
+                    Some((r as RoleId, id(range)))
+                }
+                _ => None,
+            })
+            .collect();
+        let num_concepts = store.num_concepts();
+        Normalizer::new(&mut store, num_concepts).add_ranges(&ranges);
+        is_subsumed_by(&store, sub, sup)
+    }
+
+    /// A minimal subset of `axioms`, which must entail `sub ⊑ sup`, that
+    /// still does. Halves, quarters, … are dropped before single axioms, so
+    /// large irrelevant parts go in a few steps.
+    fn shrink(
+        &self,
+        axioms: &[AxiomId],
+        ids: &HashMap<usize, ConceptId>,
+        sub: ConceptId,
+        sup: ConceptId,
+    ) -> Vec<AxiomId> {
+        let mut kept = axioms.to_vec();
+        let mut chunk = kept.len().div_ceil(2);
+        while chunk > 0 {
+            let mut i = 0;
+            while i < kept.len() {
+                let end = (i + chunk).min(kept.len());
+                let dropped: Vec<AxiomId> = kept.drain(i..end).collect();
+                if !self.entails(&kept, ids, sub, sup) {
+                    kept.splice(i..i, dropped);
+                    i = end;
+                }
+            }
+            chunk /= 2;
+        }
+        kept
+    }
+
+    /// Justifications of `sub ⊑ sup`, each sorted; none when it doesn't
+    /// follow, and a single empty one when it holds trivially.
+    pub fn explain(&self, sub: ConceptId, sup: ConceptId) -> Vec<Vec<AxiomId>> {
+        if sub == sup || sup == TOP {
+            return vec![Vec::new()];
+        }
+        let module = self.module(sub);
+        let ids = self.dense_ids(&module, sub);
+        // A concept outside the module is entailed only by unsatisfiability.
+        let (sub, sup) = (
+            ids[&(sub as usize)],
+            ids.get(&(sup as usize)).copied().unwrap_or(BOTTOM),
+        );
+        if !self.entails(&module, &ids, sub, sup) {
+            return Vec::new();
+        }
+
+        let mut found: Vec<Vec<AxiomId>> = Vec::new();
+        let mut seen: HashSet<Vec<AxiomId>> = HashSet::new();
+        let mut queue: VecDeque<Vec<AxiomId>> = VecDeque::from([Vec::new()]);
+        while let Some(path) = queue.pop_front() {
+            if found.len() >= self.limit {
+                break;
+            }
+            let reused = found
+                .iter()
+                .find(|j| j.iter().all(|a| path.binary_search(a).is_err()));
+            let justification = match reused {
+                Some(j) => j.clone(),
+                None => {
+                    let rest: Vec<AxiomId> = module
+                        .iter()
+                        .copied()
+                        .filter(|a| path.binary_search(a).is_err())
+                        .collect();
+                    if !self.entails(&rest, &ids, sub, sup) {
+                        continue;
+                    }
+                    let j = self.shrink(&rest, &ids, sub, sup);
+                    found.push(j.clone());
+                    j
+                }
+            };
+            for a in justification {
+                let mut branch = path.clone();
+                let at = branch.binary_search(&a).unwrap_err();
+                branch.insert(at, a);
+                if seen.insert(branch.clone()) {
+                    queue.push_back(branch);
+                }
+            }
+        }
+        found
+    }
+
+    /// Justifications of `concept ⊑ ⊥` and the repairs they allow.
+    pub fn explain_unsat(&self, concept: ConceptId) -> UnsatExplanation {
+        let justifications = self.explain(concept, BOTTOM);
+        let repairs = hitting_sets(&justifications);
+        UnsatExplanation { concept, justifications, repairs }
+    }
+
+    fn name(&self, c: usize) -> &str {
+        &self.parsed.concepts[c]
+    }
+
+    fn expression(&self, expr: &ClassExpr) -> String {
+        match expr {
+            ClassExpr::Named(c) => self.name(*c as usize).to_string(),
+            ClassExpr::And(parts) => {
+                let parts: Vec<String> = parts.iter().map(|p| self.expression(p)).collect();
+                format!("({})", parts.join(" and "))
+            }
+            ClassExpr::Some(r, filler) => {
+                format!(
+                    "({} some {})",
+                    self.parsed.roles[*r as usize],
+                    self.expression(filler)
+                )
+            }
+        }
+    }
+
+    /// The axiom in OBO-flavored Manchester syntax.
+    pub fn describe(&self, id: AxiomId) -> String {
+        let p = self.parsed;
+        match id {
+            AxiomId::IsA(i) => {
+                let (a, b) = p.subsumptions[i];
+                format!("{} is_a {}", self.name(a), self.name(b))
+            }
+            AxiomId::Relationship(i) => {
+                let (a, r, b) = p.relations[i];
+                format!("{} {} some {}", self.name(a), p.roles[r], self.name(b))
+            }
+            AxiomId::Disjoint(i) => {
+                let (a, b) = p.disjoint_pairs[i];
+                format!("{} disjoint_from {}", self.name(a), self.name(b))
+            }
+            AxiomId::Definition(i) => {
+                let definition = &p.definitions[i];
+                let expr = self.expression(&definition.expression());
+                format!("{} equivalent_to {}", self.name(definition.term), expr)
+            }
+            AxiomId::ClassAxiom(i) => match &p.class_axioms[i] {
+                ClassAxiom::SubClassOf(a, b) => {
+                    format!("{} SubClassOf {}", self.expression(a), self.expression(b))
+                }
+                ClassAxiom::Equivalent(a, b) => {
+                    format!("{} EquivalentTo {}", self.expression(a), self.expression(b))
+                }
+            },
+            AxiomId::SubRole(i) => {
+                let (r, s) = p.role_subsumptions[i];
+                format!("{} is_a {}", p.roles[r], p.roles[s])
+            }
+            AxiomId::RoleChain(i) => {
+                let (r, s, t) = p.role_chains[i];
+                format!(
+                    "{} o {} SubPropertyOf {}",
+                    p.roles[r], p.roles[s], p.roles[t]
+                )
+            }
+            AxiomId::Reflexive(i) => format!("{} is_reflexive", p.roles[p.reflexive_roles[i]]),
+            AxiomId::Domain(i) => {
+                let (r, domain) = p.role_domains[i];
+                format!("{} domain {}", p.roles[r], self.name(domain))
+            }
+            AxiomId::Range(i) => {
+                let (r, range) = p.role_ranges[i];
+                format!("{} range {}", p.roles[r], self.name(range))
+            }
+        }
+    }
+}
+
+/// Justifications of `sub ⊑ sup` over the told axioms of `parsed`.
+pub fn explain(parsed: &ParseResult, sub: ConceptId, sup: ConceptId) -> Vec<Vec<AxiomId>> {
+    Explainer::new(parsed).explain(sub, sup)
+}
+
+/// Why a class is unsatisfiable.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnsatExplanation {
+    pub concept: ConceptId,
+    /// Justifications of `concept ⊑ ⊥`; none when it is satisfiable.
+    pub justifications: Vec<Vec<AxiomId>>,
+    /// Minimal sets of axioms whose removal makes `concept` satisfiable,
+    /// smallest first. Only complete when every justification was found.
+    pub repairs: Vec<Vec<AxiomId>>,
+}
+
+/// [`Explainer::explain_unsat`] over the told axioms of `parsed`.
+pub fn explain_unsat(parsed: &ParseResult, concept: ConceptId) -> UnsatExplanation {
+    Explainer::new(parsed).explain_unsat(concept)
+}
+
+/// The minimal hitting sets of `sets`, each sorted, smallest first.
+fn hitting_sets(sets: &[Vec<AxiomId>]) -> Vec<Vec<AxiomId>> {
+    if sets.is_empty() {
+        return Vec::new();
+    }
+    let mut hits: Vec<Vec<AxiomId>> = vec![Vec::new()];
+    for set in sets {
+        let mut grown: Vec<Vec<AxiomId>> = Vec::new();
+        for hit in hits {
+            if set.iter().any(|a| hit.binary_search(a).is_ok()) {
+                grown.push(hit);
+                continue;
+            }
+            for &a in set {
+                let mut more = hit.clone();
+                more.insert(more.binary_search(&a).unwrap_err(), a);
+                grown.push(more);
+            }
+        }
+        grown.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        grown.dedup();
+        hits = Vec::new();
+        for hit in grown {
+            if !hits.iter().any(|h| h.iter().all(|a| hit.binary_search(a).is_ok())) {
+                hits.push(hit);
+            }
+        }
+    }
+    hits
+}
+
+/// An unsatisfiable class, its justifications and repairs, with axioms
+/// described.
+#[derive(Clone, Debug)]
+pub struct UnsatClass {
+    pub term: TermRef,
+    pub justifications: Vec<Vec<String>>,
+    pub repairs: Vec<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UnsatReport {
+    /// Classes unsatisfiable in their own right, explained.
+    pub roots: Vec<UnsatClass>,
+    /// Classes unsatisfiable because a root is among their superclasses or
+    /// link targets, with those roots; repairing the roots repairs them.
+    pub derived: Vec<(TermRef, Vec<TermRef>)>,
+    /// Axioms whose removal alone repairs roots, with how many, most first.
+    pub removals: Vec<(String, usize)>,
+}
+
+impl UnsatReport {
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty() && self.derived.is_empty()
+    }
+}
+
+/// Explains the `unsatisfiable` classes of the saturated `contexts`:
+/// those below another unsatisfiable class are only traced to it.
+pub fn unsat_report(explainer: &Explainer, contexts: &[Context], unsatisfiable: &[ConceptId]) -> UnsatReport {
+    let parsed = explainer.parsed;
+    let term = |c: ConceptId| TermRef {
+        id: parsed.concepts[c as usize].clone(),
+        label: parsed.labels.get(&(c as usize)).cloned(),
+    };
+    let is_unsat: HashSet<ConceptId> = unsatisfiable.iter().copied().collect();
+    // Unsatisfiable classes `c` gets ⊥ from, equivalent ones aside.
+    let above = |c: ConceptId| -> Vec<ConceptId> {
+        let ctx = &contexts[c as usize];
+        let linked = ctx.link_map.iter().flatten();
+        let mut above: Vec<ConceptId> = ctx
+            .super_set
+            .iter()
+            .chain(linked)
+            .copied()
+            .filter(|&d| d != c && is_unsat.contains(&d) && !contexts[d as usize].super_set.contains(&c))
+            .collect();
+        above.sort_unstable();
+        above.dedup();
+        above
+    };
+    let (roots, derived): (Vec<ConceptId>, Vec<ConceptId>) =
+        unsatisfiable.iter().copied().partition(|&c| above(c).is_empty());
+    let is_root: HashSet<ConceptId> = roots.iter().copied().collect();
+
+    let mut report = UnsatReport::default();
+    let mut removals: HashMap<AxiomId, usize> = HashMap::new();
+    for &c in &roots {
+        let explanation = explainer.explain_unsat(c);
+        for repair in explanation.repairs.iter().filter(|r| r.len() == 1) {
+            *removals.entry(repair[0]).or_default() += 1;
+        }
+        let describe = |axioms: &Vec<AxiomId>| axioms.iter().map(|&a| explainer.describe(a)).collect();
+        report.roots.push(UnsatClass {
+            term: term(c),
+            justifications: explanation.justifications.iter().map(describe).collect(),
+            repairs: explanation.repairs.iter().map(describe).collect(),
+        });
+    }
+    for &c in &derived {
+        // Follow unsatisfiable classes upwards until roots are reached.
+        let mut found: Vec<ConceptId> = Vec::new();
+        let mut seen: HashSet<ConceptId> = HashSet::from([c]);
+        let mut stack = above(c);
+        while let Some(d) = stack.pop() {
+            if !seen.insert(d) {
+                continue;
+            }
+            match is_root.contains(&d) {
+                true => found.push(d),
+                false => stack.extend(above(d)),
+            }
+        }
+        found.sort_unstable();
+        report.derived.push((term(c), found.into_iter().map(term).collect()));
+    }
+    let mut removals: Vec<(AxiomId, usize)> = removals.into_iter().collect();
+    removals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    report.removals = removals.into_iter().map(|(a, n)| (explainer.describe(a), n)).collect();
+    report
+}
+
+/// Markdown, in the style of the `changes` report.
+impl fmt::Display for UnsatReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "## Unsatisfiable classes ({})
+", self.roots.len())?;
+        for class in &self.roots {
+            writeln!(f, "### {}
+", class.term)?;
+            for (i, justification) in class.justifications.iter().enumerate() {
+                writeln!(f, "Justification {}:", i + 1)?;
+                for axiom in justification {
+                    writeln!(f, "- {}", axiom)?;
+                }
+            }
+            writeln!(f, "
+Repairs (remove every axiom of one line):")?;
+            for repair in &class.repairs {
+                writeln!(f, "- {}", repair.join("; "))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "## Unsatisfiable through another class ({})
+", self.derived.len())?;
+        for (term, roots) in &self.derived {
+            let roots: Vec<String> = roots.iter().map(ToString::to_string).collect();
+            writeln!(f, "- {}, from {}", term, roots.join(", "))?;
+        }
+        writeln!(f, "
+## Suggested removals ({})
+", self.removals.len())?;
+        for (axiom, n) in &self.removals {
+            writeln!(f, "- {}: repairs {} {}", axiom, n, if *n == 1 { "class" } else { "classes" })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+//! Exports of the inferred hierarchy for graph and machine-learning tools,
+//! and of the normalized axioms it is computed from.
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use crate::kernel::{AxiomStore, ConceptId, Context, RoleId, BOTTOM, TOP};
+
+/// Writes every named concept as a sparse binary vector over its inferred
+/// ancestors (itself included, TOP left out) in libsvm/svmlight format:
+///
+/// ```text
+/// 0 1:1 4:1 9:1 # GO:0000004
+/// ```
+///
+/// Rows and feature columns share one order, so feature `j` is the concept
+/// on row `j` (1-based), named in that row's trailing comment. Fresh
+/// normalization concepts and concepts without a name are skipped.
+pub fn write_ancestor_libsvm<W: Write>(
+    out: &mut W,
+    contexts: &[Context],
+    names: &[String],
+) -> io::Result<usize> {
+    let rows: Vec<ConceptId> = (2..names.len() as ConceptId)
+        .filter(|&c| !names[c as usize].is_empty())
+        .collect();
+    let mut column = vec![0u32; names.len()];
+    for (i, &c) in rows.iter().enumerate() {
+        column[c as usize] = i as u32 + 1;
+    }
+
+    let mut features: Vec<u32> = Vec::new();
+    for &c in &rows {
+        features.clear();
+        features.extend(
+            contexts[c as usize]
+                .super_set
+                .iter()
+                .filter(|&&a| a != TOP && a != BOTTOM && (a as usize) < names.len())
+                .map(|&a| column[a as usize])
+                .filter(|&j| j != 0),
+        );
+        features.sort_unstable();
+        write!(out, "0")?;
+        for j in &features {
+            write!(out, " {}:1", j)?;
+        }
+        writeln!(out, " # {}", names[c as usize])?;
+    }
+    out.flush()?;
+    Ok(rows.len())
+}
+
+/// What an edge-list export reads: the told axioms as parsed and the
+/// classification derived from them.
+pub struct EdgeListView<'a> {
+    pub concepts: &'a [String],
+    pub roles: &'a [String],
+    /// Told `(sub, sup)` pairs.
+    pub told_subsumptions: &'a [(usize, usize)],
+    /// Told `(source, role, target)` links.
+    pub told_relations: &'a [(usize, usize, usize)],
+    pub direct_parents: &'a [Vec<ConceptId>],
+    pub contexts: &'a [Context],
+}
+
+/// Writes a `source<TAB>relation<TAB>target<TAB>inferred` edge list, with
+/// `is_a` as the relation of subsumption edges and role IDs for the rest.
+///
+/// With `include_inferred`, `is_a` edges are the reduced inferred hierarchy
+/// and role edges include the links saturation added (e.g. inherited from a
+/// superclass); `inferred` marks edges that weren't told. Without it, only
+/// told edges are written. Edges to TOP and to unnamed concepts are skipped.
+pub fn write_edge_list<W: Write>(
+    out: &mut W,
+    view: &EdgeListView,
+    include_inferred: bool,
+) -> io::Result<usize> {
+    let n = view.concepts.len();
+    let named = |c: usize| c > 1 && c < n && !view.concepts[c].is_empty();
+    let told_is_a: HashSet<(usize, usize)> = view.told_subsumptions.iter().copied().collect();
+    let told_links: HashSet<(usize, usize, usize)> = view.told_relations.iter().copied().collect();
+
+    let mut is_a: Vec<(usize, usize, bool)> = if include_inferred {
+        (2..view.direct_parents.len())
+            .flat_map(|c| view.direct_parents[c].iter().map(move |&p| (c, p as usize)))
+            .map(|(c, p)| (c, p, !told_is_a.contains(&(c, p))))
+            .collect()
+    } else {
+        told_is_a.iter().map(|&(c, p)| (c, p, false)).collect()
+    };
+    is_a.retain(|&(c, p, _)| named(c) && named(p));
+    is_a.sort_unstable();
+
+    let mut links: Vec<(usize, usize, usize, bool)> =
+        told_links.iter().map(|&(c, r, t)| (c, r, t, false)).collect();
+    if include_inferred {
+        for (c, ctx) in view.contexts.iter().enumerate().take(n) {
+            for (r, targets) in ctx.link_map.iter().enumerate() {
+                for &t in targets {
+                    if !told_links.contains(&(c, r, t as usize)) {
+                        links.push((c, r, t as usize, true));
+                    }
+                }
+            }
+        }
+    }
+    links.retain(|&(c, _, t, _)| named(c) && named(t));
+    links.sort_unstable();
+
+    writeln!(out, "source\trelation\ttarget\tinferred")?;
+    for &(c, p, inferred) in &is_a {
+        writeln!(out, "{}\tis_a\t{}\t{}", view.concepts[c], view.concepts[p], inferred)?;
+    }
+    for &(c, r, t, inferred) in &links {
+        writeln!(out, "{}\t{}\t{}\t{}", view.concepts[c], view.roles[r], view.concepts[t], inferred)?;
+    }
+    out.flush()?;
+    Ok(is_a.len() + links.len())
+}
+
+/// Every `(c, d)` of named concepts with `c ⊑ ∃role.d` inferred, sorted.
+///
+/// Saturation has already applied sub-roles, chains and transitivity to
+/// the links, so `d` ranges over the superclasses of each link target:
+/// `nucleus ⊑ ∃part_of.cell` gives `(nucleus, cell)` and, with
+/// `cell ⊑ anatomical_entity`, `(nucleus, anatomical_entity)` as well.
+/// `contexts` must include the fresh normalization concepts, since links
+/// can end at one. Unsatisfiable sources, which are trivially related to
+/// everything, and TOP as a target are left out.
+pub fn relation_closure(
+    contexts: &[Context],
+    num_concepts: usize,
+    role: RoleId,
+) -> Vec<(ConceptId, ConceptId)> {
+    let mut pairs = Vec::new();
+    for (c, ctx) in contexts.iter().enumerate().take(num_concepts).skip(2) {
+        if ctx.super_set.contains(&BOTTOM) {
+            continue;
+        }
+        let Some(targets) = ctx.link_map.get(role as usize) else {
+            continue;
+        };
+        for &t in targets {
+            let named = |&&d: &&ConceptId| d > BOTTOM && (d as usize) < num_concepts;
+            pairs.extend(contexts[t as usize].super_set.iter().filter(named).map(|&d| (c as ConceptId, d)));
+        }
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+/// Writes [`relation_closure`] for each of `roles` as a
+/// `source<TAB>relation<TAB>target` table; unnamed concepts are skipped.
+pub fn write_relation_closure<W: Write>(
+    out: &mut W,
+    contexts: &[Context],
+    concepts: &[String],
+    roles: &[String],
+    selected: &[RoleId],
+) -> io::Result<usize> {
+    let mut written = 0;
+    writeln!(out, "source\trelation\ttarget")?;
+    for &r in selected {
+        for (c, d) in relation_closure(contexts, concepts.len(), r) {
+            let (c, d) = (&concepts[c as usize], &concepts[d as usize]);
+            if !c.is_empty() && !d.is_empty() {
+                writeln!(out, "{}\t{}\t{}", c, roles[r as usize], d)?;
+                written += 1;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+/// Writes the normalized axiom store, one tab-separated axiom per line:
+///
+/// ```text
+/// NF1  sub    sup              sub ⊑ sup
+/// NF2  left1  left2  sup       left1 ⊓ left2 ⊑ sup
+/// NF3  sub    role   fill      sub ⊑ ∃role.fill
+/// NF4  role   fill   sup       ∃role.fill ⊑ sup
+/// NF5  role   sup              role ⊑ sup
+/// NF6  role1  role2  sup       role1 ∘ role2 ⊑ sup
+/// NF7  role                    ⊤ ⊑ ∃role.Self
+/// ```
+///
+/// Concepts are written by name (`owl:Thing` and `owl:Nothing` for TOP and
+/// BOTTOM); fresh normalization concepts, which have none, as `_:N` with
+/// their internal id. Lines starting with `#` are comments. Axioms come in
+/// concept (NF1–NF3) or role (NF4–NF7) order, each group sorted, so the dump
+/// of an unchanged input is byte-identical across runs.
+pub fn write_normalized<W: Write>(
+    out: &mut W,
+    store: &AxiomStore,
+    concepts: &[String],
+    roles: &[String],
+) -> io::Result<usize> {
+    let name = |c: ConceptId| match concepts.get(c as usize) {
+        Some(n) if !n.is_empty() => n.clone(),
+        _ => format!("_:{}", c),
+    };
+    let mut written = 0;
+    writeln!(out, "# el-reasoner normalized axioms v1")?;
+
+    let mut sorted: Vec<ConceptId> = Vec::new();
+    for c in 0..store.num_concepts() as ConceptId {
+        sorted.clear();
+        sorted.extend(&store.sub_to_sups[c as usize]);
+        sorted.sort_unstable();
+        sorted.dedup();
+        for &d in &sorted {
+            writeln!(out, "NF1\t{}\t{}", name(c), name(d))?;
+        }
+        written += sorted.len();
+
+        // Stored under both conjuncts; written once, from the smaller one.
+        let mut conjunctions: Vec<(ConceptId, ConceptId)> = store.conj_index[c as usize]
+            .iter()
+            .filter(|(&other, _)| other >= c)
+            .flat_map(|(&other, sups)| sups.iter().map(move |&d| (other, d)))
+            .collect();
+        conjunctions.sort_unstable();
+        conjunctions.dedup();
+        for &(other, d) in &conjunctions {
+            writeln!(out, "NF2\t{}\t{}\t{}", name(c), name(other), name(d))?;
+        }
+        written += conjunctions.len();
+
+        let mut existentials: Vec<(usize, ConceptId)> = store.exist_right[c as usize]
+            .iter()
+            .map(|rf| (rf.role as usize, rf.fill))
+            .collect();
+        existentials.sort_unstable();
+        existentials.dedup();
+        for &(r, fill) in &existentials {
+            writeln!(out, "NF3\t{}\t{}\t{}", name(c), roles[r], name(fill))?;
+        }
+        written += existentials.len();
+    }
+
+    for (r, by_fill) in store.exist_left.iter().enumerate() {
+        let mut axioms: Vec<(ConceptId, ConceptId)> = by_fill
+            .iter()
+            .flat_map(|(&fill, sups)| sups.iter().map(move |&d| (fill, d)))
+            .collect();
+        axioms.sort_unstable();
+        axioms.dedup();
+        for &(fill, d) in &axioms {
+            writeln!(out, "NF4\t{}\t{}\t{}", roles[r], name(fill), name(d))?;
+        }
+        written += axioms.len();
+
+        let mut sups = store.role_sub_to_sups.get(r).cloned().unwrap_or_default();
+        sups.sort_unstable();
+        sups.dedup();
+        for &s in &sups {
+            writeln!(out, "NF5\t{}\t{}", roles[r], roles[s as usize])?;
+        }
+        written += sups.len();
+
+        let mut chains: Vec<_> = store.role_chains_from(r as RoleId).map(|(_, s, t)| (s, t)).collect();
+        chains.sort_unstable();
+        chains.dedup();
+        for &(second, sup) in &chains {
+            writeln!(out, "NF6\t{}\t{}\t{}", roles[r], roles[second as usize], roles[sup as usize])?;
+        }
+        written += chains.len();
+
+        if store.reflexive_roles.contains(&(r as RoleId)) {
+            writeln!(out, "NF7\t{}", roles[r])?;
+            written += 1;
+        }
+    }
+    out.flush()?;
+    Ok(written)
+}
@@ -0,0 +1,372 @@
+//! A C API over [`Ontology`] for embedding the reasoner in Java (JNI or
+//! Panama), C++ and ctypes tooling; `ffi/el_reasoner.h` declares it and the
+//! `el-reasoner-ffi` cdylib in `ffi/` exports it.
+//! Concepts and roles are interned by name into `uint32_t` IDs, axioms are
+//! added between IDs, and queries classify on first use (again after every
+//! added axiom). Strings handed out are freed with [`el_string_free`]; a
+//! failing call returns NULL and, when `error` isn't NULL, stores a message
+//! there.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use crate::normalize::ClassExpr;
+use crate::{is_consistent, ConceptId, Ontology, Taxonomy};
+
+/// An ontology and, once classified, its taxonomy.
+pub struct ElOntology {
+    ontology: Ontology,
+    classified: Option<(Taxonomy, bool)>,
+}
+
+impl ElOntology {
+    fn new(ontology: Ontology) -> *mut ElOntology {
+        Box::into_raw(Box::new(ElOntology { ontology, classified: None }))
+    }
+
+    /// The taxonomy and whether the ontology is consistent, classifying
+    /// first if an axiom came in since the last time.
+    fn classified(&mut self) -> (&Taxonomy, bool) {
+        let ontology = &self.ontology;
+        let (taxonomy, consistent) = self.classified.get_or_insert_with(|| {
+            let n = ontology.num_concepts();
+            let mut contexts = ontology.saturate();
+            contexts.truncate(n);
+            (Taxonomy::new(&contexts, n), is_consistent(&contexts))
+        });
+        (taxonomy, *consistent)
+    }
+
+    fn changed(&mut self) -> &mut Ontology {
+        self.classified = None;
+        &mut self.ontology
+    }
+}
+
+unsafe fn name<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+fn c_string(s: &str) -> *mut c_char {
+    // Names come from UTF-8 documents, which may hold NULs.
+    CString::new(s.replace('\0', " ")).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+unsafe fn report(error: *mut *mut c_char, message: &str) {
+    if !error.is_null() {
+        *error = c_string(message);
+    }
+}
+
+/// Copies up to `cap` of `ids` to `out` and returns how many there are.
+unsafe fn fill(ids: &[ConceptId], out: *mut u32, cap: usize) -> usize {
+    if !out.is_null() {
+        let n = ids.len().min(cap);
+        slice::from_raw_parts_mut(out, n).copy_from_slice(&ids[..n]);
+    }
+    ids.len()
+}
+
+/// An empty ontology; `owl:Thing` and `owl:Nothing` are concepts 0 and 1.
+#[no_mangle]
+pub extern "C" fn el_ontology_new() -> *mut ElOntology {
+    ElOntology::new(Ontology::new())
+}
+
+/// Reads the ontology at `path` and its imports; see [`Ontology::read`].
+/// Warnings are dropped.
+///
+/// # Safety
+/// `path` is a NUL-terminated string; `error` is NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn el_ontology_read(path: *const c_char, error: *mut *mut c_char) -> *mut ElOntology {
+    let Some(path) = name(path) else {
+        report(error, "path is not UTF-8");
+        return ptr::null_mut();
+    };
+    match Ontology::read(Path::new(path)) {
+        Ok((ontology, _)) => ElOntology::new(ontology),
+        Err(e) => {
+            report(error, &e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+/// `o` is NULL or comes from this API and isn't used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn el_ontology_free(o: *mut ElOntology) {
+    if !o.is_null() {
+        drop(Box::from_raw(o));
+    }
+}
+
+/// # Safety
+/// `s` is NULL or a string from this API, not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn el_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// The concept named `name`, interned on first use, or `u32::MAX`
+/// (`EL_NONE`) when `name` is NULL, empty or not UTF-8.
+///
+/// # Safety
+/// `o` comes from this API; `name` is a NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn el_concept(o: *mut ElOntology, name: *const c_char) -> u32 {
+    let Some(name) = self::name(name).filter(|name| !name.is_empty()) else {
+        return u32::MAX;
+    };
+    match (*o).ontology.find_concept(name) {
+        Some(c) => c,
+        None => (*o).changed().concept(name),
+    }
+}
+
+/// The role named `name`, interned on first use, or `u32::MAX` as in
+/// [`el_concept`].
+///
+/// # Safety
+/// As [`el_concept`].
+#[no_mangle]
+pub unsafe extern "C" fn el_role(o: *mut ElOntology, name: *const c_char) -> u32 {
+    let Some(name) = self::name(name).filter(|name| !name.is_empty()) else {
+        return u32::MAX;
+    };
+    match (*o).ontology.find_role(name) {
+        Some(r) => r,
+        None => (*o).changed().role(name),
+    }
+}
+
+/// The concept named `name`, or -1 if there is none.
+///
+/// # Safety
+/// As [`el_concept`].
+#[no_mangle]
+pub unsafe extern "C" fn el_find_concept(o: *const ElOntology, name: *const c_char) -> i64 {
+    let found = self::name(name).and_then(|name| (*o).ontology.find_concept(name));
+    found.map_or(-1, i64::from)
+}
+
+/// The name of `c`, or NULL if there is no such concept.
+///
+/// # Safety
+/// `o` comes from this API.
+#[no_mangle]
+pub unsafe extern "C" fn el_concept_name(o: *const ElOntology, c: u32) -> *mut c_char {
+    let ontology = &(*o).ontology;
+    if c as usize >= ontology.num_concepts() {
+        return ptr::null_mut();
+    }
+    c_string(ontology.concept_name(c))
+}
+
+/// # Safety
+/// `o` comes from this API.
+#[no_mangle]
+pub unsafe extern "C" fn el_num_concepts(o: *const ElOntology) -> usize {
+    (*o).ontology.num_concepts()
+}
+
+/// `sub ⊑ sup`.
+///
+/// # Safety
+/// `o` comes from this API; IDs come from it too.
+#[no_mangle]
+pub unsafe extern "C" fn el_add_subclass_of(o: *mut ElOntology, sub: u32, sup: u32) {
+    (*o).changed().add_subclass_of(sub, sup);
+}
+
+/// `sub ⊑ ∃role.filler`.
+///
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_existential(o: *mut ElOntology, sub: u32, role: u32, filler: u32) {
+    (*o).changed().add_existential(sub, role, filler);
+}
+
+/// `c ≡ A₁ ⊓ … ⊓ Aₙ ⊓ ∃r₁.F₁ ⊓ … ⊓ ∃rₘ.Fₘ`, from the `n` concepts at
+/// `conjuncts` and the `m` pairs at `roles` and `fillers`.
+///
+/// # Safety
+/// As [`el_add_subclass_of`]; the arrays hold `n`, `m` and `m` IDs.
+#[no_mangle]
+pub unsafe extern "C" fn el_add_equivalent(
+    o: *mut ElOntology,
+    c: u32,
+    conjuncts: *const u32,
+    n: usize,
+    roles: *const u32,
+    fillers: *const u32,
+    m: usize,
+) {
+    let ids = |p: *const u32, len: usize| if len == 0 { &[][..] } else { slice::from_raw_parts(p, len) };
+    let named = ids(conjuncts, n).iter().map(|&a| ClassExpr::Named(a));
+    let some = ids(roles, m)
+        .iter()
+        .zip(ids(fillers, m))
+        .map(|(&r, &f)| ClassExpr::Some(r, Box::new(ClassExpr::Named(f))));
+    (*o).changed().add_equivalent(c, ClassExpr::And(named.chain(some).collect()));
+}
+
+/// `a ⊓ b ⊑ ⊥`.
+///
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_disjoint(o: *mut ElOntology, a: u32, b: u32) {
+    (*o).changed().add_disjoint(a, b);
+}
+
+/// `sub ⊑ sup` between roles.
+///
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_sub_role(o: *mut ElOntology, sub: u32, sup: u32) {
+    (*o).changed().add_sub_role(sub, sup);
+}
+
+/// `first ∘ second ⊑ sup`.
+///
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_role_chain(o: *mut ElOntology, first: u32, second: u32, sup: u32) {
+    (*o).changed().add_role_chain(first, second, sup);
+}
+
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_transitive(o: *mut ElOntology, role: u32) {
+    (*o).changed().add_transitive(role);
+}
+
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_reflexive(o: *mut ElOntology, role: u32) {
+    (*o).changed().add_reflexive(role);
+}
+
+/// `∃role.⊤ ⊑ domain`.
+///
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_domain(o: *mut ElOntology, role: u32, domain: u32) {
+    (*o).changed().add_domain(role, domain);
+}
+
+/// Every `role` successor is a `range`.
+///
+/// # Safety
+/// As [`el_add_subclass_of`].
+#[no_mangle]
+pub unsafe extern "C" fn el_add_range(o: *mut ElOntology, role: u32, range: u32) {
+    (*o).changed().add_range(role, range);
+}
+
+/// Classifies the ontology: 1 if it is consistent, 0 if not (every
+/// concept is then unsatisfiable and the taxonomy meaningless).
+///
+/// # Safety
+/// `o` comes from this API.
+#[no_mangle]
+pub unsafe extern "C" fn el_classify(o: *mut ElOntology) -> c_int {
+    (*o).classified().1 as c_int
+}
+
+/// Superclasses of `c`, the direct ones only when `direct` isn't 0, in
+/// ascending ID order (`owl:Thing` included). Up to `cap` go to `out`;
+/// the return value is how many there are, so a call with `cap` 0 sizes
+/// the buffer. Equivalents share their representative's place in the
+/// hierarchy.
+///
+/// # Safety
+/// `o` comes from this API; `out` is NULL or has room for `cap` IDs.
+#[no_mangle]
+pub unsafe extern "C" fn el_superclasses(
+    o: *mut ElOntology,
+    c: u32,
+    direct: c_int,
+    out: *mut u32,
+    cap: usize,
+) -> usize {
+    let (taxonomy, _) = (*o).classified();
+    if c as usize >= taxonomy.len() {
+        return 0;
+    }
+    let r = taxonomy.representative(c);
+    match direct {
+        0 => fill(&taxonomy.ancestors(r), out, cap),
+        _ => {
+            let mut parents = taxonomy.parents(r).to_vec();
+            parents.sort_unstable();
+            fill(&parents, out, cap)
+        }
+    }
+}
+
+/// Subclasses of `c`; otherwise as [`el_superclasses`].
+///
+/// # Safety
+/// As [`el_superclasses`].
+#[no_mangle]
+pub unsafe extern "C" fn el_subclasses(
+    o: *mut ElOntology,
+    c: u32,
+    direct: c_int,
+    out: *mut u32,
+    cap: usize,
+) -> usize {
+    let (taxonomy, _) = (*o).classified();
+    if c as usize >= taxonomy.len() {
+        return 0;
+    }
+    let r = taxonomy.representative(c);
+    match direct {
+        0 => fill(&taxonomy.descendants(r), out, cap),
+        _ => fill(taxonomy.children(r), out, cap),
+    }
+}
+
+/// Concepts equivalent to `c`, itself excluded; otherwise as
+/// [`el_superclasses`].
+///
+/// # Safety
+/// As [`el_superclasses`].
+#[no_mangle]
+pub unsafe extern "C" fn el_equivalents(o: *mut ElOntology, c: u32, out: *mut u32, cap: usize) -> usize {
+    let (taxonomy, _) = (*o).classified();
+    let class = taxonomy.equivalences().iter().find(|class| class.contains(&c));
+    let others: Vec<ConceptId> = class.into_iter().flatten().copied().filter(|&e| e != c).collect();
+    fill(&others, out, cap)
+}
+
+/// 1 if `sub ⊑ sup`, 0 if not, saturating only what `sub` depends on
+/// rather than classifying; see [`crate::is_subsumed_by`].
+///
+/// # Safety
+/// `o` comes from this API.
+#[no_mangle]
+pub unsafe extern "C" fn el_is_subsumed_by(o: *const ElOntology, sub: u32, sup: u32) -> c_int {
+    let ontology = &(*o).ontology;
+    let n = ontology.num_concepts() as u32;
+    if sub >= n || sup >= n {
+        return 0;
+    }
+    crate::is_subsumed_by(&ontology.axiom_store(), sub, sup) as c_int
+}
@@ -0,0 +1,144 @@
+//! Synthetic OBO ontologies of a chosen shape and size, for the benchmarks
+//! in `benches/reasoner.rs`. The same shape, size and seed always give the
+//! same document.
+
+use std::fmt::{self, Write};
+
+/// How the generated terms relate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// `T:1 ⊑ T:0`, `T:2 ⊑ T:1`, …: one path as deep as the ontology is
+    /// large, the worst case for depth-first propagation.
+    Chain,
+    /// Every term defined as the conjunction of its parent and `width - 1`
+    /// of the primitive terms `A:0` … `A:63`: wide `intersection_of:` fans
+    /// that stress conjunction rules.
+    Conjunctions { width: usize },
+    /// `is_a` its parent and `degree` `relationship:`s over `roles`
+    /// relations: the first, transitive, to siblings of its parent, the
+    /// others to random terms. Every tenth term is defined by a genus and a
+    /// differentia so existentials get back-propagated.
+    Existentials { degree: usize, roles: usize },
+}
+
+impl Shape {
+    /// `chain`, `conjunctions` or `existentials`, with default parameters.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "chain" => Some(Shape::Chain),
+            "conjunctions" => Some(Shape::Conjunctions { width: 4 }),
+            "existentials" => Some(Shape::Existentials { degree: 3, roles: 4 }),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Chain => "chain",
+            Shape::Conjunctions { .. } => "conjunctions",
+            Shape::Existentials { .. } => "existentials",
+        }
+    }
+}
+
+/// Children per term. Parents are those of an `ARITY`-ary heap, so depth
+/// grows with the logarithm of the size and so do subsumer sets; random
+/// earlier terms would make nearly every term subsume nearly every later one.
+const ARITY: usize = 8;
+
+fn parent(t: usize) -> usize {
+    (t - 1) / ARITY
+}
+
+/// Primitive terms of [`Shape::Conjunctions`]. Terms that share a parent
+/// subsume each other only when one's attributes include the other's, so
+/// subsumer sets stay small.
+const ATTRIBUTES: usize = 64;
+
+/// xorshift64*: deterministic and dependency-free, which is all a
+/// generator needs.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    /// Uniform in `0..n`; `n` > 0.
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % n as u64) as usize
+    }
+
+    /// A sibling of `t`'s parent, or the root while there is none.
+    fn uncle(&mut self, t: usize) -> usize {
+        let p = parent(t);
+        if p == 0 {
+            return 0;
+        }
+        let first = parent(p) * ARITY + 1;
+        match first + self.below(ARITY - 1) {
+            u if u >= p => u + 1,
+            u => u,
+        }
+    }
+}
+
+/// An OBO document of `terms` terms (`T:0` … ) shaped like `shape`, plus
+/// the attributes of [`Shape::Conjunctions`].
+pub fn generate(shape: Shape, terms: usize, seed: u64) -> String {
+    let mut out = String::with_capacity(terms * 48);
+    write_obo(&mut out, shape, terms, seed).expect("writing to a String");
+    out
+}
+
+/// [`generate`] into any writer.
+pub fn write_obo(out: &mut impl Write, shape: Shape, terms: usize, seed: u64) -> fmt::Result {
+    let mut rng = Rng::new(seed);
+    writeln!(out, "format-version: 1.2\nontology: synthetic-{}\n", shape.name())?;
+    for t in 0..terms {
+        writeln!(out, "[Term]\nid: T:{}", t)?;
+        if t > 0 {
+            match shape {
+                Shape::Chain => writeln!(out, "is_a: T:{}", t - 1)?,
+                Shape::Conjunctions { width } => {
+                    writeln!(out, "intersection_of: T:{}", parent(t))?;
+                    for _ in 1..width.max(2) {
+                        writeln!(out, "intersection_of: A:{}", rng.below(ATTRIBUTES))?;
+                    }
+                }
+                Shape::Existentials { degree, roles } => {
+                    writeln!(out, "is_a: T:{}", parent(t))?;
+                    for _ in 0..degree {
+                        match rng.below(roles) {
+                            0 => writeln!(out, "relationship: R:0 T:{}", rng.uncle(t))?,
+                            r => writeln!(out, "relationship: R:{} T:{}", r, rng.below(terms))?,
+                        }
+                    }
+                    if t % 10 == 0 {
+                        writeln!(out, "intersection_of: T:{}", parent(t))?;
+                        writeln!(out, "intersection_of: R:{} T:{}", rng.below(roles), rng.below(t))?;
+                    }
+                }
+            }
+        }
+        writeln!(out)?;
+    }
+    if let Shape::Conjunctions { .. } = shape {
+        for a in 0..ATTRIBUTES {
+            writeln!(out, "[Term]\nid: A:{}\n", a)?;
+        }
+    }
+    if let Shape::Existentials { roles, .. } = shape {
+        for r in 0..roles {
+            writeln!(out, "[Typedef]\nid: R:{}", r)?;
+            if r == 0 {
+                writeln!(out, "is_transitive: true")?;
+            }
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,243 @@
+//! Impact of obsoleting or merging terms, assessed before the edit is made:
+//! the told axioms that mention them, the children that lose a direct
+//! parent, and the inferred subsumptions between the remaining terms that
+//! would no longer hold.
+
+use std::fmt;
+
+use crate::changes::TermRef;
+use crate::kernel::{saturate, AxiomStore, ConceptId, Context, RoleId, BOTTOM, TOP};
+use crate::normalize::Normalizer;
+use crate::obo::{add_nominals, Definition, ParseResult};
+use crate::taxonomy::build_taxonomy;
+
+/// A term to obsolete, or to merge into `replacement`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub term: ConceptId,
+    pub replacement: Option<ConceptId>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ToldAxiom {
+    pub sub: TermRef,
+    /// `None` for `is_a`.
+    pub relation: Option<String>,
+    pub sup: TermRef,
+}
+
+#[derive(Clone, Debug)]
+pub struct OrphanedChild {
+    pub term: TermRef,
+    pub lost: Vec<TermRef>,
+    /// Direct parents once the candidates are gone.
+    pub new_parents: Vec<TermRef>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ObsoletionImpact {
+    pub candidates: Vec<(TermRef, Option<TermRef>)>,
+    pub told_axioms: Vec<ToldAxiom>,
+    pub children: Vec<OrphanedChild>,
+    pub lost_subsumptions: Vec<(TermRef, TermRef)>,
+}
+
+fn term(parsed: &ParseResult, c: ConceptId) -> TermRef {
+    TermRef {
+        id: parsed.concepts[c as usize].clone(),
+        label: parsed.labels.get(&(c as usize)).cloned(),
+    }
+}
+
+/// Compares the classification `contexts` of `parsed` with one where every
+/// candidate is obsoleted (its axioms dropped) or merged (its occurrences
+/// rewritten to the replacement).
+pub fn obsoletion_impact(
+    parsed: &ParseResult,
+    contexts: &[Context],
+    candidates: &[Candidate],
+) -> ObsoletionImpact {
+    let n = parsed.concepts.len();
+    let mut fate: Vec<Option<Option<ConceptId>>> = vec![None; n];
+    for c in candidates {
+        fate[c.term as usize] = Some(c.replacement);
+    }
+    let is_candidate = |c: usize| fate[c].is_some();
+    // Where an axiom endpoint goes after the edit; `None` drops the axiom.
+    let rewrite = |c: usize| -> Option<ConceptId> {
+        match fate[c] {
+            None => Some(c as ConceptId),
+            Some(replacement) => replacement,
+        }
+    };
+
+    let mut impact = ObsoletionImpact {
+        candidates: candidates
+            .iter()
+            .map(|c| (term(parsed, c.term), c.replacement.map(|r| term(parsed, r))))
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut store = AxiomStore::new(n, parsed.roles.len());
+    for &(sub, sup) in &parsed.subsumptions {
+        if is_candidate(sub) || is_candidate(sup) {
+            impact.told_axioms.push(ToldAxiom {
+                sub: term(parsed, sub as ConceptId),
+                relation: None,
+                sup: term(parsed, sup as ConceptId),
+            });
+        }
+        if let (Some(a), Some(b)) = (rewrite(sub), rewrite(sup)) {
+            if a != b {
+                store.add_subsumption(a, b);
+            }
+        }
+    }
+    for &(sub, role, target) in &parsed.relations {
+        if is_candidate(sub) || is_candidate(target) {
+            impact.told_axioms.push(ToldAxiom {
+                sub: term(parsed, sub as ConceptId),
+                relation: Some(parsed.roles[role].clone()),
+                sup: term(parsed, target as ConceptId),
+            });
+        }
+        if let (Some(a), Some(b)) = (rewrite(sub), rewrite(target)) {
+            store.add_exist_right(a, role as RoleId, b);
+        }
+    }
+
+    for &(sub, sup) in &parsed.role_subsumptions {
+        store.add_role_subsumption(sub as RoleId, sup as RoleId);
+    }
+    for &(first, second, sup) in &parsed.role_chains {
+        store.add_role_chain(first as RoleId, second as RoleId, sup as RoleId);
+    }
+    for &role in &parsed.reflexive_roles {
+        store.add_reflexive(role as RoleId);
+    }
+    add_nominals(parsed, &mut store);
+    for &(role, domain) in &parsed.role_domains {
+        if let Some(d) = rewrite(domain) {
+            store.add_exist_left(role as RoleId, TOP, d);
+        }
+    }
+
+    for &(a, b) in &parsed.disjoint_pairs {
+        if let (Some(a), Some(b)) = (rewrite(a), rewrite(b)) {
+            store.add_conjunction(a, b, BOTTOM);
+        }
+    }
+
+    let mut normalizer = Normalizer::new(&mut store, n);
+    for definition in &parsed.definitions {
+        if let Some(d) = rewrite_definition(definition, rewrite) {
+            normalizer.add_equivalence(d.term as ConceptId, &d.expression());
+        }
+    }
+
+    for axiom in &parsed.class_axioms {
+        if let Some(axiom) = axiom.try_map(&mut |c| rewrite(c as usize)) {
+            normalizer.add_axiom(&axiom);
+        }
+    }
+    let ranges: Vec<(RoleId, ConceptId)> = parsed
+        .role_ranges
+        .iter()
+        .filter_map(|&(role, range)| Some((role as RoleId, rewrite(range)?)))
+        .collect();
+    normalizer.add_ranges(&ranges);
+
+    let after = saturate(&store, store.num_concepts(), parsed.roles.len());
+    let before_parents = build_taxonomy(contexts, n);
+    let after_parents = build_taxonomy(&after, n);
+
+    let kept = |c: ConceptId| c != TOP && c != BOTTOM && !is_candidate(c as usize);
+    for c in (2..n as ConceptId).filter(|&c| kept(c)) {
+        let lost: Vec<ConceptId> = before_parents[c as usize]
+            .iter()
+            .copied()
+            .filter(|&p| is_candidate(p as usize))
+            .collect();
+        if !lost.is_empty() {
+            let mut new_parents = after_parents[c as usize].clone();
+            new_parents.sort_unstable();
+            impact.children.push(OrphanedChild {
+                term: term(parsed, c),
+                lost: lost.into_iter().map(|p| term(parsed, p)).collect(),
+                new_parents: new_parents.into_iter().map(|p| term(parsed, p)).collect(),
+            });
+        }
+
+        let mut lost_supers: Vec<ConceptId> = contexts[c as usize]
+            .super_set
+            .iter()
+            .copied()
+            .filter(|&s| (s as usize) < n && kept(s) && s != c)
+            .filter(|s| !after[c as usize].super_set.contains(s))
+            .collect();
+        lost_supers.sort_unstable();
+        impact
+            .lost_subsumptions
+            .extend(lost_supers.into_iter().map(|s| (term(parsed, c), term(parsed, s))));
+    }
+    impact
+}
+
+/// `definition` with its concepts rewritten, or `None` when it mentions an
+/// obsoleted term and no longer holds.
+pub(crate) fn rewrite_definition(
+    definition: &Definition,
+    rewrite: impl Fn(usize) -> Option<ConceptId>,
+) -> Option<Definition> {
+    let concept = |c: usize| rewrite(c).map(|c| c as usize);
+    Some(Definition {
+        term: concept(definition.term)?,
+        genus: definition.genus.iter().map(|&g| concept(g)).collect::<Option<_>>()?,
+        differentia: definition
+            .differentia
+            .iter()
+            .map(|&(r, f)| Some((r, concept(f)?)))
+            .collect::<Option<_>>()?,
+    })
+}
+
+impl fmt::Display for ToldAxiom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.relation {
+            Some(r) => write!(f, "{} {} some {}", self.sub, r, self.sup),
+            None => write!(f, "{} is_a {}", self.sub, self.sup),
+        }
+    }
+}
+
+fn join(terms: &[TermRef]) -> String {
+    terms.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// Markdown, in the style of the `changes` report.
+impl fmt::Display for ObsoletionImpact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "## Candidates ({})\n", self.candidates.len())?;
+        for (t, replacement) in &self.candidates {
+            match replacement {
+                Some(r) => writeln!(f, "- {}: merge into {}", t, r)?,
+                None => writeln!(f, "- {}: obsolete", t)?,
+            }
+        }
+        writeln!(f, "\n## Told axioms mentioning candidates ({})\n", self.told_axioms.len())?;
+        for a in &self.told_axioms {
+            writeln!(f, "- {}", a)?;
+        }
+        writeln!(f, "\n## Children losing a direct parent ({})\n", self.children.len())?;
+        for c in &self.children {
+            let (lost, new_parents) = (join(&c.lost), join(&c.new_parents));
+            writeln!(f, "- {}: loses {}; new parents {}", c.term, lost, new_parents)?;
+        }
+        writeln!(f, "\n## Lost inferred subsumptions ({})\n", self.lost_subsumptions.len())?;
+        for (sub, sup) in &self.lost_subsumptions {
+            writeln!(f, "- {} ⊑ {}", sub, sup)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! Incremental reclassification after an edit.
+//!
+//! Concepts and roles are matched across the two versions by name. A concept
+//! is *changed* when the axioms with it on the left-hand side differ; it is
+//! *affected* when a changed concept is reachable from it through the
+//! axioms of either version, i.e. when a changed axiom may sit in its
+//! module. Only the module of the affected concepts is saturated; every
+//! other context is carried over from the previous classification.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::kernel::{resaturate, AxiomStore, ConceptId, Context, RoleId, BOTTOM, TOP};
+use crate::module::Module;
+
+/// One version of an ontology: its names and normalized axioms. Concepts
+/// past `concepts.len()`, or with an empty name, are normalization-fresh and
+/// never matched across versions.
+pub struct Snapshot<'a> {
+    pub concepts: &'a [String],
+    pub roles: &'a [String],
+    pub store: &'a AxiomStore,
+}
+
+pub struct Reclassification {
+    pub contexts: Vec<Context>,
+    /// Concepts whose contexts were recomputed.
+    pub affected: usize,
+    /// Signature size of the module that was saturated.
+    pub module_size: usize,
+}
+
+/// An axiom keyed by its left-hand concept, for comparing versions.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LhsAxiom {
+    Sub(ConceptId),
+    Conj(ConceptId, ConceptId),
+    ExistRight(RoleId, ConceptId),
+    ExistLeft(RoleId, ConceptId),
+}
+
+/// Replaces `axioms` with the sorted axioms that have `a` on the left.
+fn lhs_axioms(store: &AxiomStore, a: ConceptId, axioms: &mut Vec<LhsAxiom>) {
+    let a_idx = a as usize;
+    axioms.clear();
+    axioms.extend(store.sub_to_sups[a_idx].iter().map(|&b| LhsAxiom::Sub(b)));
+    for (&other, rights) in &store.conj_index[a_idx] {
+        axioms.extend(rights.iter().map(|&c| LhsAxiom::Conj(other, c)));
+    }
+    axioms.extend(
+        store.exist_right[a_idx].iter().map(|rf| LhsAxiom::ExistRight(rf.role, rf.fill)),
+    );
+    for (r, by_fill) in store.exist_left.iter().enumerate() {
+        if let Some(sups) = by_fill.get(&a) {
+            axioms.extend(sups.iter().map(|&c| LhsAxiom::ExistLeft(r as RoleId, c)));
+        }
+    }
+    axioms.sort_unstable();
+    axioms.dedup();
+}
+
+/// Old id → new id, by name.
+fn match_names(old: &[String], new: &[String], fixed: &[u32]) -> Vec<Option<u32>> {
+    let by_name: BTreeMap<&str, u32> = new
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| !n.is_empty())
+        .map(|(i, n)| (n.as_str(), i as u32))
+        .collect();
+    old.iter()
+        .enumerate()
+        .map(|(i, n)| {
+            if fixed.contains(&(i as u32)) {
+                Some(i as u32)
+            } else if n.is_empty() {
+                None
+            } else {
+                by_name.get(n.as_str()).copied()
+            }
+        })
+        .collect()
+}
+
+/// Recomputes the contexts of the concepts affected by the edit from `old`
+/// to `new`, reusing `old_contexts` (the saturation of `old`) for the rest.
+/// Only the module of the affected concepts is re-saturated.
+pub fn reclassify(old: &Snapshot, old_contexts: Vec<Context>, new: &Snapshot) -> Reclassification {
+    let new_n = new.store.num_concepts();
+    let num_roles = new.roles.len();
+    let mut concept_map = match_names(old.concepts, new.concepts, &[TOP, BOTTOM]);
+    concept_map.resize(old.store.num_concepts(), None);
+    let role_map = match_names(old.roles, new.roles, &[]);
+
+    let map_axiom = |ax: LhsAxiom| -> Option<LhsAxiom> {
+        let c = |x: ConceptId| concept_map[x as usize];
+        let r = |x: RoleId| role_map[x as usize];
+        Some(match ax {
+            LhsAxiom::Sub(b) => LhsAxiom::Sub(c(b)?),
+            LhsAxiom::Conj(o, b) => LhsAxiom::Conj(c(o)?, c(b)?),
+            LhsAxiom::ExistRight(role, b) => LhsAxiom::ExistRight(r(role)?, c(b)?),
+            LhsAxiom::ExistLeft(role, b) => LhsAxiom::ExistLeft(r(role)?, c(b)?),
+        })
+    };
+
+    let mut old_of: Vec<Option<ConceptId>> = vec![None; new_n];
+    for (o, c) in concept_map.iter().enumerate() {
+        if let Some(c) = c {
+            old_of[*c as usize] = Some(o as ConceptId);
+        }
+    }
+
+    // Changed concepts, and the forward edges of both versions in new ids.
+    let mut changed = vec![true; new_n];
+    let mut reverse: Vec<Vec<ConceptId>> = vec![Vec::new(); new_n];
+    let (mut new_axioms, mut old_axioms) = (Vec::new(), Vec::new());
+    for a in 0..new_n as ConceptId {
+        lhs_axioms(new.store, a, &mut new_axioms);
+        for &ax in &new_axioms {
+            add_reverse_edge(&mut reverse, a, ax);
+        }
+        let Some(o) = old_of[a as usize] else { continue };
+        lhs_axioms(old.store, o, &mut old_axioms);
+        let translated: Option<Vec<LhsAxiom>> =
+            old_axioms.iter().map(|&ax| map_axiom(ax)).collect();
+        changed[a as usize] = match translated {
+            Some(mut translated) => {
+                translated.sort_unstable();
+                translated.dedup();
+                for &ax in &translated {
+                    add_reverse_edge(&mut reverse, a, ax);
+                }
+                translated != new_axioms
+            }
+            // Mentions a concept or role that no longer exists.
+            None => true,
+        };
+    }
+
+    let mut affected = changed;
+    let mut worklist: Vec<ConceptId> =
+        (0..new_n as ConceptId).filter(|&c| affected[c as usize]).collect();
+    while let Some(b) = worklist.pop() {
+        for &a in &reverse[b as usize] {
+            if !affected[a as usize] {
+                affected[a as usize] = true;
+                worklist.push(a);
+            }
+        }
+    }
+    // Axioms on TOP apply to every concept, role axioms to every link, and
+    // nominals merge contexts however far apart.
+    let nominals = !old.store.nominals.is_empty() || !new.store.nominals.is_empty();
+    if affected[TOP as usize] || nominals || role_axioms_changed(old.store, new.store, &role_map) {
+        affected.iter_mut().for_each(|a| *a = true);
+    }
+
+    let module = Module::extract(
+        new.store,
+        num_roles,
+        (0..new_n as ConceptId).filter(|&c| affected[c as usize]),
+    );
+
+    // Carry the unaffected contexts over, renumbered if ids moved.
+    let identity = concept_map.iter().enumerate().all(|(i, m)| *m == Some(i as ConceptId))
+        && role_map.iter().enumerate().all(|(i, m)| *m == Some(i as RoleId));
+    let mut carried: Vec<Option<Context>> = (0..new_n).map(|_| None).collect();
+    for (old_c, mut old_ctx) in old_contexts.into_iter().enumerate() {
+        let Some(c) = concept_map.get(old_c).copied().flatten() else { continue };
+        if affected[c as usize] {
+            continue;
+        }
+        if !identity {
+            let mut ctx = Context::with_capacity(c, num_roles, old_ctx.super_set.len());
+            ctx.super_set
+                .extend(old_ctx.super_set.iter().filter_map(|&s| concept_map[s as usize]));
+            for (r, targets) in old_ctx.link_map.iter().enumerate() {
+                if let Some(r_new) = role_map[r] {
+                    ctx.link_map[r_new as usize]
+                        .extend(targets.iter().filter_map(|&t| concept_map[t as usize]));
+                }
+            }
+            old_ctx = ctx;
+        }
+        old_ctx.link_map.resize_with(num_roles, Vec::new);
+        old_ctx.pred_map.resize_with(num_roles, Vec::new);
+        carried[c as usize] = Some(old_ctx);
+    }
+    let mut contexts: Vec<Context> = carried
+        .into_iter()
+        .enumerate()
+        .map(|(c, ctx)| ctx.unwrap_or_else(|| Context::new(c as ConceptId, num_roles)))
+        .collect();
+
+    // Clean contexts only keep predecessors that are themselves clean; the
+    // links of dirty concepts are re-derived.
+    if identity {
+        for ctx in &mut contexts {
+            for preds in &mut ctx.pred_map {
+                preds.retain(|&p| !affected[p as usize]);
+            }
+        }
+    } else {
+        for c in (0..new_n).filter(|&c| !affected[c]) {
+            for r in 0..num_roles {
+                for i in 0..contexts[c].link_map[r].len() {
+                    let t = contexts[c].link_map[r][i];
+                    contexts[t as usize].pred_map[r].push(c as ConceptId);
+                }
+            }
+        }
+    }
+
+    let dirty: Vec<ConceptId> = (0..new_n as ConceptId).filter(|&c| affected[c as usize]).collect();
+    let affected_count = dirty.len();
+    let contexts = resaturate(&module.restrict(new.store), contexts, num_roles, dirty);
+
+    Reclassification { contexts, affected: affected_count, module_size: module.len() }
+}
+
+/// Role inclusions `r ⊑ t` as `(r, None, t)`, chains `r ∘ s ⊑ t` as
+/// `(r, Some(s), t)` and reflexivity of `r`, which is no inclusion, as
+/// `(r, None, r)`.
+fn role_axioms(store: &AxiomStore) -> Vec<(RoleId, Option<RoleId>, RoleId)> {
+    let mut axioms = Vec::new();
+    for (r, sups) in store.role_sub_to_sups.iter().enumerate() {
+        axioms.extend(sups.iter().map(|&t| (r as RoleId, None, t)));
+    }
+    axioms.extend(store.role_chains().map(|(r, s, t)| (r, Some(s), t)));
+    axioms.extend(store.reflexive_roles.iter().map(|&r| (r, None, r)));
+    axioms
+}
+
+fn role_axioms_changed(old: &AxiomStore, new: &AxiomStore, role_map: &[Option<RoleId>]) -> bool {
+    let map = |r: RoleId| role_map[r as usize];
+    let translated: Option<Vec<_>> = role_axioms(old)
+        .into_iter()
+        .map(|(r, s, t)| {
+            let s = match s {
+                Some(s) => Some(map(s)?),
+                None => None,
+            };
+            Some((map(r)?, s, map(t)?))
+        })
+        .collect();
+    let Some(mut translated) = translated else {
+        return true;
+    };
+    let mut current = role_axioms(new);
+    translated.sort_unstable();
+    translated.dedup();
+    current.sort_unstable();
+    current.dedup();
+    translated != current
+}
+
+/// Records `a → b` for the concept the axiom can put into Σ(a).
+fn add_reverse_edge(reverse: &mut [Vec<ConceptId>], a: ConceptId, ax: LhsAxiom) {
+    let b = match ax {
+        LhsAxiom::Sub(b)
+        | LhsAxiom::Conj(_, b)
+        | LhsAxiom::ExistRight(_, b)
+        | LhsAxiom::ExistLeft(_, b) => b,
+    };
+    if reverse[b as usize].last() != Some(&a) {
+        reverse[b as usize].push(a);
+    }
+}
@@ -0,0 +1,212 @@
+//! Input format detection by file extension and content sniffing, and
+//! reading a document together with its imports.
+
+#[cfg(feature = "obo")]
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+#[cfg(feature = "obo")]
+use std::io::BufRead;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "obo")]
+use crate::error::ReasonerError;
+#[cfg(feature = "obo")]
+use crate::obo::{parse_obo_with, ParseResult, Warning, WarningKind};
+#[cfg(feature = "obo")]
+use crate::resolve::IdResolver;
+
+/// Bytes read from the start of a file when sniffing its format.
+pub(crate) const SNIFF_LEN: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Obo,
+    OwlFunctional,
+    RdfXml,
+    Turtle,
+    ObographsJson,
+    Krss,
+}
+
+impl InputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "obo" => Some(InputFormat::Obo),
+            "ofn" | "functional" => Some(InputFormat::OwlFunctional),
+            "rdfxml" | "rdf" | "owl" => Some(InputFormat::RdfXml),
+            "ttl" | "turtle" => Some(InputFormat::Turtle),
+            "json" | "obographs" => Some(InputFormat::ObographsJson),
+            "krss" | "krss2" => Some(InputFormat::Krss),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InputFormat::Obo => "obo",
+            InputFormat::OwlFunctional => "ofn",
+            InputFormat::RdfXml => "rdfxml",
+            InputFormat::Turtle => "ttl",
+            InputFormat::ObographsJson => "json",
+            InputFormat::Krss => "krss",
+        }
+    }
+
+    /// Format implied by the extension alone. `.owl` is deliberately not
+    /// mapped: it is used for RDF/XML, functional syntax and OWL/XML alike.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "obo" => Some(InputFormat::Obo),
+            "ofn" => Some(InputFormat::OwlFunctional),
+            "rdf" | "rdfxml" => Some(InputFormat::RdfXml),
+            "ttl" => Some(InputFormat::Turtle),
+            "json" => Some(InputFormat::ObographsJson),
+            "krss" | "krss2" => Some(InputFormat::Krss),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the first bytes of a document.
+    pub fn sniff(head: &[u8]) -> Option<Self> {
+        let text = String::from_utf8_lossy(head);
+        let text = text.trim_start_matches('\u{feff}');
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('<') {
+                return Some(InputFormat::RdfXml);
+            }
+            if let Some(stanza) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if !stanza.is_empty() && stanza.bytes().all(|b| b.is_ascii_alphabetic()) {
+                    return Some(InputFormat::Obo);
+                }
+            }
+            if line.starts_with('{') || line.starts_with('[') {
+                return Some(InputFormat::ObographsJson);
+            }
+            if line.starts_with("Prefix(") || line.starts_with("Ontology(") {
+                return Some(InputFormat::OwlFunctional);
+            }
+            if line.starts_with('(') {
+                return Some(InputFormat::Krss);
+            }
+            if line.starts_with("@prefix")
+                || line.starts_with("@base")
+                || line.starts_with("PREFIX")
+                || line.starts_with("BASE")
+            {
+                return Some(InputFormat::Turtle);
+            }
+            // OBO header tags ("format-version: 1.2") look like `tag: value`.
+            if let Some((tag, _)) = line.split_once(':') {
+                let tag_like = tag.bytes().all(|b| b.is_ascii_lowercase() || b == b'-' || b == b'_');
+                if !tag.is_empty() && tag_like {
+                    return Some(InputFormat::Obo);
+                }
+            }
+            return None;
+        }
+        None
+    }
+
+    /// Extension first, content sniffing when the extension is missing or
+    /// ambiguous.
+    pub fn detect(path: &Path) -> io::Result<Option<Self>> {
+        if let Some(format) = Self::from_extension(path) {
+            return Ok(Some(format));
+        }
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+        Ok(Self::sniff(&head))
+    }
+}
+
+/// Parses `reader` with the front-end for `format`. `strict` turns any
+/// warning into [`ReasonerError::Rejected`].
+#[cfg(feature = "obo")]
+pub fn parse_document<R: BufRead>(
+    reader: R,
+    format: InputFormat,
+    resolver: &dyn IdResolver,
+    strict: bool,
+) -> Result<(ParseResult, Vec<Warning>), ReasonerError> {
+    let (parsed, warnings) = match format {
+        InputFormat::Obo => parse_obo_with(reader, resolver)?,
+        #[cfg(feature = "owl")]
+        InputFormat::OwlFunctional => crate::owl::parse_ofn(reader, resolver)?,
+        #[cfg(feature = "owl")]
+        InputFormat::RdfXml => crate::owl::parse_rdfxml(reader, resolver)?,
+        #[cfg(feature = "owl")]
+        InputFormat::Turtle => crate::owl::parse_turtle(reader, resolver)?,
+        #[cfg(feature = "owl")]
+        InputFormat::Krss => crate::owl::parse_krss(reader, resolver)?,
+        format => return Err(ReasonerError::UnsupportedFormat(format)),
+    };
+    if strict && !warnings.is_empty() {
+        return Err(ReasonerError::Rejected(warnings));
+    }
+    Ok((parsed, warnings))
+}
+
+/// The local file an import of the document at `from` names: a path, taken
+/// relative to that document, or for a URL the file with the URL's last
+/// segment next to it, since nothing is fetched. `None` when there is no
+/// such file.
+pub fn import_path(from: &Path, import: &str) -> Option<PathBuf> {
+    let dir = from.parent().unwrap_or(Path::new("."));
+    let path = match import.split_once("://") {
+        Some(("file", path)) => PathBuf::from(path),
+        Some((_, rest)) => dir.join(rest.rsplit('/').next().filter(|name| !name.is_empty())?),
+        None => dir.join(import),
+    };
+    path.is_file().then_some(path)
+}
+
+/// Reads `paths` and, transitively, the documents they import, each once,
+/// merged in that order into one result with shared IDs (see
+/// [`ParseResult::merge`]). `parse` reads one document; its warnings go to
+/// `report` with its path, along with one for each import that has no local
+/// file (see [`import_path`]).
+#[cfg(feature = "obo")]
+pub fn read_with_imports<E, P>(
+    paths: &[&Path],
+    mut parse: P,
+    mut report: impl FnMut(&Path, Vec<Warning>),
+) -> Result<ParseResult, E>
+where
+    P: FnMut(&Path) -> Result<(ParseResult, Vec<Warning>), E>,
+{
+    let key = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut seen: HashSet<PathBuf> = paths.iter().map(|p| key(p)).collect();
+    let mut queue: VecDeque<PathBuf> = paths.iter().map(|p| p.to_path_buf()).collect();
+    let mut merged: Option<ParseResult> = None;
+    while let Some(path) = queue.pop_front() {
+        let (parsed, mut warnings) = parse(&path)?;
+        for (import, location) in &parsed.imports {
+            match import_path(&path, import) {
+                Some(file) => {
+                    if seen.insert(key(&file)) {
+                        queue.push_back(file);
+                    }
+                }
+                None => warnings.push(Warning {
+                    kind: WarningKind::UnresolvedImport,
+                    location: *location,
+                    message: format!("no local copy of {}", import),
+                }),
+            }
+        }
+        warnings.sort_by_key(|w| w.location);
+        report(&path, warnings);
+        match &mut merged {
+            Some(merged) => merged.merge(parsed),
+            None => merged = Some(parsed),
+        }
+    }
+    Ok(merged.unwrap_or_default())
+}
@@ -0,0 +1,982 @@
+//! Saturation kernel: axiom store, contexts and the engine applying the
+//! completion rules of [`crate::rules`].
+//!
+//! Nothing in here touches the filesystem, the environment or other std-only
+//! facilities, so the module builds with `alloc` alone (`--no-default-features
+//! --features alloc`) for embedded and WASM targets.
+
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+use core::ops::ControlFlow;
+
+use crate::progress::{Phase, ProgressSink};
+use crate::rules::{Conclusions, RuleSet};
+
+pub type ConceptId = u32;
+pub type RoleId = u32;
+
+pub const TOP: ConceptId = 0;
+pub const BOTTOM: ConceptId = 1;
+
+// Both hashers are unseeded, so iteration order depends only on what was
+// inserted in which order, never on the run.
+#[cfg(feature = "std")]
+pub type ConceptMap<V> = fxhash::FxHashMap<ConceptId, V>;
+#[cfg(feature = "std")]
+pub type ConceptSet = fxhash::FxHashSet<ConceptId>;
+
+#[cfg(not(feature = "std"))]
+pub type ConceptMap<V> = hashbrown::HashMap<ConceptId, V, foldhash::fast::FixedState>;
+#[cfg(not(feature = "std"))]
+pub type ConceptSet = hashbrown::HashSet<ConceptId, foldhash::fast::FixedState>;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RoleFiller {
+    pub role: RoleId,
+    pub fill: ConceptId,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AxiomStore {
+    pub sub_to_sups: Vec<Vec<ConceptId>>,
+    pub conj_index: Vec<ConceptMap<Vec<ConceptId>>>,
+    pub exist_right: Vec<Vec<RoleFiller>>,
+    pub exist_left: Vec<ConceptMap<Vec<ConceptId>>>,
+    /// Told role inclusions `r ⊑ s`, indexed by `r`.
+    pub role_sub_to_sups: Vec<Vec<RoleId>>,
+    /// Role chains `r ∘ s ⊑ t` as `(s, t)`, indexed by `r`.
+    pub chains_by_first: Vec<Vec<(RoleId, RoleId)>>,
+    /// The same chains as `(r, t)`, indexed by `s`.
+    pub chains_by_second: Vec<Vec<(RoleId, RoleId)>>,
+    /// Right identities `r ∘ s ⊑ r` as `s`, indexed by `r`. These, with
+    /// transitivity as `r ∘ r ⊑ r`, are kept out of the general chains.
+    pub right_identities: Vec<Vec<RoleId>>,
+    /// The same right identities as `r`, indexed by `s`.
+    pub right_identities_by_second: Vec<Vec<RoleId>>,
+    /// Left identities `s ∘ r ⊑ r` as `s`, indexed by `r`.
+    pub left_identities: Vec<Vec<RoleId>>,
+    /// The same left identities as `r`, indexed by `s`.
+    pub left_identities_by_first: Vec<Vec<RoleId>>,
+    /// Reflexive roles: every concept is linked to itself along them.
+    pub reflexive_roles: Vec<RoleId>,
+    /// Nominals: concepts `{a}` with exactly one instance.
+    pub nominals: Vec<ConceptId>,
+}
+
+impl AxiomStore {
+    pub fn new(num_concepts: usize, num_roles: usize) -> Self {
+        Self {
+            sub_to_sups: vec![Vec::new(); num_concepts],
+            conj_index: vec![ConceptMap::default(); num_concepts],
+            exist_right: vec![Vec::new(); num_concepts],
+            exist_left: vec![ConceptMap::default(); num_roles],
+            role_sub_to_sups: vec![Vec::new(); num_roles],
+            chains_by_first: vec![Vec::new(); num_roles],
+            chains_by_second: vec![Vec::new(); num_roles],
+            right_identities: vec![Vec::new(); num_roles],
+            right_identities_by_second: vec![Vec::new(); num_roles],
+            left_identities: vec![Vec::new(); num_roles],
+            left_identities_by_first: vec![Vec::new(); num_roles],
+            reflexive_roles: Vec::new(),
+            nominals: Vec::new(),
+        }
+    }
+
+    /// Number of concepts the concept-indexed tables cover.
+    pub fn num_concepts(&self) -> usize {
+        self.sub_to_sups.len()
+    }
+
+    /// Extends the concept-indexed tables to cover fresh concepts.
+    pub fn grow(&mut self, num_concepts: usize) {
+        if num_concepts > self.sub_to_sups.len() {
+            self.sub_to_sups.resize_with(num_concepts, Vec::new);
+            self.conj_index.resize_with(num_concepts, ConceptMap::default);
+            self.exist_right.resize_with(num_concepts, Vec::new);
+        }
+    }
+
+    #[inline]
+    pub fn add_subsumption(&mut self, sub: ConceptId, sup: ConceptId) {
+        self.sub_to_sups[sub as usize].push(sup);
+    }
+
+    /// NF2: `left1 ⊓ left2 ⊑ right`, stored under both conjuncts so CR2
+    /// fires whichever of them is derived last.
+    pub fn add_conjunction(&mut self, left1: ConceptId, left2: ConceptId, right: ConceptId) {
+        self.conj_index[left1 as usize].entry(left2).or_default().push(right);
+        if left1 != left2 {
+            self.conj_index[left2 as usize].entry(left1).or_default().push(right);
+        }
+    }
+
+    #[inline]
+    pub fn add_exist_right(&mut self, sub: ConceptId, role: RoleId, fill: ConceptId) {
+        self.exist_right[sub as usize].push(RoleFiller { role, fill });
+    }
+
+    /// Extends the role-indexed tables to cover new roles.
+    pub fn grow_roles(&mut self, num_roles: usize) {
+        if num_roles > self.exist_left.len() {
+            self.exist_left.resize_with(num_roles, ConceptMap::default);
+            self.role_sub_to_sups.resize_with(num_roles, Vec::new);
+            self.chains_by_first.resize_with(num_roles, Vec::new);
+            self.chains_by_second.resize_with(num_roles, Vec::new);
+            self.right_identities.resize_with(num_roles, Vec::new);
+            self.right_identities_by_second.resize_with(num_roles, Vec::new);
+            self.left_identities.resize_with(num_roles, Vec::new);
+            self.left_identities_by_first.resize_with(num_roles, Vec::new);
+        }
+    }
+
+    /// `sub ⊑ sup` between roles.
+    pub fn add_role_subsumption(&mut self, sub: RoleId, sup: RoleId) {
+        if sub != sup {
+            self.role_sub_to_sups[sub as usize].push(sup);
+        }
+    }
+
+    /// `first ∘ second ⊑ sup`; transitivity of `r` is `r ∘ r ⊑ r`. Right and
+    /// left identities go to their own tables.
+    pub fn add_role_chain(&mut self, first: RoleId, second: RoleId, sup: RoleId) {
+        if sup == first {
+            self.right_identities[first as usize].push(second);
+            self.right_identities_by_second[second as usize].push(first);
+        } else if sup == second {
+            self.left_identities[second as usize].push(first);
+            self.left_identities_by_first[first as usize].push(second);
+        } else {
+            self.chains_by_first[first as usize].push((second, sup));
+            self.chains_by_second[second as usize].push((first, sup));
+        }
+    }
+
+    /// Every chain `r ∘ s ⊑ t` as `(r, s, t)`, identities included, by `r`.
+    pub fn role_chains(&self) -> impl Iterator<Item = (RoleId, RoleId, RoleId)> + '_ {
+        (0..self.chains_by_first.len()).flat_map(move |r| self.role_chains_from(r as RoleId))
+    }
+
+    /// The chains `r ∘ s ⊑ t` of [`Self::role_chains`] with the given `r`.
+    pub fn role_chains_from(&self, r: RoleId) -> impl Iterator<Item = (RoleId, RoleId, RoleId)> + '_ {
+        let r_usize = r as usize;
+        let general = self.chains_by_first.get(r_usize).into_iter().flatten();
+        let right = self.right_identities.get(r_usize).into_iter().flatten();
+        let left = self.left_identities_by_first.get(r_usize).into_iter().flatten();
+        let general = general.map(move |&(s, t)| (r, s, t));
+        let right = right.map(move |&s| (r, s, r));
+        let left = left.map(move |&t| (r, t, t));
+        general.chain(right).chain(left)
+    }
+
+    /// `⊤ ⊑ ∃role.Self`.
+    pub fn add_reflexive(&mut self, role: RoleId) {
+        if !self.reflexive_roles.contains(&role) {
+            self.reflexive_roles.push(role);
+        }
+    }
+
+    /// `c` is a nominal `{a}`.
+    pub fn add_nominal(&mut self, c: ConceptId) {
+        if !self.nominals.contains(&c) {
+            self.nominals.push(c);
+        }
+    }
+
+    /// NF4: `∃role.fill ⊑ sup`.
+    pub fn add_exist_left(&mut self, role: RoleId, fill: ConceptId, sup: ConceptId) {
+        self.exist_left[role as usize].entry(fill).or_default().push(sup);
+    }
+
+    /// True if some axiom can put BOTTOM into a super set. When false, CR5 and
+    /// all BOTTOM bookkeeping can be skipped (the common ChEBI/GO case).
+    pub fn derives_bottom(&self) -> bool {
+        self.sub_to_sups.iter().any(|sups| sups.contains(&BOTTOM))
+            || self.conj_index.iter().any(|m| m.values().any(|v| v.contains(&BOTTOM)))
+            || self.exist_right.iter().any(|rfs| rfs.iter().any(|rf| rf.fill == BOTTOM))
+            || self.exist_left.iter().any(|m| m.values().any(|v| v.contains(&BOTTOM)))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Context {
+    pub id: ConceptId,
+    pub super_set: ConceptSet,
+    pub link_map: Vec<Vec<ConceptId>>,
+    pub pred_map: Vec<Vec<ConceptId>>,
+}
+
+impl Context {
+    pub fn new(id: ConceptId, num_roles: usize) -> Self {
+        Self::with_capacity(id, num_roles, 16)
+    }
+
+    pub fn with_capacity(id: ConceptId, num_roles: usize, super_capacity: usize) -> Self {
+        Self {
+            id,
+            super_set: ConceptSet::with_capacity_and_hasher(super_capacity, Default::default()),
+            link_map: vec![Vec::new(); num_roles],
+            pred_map: vec![Vec::new(); num_roles],
+        }
+    }
+
+    /// Rebuilds the context in a form that depends only on its contents:
+    /// link and predecessor lists ascending, and the super set re-inserted
+    /// in ascending order so iterating it gives the same order however
+    /// saturation got there (worklist order, thread count).
+    pub fn canonicalize(&mut self) {
+        for targets in self.link_map.iter_mut().chain(&mut self.pred_map) {
+            targets.sort_unstable();
+        }
+        let mut supers: Vec<ConceptId> = self.super_set.drain().collect();
+        supers.sort_unstable();
+        self.super_set = ConceptSet::with_capacity_and_hasher(supers.len(), Default::default());
+        self.super_set.extend(supers);
+    }
+}
+
+/// Heuristics used to pre-size saturation state from the told axiom counts,
+/// so the hot loop doesn't keep rehashing super sets and regrowing worklists.
+#[derive(Clone, Copy, Debug)]
+pub struct CapacityHints {
+    /// Super set capacity every context starts with.
+    pub super_set_base: usize,
+    /// Extra super set capacity per told superclass (`is_a`) of the concept.
+    pub super_set_per_told: usize,
+    /// Upper bound on the initial super set capacity of a single context.
+    pub super_set_max: usize,
+    /// Initial concept worklist capacity per concept.
+    pub worklist_per_concept: usize,
+    /// Initial link worklist capacity per told existential filler.
+    pub link_worklist_per_filler: usize,
+    /// Pre-size `link_map`/`pred_map` entries from told role usage.
+    pub presize_links: bool,
+}
+
+impl Default for CapacityHints {
+    fn default() -> Self {
+        Self {
+            super_set_base: 16,
+            super_set_per_told: 16,
+            super_set_max: 512,
+            worklist_per_concept: 2,
+            link_worklist_per_filler: 2,
+            presize_links: true,
+        }
+    }
+}
+
+impl CapacityHints {
+    fn super_capacity(&self, told_supers: usize) -> usize {
+        (self.super_set_base + self.super_set_per_told * told_supers).min(self.super_set_max)
+    }
+}
+
+/// The order derived facts are taken off the worklists in. Every order
+/// reaches the same saturation; they differ in locality and in how large
+/// the worklists get on the way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorklistOrder {
+    /// Newest first, staying with a context while it grows.
+    #[default]
+    Stack,
+    /// Oldest first, breadth-first over derivations.
+    Queue,
+    /// Lowest concept (link source) first, finishing contexts in ID order.
+    Priority,
+}
+
+impl WorklistOrder {
+    pub const ALL: [WorklistOrder; 3] =
+        [WorklistOrder::Stack, WorklistOrder::Queue, WorklistOrder::Priority];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "stack" => Some(WorklistOrder::Stack),
+            "queue" => Some(WorklistOrder::Queue),
+            "priority" => Some(WorklistOrder::Priority),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WorklistOrder::Stack => "stack",
+            WorklistOrder::Queue => "queue",
+            WorklistOrder::Priority => "priority",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SaturationOptions {
+    pub capacity: CapacityHints,
+    pub worklist: WorklistOrder,
+    /// The rules to apply; [`RuleSet::standard`] unless profiling one.
+    pub rules: RuleSet,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct WorkItem {
+    pub(crate) concept: ConceptId,
+    pub(crate) added: ConceptId,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct LinkItem {
+    pub(crate) source: ConceptId,
+    pub(crate) role: RoleId,
+    pub(crate) target: ConceptId,
+}
+
+/// Pending items in [`WorklistOrder`]. Nothing is deduplicated here: an
+/// item is pushed only when its fact first enters a super set or link map,
+/// so those already serve as the seen-set.
+pub(crate) enum Worklist<T> {
+    Stack(Vec<T>),
+    Queue(VecDeque<T>),
+    Priority(BinaryHeap<Reverse<T>>),
+}
+
+impl<T: Ord> Worklist<T> {
+    fn with_capacity(order: WorklistOrder, capacity: usize) -> Self {
+        match order {
+            WorklistOrder::Stack => Worklist::Stack(Vec::with_capacity(capacity)),
+            WorklistOrder::Queue => Worklist::Queue(VecDeque::with_capacity(capacity)),
+            WorklistOrder::Priority => Worklist::Priority(BinaryHeap::with_capacity(capacity)),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push(&mut self, item: T) {
+        match self {
+            Worklist::Stack(items) => items.push(item),
+            Worklist::Queue(items) => items.push_back(item),
+            Worklist::Priority(items) => items.push(Reverse(item)),
+        }
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        match self {
+            Worklist::Stack(items) => items.pop(),
+            Worklist::Queue(items) => items.pop_front(),
+            Worklist::Priority(items) => items.pop().map(|Reverse(item)| item),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Worklist::Stack(items) => items.len(),
+            Worklist::Queue(items) => items.len(),
+            Worklist::Priority(items) => items.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Worklist::Stack(items) => items.clear(),
+            Worklist::Queue(items) => items.clear(),
+            Worklist::Priority(items) => items.clear(),
+        }
+    }
+
+    /// The pending items, in no particular order.
+    fn items(&self) -> impl Iterator<Item = &T> {
+        let (stack, queue, heap) = match self {
+            Worklist::Stack(items) => (Some(items), None, None),
+            Worklist::Queue(items) => (None, Some(items), None),
+            Worklist::Priority(items) => (None, None, Some(items)),
+        };
+        let stack = stack.into_iter().flatten();
+        let queue = queue.into_iter().flatten();
+        stack.chain(queue).chain(heap.into_iter().flatten().map(|Reverse(item)| item))
+    }
+}
+
+pub fn saturate(store: &AxiomStore, num_concepts: usize, num_roles: usize) -> Vec<Context> {
+    saturate_with(store, num_concepts, num_roles, &SaturationOptions::default())
+}
+
+fn init_contexts(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    hints: &CapacityHints,
+) -> Vec<Context> {
+    let mut contexts: Vec<Context> = (0..num_concepts)
+        .map(|i| {
+            let told = store.sub_to_sups.get(i).map_or(0, Vec::len);
+            Context::with_capacity(i as ConceptId, num_roles, hints.super_capacity(told))
+        })
+        .collect();
+
+    if hints.presize_links {
+        let mut per_role = vec![0usize; num_roles];
+        let mut incoming: Vec<(ConceptId, RoleId)> = Vec::new();
+        for (c, fillers) in store.exist_right.iter().enumerate().take(num_concepts) {
+            for rf in fillers {
+                per_role[rf.role as usize] += 1;
+                incoming.push((rf.fill, rf.role));
+            }
+            for rf in fillers {
+                let count = core::mem::take(&mut per_role[rf.role as usize]);
+                if count > 0 {
+                    contexts[c].link_map[rf.role as usize].reserve_exact(count);
+                }
+            }
+        }
+        incoming.sort_unstable();
+        for run in incoming.chunk_by(|a, b| a == b) {
+            let (fill, role) = run[0];
+            if let Some(ctx) = contexts.get_mut(fill as usize) {
+                ctx.pred_map[role as usize].reserve_exact(run.len());
+            }
+        }
+    }
+
+    contexts
+}
+
+pub fn saturate_with(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+) -> Vec<Context> {
+    let mut engine = Engine::new(store, num_concepts, num_roles, options);
+    let _ = engine.run(|_| ControlFlow::<()>::Continue(()));
+    engine.contexts
+}
+
+/// Worklist items between two progress reports.
+const PROGRESS_STEP: usize = 1 << 16;
+
+/// [`saturate_with`], reporting [`Phase::Saturate`] progress to `progress`.
+pub fn saturate_with_progress(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+    progress: &mut dyn ProgressSink,
+) -> Vec<Context> {
+    progress.on_phase(Phase::Saturate);
+    let mut engine = Engine::new(store, num_concepts, num_roles, options);
+    let mut processed = 0;
+    loop {
+        // Stop at each step to report, with that item processed here.
+        let stopped = engine.run(|event| {
+            processed += 1;
+            match processed % PROGRESS_STEP == 0 {
+                true => ControlFlow::Break(event),
+                false => ControlFlow::Continue(()),
+            }
+        });
+        let ControlFlow::Break(event) = stopped else {
+            break;
+        };
+        engine.process_event(event);
+        progress.on_progress(processed, processed + engine.pending());
+    }
+    progress.on_progress(processed, processed);
+    engine.contexts
+}
+
+/// A saturation stopped part way: the contexts so far and the worklist
+/// items not yet processed. Every fact in a context has either been
+/// processed or is pending, so saturating on from here reaches the same
+/// contexts as an uninterrupted run.
+#[derive(Clone, Debug, Default)]
+pub struct PartialSaturation {
+    pub contexts: Vec<Context>,
+    pub pending: Vec<TraceEvent>,
+}
+
+/// [`saturate_with`], continuing `from` a stopped run when given. Every
+/// `step` worklist items the contexts and pending items are shown to
+/// `checkpoint`; a `Break` stops saturation there and returns them.
+pub fn saturate_checkpointed(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+    from: Option<PartialSaturation>,
+    step: usize,
+    mut checkpoint: impl FnMut(&[Context], &[TraceEvent]) -> ControlFlow<()>,
+) -> Result<Vec<Context>, PartialSaturation> {
+    let mut engine = match from {
+        Some(from) => Engine::from_partial(store, num_roles, options, from),
+        None => Engine::new(store, num_concepts, num_roles, options),
+    };
+    loop {
+        let mut processed = 0;
+        let stopped = engine.run(|event| {
+            processed += 1;
+            match processed > step.max(1) {
+                true => ControlFlow::Break(event),
+                false => ControlFlow::Continue(()),
+            }
+        });
+        let ControlFlow::Break(event) = stopped else {
+            return Ok(engine.contexts);
+        };
+        engine.push_event(event);
+        let pending = engine.pending_events();
+        if checkpoint(&engine.contexts, &pending).is_break() {
+            return Err(PartialSaturation { contexts: engine.contexts, pending });
+        }
+    }
+}
+
+/// One item taken off a worklist, in the order the engine processed it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// `added` entered the super set of `concept`.
+    Concept { concept: ConceptId, added: ConceptId },
+    /// The link `source -role-> target` was created.
+    Link { source: ConceptId, role: RoleId, target: ConceptId },
+}
+
+/// A replayed event whose fact had not been derived at that point of the
+/// trace, i.e. the trace does not belong to this input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayError {
+    pub index: usize,
+    pub event: TraceEvent,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "trace event {} ({:?}) was never derived", self.index, self.event)
+    }
+}
+
+/// A completion rule, as named in the EL++ calculus; `Init` starts each
+/// context with itself and TOP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    Init,
+    CR1,
+    CR2,
+    CR3,
+    CR4,
+    CR5,
+    CR6,
+    CR10,
+    CR11,
+    CR12,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 10] = [
+        Rule::Init,
+        Rule::CR1,
+        Rule::CR2,
+        Rule::CR3,
+        Rule::CR4,
+        Rule::CR5,
+        Rule::CR6,
+        Rule::CR10,
+        Rule::CR11,
+        Rule::CR12,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::Init => "init",
+            Rule::CR1 => "CR1",
+            Rule::CR2 => "CR2",
+            Rule::CR3 => "CR3",
+            Rule::CR4 => "CR4",
+            Rule::CR5 => "CR5",
+            Rule::CR6 => "CR6",
+            Rule::CR10 => "CR10",
+            Rule::CR11 => "CR11",
+            Rule::CR12 => "CR12",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+/// One rule application that derived a new fact. `premise` is the
+/// worklist item being processed when the rule fired (`None` for `Init`);
+/// any other premises were derived earlier. For CR6 it is the fact copied
+/// from another context holding the same nominal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Derivation {
+    pub rule: Rule,
+    pub premise: Option<TraceEvent>,
+    pub derived: TraceEvent,
+}
+
+/// Saturates like [`saturate_with`], appending every rule application that
+/// derived something to `log`, in the order they happened.
+pub fn saturate_logged(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+    log: &mut Vec<Derivation>,
+) -> Vec<Context> {
+    let mut engine = Engine::new(store, num_concepts, num_roles, options);
+    for c in 0..num_concepts as ConceptId {
+        let seeded = if c == TOP { 1 } else { 2 };
+        for added in [c, TOP].into_iter().take(seeded) {
+            let derived = TraceEvent::Concept { concept: c, added };
+            log.push(Derivation { rule: Rule::Init, premise: None, derived });
+        }
+    }
+    engine.log = Some(core::mem::take(log));
+    let _ = engine.run(|_| ControlFlow::<()>::Continue(()));
+    *log = engine.log.take().unwrap_or_default();
+    engine.contexts
+}
+
+/// Saturates like [`saturate_with`], appending every processed worklist item
+/// to `trace`.
+pub fn saturate_traced(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+    trace: &mut Vec<TraceEvent>,
+) -> Vec<Context> {
+    let mut engine = Engine::new(store, num_concepts, num_roles, options);
+    let _ = engine.run(|event| {
+        trace.push(event);
+        ControlFlow::<()>::Continue(())
+    });
+    engine.contexts
+}
+
+/// Saturates until one of the first `num_named` concepts derives BOTTOM and
+/// returns that concept instead of finishing. With a `trace`, processed items
+/// are appended to it, ending with the offending BOTTOM item.
+pub fn saturate_fail_fast(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+    num_named: usize,
+    mut trace: Option<&mut Vec<TraceEvent>>,
+) -> Result<Vec<Context>, ConceptId> {
+    let mut engine = Engine::new(store, num_concepts, num_roles, options);
+    let flow = engine.run(|event| {
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.push(event);
+        }
+        match event {
+            TraceEvent::Concept { concept, added: BOTTOM }
+                if concept != BOTTOM && (concept as usize) < num_named =>
+            {
+                ControlFlow::Break(concept)
+            }
+            _ => ControlFlow::Continue(()),
+        }
+    });
+    match flow {
+        ControlFlow::Break(concept) => Err(concept),
+        ControlFlow::Continue(()) => Ok(engine.contexts),
+    }
+}
+
+/// Processes exactly the items of a recorded `trace`, in order, instead of
+/// popping the worklists. Same input and trace give the same contexts as the
+/// recorded run, whatever order that run happened to use.
+pub fn replay(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    options: &SaturationOptions,
+    trace: &[TraceEvent],
+) -> Result<Vec<Context>, ReplayError> {
+    let mut engine = Engine::new(store, num_concepts, num_roles, options);
+    let derived = |engine: &Engine, event: TraceEvent| match event {
+        TraceEvent::Concept { concept, added } => engine
+            .contexts
+            .get(concept as usize)
+            .is_some_and(|ctx| ctx.super_set.contains(&added)),
+        TraceEvent::Link { source, role, target } => (target as usize) < num_concepts
+            && engine
+                .contexts
+                .get(source as usize)
+                .and_then(|ctx| ctx.link_map.get(role as usize))
+                .is_some_and(|targets| targets.contains(&target)),
+    };
+    for (index, &event) in trace.iter().enumerate() {
+        // Nominal merges happen between worklist items, not in them.
+        let merged = |engine: &mut Engine| engine.idle() && derived(engine, event);
+        if !derived(&engine, event) && !merged(&mut engine) {
+            return Err(ReplayError { index, event });
+        }
+        match event {
+            TraceEvent::Concept { concept, added } => engine.process(WorkItem { concept, added }),
+            TraceEvent::Link { source, role, target } => {
+                engine.process_link(LinkItem { source, role, target })
+            }
+        }
+        engine.worklist.clear();
+        engine.link_worklist.clear();
+    }
+    Ok(engine.contexts)
+}
+
+/// Saturates the `dirty` concepts on top of `contexts`, in which every other
+/// concept is already saturated for `store` and the dirty ones are empty.
+/// Clean contexts only gain predecessors; the dirty ones are computed from
+/// scratch, reading the clean ones as they are.
+pub fn resaturate(
+    store: &AxiomStore,
+    contexts: Vec<Context>,
+    num_roles: usize,
+    dirty: impl IntoIterator<Item = ConceptId>,
+) -> Vec<Context> {
+    resume(store, contexts, num_roles, dirty, [], |_| {})
+}
+
+/// Continues saturating `contexts` after axioms were added to `store`.
+/// `seeds` are concepts whose (empty) contexts start now; each `(concept,
+/// added)` in `revisit`, with `added` already in the super set of `concept`,
+/// is processed again against the grown store. EL saturation is monotone, so
+/// revisiting every holder of each concept that gained axioms yields the
+/// saturation of the final store. Processed items are shown to `visit`.
+pub fn resume(
+    store: &AxiomStore,
+    contexts: Vec<Context>,
+    num_roles: usize,
+    seeds: impl IntoIterator<Item = ConceptId>,
+    revisit: impl IntoIterator<Item = (ConceptId, ConceptId)>,
+    mut visit: impl FnMut(TraceEvent),
+) -> Vec<Context> {
+    let mut worklist = Worklist::with_capacity(WorklistOrder::Stack, 0);
+    let mut contexts = contexts;
+    for c in seeds {
+        seed(&mut contexts[c as usize], c, &mut worklist);
+    }
+    // Holders of several changed concepts are named once per concept.
+    let mut revisit: Vec<WorkItem> =
+        revisit.into_iter().map(|(concept, added)| WorkItem { concept, added }).collect();
+    revisit.sort_unstable();
+    revisit.dedup();
+    for item in revisit {
+        worklist.push(item);
+    }
+    let mut engine = Engine {
+        store,
+        num_roles,
+        track_bottom: store.derives_bottom(),
+        contexts,
+        worklist,
+        link_worklist: Worklist::with_capacity(WorklistOrder::Stack, 0),
+        log: None,
+        derived: [0; Rule::ALL.len()],
+        rules: RuleSet::standard(),
+    };
+    let _ = engine.run(|event| {
+        visit(event);
+        ControlFlow::<()>::Continue(())
+    });
+    engine.contexts
+}
+
+struct Engine<'a> {
+    store: &'a AxiomStore,
+    num_roles: usize,
+    track_bottom: bool,
+    contexts: Vec<Context>,
+    worklist: Worklist<WorkItem>,
+    link_worklist: Worklist<LinkItem>,
+    /// Rule applications, when saturating with [`saturate_logged`].
+    log: Option<Vec<Derivation>>,
+    /// New facts by [`Rule`] discriminant, since the last report.
+    derived: [usize; Rule::ALL.len()],
+    rules: RuleSet,
+}
+
+impl<'a> Engine<'a> {
+    fn new(
+        store: &'a AxiomStore,
+        num_concepts: usize,
+        num_roles: usize,
+        options: &SaturationOptions,
+    ) -> Self {
+        let hints = &options.capacity;
+        let mut contexts = init_contexts(store, num_concepts, num_roles, hints);
+
+        let told_fillers: usize = store.exist_right.iter().map(Vec::len).sum();
+        let order = options.worklist;
+        let mut worklist = Worklist::with_capacity(order, num_concepts * hints.worklist_per_concept);
+        let link_worklist = Worklist::with_capacity(
+            order,
+            told_fillers.max(num_concepts / 4) * hints.link_worklist_per_filler,
+        );
+
+        for (c, ctx) in contexts.iter_mut().enumerate() {
+            seed(ctx, c as ConceptId, &mut worklist);
+        }
+
+        Self {
+            store,
+            num_roles,
+            track_bottom: store.derives_bottom(),
+            contexts,
+            worklist,
+            link_worklist,
+            log: None,
+            derived: [0; Rule::ALL.len()],
+            rules: options.rules.clone(),
+        }
+    }
+
+    /// An engine that goes on from a stopped run.
+    fn from_partial(
+        store: &'a AxiomStore,
+        num_roles: usize,
+        options: &SaturationOptions,
+        from: PartialSaturation,
+    ) -> Self {
+        let mut engine = Self {
+            store,
+            num_roles,
+            track_bottom: store.derives_bottom(),
+            contexts: from.contexts,
+            worklist: Worklist::with_capacity(options.worklist, from.pending.len()),
+            link_worklist: Worklist::with_capacity(options.worklist, 0),
+            log: None,
+            derived: [0; Rule::ALL.len()],
+            rules: options.rules.clone(),
+        };
+        for event in from.pending {
+            engine.push_event(event);
+        }
+        engine
+    }
+
+    fn push_event(&mut self, event: TraceEvent) {
+        match event {
+            TraceEvent::Concept { concept, added } => self.worklist.push(WorkItem { concept, added }),
+            TraceEvent::Link { source, role, target } => {
+                self.link_worklist.push(LinkItem { source, role, target })
+            }
+        }
+    }
+
+    fn process_event(&mut self, event: TraceEvent) {
+        match event {
+            TraceEvent::Concept { concept, added } => self.process(WorkItem { concept, added }),
+            TraceEvent::Link { source, role, target } => self.process_link(LinkItem { source, role, target }),
+        }
+    }
+
+    fn pending(&self) -> usize {
+        self.worklist.len() + self.link_worklist.len()
+    }
+
+    fn pending_events(&self) -> Vec<TraceEvent> {
+        let concepts = self.worklist.items().map(|i| TraceEvent::Concept { concept: i.concept, added: i.added });
+        let links = self.link_worklist.items().map(|li| TraceEvent::Link {
+            source: li.source,
+            role: li.role,
+            target: li.target,
+        });
+        concepts.chain(links).collect()
+    }
+
+    /// Drains the worklists, showing each item to `visit` before processing
+    /// it; a `Break` stops saturation with that item unprocessed.
+    fn run<B>(&mut self, mut visit: impl FnMut(TraceEvent) -> ControlFlow<B>) -> ControlFlow<B> {
+        while !self.worklist.is_empty() || !self.link_worklist.is_empty() || self.idle() {
+            while let Some(item) = self.worklist.pop() {
+                visit(TraceEvent::Concept { concept: item.concept, added: item.added })?;
+                self.process(item);
+            }
+            while let Some(li) = self.link_worklist.pop() {
+                visit(TraceEvent::Link { source: li.source, role: li.role, target: li.target })?;
+                self.process_link(li);
+            }
+        }
+        self.report_derived();
+        ControlFlow::Continue(())
+    }
+
+    /// Reports the new facts of each rule since the last report.
+    fn report_derived(&mut self) {
+        report_derived(&core::mem::take(&mut self.derived));
+    }
+
+    /// The rules' view of the engine, logging from `premise`, and the rules.
+    fn conclusions(&mut self, premise: Option<TraceEvent>) -> (Conclusions<'a, '_>, &RuleSet) {
+        let cx = Conclusions {
+            store: self.store,
+            num_roles: self.num_roles,
+            track_bottom: self.track_bottom,
+            contexts: &mut self.contexts,
+            worklist: &mut self.worklist,
+            link_worklist: &mut self.link_worklist,
+            log: &mut self.log,
+            derived: &mut self.derived,
+            rule: Rule::Init,
+            premise,
+        };
+        (cx, &self.rules)
+    }
+
+    /// Runs the idle rules once the worklists are empty; returns whether
+    /// anything new went onto them.
+    fn idle(&mut self) -> bool {
+        let (mut cx, rules) = self.conclusions(None);
+        rules.idle(&mut cx)
+    }
+
+    #[inline]
+    fn process(&mut self, item: WorkItem) {
+        let (c, d) = (item.concept, item.added);
+        let premise = Some(TraceEvent::Concept { concept: c, added: d });
+        let (mut cx, rules) = self.conclusions(premise);
+        rules.concept(&mut cx, c, d);
+        if self.rules.has_added() {
+            let (mut cx, rules) = self.conclusions(premise);
+            rules.added_concept(&mut cx, c, d);
+        }
+    }
+
+    #[inline]
+    fn process_link(&mut self, li: LinkItem) {
+        let (c, r, d) = (li.source, li.role, li.target);
+        let premise = Some(TraceEvent::Link { source: c, role: r, target: d });
+        let (mut cx, rules) = self.conclusions(premise);
+        rules.link(&mut cx, c, r, d);
+        if self.rules.has_added() {
+            let (mut cx, rules) = self.conclusions(premise);
+            rules.added_link(&mut cx, c, r, d);
+        }
+    }
+}
+
+/// Emits `derived`, new facts by [`Rule`] discriminant, as `tracing`
+/// events with `rule` and `derived` fields.
+pub(crate) fn report_derived(derived: &[usize; Rule::ALL.len()]) {
+    for (rule, &derived) in Rule::ALL.into_iter().zip(derived) {
+        if derived > 0 {
+            tracing::debug!(rule = rule.name(), derived = derived as u64, "rule derivations");
+        }
+    }
+}
+
+/// Starts the context of `c` with `c` and TOP, once each for TOP itself.
+fn seed(ctx: &mut Context, c: ConceptId, worklist: &mut Worklist<WorkItem>) {
+    for added in [c, TOP] {
+        if ctx.super_set.insert(added) {
+            worklist.push(WorkItem { concept: c, added });
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Least-common-subsumer index over a classified hierarchy.
+//!
+//! Concepts are renumbered by topological rank (ancestors before
+//! descendants) and each one keeps its inferred ancestors as a sorted rank
+//! list. Common ancestors are then a linear merge of two short lists and the
+//! least of them fall out of a single scan from the deepest rank up, so
+//! queries cost O(ancestors) however large the ontology is.
+//!
+//! [`Descriptions`] goes past named concepts: the least common subsumer of
+//! two concepts and the most specific concept of an individual as EL class
+//! expressions, read off the saturated contexts as their description graphs
+//! (named super concepts as labels, links as edges) unraveled to a bounded
+//! depth. Cyclic graphs have no finite answer, so past the depth both are
+//! approximations from above.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::kernel::{AxiomStore, ConceptId, Context, RoleId, BOTTOM, TOP};
+use crate::normalize::ClassExpr;
+use crate::taxonomy::Taxonomy;
+
+pub struct LcsIndex {
+    /// Rank → concept.
+    order: Vec<ConceptId>,
+    /// Concept → rank.
+    rank: Vec<u32>,
+    /// `ancestors[offsets[c]..offsets[c + 1]]`: ranks of the ancestors of
+    /// concept `c` (itself included), ascending.
+    offsets: Vec<usize>,
+    ancestors: Vec<u32>,
+}
+
+impl LcsIndex {
+    /// Indexes the concepts of `taxonomy`, reading ancestors off the
+    /// saturated `contexts` it was built from.
+    pub fn new(contexts: &[Context], taxonomy: &Taxonomy) -> Self {
+        let n = taxonomy.len();
+        let order = taxonomy.topological_order();
+        let mut rank = vec![0u32; n];
+        for (r, &c) in order.iter().enumerate() {
+            rank[c as usize] = r as u32;
+        }
+
+        let mut offsets = Vec::with_capacity(n + 1);
+        let mut ancestors = Vec::new();
+        offsets.push(0);
+        for ctx in &contexts[..n] {
+            let start = ancestors.len();
+            ancestors.extend(
+                ctx.super_set
+                    .iter()
+                    .filter(|&&s| (s as usize) < n && s != BOTTOM)
+                    .map(|&s| rank[s as usize]),
+            );
+            ancestors[start..].sort_unstable();
+            offsets.push(ancestors.len());
+        }
+
+        Self { order, rank, offsets, ancestors }
+    }
+
+    fn ancestor_ranks(&self, c: ConceptId) -> &[u32] {
+        &self.ancestors[self.offsets[c as usize]..self.offsets[c as usize + 1]]
+    }
+
+    /// Number of ancestors of `c`, itself included.
+    pub fn ancestor_count(&self, c: ConceptId) -> usize {
+        self.ancestor_ranks(c).len()
+    }
+
+    /// True if `a` is subsumed by `b` (or is `b`).
+    pub fn is_ancestor(&self, b: ConceptId, a: ConceptId) -> bool {
+        self.ancestor_ranks(a).binary_search(&self.rank[b as usize]).is_ok()
+    }
+
+    fn common_ranks(&self, a: ConceptId, b: ConceptId) -> Vec<u32> {
+        let (x, y) = (self.ancestor_ranks(a), self.ancestor_ranks(b));
+        let mut common = Vec::with_capacity(x.len().min(y.len()));
+        let (mut i, mut j) = (0, 0);
+        while i < x.len() && j < y.len() {
+            match x[i].cmp(&y[j]) {
+                core::cmp::Ordering::Less => i += 1,
+                core::cmp::Ordering::Greater => j += 1,
+                core::cmp::Ordering::Equal => {
+                    common.push(x[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        common
+    }
+
+    /// Concepts subsuming both `a` and `b`, ancestors before descendants.
+    pub fn common_ancestors(&self, a: ConceptId, b: ConceptId) -> Vec<ConceptId> {
+        self.common_ranks(a, b).into_iter().map(|r| self.order[r as usize]).collect()
+    }
+
+    /// The most specific common subsumers of `a` and `b`: common ancestors
+    /// with no other common ancestor below them. Usually a single concept;
+    /// several when the hierarchy branches, in ascending concept order.
+    pub fn least_common_subsumers(&self, a: ConceptId, b: ConceptId) -> Vec<ConceptId> {
+        let mut least: Vec<ConceptId> = Vec::new();
+        // Descendants have higher ranks, so each candidate is met before
+        // any of its ancestors.
+        for r in self.common_ranks(a, b).into_iter().rev() {
+            let c = self.order[r as usize];
+            if !least.iter().any(|&l| self.is_ancestor(c, l)) {
+                least.push(c);
+            }
+        }
+        least.sort_unstable();
+        least
+    }
+}
+
+/// Least common subsumers and most specific concepts as class expressions.
+pub struct Descriptions<'c> {
+    contexts: &'c [Context],
+    /// Concepts below this may name a description; the rest are unraveled.
+    num_named: usize,
+    /// Told super roles of each role, itself included.
+    role_sups: Vec<Vec<RoleId>>,
+    depth: usize,
+}
+
+impl<'c> Descriptions<'c> {
+    /// Nesting depth of existentials unless [`Self::with_depth`] says otherwise.
+    pub const DEFAULT_DEPTH: usize = 3;
+
+    /// Reads descriptions off `contexts`, the saturation of `store`.
+    pub fn new(store: &AxiomStore, contexts: &'c [Context], num_named: usize) -> Self {
+        let num_roles = store.role_sub_to_sups.len();
+        let role_sups = (0..num_roles as RoleId)
+            .map(|r| {
+                let mut sups = vec![r];
+                let mut i = 0;
+                while i < sups.len() {
+                    for &s in &store.role_sub_to_sups[sups[i] as usize] {
+                        if !sups.contains(&s) {
+                            sups.push(s);
+                        }
+                    }
+                    i += 1;
+                }
+                sups
+            })
+            .collect();
+        Self { contexts, num_named, role_sups, depth: Self::DEFAULT_DEPTH }
+    }
+
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    fn is_sub(&self, a: ConceptId, b: ConceptId) -> bool {
+        a == b || self.contexts[a as usize].super_set.contains(&b)
+    }
+
+    fn is_sub_role(&self, r: RoleId, s: RoleId) -> bool {
+        r == s || self.role_sups.get(r as usize).is_some_and(|sups| sups.contains(&s))
+    }
+
+    /// The least common subsumer of `a` and `b`: every named concept and
+    /// existential, up to the depth, that subsumes both.
+    pub fn least_common_subsumer(&self, a: ConceptId, b: ConceptId) -> ClassExpr {
+        self.lcs(a, b, self.depth)
+    }
+
+    /// The most specific concept of `c`, usually an individual's `{a}` or a
+    /// fresh concept: a named `c` is its own.
+    pub fn most_specific_concept(&self, c: ConceptId) -> ClassExpr {
+        self.msc(c, self.depth)
+    }
+
+    fn lcs(&self, a: ConceptId, b: ConceptId, depth: usize) -> ClassExpr {
+        if self.is_sub(a, b) || self.is_sub(a, BOTTOM) {
+            return self.msc(b, depth);
+        }
+        if self.is_sub(b, a) || self.is_sub(b, BOTTOM) {
+            return self.msc(a, depth);
+        }
+        let (x, y) = (&self.contexts[a as usize], &self.contexts[b as usize]);
+        let common = x.super_set.iter().copied().filter(|&c| y.super_set.contains(&c));
+        let labels = self.least(common);
+        let mut existentials = Vec::new();
+        if depth > 0 {
+            // ∃r.lcs(d, e) for r-successors d of a and e of b, skipping pairs
+            // below another one under a sub role.
+            let mut pairs: Vec<(RoleId, ConceptId, ConceptId)> = Vec::new();
+            for (r, (ds, es)) in x.link_map.iter().zip(&y.link_map).enumerate() {
+                for &d in ds {
+                    pairs.extend(es.iter().map(|&e| (r as RoleId, d, e)));
+                }
+            }
+            let implies = |(r, d, e): (RoleId, ConceptId, ConceptId), (s, f, g): (RoleId, ConceptId, ConceptId)| {
+                self.is_sub_role(r, s) && self.is_sub(d, f) && self.is_sub(e, g)
+            };
+            for (i, &pair) in pairs.iter().enumerate() {
+                let redundant = pairs.iter().enumerate().any(|(j, &other)| {
+                    j != i && implies(other, pair) && (!implies(pair, other) || j < i)
+                });
+                if !redundant {
+                    let (r, d, e) = pair;
+                    existentials.push((r, self.lcs(d, e, depth - 1)));
+                }
+            }
+        }
+        self.reduce(labels, existentials)
+    }
+
+    fn msc(&self, c: ConceptId, depth: usize) -> ClassExpr {
+        if (c as usize) < self.num_named {
+            return ClassExpr::Named(c);
+        }
+        let ctx = &self.contexts[c as usize];
+        if ctx.super_set.contains(&BOTTOM) {
+            return ClassExpr::Named(BOTTOM);
+        }
+        let labels = self.least(ctx.super_set.iter().copied());
+        let mut existentials = Vec::new();
+        if depth > 0 {
+            for (r, ds) in ctx.link_map.iter().enumerate() {
+                existentials.extend(ds.iter().map(|&d| (r as RoleId, self.msc(d, depth - 1))));
+            }
+        }
+        self.reduce(labels, existentials)
+    }
+
+    /// The conjunction of the named `labels` and the existentials `∃r.F`,
+    /// less the existentials a label or another existential implies.
+    fn reduce(&self, labels: Vec<ConceptId>, existentials: Vec<(RoleId, ClassExpr)>) -> ClassExpr {
+        let below = |(r, f): &(RoleId, ClassExpr), (s, g): &(RoleId, ClassExpr)| {
+            self.is_sub_role(*r, *s) && self.subsumes(f, g)
+        };
+        let mut parts: Vec<ClassExpr> = labels.iter().copied().map(ClassExpr::Named).collect();
+        for (i, some) in existentials.iter().enumerate() {
+            let by_label = labels.iter().any(|&l| self.entails_some(l, some.0, &some.1));
+            let by_other = existentials.iter().enumerate().any(|(j, other)| {
+                j != i && below(other, some) && (!below(some, other) || j < i)
+            });
+            if !by_label && !by_other {
+                parts.push(ClassExpr::Some(some.0, Box::new(some.1.clone())));
+            }
+        }
+        conjunction(parts)
+    }
+
+    /// True if `c ⊑ expr` follows from the super concepts and links of `c`.
+    fn entails(&self, c: ConceptId, expr: &ClassExpr) -> bool {
+        match expr {
+            ClassExpr::Named(d) => *d == TOP || self.is_sub(c, *d),
+            ClassExpr::And(parts) => parts.iter().all(|p| self.entails(c, p)),
+            ClassExpr::Some(r, filler) => self.entails_some(c, *r, filler),
+        }
+    }
+
+    fn entails_some(&self, c: ConceptId, r: RoleId, filler: &ClassExpr) -> bool {
+        self.contexts[c as usize].link_map.iter().enumerate().any(|(q, targets)| {
+            self.is_sub_role(q as RoleId, r) && targets.iter().any(|&d| self.entails(d, filler))
+        })
+    }
+
+    /// True if `sub ⊑ sup` structurally: each conjunct of `sup` follows from
+    /// a conjunct of `sub`. Sound, not complete.
+    fn subsumes(&self, sub: &ClassExpr, sup: &ClassExpr) -> bool {
+        let subs = sub.conjuncts();
+        sup.conjuncts().into_iter().all(|goal| {
+            *goal == ClassExpr::Named(TOP)
+                || subs.iter().any(|&have| match (have, goal) {
+                    (ClassExpr::Named(c), goal) => self.entails(*c, goal),
+                    (ClassExpr::Some(s, f), ClassExpr::Some(r, g)) => self.is_sub_role(*s, *r) && self.subsumes(f, g),
+                    _ => false,
+                })
+        })
+    }
+
+    /// The named concepts among `concepts` with no other one strictly below
+    /// them, one per equivalence class, ascending; TOP is left out.
+    fn least(&self, concepts: impl Iterator<Item = ConceptId>) -> Vec<ConceptId> {
+        let mut named: Vec<ConceptId> =
+            concepts.filter(|&c| c != TOP && c != BOTTOM && (c as usize) < self.num_named).collect();
+        named.sort_unstable();
+        named
+            .iter()
+            .enumerate()
+            .filter(|&(i, &c)| {
+                !named.iter().enumerate().any(|(j, &d)| {
+                    j != i && self.is_sub(d, c) && (!self.is_sub(c, d) || j < i)
+                })
+            })
+            .map(|(_, &c)| c)
+            .collect()
+    }
+}
+
+/// `parts` as one expression, TOP when there are none.
+fn conjunction(mut parts: Vec<ClassExpr>) -> ClassExpr {
+    match parts.len() {
+        0 => ClassExpr::Named(TOP),
+        1 => parts.pop().unwrap(),
+        _ => ClassExpr::And(parts),
+    }
+}
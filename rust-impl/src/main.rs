@@ -1,208 +1,2288 @@
-use el_reasoner::{saturate, build_taxonomy, count_inferred_subsumptions, AxiomStore};
-use std::collections::HashMap;
+use el_reasoner::analysis::analyze;
+use el_reasoner::batch::Batch;
+use el_reasoner::cache::{write_taxonomy, CacheKey, ClassificationCache};
+use el_reasoner::changes::diff;
+use el_reasoner::checkpoint::saturate_resumable;
+use el_reasoner::closure::write_closure_index;
+use el_reasoner::dangling::dangling_references;
+use el_reasoner::derivations::{summarize, write_derivations};
+use el_reasoner::explain::{unsat_report, Explainer};
+use el_reasoner::export::{
+    write_ancestor_libsvm, write_edge_list, write_normalized, write_relation_closure, EdgeListView,
+};
+use el_reasoner::impact::{obsoletion_impact, Candidate};
+use el_reasoner::incremental::{reclassify, Snapshot};
+use el_reasoner::input::{parse_document, read_with_imports, InputFormat};
+use el_reasoner::memory::{self, Bytes, Footprint};
+use el_reasoner::merge::{EquivalenceMerge, Precedence};
+use el_reasoner::normalize::Normalizer;
+use el_reasoner::ontology::Ontology;
+use el_reasoner::obo::{
+    add_definitions, build_axiom_store, build_told_axiom_store, content_stats,
+    scan_alt_ids, scan_idspaces, scan_xrefs, write_with_inferred, ParseResult, SymbolIndex, Warning, WarningKind,
+};
+use el_reasoner::obsolete::{self, ObsoleteMode};
+use el_reasoner::pipeline::parse_and_saturate;
+use el_reasoner::profile::{self, NonEl};
+use el_reasoner::progress::{NoProgress, Phase, ProgressReader, ProgressSink};
+use el_reasoner::query::{
+    parse_class_expression, parse_retrieval, read_named_queries, render_class_expression, QueryEngine, QueryError, Retrieval, Symbols,
+};
+#[cfg(feature = "owl")]
+use el_reasoner::owl::{write_ofn, write_ofn_with_inferred};
+use el_reasoner::json::Value;
+use el_reasoner::output::{
+    open_sink, write_classification, write_dot, ClassificationView, JsonWriter, OutputFormat,
+};
+use el_reasoner::resolve::{AltIds, CurieMap, IdResolver, PrefixCase, Verbatim};
+#[cfg(feature = "serve")]
+use el_reasoner::server::Service;
+use el_reasoner::similarity::{write_matrix_tsv, Measure, Similarity};
+use el_reasoner::telemetry::Recorder;
+use el_reasoner::trace::{read_trace, write_trace};
+use el_reasoner::verify::{read_reference_tsv, reference_pairs, verify};
+use el_reasoner::{
+    realize, replay, saturate, saturate_fail_fast, saturate_parallel, build_taxonomy_parallel_among, saturate_logged, saturate_traced, saturate_with, saturate_with_progress,
+    build_taxonomy, build_taxonomy_among, count_inferred_subsumptions, equivalence_classes_among, is_consistent, sort_direct_parents, unsatisfiable_concepts, AxiomStore, ConceptId, Context, Descriptions,
+    Reasoner, RoleId, Rule, RuleSet, SaturationOptions, Taxonomy, TraceEvent, WorklistOrder, BOTTOM, TOP,
+};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
 use std::env;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::time::Instant;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+#[cfg(feature = "serve")]
+use std::net::TcpListener;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{info, info_span};
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <input.obo>", args[0]);
-        std::process::exit(1);
-    }
+#[cfg(feature = "arena")]
+#[global_allocator]
+static ALLOCATOR: el_reasoner::arena::Arena = el_reasoner::arena::Arena::new();
 
-    let input_path = &args[1];
-    
-    // Parse OBO
-    let parse_start = Instant::now();
-    let file = File::open(input_path).expect("Failed to open input");
-    let reader = BufReader::with_capacity(1024 * 1024, file);
-    let parse_result = parse_obo(reader);
-    let parse_time = parse_start.elapsed();
-    
-    let num_concepts = parse_result.concepts.len();
-    let num_roles = parse_result.roles.len();
-    eprintln!("Parsed {} concepts, {} roles in {:?}", num_concepts, num_roles, parse_time);
+const USAGE: &str = "Usage: el-reasoner [classify | stats [--content]] <input>... \
+                     [--input-format auto|obo|ofn|rdfxml|ttl|json|krss] \
+                     [--output <path|->] [--format tsv|obo|dot|json]\n                     \
+                     [--subtree <term>] [--queries <file>]\n                     \
+                     [--record-trace <file> | --replay-trace <file> | --derivation-log <file>]\n                     \
+                     [--deterministic] \
+                     [--fail-on-unsat] [--fail-on-undeclared] [--strict]\n                     \
+                     [--alt-ids] [--prefix P[=IRI]]...\n                     \
+                     [--xref-equivalence PREFIX]... [--non-el error|skip|approximate]\n                     \
+                     [--approximation-report <file>] \
+                     [--obsolete keep|drop|replace] [--ids curie|iri]\n                     \
+                     [--roots] [--leaves] [--with-depth] [--cache-dir <dir>] [--pipeline]\n                     \
+                     [--dump-normalized <file>] [--types <file>] [--save-taxonomy <file>] [--save-closure <file>]
+                     [--stats-json <file>]\n                     \
+                     [--threads N] [--worklist stack|queue|priority] [--disable-rule CRn]... [--progress] [--memory]\n                     \
+                     [--checkpoint <file> [--checkpoint-interval SECS]]\n                     \
+                     [--merge-equivalents [--canonical-prefix P]... [--merge-map <file>]]\n       \
+                     el-reasoner changes|diff <old.obo> <new.obo> [--output <path|->]\n       \
+                     el-reasoner query [sub|sup|ancestors|descendants|equivalents] <input> \
+                     ['<class expression or retrieval query>'] [--indirect]\n       \
+                     el-reasoner convert <input> [--format ofn] [--output <path|->]\n       \
+                     el-reasoner materialize <input> [--format obo|ofn] [--equivalents] \
+                     [--output <path|->]\n       \
+                     el-reasoner similarity <input> <terms.txt> [--measure resnik|lin|jaccard] \
+                     [--threads N] [--output <path|->] [--format tsv|parquet]\n       \
+                     el-reasoner export <input> --kind ancestors|edges|relations [--told-only] \
+                     [--role <role>]... [--output <path|->]\n       \
+                     el-reasoner impact <input> <candidates.txt> [--output <path|->]\n       \
+                     el-reasoner explain <input> <sub> <sup> [--limit N] [--output <path|->]\n       \
+                     el-reasoner lcs <input> <term> <term> [--depth N] [--output <path|->]\n       \
+                     el-reasoner batch <input> <questions.tsv|-> [--output <path|->]\n       \
+                     el-reasoner analyze <input> [--format text|json] [--output <path|->]
+       el-reasoner dangling <input> [--output <path|->]\n       \
+                     el-reasoner unsat <input> [<term>] [--limit N] [--output <path|->]\n       \
+                     el-reasoner verify <input> <reference.tsv|reference ontology> [--output <path|->]\n       \
+                     el-reasoner derivations <log> [--limit N] [--output <path|->]\n       \
+                     el-reasoner watch <input> [--interval <ms>] [--output <path>] [--format tsv|obo]\n       \
+                     el-reasoner serve <input> [--listen <host:port>]";
 
-    // Build axiom store
-    let build_start = Instant::now();
-    let store = build_axiom_store(&parse_result);
-    let build_time = build_start.elapsed();
-    eprintln!("Built axiom store in {:?}", build_time);
+enum Command {
+    Classify,
+    /// Classification stats on stdout, or a content scan without reasoning.
+    Stats { content: bool },
+    /// Term change report between two releases; `input` is the new one.
+    Changes { old: String },
+    /// DL query; `expression` is the second positional argument.
+    Query { expression: String, indirect: bool, kind: QueryKind },
+    /// The parsed axioms rewritten as OWL functional syntax.
+    Convert,
+    /// The input with the inferred direct subsumptions, and equivalences
+    /// with `equivalents`, added as axioms marked inferred: OBO when `ofn`
+    /// is false, OWL functional syntax when true, the input's own format
+    /// when `None`.
+    Materialize { ofn: Option<bool>, equivalents: bool },
+    /// Pairwise similarity of the terms (IDs or labels, one per line) listed
+    /// in `terms`, the second positional argument.
+    Similarity { terms: String, measure: Measure, parquet: bool },
+    /// Whole-hierarchy exports for graph/ML tools; `roles` (IDs or labels)
+    /// select the relations of [`ExportKind::Relations`].
+    Export { kind: ExportKind, told_only: bool, roles: Vec<String> },
+    /// Impact report for obsoleting or merging the terms listed in
+    /// `candidates` (`ID` or `ID REPLACEMENT` per line).
+    Impact { candidates: String },
+    /// Justifications of `sub ⊑ sup`, both IDs or labels; at most `limit`.
+    Explain { sub: String, sup: String, limit: usize },
+    /// Least common subsumer of `a` and `b`, both IDs or labels, as a class
+    /// expression with existentials nested at most `depth` deep.
+    Lcs { a: String, b: String, depth: usize },
+    /// Answers to the subsumption and hierarchy questions in `questions`,
+    /// the second positional argument (see [`el_reasoner::batch`]).
+    Batch { questions: String },
+    /// Structural metrics of the inferred taxonomy, as text or JSON.
+    Analyze { json: bool },
+    /// Classes referred to but never declared, by ID prefix.
+    Dangling,
+    /// Why classes are unsatisfiable and which axiom removals fix them:
+    /// every unsatisfiable class, or just `term`; at most `limit`
+    /// justifications each.
+    Unsat { term: Option<String>, limit: usize },
+    /// Conformance against a reference classification, the second
+    /// positional argument: a TSV of sub/super pairs or an ontology.
+    Verify { reference: String },
+    /// Rule firing counts of a `--derivation-log` file, the input, and its
+    /// `limit` busiest contexts.
+    Derivations { limit: usize },
+    /// Reclassify incrementally whenever the input file changes.
+    Watch { interval: Duration },
+    /// Answer hierarchy queries over HTTP (see `openapi.yaml`) on `listen`.
+    Serve { listen: String },
+}
 
-    // Saturate
-    let sat_start = Instant::now();
-    let contexts = saturate(&store, num_concepts, num_roles);
-    let sat_time = sat_start.elapsed();
-    eprintln!("Saturation complete in {:?}", sat_time);
+/// Which part of a DL query answer to print.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum QueryKind {
+    All,
+    Equivalents,
+    /// Direct subclasses, or all with `--indirect`.
+    Sub,
+    /// Direct superclasses, or all with `--indirect`.
+    Sup,
+    Ancestors,
+    Descendants,
+}
 
-    // Build taxonomy
-    let tax_start = Instant::now();
-    let _taxonomy = build_taxonomy(&contexts, num_concepts);
-    let tax_time = tax_start.elapsed();
-    eprintln!("Taxonomy built in {:?}", tax_time);
+#[derive(Clone, Copy)]
+enum ExportKind {
+    /// Sparse ancestor vectors in libsvm format.
+    Ancestors,
+    /// Role-labeled edge list.
+    Edges,
+    /// Inferred `C ⊑ ∃r.D` pairs of the chosen roles.
+    Relations,
+}
 
-    // Count inferred subsumptions
-    let inferred = count_inferred_subsumptions(&contexts);
-
-    eprintln!("\n=== Classification Stats ===");
-    eprintln!("Concepts: {}", num_concepts - 2);
-    eprintln!("Roles: {}", num_roles);
-    eprintln!("Inferred subsumptions: {}", inferred);
-    eprintln!("Parse time: {:?}", parse_time);
-    eprintln!("Normalize time: {:?}", build_time);
-    eprintln!("Saturation time: {:?}", sat_time);
-    eprintln!("Reduction time: {:?}", tax_time);
-    eprintln!("Total time: {:?}", parse_time + build_time + sat_time + tax_time);
-}
-
-struct ParseResult {
-    concepts: Vec<String>,
-    roles: Vec<String>,
-    concept_idx: HashMap<String, usize>,
-    role_idx: HashMap<String, usize>,
-    subsumptions: Vec<(usize, usize)>,
-    relations: Vec<(usize, usize, usize)>,
-}
-
-fn parse_obo(reader: BufReader<File>) -> ParseResult {
-    let mut concepts: Vec<String> = vec!["owl:Thing".to_string(), "owl:Nothing".to_string()];
-    let mut roles: Vec<String> = Vec::new();
-    let mut concept_idx: HashMap<String, usize> = HashMap::new();
-    let mut role_idx: HashMap<String, usize> = HashMap::new();
-    
-    concept_idx.insert("owl:Thing".to_string(), 0);
-    concept_idx.insert("owl:Nothing".to_string(), 1);
-
-    let mut subsumptions: Vec<(usize, usize)> = Vec::new();
-    let mut relations: Vec<(usize, usize, usize)> = Vec::new();
-
-    let mut current_id: Option<usize> = None;
-    let mut is_obsolete = false;
-    let mut in_term = false;
-
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => continue,
-        };
-        let line = line.trim();
-        
-        if line.is_empty() {
-            continue;
+struct Args {
+    command: Command,
+    input: String,
+    /// Further inputs of classify and stats, merged into the first.
+    more_inputs: Vec<String>,
+    input_format: Option<InputFormat>,
+    output: Option<PathBuf>,
+    format: OutputFormat,
+    /// `name = expression` file of standing queries added before saturation.
+    queries: Option<PathBuf>,
+    trace: Option<TraceMode>,
+    /// Canonical contexts and direct-parent output, identical across runs
+    /// and thread counts.
+    deterministic: bool,
+    /// Stop at the first named concept that derives BOTTOM.
+    fail_on_unsat: bool,
+    /// Stop if axioms refer to classes nothing declares.
+    fail_on_undeclared: bool,
+    /// Treat parse warnings as errors.
+    strict: bool,
+    /// `--prefix PREFIX[=BASE_IRI]`: canonical prefix spelling, optional IRI base.
+    prefixes: Vec<(String, Option<String>)>,
+    /// Resolve references to `alt_id`s to their primary term.
+    alt_ids: bool,
+    /// `--xref-equivalence PREFIX`: OBO `xref:`s to terms with these
+    /// prefixes are read as equivalences.
+    xref_prefixes: Vec<String>,
+    /// What to do with axioms outside OWL 2 EL.
+    non_el: NonEl,
+    /// Where to list the EL axioms `--non-el approximate` added.
+    approximation_report: Option<PathBuf>,
+    /// What to do with obsolete terms and the axioms referring to them.
+    obsolete: ObsoleteMode,
+    /// `--ids iri`: name terms by full IRI rather than CURIE everywhere.
+    iris: bool,
+    /// Print the inferred roots (concepts directly under owl:Thing).
+    print_roots: bool,
+    /// Print the inferred leaves (concepts without children).
+    print_leaves: bool,
+    /// Add min/max depth columns to TSV output.
+    with_depth: bool,
+    /// Worker threads for parallel steps. Similarity defaults to all cores;
+    /// saturation and taxonomy reduction run on the calling thread unless
+    /// this is given.
+    threads: Option<NonZeroUsize>,
+    /// Order of the saturation worklists on the calling thread.
+    worklist: WorklistOrder,
+    /// Completion rules left out of saturation, to profile them.
+    disabled_rules: Vec<Rule>,
+    /// Saturation state file to resume from and to write every
+    /// `checkpoint_interval`.
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    /// Directory of saturated results keyed by input content hash.
+    cache_dir: Option<PathBuf>,
+    /// Collapse equivalence groups to one canonical term in the output.
+    merge_equivalents: bool,
+    /// Prefixes in order of preference for the canonical term.
+    canonical_prefixes: Vec<String>,
+    /// Where to write the `merged<TAB>canonical` map.
+    merge_map: Option<PathBuf>,
+    /// Saturate on a second thread while the input is still being parsed.
+    pipeline: bool,
+    /// Where to write the normalized axioms (`NF1`–`NF4` lines) reasoned over.
+    dump_normalized: Option<PathBuf>,
+    /// Where to write the `individual<TAB>type` direct types of the individuals.
+    types: Option<PathBuf>,
+    /// Where to write the taxonomy in the binary format of `cache::read_taxonomy`.
+    save_taxonomy: Option<PathBuf>,
+    /// Closure index to write; see [`el_reasoner::closure`].
+    save_closure: Option<PathBuf>,
+    /// Machine-readable run statistics to write; see [`el_reasoner::telemetry`].
+    stats_json: Option<PathBuf>,
+    /// Draw only this concept (ID or label) and its descendants in `dot` output.
+    subtree: Option<String>,
+    /// Show a progress bar on stderr while parsing, saturating and reducing.
+    progress: bool,
+    /// Report peak memory per phase and the footprint of the contexts.
+    memory: bool,
+}
+
+/// Saturation trace handling for classify/stats.
+enum TraceMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+    /// Log every rule application as JSON lines.
+    Derivations(PathBuf),
+}
+
+fn parse_args() -> Args {
+    let mut positional: Vec<String> = Vec::new();
+    let mut input_format = None;
+    let mut output = None;
+    let mut format = OutputFormat::Tsv;
+    let mut queries = None;
+    let mut trace = None;
+    let mut deterministic = false;
+    let mut fail_on_unsat = false;
+    let mut fail_on_undeclared = false;
+    let mut strict = false;
+    let mut prefixes = Vec::new();
+    let mut alt_ids = false;
+    let mut xref_prefixes = Vec::new();
+    let mut iris = false;
+    let mut non_el = NonEl::default();
+    let mut approximation_report = None;
+    let mut obsolete = ObsoleteMode::default();
+    let mut print_roots = false;
+    let mut print_leaves = false;
+    let mut with_depth = false;
+    let mut threads = None;
+    let mut worklist = WorklistOrder::default();
+    let mut disabled_rules = Vec::new();
+    let mut checkpoint = None;
+    let mut checkpoint_interval = Duration::from_secs(600);
+    let mut cache_dir = None;
+    let mut merge_equivalents = false;
+    let mut canonical_prefixes = Vec::new();
+    let mut merge_map = None;
+    let mut pipeline = false;
+    let mut dump_normalized = None;
+    let mut types = None;
+    let mut save_taxonomy = None;
+    let mut save_closure = None;
+    let mut stats_json = None;
+    let mut subtree = None;
+    let mut progress = false;
+    let mut memory = false;
+
+    let mut args = env::args().skip(1).peekable();
+    let mut command = match args.peek().map(String::as_str) {
+        Some("stats") => {
+            args.next();
+            Command::Stats { content: false }
+        }
+        Some("classify") => {
+            args.next();
+            Command::Classify
+        }
+        Some("changes" | "diff") => {
+            args.next();
+            let old = args.next().unwrap_or_else(|| fail("changes needs <old.obo> <new.obo>"));
+            Command::Changes { old }
+        }
+        Some("query") => {
+            args.next();
+            let kind = match args.peek().map(String::as_str) {
+                Some("sub") => QueryKind::Sub,
+                Some("sup") => QueryKind::Sup,
+                Some("ancestors") => QueryKind::Ancestors,
+                Some("descendants") => QueryKind::Descendants,
+                Some("equivalents") => QueryKind::Equivalents,
+                _ => QueryKind::All,
+            };
+            if kind != QueryKind::All {
+                args.next();
+            }
+            Command::Query { expression: String::new(), indirect: false, kind }
+        }
+        Some("convert") => {
+            args.next();
+            Command::Convert
+        }
+        Some("materialize") => {
+            args.next();
+            Command::Materialize { ofn: None, equivalents: false }
+        }
+        Some("export") => {
+            args.next();
+            Command::Export { kind: ExportKind::Ancestors, told_only: false, roles: Vec::new() }
+        }
+        Some("impact") => {
+            args.next();
+            Command::Impact { candidates: String::new() }
         }
+        Some("explain") => {
+            args.next();
+            Command::Explain { sub: String::new(), sup: String::new(), limit: 10 }
+        }
+        Some("lcs") => {
+            args.next();
+            Command::Lcs { a: String::new(), b: String::new(), depth: Descriptions::DEFAULT_DEPTH }
+        }
+        Some("batch") => {
+            args.next();
+            Command::Batch { questions: String::new() }
+        }
+        Some("analyze") => {
+            args.next();
+            Command::Analyze { json: false }
+        }
+        Some("dangling") => {
+            args.next();
+            Command::Dangling
+        }
+        Some("unsat") => {
+            args.next();
+            Command::Unsat { term: None, limit: 10 }
+        }
+        Some("verify") => {
+            args.next();
+            Command::Verify { reference: String::new() }
+        }
+        Some("derivations") => {
+            args.next();
+            Command::Derivations { limit: 10 }
+        }
+        Some("watch") => {
+            args.next();
+            Command::Watch { interval: Duration::from_millis(500) }
+        }
+        Some("serve") => {
+            args.next();
+            Command::Serve { listen: "127.0.0.1:8080".to_string() }
+        }
+        Some("similarity") => {
+            args.next();
+            Command::Similarity { terms: String::new(), measure: Measure::Resnik, parquet: false }
+        }
+        _ => Command::Classify,
+    };
 
-        if line == "[Term]" {
-            in_term = true;
-            current_id = None;
-            is_obsolete = false;
-            continue;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--content" => match &mut command {
+                Command::Stats { content } => *content = true,
+                _ => fail("--content is only valid with `stats`"),
+            },
+            "--indirect" => match &mut command {
+                Command::Query { indirect, .. } => *indirect = true,
+                _ => fail("--indirect is only valid with `query`"),
+            },
+            "--input-format" => {
+                let name = expect_value(&arg, args.next());
+                if name != "auto" {
+                    input_format = Some(InputFormat::from_name(&name)
+                        .unwrap_or_else(|| fail(&format!("unknown input format '{}'", name))));
+                }
+            }
+            "--deterministic" => deterministic = true,
+            "--fail-on-unsat" => fail_on_unsat = true,
+            "--fail-on-undeclared" => fail_on_undeclared = true,
+            "--strict" => strict = true,
+            "--alt-ids" => alt_ids = true,
+            "--xref-equivalence" => xref_prefixes.push(expect_value(&arg, args.next())),
+            "--non-el" => {
+                let name = expect_value(&arg, args.next());
+                non_el = NonEl::from_name(&name)
+                    .unwrap_or_else(|| fail(&format!("unknown --non-el mode '{}'", name)));
+            }
+            "--approximation-report" => {
+                approximation_report = Some(PathBuf::from(expect_value(&arg, args.next())))
+            }
+            "--ids" => {
+                iris = match expect_value(&arg, args.next()).as_str() {
+                    "curie" => false,
+                    "iri" => true,
+                    other => fail(&format!("unknown --ids form '{}'", other)),
+                }
+            }
+            "--roots" => print_roots = true,
+            "--leaves" => print_leaves = true,
+            "--with-depth" => with_depth = true,
+            "--merge-equivalents" => merge_equivalents = true,
+            "--canonical-prefix" => canonical_prefixes.push(expect_value(&arg, args.next())),
+            "--merge-map" => merge_map = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "--pipeline" => pipeline = true,
+            "--progress" => progress = true,
+            "--memory" => memory = true,
+            "--dump-normalized" => {
+                dump_normalized = Some(PathBuf::from(expect_value(&arg, args.next())))
+            }
+            "--types" => types = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "--subtree" => subtree = Some(expect_value(&arg, args.next())),
+            "--save-taxonomy" => {
+                save_taxonomy = Some(PathBuf::from(expect_value(&arg, args.next())))
+            }
+            "--save-closure" => save_closure = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "--stats-json" => stats_json = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "--measure" => match &mut command {
+                Command::Similarity { measure, .. } => {
+                    let name = expect_value(&arg, args.next());
+                    *measure = Measure::from_name(&name)
+                        .unwrap_or_else(|| fail(&format!("unknown measure '{}'", name)));
+                }
+                _ => fail("--measure is only valid with `similarity`"),
+            },
+            "--kind" => match &mut command {
+                Command::Export { kind, .. } => {
+                    let name = expect_value(&arg, args.next());
+                    *kind = match name.as_str() {
+                        "ancestors" => ExportKind::Ancestors,
+                        "edges" => ExportKind::Edges,
+                        "relations" => ExportKind::Relations,
+                        _ => fail(&format!("unknown export kind '{}'", name)),
+                    };
+                }
+                _ => fail("--kind is only valid with `export`"),
+            },
+            "--limit" => match &mut command {
+                Command::Explain { limit, .. } | Command::Unsat { limit, .. } | Command::Derivations { limit } => {
+                    let n = expect_value(&arg, args.next());
+                    *limit = n.parse().unwrap_or_else(|_| fail(&format!("bad limit '{}'", n)));
+                }
+                _ => fail("--limit is only valid with `explain`, `unsat` and `derivations`"),
+            },
+            "--depth" => match &mut command {
+                Command::Lcs { depth, .. } => {
+                    let n = expect_value(&arg, args.next());
+                    *depth = n.parse().unwrap_or_else(|_| fail(&format!("bad depth '{}'", n)));
+                }
+                _ => fail("--depth is only valid with `lcs`"),
+            },
+            "--interval" => match &mut command {
+                Command::Watch { interval } => {
+                    let ms = expect_value(&arg, args.next());
+                    *interval = Duration::from_millis(
+                        ms.parse().unwrap_or_else(|_| fail(&format!("bad interval '{}'", ms))),
+                    );
+                }
+                _ => fail("--interval is only valid with `watch`"),
+            },
+            "--listen" => match &mut command {
+                Command::Serve { listen } => *listen = expect_value(&arg, args.next()),
+                _ => fail("--listen is only valid with `serve`"),
+            },
+            "--equivalents" => match &mut command {
+                Command::Materialize { equivalents, .. } => *equivalents = true,
+                _ => fail("--equivalents is only valid with `materialize`"),
+            },
+            "--told-only" => match &mut command {
+                Command::Export { told_only, .. } => *told_only = true,
+                _ => fail("--told-only is only valid with `export`"),
+            },
+            "--role" => match &mut command {
+                Command::Export { roles, .. } => roles.push(expect_value(&arg, args.next())),
+                _ => fail("--role is only valid with `export`"),
+            },
+            "--threads" => {
+                let n = expect_value(&arg, args.next());
+                threads = Some(n.parse().unwrap_or_else(|_| fail(&format!("bad thread count '{}'", n))));
+            }
+            "--obsolete" => {
+                let name = expect_value(&arg, args.next());
+                obsolete = ObsoleteMode::from_name(&name)
+                    .unwrap_or_else(|| fail(&format!("unknown --obsolete mode '{}'", name)));
+            }
+            "--checkpoint" => checkpoint = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "--checkpoint-interval" => {
+                let secs = expect_value(&arg, args.next());
+                checkpoint_interval = Duration::from_secs(
+                    secs.parse().unwrap_or_else(|_| fail(&format!("bad checkpoint interval '{}'", secs))),
+                );
+            }
+            "--worklist" => {
+                let name = expect_value(&arg, args.next());
+                worklist = WorklistOrder::from_name(&name)
+                    .unwrap_or_else(|| fail(&format!("unknown --worklist order '{}'", name)));
+            }
+            "--disable-rule" => {
+                let name = expect_value(&arg, args.next());
+                disabled_rules.push(
+                    Rule::from_name(&name)
+                        .filter(|&rule| rule != Rule::Init)
+                        .unwrap_or_else(|| fail(&format!("unknown completion rule '{}'", name))),
+                );
+            }
+            "--prefix" => {
+                let spec = expect_value(&arg, args.next());
+                prefixes.push(match spec.split_once('=') {
+                    Some((prefix, base)) => (prefix.to_string(), Some(base.to_string())),
+                    None => (spec, None),
+                });
+            }
+            "--cache-dir" => cache_dir = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "--queries" => queries = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "--record-trace" | "--replay-trace" | "--derivation-log" => {
+                if trace.is_some() {
+                    fail("--record-trace, --replay-trace and --derivation-log are mutually exclusive");
+                }
+                let path = PathBuf::from(expect_value(&arg, args.next()));
+                trace = Some(match arg.as_str() {
+                    "--record-trace" => TraceMode::Record(path),
+                    "--replay-trace" => TraceMode::Replay(path),
+                    _ => TraceMode::Derivations(path),
+                });
+            }
+            "-o" | "--output" => output = Some(PathBuf::from(expect_value(&arg, args.next()))),
+            "-f" | "--format" => {
+                let name = expect_value(&arg, args.next());
+                match &mut command {
+                    Command::Similarity { parquet, .. } => match name.as_str() {
+                        "tsv" => *parquet = false,
+                        "parquet" => *parquet = true,
+                        _ => fail(&format!("unknown similarity format '{}'", name)),
+                    },
+                    Command::Convert if name != "ofn" => {
+                        fail(&format!("unknown convert format '{}'", name))
+                    }
+                    Command::Convert => {}
+                    Command::Analyze { json } => match name.as_str() {
+                        "text" => *json = false,
+                        "json" => *json = true,
+                        _ => fail(&format!("unknown analyze format '{}'", name)),
+                    },
+                    Command::Materialize { ofn, .. } => match name.as_str() {
+                        "obo" => *ofn = Some(false),
+                        "ofn" => *ofn = Some(true),
+                        _ => fail(&format!("unknown materialize format '{}'", name)),
+                    },
+                    _ => {
+                        format = OutputFormat::from_name(&name)
+                            .unwrap_or_else(|| fail(&format!("unknown output format '{}'", name)))
+                    }
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg == "-" || !arg.starts_with('-') => positional.push(arg),
+            _ => fail(&format!("unexpected argument '{}'", arg)),
         }
+    }
 
-        if line.starts_with("[Typedef]") {
-            in_term = false;
-            current_id = None;
-            continue;
+    let mut positional = positional.into_iter();
+    let input = positional.next().unwrap_or_else(|| fail("missing input file"));
+    if let Command::Query { expression, .. } = &mut command {
+        // Without one, queries are read from standard input.
+        *expression = positional.next().unwrap_or_default();
+    }
+    if let Command::Similarity { terms, .. } = &mut command {
+        *terms = positional.next().unwrap_or_else(|| fail("similarity needs a terms file"));
+    }
+    if let Command::Impact { candidates } = &mut command {
+        *candidates = positional.next().unwrap_or_else(|| fail("impact needs a candidates file"));
+    }
+    if let Command::Explain { sub, sup, .. } = &mut command {
+        *sub = positional.next().unwrap_or_else(|| fail("explain needs a subclass"));
+        *sup = positional.next().unwrap_or_else(|| fail("explain needs a superclass"));
+    }
+    if let Command::Lcs { a, b, .. } = &mut command {
+        *a = positional.next().unwrap_or_else(|| fail("lcs needs two terms"));
+        *b = positional.next().unwrap_or_else(|| fail("lcs needs two terms"));
+    }
+    if let Command::Batch { questions } = &mut command {
+        *questions = positional.next().unwrap_or_else(|| fail("batch needs a questions file"));
+    }
+    if let Command::Unsat { term, .. } = &mut command {
+        *term = positional.next();
+    }
+    if let Command::Verify { reference } = &mut command {
+        *reference = positional.next().unwrap_or_else(|| fail("verify needs a reference classification"));
+    }
+    match &command {
+        Command::Export { kind: ExportKind::Relations, roles, .. } if roles.is_empty() => {
+            fail("--kind relations needs at least one --role")
+        }
+        Command::Export { kind: ExportKind::Ancestors | ExportKind::Edges, roles, .. }
+            if !roles.is_empty() =>
+        {
+            fail("--role is only valid with --kind relations")
         }
+        _ => {}
+    }
+    if approximation_report.is_some() && non_el != NonEl::Approximate {
+        fail("--approximation-report is only valid with --non-el approximate");
+    }
+    if checkpoint.is_some() && (threads.is_some() || trace.is_some() || fail_on_unsat) {
+        fail("--checkpoint excludes --threads, traces and --fail-on-unsat");
+    }
+    if !disabled_rules.is_empty() && (threads.is_some() || cache_dir.is_some() || checkpoint.is_some() || pipeline) {
+        fail("--disable-rule excludes --threads, --cache-dir, --checkpoint and --pipeline");
+    }
+    let more_inputs: Vec<String> = match command {
+        Command::Classify | Command::Stats { content: false } => positional.by_ref().collect(),
+        _ => Vec::new(),
+    };
+    if let Some(extra) = positional.next() {
+        fail(&format!("unexpected argument '{}'", extra));
+    }
+    Args {
+        command,
+        input,
+        more_inputs,
+        input_format,
+        output,
+        format,
+        queries,
+        trace,
+        deterministic,
+        fail_on_unsat,
+        fail_on_undeclared,
+        strict,
+        prefixes,
+        alt_ids,
+        xref_prefixes,
+        iris,
+        non_el,
+        approximation_report,
+        obsolete,
+        print_roots,
+        print_leaves,
+        with_depth,
+        threads,
+        worklist,
+        disabled_rules,
+        checkpoint,
+        checkpoint_interval,
+        cache_dir,
+        merge_equivalents,
+        canonical_prefixes,
+        merge_map,
+        pipeline,
+        dump_normalized,
+        types,
+        save_taxonomy,
+        save_closure,
+        stats_json,
+        subtree,
+        progress,
+        memory,
+    }
+}
 
-        if line.starts_with('[') {
-            in_term = false;
-            continue;
+fn expect_value(flag: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| fail(&format!("{} needs a value", flag)))
+}
+
+/// Usage errors: the message followed by the usage text.
+fn fail(msg: &str) -> ! {
+    eprintln!("error: {}\n{}", msg, USAGE);
+    process::exit(1);
+}
+
+/// Runtime errors (I/O, bad queries).
+fn die(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(1);
+}
+
+fn resolve_input_format(args: &Args, path: &str) -> InputFormat {
+    match args.input_format {
+        Some(format) => format,
+        None => detect_format(path),
+    }
+}
+
+fn detect_format(path: &str) -> InputFormat {
+    match InputFormat::detect(Path::new(path)) {
+        Ok(Some(format)) => format,
+        Ok(None) => fail(&format!("cannot detect the format of {}; pass --input-format", path)),
+        Err(e) => die(&format!("reading {}: {}", path, e)),
+    }
+}
+
+/// Formats with a front-end in this build.
+fn supported(format: InputFormat) -> bool {
+    match format {
+        InputFormat::Obo => true,
+        InputFormat::OwlFunctional | InputFormat::RdfXml | InputFormat::Turtle | InputFormat::Krss => {
+            cfg!(feature = "owl")
         }
+        InputFormat::ObographsJson => false,
+    }
+}
 
-        if !in_term {
-            continue;
+fn open_input(path: &str) -> BufReader<File> {
+    let file = File::open(path).unwrap_or_else(|e| die(&format!("{}: {}", path, e)));
+    BufReader::with_capacity(1024 * 1024, file)
+}
+
+/// Warnings printed in full before the per-category summary.
+const WARNINGS_SHOWN: usize = 5;
+
+/// The resolver for `paths` (alt IDs are scanned from the first), or
+/// `Verbatim` when no ID options were given and no input has an `idspace:`.
+fn id_resolver(args: &Args, paths: &[&str]) -> Box<dyn IdResolver> {
+    // `idspace:` bases of the OBO inputs, so OWL inputs name their terms alike.
+    let mut idspaces = Vec::new();
+    for &path in paths.iter().filter(|&&path| resolve_input_format(args, path) == InputFormat::Obo) {
+        idspaces.extend(scan_idspaces(open_input(path)).unwrap_or_else(|e| die(&format!("{}: {}", path, e))));
+    }
+    if args.prefixes.is_empty() && idspaces.is_empty() && !args.alt_ids {
+        return Box::new(Verbatim);
+    }
+    let mut curies = CurieMap::obo();
+    for (prefix, base) in &idspaces {
+        curies.add_prefix(prefix, base);
+    }
+    for (prefix, base) in &args.prefixes {
+        if let Some(base) = base {
+            curies.add_prefix(prefix, base);
         }
+    }
+    let case = PrefixCase::new(args.prefixes.iter().map(|(p, _)| p.as_str()));
+    let alt_ids = if args.alt_ids {
+        scan_alt_ids(open_input(paths[0])).unwrap_or_else(|e| die(&format!("{}: {}", paths[0], e)))
+    } else {
+        AltIds::new()
+    };
+    Box::new((curies, (case, alt_ids)))
+}
 
-        if line.starts_with("id:") {
-            let id = line[3..].trim();
-            if let Some(&existing_idx) = concept_idx.get(id) {
-                current_id = Some(existing_idx);
+/// A one-line progress bar on stderr, redrawn whenever the percentage changes.
+#[derive(Default)]
+struct ProgressBar {
+    phase: &'static str,
+    percent: Option<usize>,
+}
+
+impl ProgressSink for ProgressBar {
+    fn on_phase(&mut self, phase: Phase) {
+        self.phase = phase.name();
+        self.percent = None;
+    }
+
+    fn on_progress(&mut self, processed: usize, total: usize) {
+        let percent = (processed * 100).checked_div(total).unwrap_or(100).min(100);
+        if self.percent == Some(percent) {
+            return;
+        }
+        self.percent = Some(percent);
+        let filled = percent * 40 / 100;
+        eprint!("\r{:<9}[{}{}] {:>3}%", self.phase, "#".repeat(filled), " ".repeat(40 - filled), percent);
+        if processed == total {
+            eprintln!();
+        }
+    }
+}
+
+/// Parses an OBO file, reporting its warnings on stderr as `path:line:col`
+/// diagnostics. In strict mode any warning is fatal and all are printed.
+fn parse_input(path: &str, args: &Args) -> (ParseResult, Box<dyn IdResolver>) {
+    let (parsed, resolver, _) = parse_inputs(&[path], args);
+    (parsed, resolver)
+}
+
+/// Parses `paths` and the documents they import into one result with
+/// shared IDs, and lists every document read. IDs go through the resolver
+/// of the first path; `--input-format` applies to `paths`, not to imports.
+fn parse_inputs(paths: &[&str], args: &Args) -> (ParseResult, Box<dyn IdResolver>, Vec<PathBuf>) {
+    let resolver = id_resolver(args, paths);
+    let roots: Vec<&Path> = paths.iter().map(Path::new).collect();
+    let mut documents: Vec<PathBuf> = Vec::new();
+    let mut obo_documents: Vec<PathBuf> = Vec::new();
+    let report_path = args.approximation_report.as_deref();
+    let mut report = report_path.map(|path| {
+        open_sink(path).unwrap_or_else(|e| die(&format!("writing {}: {}", path.display(), e)))
+    });
+    let Ok(mut parsed) = read_with_imports::<Infallible, _>(
+        &roots,
+        |path: &Path| {
+            let shown = path.to_string_lossy();
+            let mut bar = ProgressBar::default();
+            let reader: Box<dyn BufRead> = if args.progress {
+                let total = std::fs::metadata(path).map_or(0, |m| m.len() as usize);
+                Box::new(ProgressReader::new(open_input(&shown), total, &mut bar))
             } else {
-                let idx = concepts.len();
-                concepts.push(id.to_string());
-                concept_idx.insert(id.to_string(), idx);
-                current_id = Some(idx);
+                Box::new(open_input(&shown))
+            };
+            let format = match args.input_format {
+                Some(format) if roots.contains(&path) => format,
+                _ => detect_format(&shown),
+            };
+            if format == InputFormat::Obo {
+                obo_documents.push(path.to_path_buf());
+            }
+            // Strict mode is applied on report, once every warning has been printed.
+            let (mut parsed, warnings) = parse_document(reader, format, resolver.as_ref(), false)
+                .unwrap_or_else(|e| die(&format!("{}:{}", shown, e)));
+            if let (Some(sink), Some(path)) = (&mut report, report_path) {
+                let written = profile::write_approximations(sink, &shown, &parsed);
+                if let Err(e) = written {
+                    die(&format!("writing {}: {}", path.display(), e));
+                }
+            }
+            apply_non_el(&shown, &mut parsed, args.non_el, &warnings);
+            Ok((parsed, warnings))
+        },
+        |path: &Path, warnings| {
+            report_warnings(&path.to_string_lossy(), &warnings, args.strict);
+            documents.push(path.to_path_buf());
+        },
+    );
+    if let (Some(mut sink), Some(path)) = (report, report_path) {
+        if let Err(e) = sink.flush() {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+    }
+    if !args.xref_prefixes.is_empty() {
+        for path in &obo_documents {
+            let shown = path.to_string_lossy();
+            let xrefs = scan_xrefs(open_input(&shown), &args.xref_prefixes)
+                .unwrap_or_else(|e| die(&format!("{}: {}", shown, e)));
+            for (id, xref) in xrefs {
+                parsed.add_equivalence(&resolver.resolve(&id), &resolver.resolve(&xref));
             }
-            continue;
         }
+    }
+    if args.obsolete != ObsoleteMode::Keep {
+        eprintln!("{}", obsolete::apply(&mut parsed, args.obsolete));
+    }
+    if args.iris {
+        parsed.expand_names();
+    }
+    (parsed, resolver, documents)
+}
 
-        if line.starts_with("is_obsolete:") {
-            is_obsolete = line.contains("true");
-            continue;
+/// Applies `--non-el` to one document: with `error`, prints its warnings
+/// and the profile report and exits.
+fn apply_non_el(path: &str, parsed: &mut ParseResult, mode: NonEl, warnings: &[Warning]) {
+    match profile::apply(parsed, mode) {
+        Ok(0) => {}
+        Ok(added) => eprintln!("{}: approximated axioms outside EL with {} EL axioms", path, added),
+        Err(report) => {
+            for w in warnings {
+                eprintln!("{}:{}", path, w);
+            }
+            die(&format!("{}: {}", path, report));
         }
+    }
+}
 
-        if is_obsolete {
-            continue;
+fn report_warnings(path: &str, warnings: &[Warning], strict: bool) {
+    let shown = if strict { warnings.len() } else { WARNINGS_SHOWN };
+    for w in warnings.iter().take(shown) {
+        eprintln!("{}:{}", path, w);
+    }
+    if !warnings.is_empty() {
+        let mut counts: BTreeMap<WarningKind, usize> = BTreeMap::new();
+        for w in warnings {
+            *counts.entry(w.kind).or_default() += 1;
+        }
+        let summary: Vec<String> =
+            counts.iter().map(|(kind, n)| format!("{} {}", kind.name(), n)).collect();
+        eprintln!("{}: {} warnings ({})", path, warnings.len(), summary.join(", "));
+        if strict {
+            die("warnings are errors in --strict mode");
+        }
+    }
+}
+
+fn run_changes(old_path: &str, new_path: &str, args: &Args) {
+    let changes = diff(&parse_input(old_path, args).0, &parse_input(new_path, args).0);
+
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        write!(sink, "{}", changes)?;
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+/// `ID ! label`, or the bare ID for unlabeled concepts.
+fn describe(parsed: &ParseResult, c: ConceptId) -> String {
+    match parsed.labels.get(&(c as usize)) {
+        Some(label) => format!("{} ! {}", parsed.concepts[c as usize], label),
+        None => parsed.concepts[c as usize].clone(),
+    }
+}
+
+fn describe_individual(parsed: &ParseResult, i: usize) -> String {
+    match parsed.individual_labels.get(&i) {
+        Some(label) => format!("{} ! {}", parsed.individuals[i], label),
+        None => parsed.individuals[i].clone(),
+    }
+}
+
+fn run_query(args: &Args, expression: &str, indirect: bool, kind: QueryKind) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let symbols = SymbolIndex::with_resolver(&parsed, resolver.as_ref());
+    let reasoner =
+        Reasoner::new(build_axiom_store(&parsed), parsed.roles.len()).with_named_concepts(parsed.concepts.len());
+    let named: Vec<bool> = parsed.concepts.iter().map(|name| !name.is_empty()).collect();
+    let mut engine = QueryEngine::new(reasoner, move |c| named.get(c as usize).copied().unwrap_or(false));
+    if !expression.is_empty() {
+        print_query(&mut engine, &symbols, &parsed, expression, indirect, kind)
+            .unwrap_or_else(|e| die(&format!("query: {}", e)));
+        return;
+    }
+
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if prompt {
+            eprint!("> ");
+        }
+        let Some(line) = lines.next() else { break };
+        let line = line.unwrap_or_else(|e| die(&format!("reading queries: {}", e)));
+        match line.trim() {
+            "" => continue,
+            "quit" | "exit" => break,
+            text => {
+                if let Err(e) = print_query(&mut engine, &symbols, &parsed, text, indirect, kind) {
+                    eprintln!("error: {}", e);
+                }
+            }
         }
+        let _ = io::stdout().flush();
+    }
+}
 
-        let Some(sub_idx) = current_id else { continue };
+/// Prints the answer to one query: the sections of a DL query for a class
+/// expression, else the classes a retrieval query finds.
+fn print_query(
+    engine: &mut QueryEngine,
+    symbols: &dyn Symbols,
+    parsed: &ParseResult,
+    text: &str,
+    indirect: bool,
+    kind: QueryKind,
+) -> Result<(), QueryError> {
+    let expr = match parse_retrieval(text, symbols)? {
+        Retrieval::Class(expr) => expr,
+        query if kind == QueryKind::All => {
+            let found = engine.retrieve(&query);
+            println!("Matches [{}]", found.len());
+            for c in found {
+                println!("  {}", describe(parsed, c));
+            }
+            return Ok(());
+        }
+        _ => {
+            let message = "query kinds apply to class expressions; use descendants(...) and the like".to_string();
+            return Err(QueryError { message, offset: 0 });
+        }
+    };
+    let indirect = indirect || matches!(kind, QueryKind::Ancestors | QueryKind::Descendants);
+    let answer = engine.answer(&expr, !indirect);
 
-        if line.starts_with("is_a:") {
-            let rest = &line[5..];
-            let target = rest.split('!').next().unwrap_or("").trim();
-            let sup_idx = if let Some(&idx) = concept_idx.get(target) {
-                idx
-            } else if !target.is_empty() {
-                let idx = concepts.len();
-                concepts.push(target.to_string());
-                concept_idx.insert(target.to_string(), idx);
-                idx
-            } else {
-                continue;
-            };
-            subsumptions.push((sub_idx, sup_idx));
-        } else if line.starts_with("relationship:") {
-            let rest = &line[13..];
-            let parts: Vec<&str> = rest.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let role_name = parts[0];
-                let target = parts[1];
+    let (supers, subs) = if indirect {
+        ("Superclasses", "Subclasses")
+    } else {
+        ("Direct superclasses", "Direct subclasses")
+    };
+    let sections = [
+        (QueryKind::Equivalents, "Equivalent classes", &answer.equivalents),
+        (QueryKind::Sup, supers, &answer.superclasses),
+        (QueryKind::Sub, subs, &answer.subclasses),
+    ];
+    let shown = match kind {
+        QueryKind::Ancestors => QueryKind::Sup,
+        QueryKind::Descendants => QueryKind::Sub,
+        kind => kind,
+    };
+    for (_, title, ids) in sections.into_iter().filter(|(k, ..)| shown == QueryKind::All || *k == shown) {
+        println!("{} [{}]", title, ids.len());
+        for c in ids {
+            println!("  {}", describe(parsed, *c));
+        }
+    }
+    Ok(())
+}
+
+/// Adds each named query as a concept equivalent to its expression, naming
+/// it after the query. Returns the query concepts, which directly follow the
+/// parsed concepts; their nested fresh concepts come after them, unnamed.
+fn register_named_queries(
+    path: &Path,
+    parsed: &mut ParseResult,
+    store: &mut AxiomStore,
+) -> Vec<ConceptId> {
+    let queries = read_named_queries(open_input(&path.to_string_lossy()))
+        .unwrap_or_else(|e| die(&format!("{}: {}", path.display(), e)));
+    let symbols = SymbolIndex::new(parsed);
+    let exprs: Vec<_> = queries
+        .iter()
+        .map(|q| {
+            parse_class_expression(&q.expression, &symbols)
+                .unwrap_or_else(|e| die(&format!("query '{}': {}", q.name, e)))
+        })
+        .collect();
+
+    let mut normalizer = Normalizer::new(store, parsed.concepts.len());
+    let ids: Vec<ConceptId> = exprs.iter().map(|_| normalizer.fresh()).collect();
+    for (&id, expr) in ids.iter().zip(&exprs) {
+        normalizer.add_equivalence(id, expr);
+    }
+    parsed.concepts.extend(queries.into_iter().map(|q| q.name));
+    ids
+}
+
+/// Everything the saturated contexts of a classify run depend on.
+fn cache_key(args: &Args, documents: &[PathBuf]) -> std::io::Result<u64> {
+    let mut key = CacheKey::new();
+    for path in documents {
+        key.add_reader(File::open(path)?)?;
+    }
+    match &args.queries {
+        Some(path) => key.add_reader(File::open(path)?)?,
+        None => key.add_bytes(b""),
+    }
+    for (prefix, base) in &args.prefixes {
+        key.add_bytes(prefix.as_bytes());
+        key.add_bytes(base.as_deref().unwrap_or("").as_bytes());
+    }
+    key.add_bytes(&[args.alt_ids as u8]);
+    for prefix in &args.xref_prefixes {
+        key.add_bytes(prefix.as_bytes());
+    }
+    key.add_bytes(&[args.non_el as u8]);
+    key.add_bytes(&[args.obsolete as u8]);
+    Ok(key.finish())
+}
+
+/// Saturates through the `--cache-dir` cache when one is given. Trace runs
+/// bypass it: recording needs a real saturation and replay is the point.
+fn saturate_for(
+    args: &Args,
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    parsed: &ParseResult,
+    documents: &[PathBuf],
+) -> Vec<Context> {
+    let cache = match (&args.cache_dir, &args.trace) {
+        (Some(dir), None) => {
+            let key = cache_key(args, documents).unwrap_or_else(|e| die(&format!("{}: {}", args.input, e)));
+            Some((ClassificationCache::new(dir), key))
+        }
+        _ => None,
+    };
+    if let Some((cache, key)) = &cache {
+        match cache.load(*key) {
+            Ok(Some(contexts)) if contexts.len() == num_concepts => {
+                eprintln!("Loaded cached classification {}", cache.path(*key).display());
+                if args.fail_on_unsat {
+                    check_unsat(&contexts, parsed, &[]);
+                }
+                return contexts;
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("warning: ignoring {}: {}", cache.path(*key).display(), e),
+        }
+    }
+
+    let contexts = saturate_uncached(args, store, num_concepts, num_roles, parsed);
+    if let Some((cache, key)) = &cache {
+        match cache.store(*key, &contexts, num_roles) {
+            Ok(path) => eprintln!("Cached classification as {}", path.display()),
+            Err(e) => eprintln!("warning: caching in {}: {}", cache.path(*key).display(), e),
+        }
+    }
+    contexts
+}
+
+/// Reports the first named unsatisfiable concept, if any, and exits.
+fn check_unsat(contexts: &[Context], parsed: &ParseResult, events: &[TraceEvent]) {
+    if let Some(&c) = unsatisfiable_concepts(&contexts[..parsed.concepts.len()]).first() {
+        report_unsat(c, parsed, events);
+    }
+}
+
+fn saturate_uncached(
+    args: &Args,
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    parsed: &ParseResult,
+) -> Vec<Context> {
+    let names = &parsed.concepts;
+    let rules = args.disabled_rules.iter().fold(RuleSet::standard(), |rules, &rule| rules.without(rule));
+    let options = SaturationOptions { worklist: args.worklist, rules, ..SaturationOptions::default() };
+    if let Some(TraceMode::Replay(path)) = &args.trace {
+        let events = read_trace(open_input(&path.to_string_lossy()))
+            .unwrap_or_else(|e| die(&format!("{}: {}", path.display(), e)));
+        let contexts = replay(store, num_concepts, num_roles, &options, &events)
+            .unwrap_or_else(|e| die(&format!("replaying {}: {}", path.display(), e)));
+        eprintln!("Replayed {} trace events from {}", events.len(), path.display());
+        if args.fail_on_unsat {
+            check_unsat(&contexts, parsed, &events);
+        }
+        return contexts;
+    }
+
+    if let Some(path) = &args.checkpoint {
+        if path.exists() {
+            eprintln!("Resuming saturation from {}", path.display());
+        }
+        let interval = args.checkpoint_interval;
+        let contexts = saturate_resumable(store, num_concepts, num_roles, &options, path, interval)
+            .unwrap_or_else(|e| die(&format!("{}: {}", path.display(), e)));
+        // Saturation finished, so there is nothing left to resume.
+        let _ = std::fs::remove_file(path);
+        return contexts;
+    }
+
+    if let Some(TraceMode::Derivations(path)) = &args.trace {
+        let mut log = Vec::new();
+        let contexts = saturate_logged(store, num_concepts, num_roles, &options, &mut log);
+        let written = File::create(path).and_then(|f| {
+            let mut out = BufWriter::with_capacity(1024 * 1024, f);
+            write_derivations(&mut out, &log, &parsed.concepts, &parsed.roles)
+        });
+        if let Err(e) = written {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+        eprintln!("Logged {} derivations to {}", log.len(), path.display());
+        if args.fail_on_unsat {
+            check_unsat(&contexts, parsed, &[]);
+        }
+        return contexts;
+    }
+
+    let record = match &args.trace {
+        Some(TraceMode::Record(path)) => Some(path),
+        _ => None,
+    };
+    let mut events = Vec::new();
+    let result = match (args.fail_on_unsat, record) {
+        (true, _) => {
+            let trace = record.map(|_| &mut events);
+            saturate_fail_fast(store, num_concepts, num_roles, &options, names.len(), trace)
+        }
+        (false, Some(_)) => {
+            Ok(saturate_traced(store, num_concepts, num_roles, &options, &mut events))
+        }
+        (false, None) => Ok(match args.threads {
+            Some(threads) => saturate_parallel(store, num_concepts, num_roles, threads),
+            None if args.progress => {
+                let mut bar = ProgressBar::default();
+                saturate_with_progress(store, num_concepts, num_roles, &options, &mut bar)
+            }
+            None => saturate_with(store, num_concepts, num_roles, &options),
+        }),
+    };
+    if let Some(path) = record {
+        let written = File::create(path)
+            .and_then(|f| write_trace(&events, BufWriter::with_capacity(1024 * 1024, f)));
+        if let Err(e) = written {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+        eprintln!("Recorded {} trace events to {}", events.len(), path.display());
+    }
+    result.unwrap_or_else(|c| report_unsat(c, parsed, &events))
+}
+
+/// Reports an ontology without a model and exits with status 3; every
+/// concept of its taxonomy would be equivalent to owl:Nothing.
+fn report_inconsistent(
+    path: &str,
+    store: &AxiomStore,
+    contexts: &[Context],
+    parsed: &ParseResult,
+) -> ! {
+    eprintln!("error: {} is inconsistent", path);
+    let empty: Vec<ConceptId> = store
+        .nominals
+        .iter()
+        .copied()
+        .filter(|&n| contexts[n as usize].super_set.contains(&BOTTOM))
+        .collect();
+    if empty.is_empty() {
+        eprintln!("  owl:Thing is unsatisfiable");
+    }
+    for n in empty {
+        eprintln!("  nominal {} is unsatisfiable", describe(parsed, n));
+    }
+    process::exit(3);
+}
+
+/// Reports an unsatisfiable concept and exits. With a trace, the items
+/// processed for the concept are listed in order as its justification.
+fn report_unsat(c: ConceptId, parsed: &ParseResult, events: &[TraceEvent]) -> ! {
+    let name = |x: ConceptId| match parsed.concepts.get(x as usize) {
+        Some(n) if !n.is_empty() => n.clone(),
+        _ => format!("<anonymous {}>", x),
+    };
+    eprintln!("error: {} is unsatisfiable", name(c));
+    let mut steps = Vec::new();
+    for event in events {
+        match *event {
+            TraceEvent::Concept { concept, added } if concept == c && added != c && added != TOP => {
+                steps.push(name(added));
+            }
+            TraceEvent::Link { source, role, target } if source == c => {
+                steps.push(format!("{} some {}", parsed.roles[role as usize], name(target)));
+            }
+            _ => {}
+        }
+    }
+    if !steps.is_empty() {
+        eprintln!("derivation (from trace):");
+        for step in steps {
+            eprintln!("  {}", step);
+        }
+    }
+    process::exit(2);
+}
 
-                let role_idx_val = if let Some(&idx) = role_idx.get(role_name) {
-                    idx
-                } else {
-                    let idx = roles.len();
-                    roles.push(role_name.to_string());
-                    role_idx.insert(role_name.to_string(), idx);
-                    idx
-                };
+fn run_similarity(args: &Args, terms_path: &str, measure: Measure, parquet: bool) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let ontology = Ontology::from(parsed);
+    let parsed = ontology.parse_result();
+    let symbols = SymbolIndex::with_resolver(parsed, resolver.as_ref());
+    let text = std::fs::read_to_string(terms_path)
+        .unwrap_or_else(|e| die(&format!("{}: {}", terms_path, e)));
+    let terms: Vec<ConceptId> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|t| symbols.concept(t).unwrap_or_else(|| die(&format!("unknown term '{}'", t))))
+        .collect();
 
-                let target_idx = if let Some(&idx) = concept_idx.get(target) {
-                    idx
-                } else {
-                    let idx = concepts.len();
-                    concepts.push(target.to_string());
-                    concept_idx.insert(target.to_string(), idx);
-                    idx
-                };
+    let classified = ontology.classify();
+    let similarity = Similarity::new(classified.contexts(), classified.taxonomy());
+    let threads = args
+        .threads
+        .or_else(|| std::thread::available_parallelism().ok())
+        .unwrap_or(NonZeroUsize::MIN);
+    let scores = similarity.matrix(measure, &terms, threads);
 
-                relations.push((sub_idx, role_idx_val, target_idx));
+    let names: Vec<&str> = terms.iter().map(|&c| parsed.concepts[c as usize].as_str()).collect();
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = if parquet {
+        write_parquet(path, &names, &scores)
+    } else {
+        open_sink(path).and_then(|mut sink| write_matrix_tsv(&mut sink, &names, &scores))
+    };
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+fn run_explain(args: &Args, sub: &str, sup: &str, limit: usize) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let symbols = SymbolIndex::with_resolver(&parsed, resolver.as_ref());
+    let concept =
+        |t: &str| symbols.concept(t).unwrap_or_else(|| die(&format!("unknown term '{}'", t)));
+    let (sub, sup) = (concept(sub), concept(sup));
+
+    let explainer = Explainer::new(&parsed).with_limit(limit);
+    let justifications = explainer.explain(sub, sup);
+    if justifications.is_empty() {
+        eprintln!("{} is not subsumed by {}", describe(&parsed, sub), describe(&parsed, sup));
+    }
+
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        for (i, justification) in justifications.iter().enumerate() {
+            writeln!(sink, "Justification {} [{}]", i + 1, justification.len())?;
+            for &axiom in justification {
+                writeln!(sink, "  {}", explainer.describe(axiom))?;
             }
         }
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+fn run_lcs(args: &Args, a: &str, b: &str, depth: usize) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let symbols = SymbolIndex::with_resolver(&parsed, resolver.as_ref());
+    let concept =
+        |t: &str| symbols.concept(t).unwrap_or_else(|| die(&format!("unknown term '{}'", t)));
+    let (a, b) = (concept(a), concept(b));
+
+    let store = build_axiom_store(&parsed);
+    let contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+    let descriptions = Descriptions::new(&store, &contexts, parsed.concepts.len()).with_depth(depth);
+    let lcs = descriptions.least_common_subsumer(a, b);
+    let concept_name = |c: ConceptId| parsed.concepts[c as usize].clone();
+    let role_name = |r: RoleId| parsed.roles[r as usize].clone();
+
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        writeln!(sink, "{}", render_class_expression(&lcs, &concept_name, &role_name))?;
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+fn run_unsat(args: &Args, term: Option<&str>, limit: usize) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let n = parsed.concepts.len();
+    let store = build_axiom_store(&parsed);
+    let contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+    let mut unsatisfiable = unsatisfiable_concepts(&contexts[..n]);
+    if let Some(term) = term {
+        let symbols = SymbolIndex::with_resolver(&parsed, resolver.as_ref());
+        let c = symbols.concept(term).unwrap_or_else(|| die(&format!("unknown term '{}'", term)));
+        if !unsatisfiable.contains(&c) {
+            die(&format!("{} is satisfiable", describe(&parsed, c)));
+        }
+        unsatisfiable = vec![c];
     }
+    unsatisfiable.retain(|&c| !parsed.concepts[c as usize].is_empty());
 
-    ParseResult {
-        concepts,
-        roles,
-        concept_idx,
-        role_idx,
-        subsumptions,
-        relations,
+    let explainer = Explainer::new(&parsed).with_limit(limit);
+    let report = unsat_report(&explainer, &contexts, &unsatisfiable);
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        write!(sink, "{}", report)?;
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
     }
 }
 
-fn build_axiom_store(result: &ParseResult) -> AxiomStore {
-    let mut store = AxiomStore::new(result.concepts.len(), result.roles.len());
+/// Writes the differences from `reference_path` and exits with status 1 if
+/// there are any.
+fn run_verify(args: &Args, reference_path: &str) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let n = parsed.concepts.len();
+    let store = build_axiom_store(&parsed);
+    let contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+    let taxonomy = Taxonomy::new(&contexts, n);
+    let reference = match Path::new(reference_path).extension().and_then(|e| e.to_str()) {
+        Some("tsv" | "txt") => read_reference_tsv(open_input(reference_path))
+            .unwrap_or_else(|e| die(&format!("{}: {}", reference_path, e))),
+        _ => reference_pairs(&parse_input(reference_path, args).0),
+    };
+    let symbols = SymbolIndex::with_resolver(&parsed, resolver.as_ref());
+    let report = verify(&parsed, &contexts, &taxonomy, &reference, |name| symbols.concept(name));
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        write!(sink, "{}", report)?;
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+    eprintln!(
+        "{} missing, {} spurious, {} unknown reference terms",
+        report.missing.len(),
+        report.spurious.len(),
+        report.unknown.len()
+    );
+    if !report.is_empty() {
+        process::exit(1);
+    }
+}
 
-    for (sub, sup) in &result.subsumptions {
-        store.add_subsumption(*sub as u32, *sup as u32);
+#[cfg(feature = "owl")]
+fn run_convert(args: &Args) {
+    let (parsed, _) = parse_input(&args.input, args);
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    if let Err(e) = open_sink(path).and_then(|sink| write_ofn(&parsed, sink)) {
+        die(&format!("writing {}: {}", path.display(), e));
     }
+}
+
+#[cfg(not(feature = "owl"))]
+fn run_convert(_args: &Args) {
+    die("convert needs a build with the `owl` feature");
+}
 
-    for (sub, role, target) in &result.relations {
-        store.add_exist_right(*sub as u32, *role as u32, *target as u32);
+/// Classifies the input and writes it back with the inferred axioms added.
+fn run_materialize(args: &Args, ofn: bool, equivalents: bool) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let n = parsed.concepts.len();
+    let store = build_axiom_store(&parsed);
+    let mut contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+    contexts.truncate(n);
+    let taxonomy = Taxonomy::new(&contexts, n);
+    let mut direct_parents = taxonomy.direct_parents().to_vec();
+    if args.deterministic {
+        sort_direct_parents(&mut direct_parents);
     }
+    let equivalences = if equivalents { taxonomy.equivalences() } else { &[] };
 
-    store
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        if ofn {
+            return write_materialized_ofn(&parsed, &direct_parents, equivalences, sink);
+        }
+        let source = BufReader::with_capacity(1024 * 1024, File::open(&args.input)?);
+        let added = write_with_inferred(source, &mut sink, &parsed, &direct_parents, equivalences, resolver.as_ref())?;
+        eprintln!("Added {} inferred lines", added);
+        Ok(())
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+#[cfg(feature = "owl")]
+fn write_materialized_ofn(
+    parsed: &ParseResult,
+    direct_parents: &[Vec<ConceptId>],
+    equivalences: &[Vec<ConceptId>],
+    sink: impl Write,
+) -> std::io::Result<()> {
+    write_ofn_with_inferred(parsed, direct_parents, equivalences, sink)
+}
+
+#[cfg(not(feature = "owl"))]
+fn write_materialized_ofn(
+    _parsed: &ParseResult,
+    _direct_parents: &[Vec<ConceptId>],
+    _equivalences: &[Vec<ConceptId>],
+    _sink: impl Write,
+) -> std::io::Result<()> {
+    die("materialize --format ofn needs a build with the `owl` feature");
+}
+
+#[cfg(feature = "serve")]
+fn run_serve(args: &Args, listen: &str) -> ! {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let mut service = Service::new(parsed, resolver.as_ref());
+    let summary = service.summary();
+    eprintln!(
+        "{}: {} concepts, {} inferred subsumptions, {} unsatisfiable",
+        args.input,
+        summary.concepts,
+        summary.inferred_subsumptions,
+        summary.unsatisfiable.len()
+    );
+    let listener = TcpListener::bind(listen).unwrap_or_else(|e| die(&format!("{}: {}", listen, e)));
+    eprintln!("listening on http://{}", listen);
+    match service.serve(&listener) {
+        Ok(()) => process::exit(0),
+        Err(e) => die(&format!("{}: {}", listen, e)),
+    }
+}
+
+#[cfg(not(feature = "serve"))]
+fn run_serve(_args: &Args, _listen: &str) -> ! {
+    die("serve needs a build with the `serve` feature");
+}
+
+fn run_impact(args: &Args, candidates_path: &str) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let ontology = Ontology::from(parsed);
+    let parsed = ontology.parse_result();
+    let symbols = SymbolIndex::with_resolver(parsed, resolver.as_ref());
+    let concept =
+        |t: &str| symbols.concept(t).unwrap_or_else(|| die(&format!("unknown term '{}'", t)));
+    let text = std::fs::read_to_string(candidates_path)
+        .unwrap_or_else(|e| die(&format!("{}: {}", candidates_path, e)));
+    let candidates: Vec<Candidate> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let term = concept(fields.next().unwrap_or_default());
+            let replacement = fields.next().map(concept);
+            Candidate { term, replacement }
+        })
+        .collect();
+
+    let contexts = ontology.saturate();
+    let impact = obsoletion_impact(parsed, &contexts, &candidates);
+
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        write!(sink, "{}", impact)?;
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+/// Streams answers to a TSV of questions, `-` for standard input, against
+/// one classification of the input.
+fn run_batch(args: &Args, questions_path: &str) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let ontology = Ontology::from(parsed);
+    let parsed = ontology.parse_result();
+    let symbols = SymbolIndex::with_resolver(parsed, resolver.as_ref());
+    let classified = ontology.classify();
+    let batch = Batch::new(classified.contexts(), classified.taxonomy(), &parsed.concepts);
+    let questions: Box<dyn BufRead> = if questions_path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(open_input(questions_path))
+    };
+
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let answered = open_sink(path).and_then(|mut sink| {
+        let summary = batch.run(questions, &symbols, &mut sink)?;
+        sink.flush()?;
+        Ok(summary)
+    });
+    let summary = answered.unwrap_or_else(|e| die(&format!("batch: {}", e)));
+    for (line, message) in &summary.first_rejected {
+        eprintln!("{}:{}: {}", questions_path, line, message);
+    }
+    eprintln!("Answered {} questions, rejected {} lines", summary.answered, summary.rejected);
+}
+
+fn run_analyze(args: &Args, json: bool) {
+    let (parsed, _) = parse_input(&args.input, args);
+    let ontology = Ontology::from(parsed);
+    let parsed = ontology.parse_result();
+    let classified = ontology.classify();
+    let analysis = analyze(classified.contexts(), classified.taxonomy(), &parsed.concepts, &parsed.roles);
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        if json {
+            writeln!(sink, "{}", analysis.to_json())?;
+        } else {
+            writeln!(sink, "{}", analysis)?;
+        }
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+fn run_dangling(args: &Args) {
+    let (parsed, _) = parse_input(&args.input, args);
+    let report = dangling_references(&parsed);
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        write!(sink, "{}", report)?;
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+/// Lists the first undeclared classes, if any, and exits.
+fn check_undeclared(path: &str, parsed: &ParseResult) {
+    let report = dangling_references(parsed);
+    if report.is_empty() {
+        return;
+    }
+    for d in report.terms.iter().take(WARNINGS_SHOWN) {
+        eprintln!("{}: undeclared class {}", path, d.term);
+    }
+    let prefixes: Vec<String> = report.by_prefix.iter().map(|(p, n)| format!("{} {}", p, n)).collect();
+    eprintln!("{}: {} undeclared classes ({})", path, report.terms.len(), prefixes.join(", "));
+    die("undeclared classes are errors with --fail-on-undeclared; is an import missing?");
+}
+
+fn run_derivations(args: &Args, limit: usize) {
+    let summary = summarize(open_input(&args.input), limit)
+        .unwrap_or_else(|e| die(&format!("{}: {}", args.input, e)));
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| {
+        write!(sink, "{}", summary)?;
+        sink.flush()
+    });
+    if let Err(e) = written {
+        die(&format!("writing {}: {}", path.display(), e));
+    }
+}
+
+fn run_export(args: &Args, kind: ExportKind, told_only: bool, roles: &[String]) {
+    let (parsed, resolver) = parse_input(&args.input, args);
+    let symbols = SymbolIndex::with_resolver(&parsed, resolver.as_ref());
+    let selected: Vec<RoleId> = roles
+        .iter()
+        .map(|r| symbols.role(r).unwrap_or_else(|| die(&format!("unknown relation '{}'", r))))
+        .collect();
+    let ontology = Ontology::from(parsed);
+    let parsed = ontology.parse_result();
+    let contexts = ontology.saturate();
+
+    let path = args.output.as_deref().unwrap_or(Path::new("-"));
+    let written = open_sink(path).and_then(|mut sink| match kind {
+        ExportKind::Ancestors => write_ancestor_libsvm(&mut sink, &contexts, &parsed.concepts),
+        ExportKind::Edges => {
+            let direct_parents = build_taxonomy(&contexts, parsed.concepts.len());
+            let view = EdgeListView {
+                concepts: &parsed.concepts,
+                roles: &parsed.roles,
+                told_subsumptions: &parsed.subsumptions,
+                told_relations: &parsed.relations,
+                direct_parents: &direct_parents,
+                contexts: &contexts,
+            };
+            write_edge_list(&mut sink, &view, !told_only)
+        }
+        ExportKind::Relations => {
+            write_relation_closure(&mut sink, &contexts, &parsed.concepts, &parsed.roles, &selected)
+        }
+    });
+    match written {
+        Ok(rows) => eprintln!("Exported {} rows", rows),
+        Err(e) => die(&format!("writing {}: {}", path.display(), e)),
+    }
+}
+
+/// Direct parents with equivalence groups collapsed, writing the merge map
+/// to `--merge-map` if given.
+fn merge_equivalents(
+    args: &Args,
+    contexts: &[Context],
+    parsed: &ParseResult,
+) -> Vec<Vec<ConceptId>> {
+    let names = &parsed.concepts;
+    let precedence = Precedence::new(&args.canonical_prefixes);
+    let merge = EquivalenceMerge::new(contexts, names.len(), names, &precedence);
+    let merged: Vec<(ConceptId, ConceptId)> = merge.merged().collect();
+    eprintln!("Merged {} concepts into {} canonical terms", merged.len(), merge.groups());
+    if let Some(path) = &args.merge_map {
+        let written = open_sink(path).and_then(|mut sink| {
+            writeln!(sink, "merged\tcanonical")?;
+            for (m, c) in merged {
+                writeln!(sink, "{}\t{}", names[m as usize], names[c as usize])?;
+            }
+            sink.flush()
+        });
+        if let Err(e) = written {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+    }
+    merge.into_direct_parents()
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Classifies once, then polls the input's modification time and
+/// reclassifies only the concepts affected by each edit.
+fn run_watch(args: &Args, interval: Duration) -> ! {
+    let (mut parsed, mut resolver) = parse_input(&args.input, args);
+    let mut store = build_axiom_store(&parsed);
+    let start = Instant::now();
+    let mut contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+    let report = |parsed: &ParseResult, contexts: &[Context], resolver: &dyn IdResolver| {
+        let mut direct_parents = build_taxonomy(contexts, parsed.concepts.len());
+        if args.deterministic {
+            sort_direct_parents(&mut direct_parents);
+        }
+        let taxonomy = Taxonomy::from_parents(direct_parents);
+        let inferred = count_inferred_subsumptions(&contexts[..parsed.concepts.len()]);
+        if let Some(path) = &args.output {
+            let summary = (args.format == OutputFormat::Json).then(|| {
+                let unsat = unsatisfiable_concepts(&contexts[..parsed.concepts.len()]);
+                let stats = vec![
+                    ("concepts", Value::from(parsed.concepts.len() - 2)),
+                    ("roles", Value::from(parsed.roles.len())),
+                    ("inferred_subsumptions", Value::from(inferred)),
+                ];
+                json_summary(parsed, &taxonomy, &unsat, stats)
+            });
+            write_output(args, path, parsed, &taxonomy, resolver, summary.unwrap_or_default());
+        }
+        inferred
+    };
+    let inferred = report(&parsed, &contexts, resolver.as_ref());
+    eprintln!(
+        "Classified {} concepts in {:?}: {} inferred subsumptions; watching {}",
+        parsed.concepts.len() - 2,
+        start.elapsed(),
+        inferred,
+        args.input
+    );
+
+    let mut seen = modified(&args.input);
+    loop {
+        std::thread::sleep(interval);
+        let now = modified(&args.input);
+        if now == seen {
+            continue;
+        }
+        seen = now;
+
+        let start = Instant::now();
+        let (new_parsed, new_resolver) = parse_input(&args.input, args);
+        let new_store = build_axiom_store(&new_parsed);
+        let result = reclassify(
+            &Snapshot { concepts: &parsed.concepts, roles: &parsed.roles, store: &store },
+            contexts,
+            &Snapshot { concepts: &new_parsed.concepts, roles: &new_parsed.roles, store: &new_store },
+        );
+        let elapsed = start.elapsed();
+        (parsed, resolver, store, contexts) = (new_parsed, new_resolver, new_store, result.contexts);
+        let inferred = report(&parsed, &contexts, resolver.as_ref());
+        eprintln!(
+            "Reclassified {} affected concepts (module of {}) in {:?}: {} inferred subsumptions",
+            result.affected, result.module_size, elapsed, inferred
+        );
+    }
+}
+
+#[cfg(feature = "arrow")]
+fn write_parquet(path: &Path, names: &[&str], scores: &[f64]) -> std::io::Result<()> {
+    if path.as_os_str() == "-" {
+        fail("parquet output needs --output <path>");
+    }
+    el_reasoner::columnar::write_similarity_parquet(path, names, scores)
+}
+
+#[cfg(not(feature = "arrow"))]
+fn write_parquet(_path: &Path, _names: &[&str], _scores: &[f64]) -> std::io::Result<()> {
+    die("parquet output needs a build with the `arrow` feature");
+}
+
+fn main() {
+    let args = parse_args();
+    // Phase timings and rule counts come back from the recorder; its echo
+    // prints the progress lines.
+    let recorder = Recorder::new().with_echo(true);
+    let _ = tracing::subscriber::set_global_default(recorder.clone());
+    if let Command::Derivations { limit } = args.command {
+        run_derivations(&args, limit);
+        return;
+    }
+    let input_path = &args.input;
+    let input_format = resolve_input_format(&args, input_path);
+    if !supported(input_format) {
+        fail(&format!("{} input is not supported yet", input_format.name()));
+    }
+    if input_format != InputFormat::Obo {
+        let obo_only = args.pipeline
+            || args.format == OutputFormat::Obo
+            || matches!(args.command, Command::Stats { content: true });
+        if obo_only {
+            fail("--pipeline, --format obo and stats --content need OBO input");
+        }
+    }
+    if args.iris && args.format == OutputFormat::Obo {
+        fail("OBO output keeps the input's IDs; --ids iri needs another format");
+    }
+
+    if let Command::Query { expression, indirect, kind } = &args.command {
+        run_query(&args, expression, *indirect, *kind);
+        return;
+    }
+
+    if let Command::Convert = args.command {
+        run_convert(&args);
+        return;
+    }
+
+    if let Command::Materialize { ofn, equivalents } = args.command {
+        let ofn = ofn.unwrap_or(input_format != InputFormat::Obo);
+        if !ofn && input_format != InputFormat::Obo {
+            fail("materialize --format obo needs OBO input");
+        }
+        if !ofn && args.iris {
+            fail("OBO output keeps the input's IDs; --ids iri needs another format");
+        }
+        run_materialize(&args, ofn, equivalents);
+        return;
+    }
+
+    if let Command::Similarity { terms, measure, parquet } = &args.command {
+        run_similarity(&args, terms, *measure, *parquet);
+        return;
+    }
+
+    if let Command::Impact { candidates } = &args.command {
+        run_impact(&args, candidates);
+        return;
+    }
+
+    if let Command::Dangling = args.command {
+        run_dangling(&args);
+        return;
+    }
+
+    if let Command::Explain { sub, sup, limit } = &args.command {
+        run_explain(&args, sub, sup, *limit);
+        return;
+    }
+
+    if let Command::Lcs { a, b, depth } = &args.command {
+        run_lcs(&args, a, b, *depth);
+        return;
+    }
+
+    if let Command::Batch { questions } = &args.command {
+        run_batch(&args, questions);
+        return;
+    }
+
+    if let Command::Analyze { json } = args.command {
+        run_analyze(&args, json);
+        return;
+    }
+
+    if let Command::Unsat { term, limit } = &args.command {
+        run_unsat(&args, term.as_deref(), *limit);
+        return;
+    }
+
+    if let Command::Verify { reference } = &args.command {
+        run_verify(&args, reference);
+        return;
+    }
+
+    if let Command::Watch { interval } = args.command {
+        run_watch(&args, interval);
+    }
+
+    if let Command::Serve { listen } = &args.command {
+        run_serve(&args, listen);
+    }
+
+    if let Command::Export { kind, told_only, roles } = &args.command {
+        run_export(&args, *kind, *told_only, roles);
+        return;
+    }
+
+    if let Command::Changes { old } = &args.command {
+        run_changes(old, input_path, &args);
+        return;
+    }
+
+    if let Command::Stats { content: true } = args.command {
+        match content_stats(open_input(input_path)) {
+            Ok(stats) => println!("{}", stats),
+            Err(e) => die(&format!("reading {}: {}", input_path, e)),
+        }
+        return;
+    }
+
+    // Parse OBO
+    // Peak resident size of each phase, for `--memory`.
+    let mut peaks: Vec<(&str, Option<usize>)> = Vec::new();
+    let mut end_phase = |phase| {
+        if args.memory {
+            peaks.push((phase, memory::peak_resident()));
+            memory::reset_peak();
+        }
+    };
+    if args.memory {
+        memory::reset_peak();
+    }
+    let parse_span = info_span!("parse").entered();
+    let (mut parse_result, resolver, documents, pipelined) = if args.pipeline {
+        let exclusive = args.queries.is_some()
+            || args.trace.is_some()
+            || args.cache_dir.is_some()
+            || args.fail_on_unsat
+            || args.non_el == NonEl::Approximate
+            || args.obsolete != ObsoleteMode::Keep
+            || args.checkpoint.is_some()
+            || !args.more_inputs.is_empty();
+        if exclusive {
+            fail(
+                "--pipeline excludes --queries, traces, --cache-dir, --fail-on-unsat, --non-el approximate, \
+                 --obsolete drop|replace, --checkpoint and more inputs",
+            );
+        }
+        let resolver = id_resolver(&args, &[input_path]);
+        let mut run = parse_and_saturate(open_input(input_path), resolver.as_ref())
+            .unwrap_or_else(|e| die(&format!("{}:{}", input_path, e)));
+        apply_non_el(input_path, &mut run.parsed, args.non_el, &run.warnings);
+        report_warnings(input_path, &run.warnings, args.strict);
+        if args.iris {
+            run.parsed.expand_names();
+        }
+        if !run.parsed.imports.is_empty() {
+            eprintln!("warning: {}: imports are not read with --pipeline", input_path);
+        }
+        let documents = vec![PathBuf::from(input_path)];
+        (run.parsed, resolver, documents, Some((run.store, run.contexts, run.early)))
+    } else {
+        let mut inputs = vec![input_path.as_str()];
+        inputs.extend(args.more_inputs.iter().map(String::as_str));
+        let (parsed, resolver, documents) = parse_inputs(&inputs, &args);
+        (parsed, resolver, documents, None)
+    };
+    drop(parse_span);
+    let parse_time = recorder.phase("parse");
+    end_phase("parse");
+
+    let num_parsed = parse_result.concepts.len();
+    let num_roles = parse_result.roles.len();
+    info!("Parsed {} concepts, {} roles in {:?}", num_parsed, num_roles, parse_time);
+    if args.fail_on_undeclared {
+        check_undeclared(input_path, &parse_result);
+    }
+    let (store, pipelined) = match pipelined {
+        Some((store, contexts, early)) => {
+            eprintln!("Saturated while parsing ({} items ahead of the final pass)", early);
+            (Some(store), Some(contexts))
+        }
+        None => (None, None),
+    };
+
+    // Build axiom store
+    let normalize_span = info_span!("normalize").entered();
+    let (mut store, query_ids) = match store {
+        Some(store) => (store, Vec::new()),
+        None => {
+            // Queries are numbered right after the parsed concepts, ahead of
+            // the fresh concepts of definitions, so they stay named.
+            let mut store = build_told_axiom_store(&parse_result);
+            let query_ids = match &args.queries {
+                Some(path) => register_named_queries(path, &mut parse_result, &mut store),
+                None => Vec::new(),
+            };
+            add_definitions(&parse_result, &mut store);
+            (store, query_ids)
+        }
+    };
+    let num_concepts = store.num_concepts();
+    drop(normalize_span);
+    let build_time = recorder.phase("normalize");
+    end_phase("normalize");
+    info!("Built axiom store in {:?}", build_time);
+    if let Some(path) = &args.dump_normalized {
+        let written = open_sink(path)
+            .and_then(|mut sink| write_normalized(&mut sink, &store, &parse_result.concepts, &parse_result.roles));
+        match written {
+            Ok(n) => eprintln!("Wrote {} normalized axioms to {}", n, path.display()),
+            Err(e) => die(&format!("writing {}: {}", path.display(), e)),
+        }
+    }
+
+    // Saturate
+    let saturate_span = info_span!("saturate").entered();
+    let mut contexts = pipelined
+        .unwrap_or_else(|| saturate_for(&args, &store, num_concepts, num_roles, &parse_result, &documents));
+    if args.deterministic {
+        contexts.iter_mut().for_each(Context::canonicalize);
+    }
+    drop(saturate_span);
+    let sat_time = recorder.phase("saturate");
+    end_phase("saturation");
+    info!("Saturation complete in {:?}", sat_time);
+    if !is_consistent(&contexts) {
+        report_inconsistent(input_path, &store, &contexts, &parse_result);
+    }
+
+    // Build taxonomy
+    let reduce_span = info_span!("reduce").entered();
+    let named = parse_result.named();
+    let equivalences = equivalence_classes_among(&contexts, &named);
+    let mut direct_parents = if args.merge_equivalents {
+        merge_equivalents(&args, &contexts, &parse_result)
+    } else if let Some(threads) = args.threads {
+        build_taxonomy_parallel_among(&contexts, &named, &equivalences, threads)
+    } else {
+        let mut bar = ProgressBar::default();
+        let progress: &mut dyn ProgressSink = if args.progress { &mut bar } else { &mut NoProgress };
+        build_taxonomy_among(&contexts, &named, &equivalences, progress)
+    };
+    if args.deterministic {
+        sort_direct_parents(&mut direct_parents);
+    }
+    let taxonomy = Taxonomy::from_parents(direct_parents).with_equivalences(equivalences);
+    drop(reduce_span);
+    let tax_time = recorder.phase("reduce");
+    end_phase("reduction");
+    info!("Taxonomy built in {:?}", tax_time);
+    if let Some(path) = &args.save_taxonomy {
+        let written = File::create(path)
+            .and_then(|f| write_taxonomy(&taxonomy, BufWriter::with_capacity(1024 * 1024, f)));
+        if let Err(e) = written {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+    }
+    if let Some(path) = &args.save_closure {
+        let written = File::create(path).and_then(|f| {
+            let out = BufWriter::with_capacity(1024 * 1024, f);
+            write_closure_index(&contexts, &parse_result.concepts[..num_parsed], &parse_result.roles, out)
+        });
+        if let Err(e) = written {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+    }
+
+    // Count inferred subsumptions
+    let inferred = count_inferred_subsumptions(&contexts[..num_parsed]);
+    let unsat = unsatisfiable_concepts(&contexts[..num_parsed]);
+
+    // Realize individuals on top of the classification
+    let realization = (!parse_result.individuals.is_empty())
+        .then(|| realize(&parse_result, &mut store, &mut contexts));
+
+    for &q in &query_ids {
+        let members = contexts[2..num_parsed]
+            .iter()
+            .filter(|ctx| ctx.super_set.contains(&q))
+            .count();
+        eprintln!("Query '{}': {} subclasses", parse_result.concepts[q as usize], members);
+    }
+
+    let mut report = vec![
+        "=== Classification Stats ===".to_string(),
+        format!("Concepts: {}", num_parsed - 2),
+        format!("Roles: {}", num_roles),
+        format!("Inferred subsumptions: {}", inferred),
+        format!("Root concepts: {}", taxonomy.roots().len()),
+        format!("Unsatisfiable concepts: {}", unsat.len()),
+        format!("Equivalence classes: {}", taxonomy.equivalences().len()),
+        format!("Parse time: {:?}", parse_time),
+        format!("Normalize time: {:?}", build_time),
+        format!("Saturation time: {:?}", sat_time),
+        format!("Reduction time: {:?}", tax_time),
+        format!("Total time: {:?}", parse_time + build_time + sat_time + tax_time),
+    ];
+    if realization.is_some() {
+        report.insert(3, format!("Individuals: {}", parse_result.individuals.len()));
+    }
+    let footprint = args.memory.then(|| (Footprint::of(&contexts), memory::store_bytes(&store)));
+    if let Some((footprint, store_bytes)) = footprint {
+        let peaks: Vec<String> = peaks
+            .iter()
+            .map(|&(phase, peak)| match peak {
+                Some(peak) => format!("{} {}", phase, Bytes(peak)),
+                None => format!("{} unknown", phase),
+            })
+            .collect();
+        report.push(format!("Peak memory: {}", peaks.join(", ")));
+        report.push(format!("Contexts: {}", footprint));
+        report.push(format!("Axiom store: {}", Bytes(store_bytes)));
+        report.extend(arena_report());
+    }
+    let report = report.join("\n");
+    match args.command {
+        Command::Stats { .. } => println!("{}", report),
+        _ => eprintln!("\n{}", report),
+    }
+
+    if !unsat.is_empty() {
+        eprintln!("Unsatisfiable [{}]", unsat.len());
+        for &c in &unsat {
+            eprintln!("  {}", describe(&parse_result, c));
+        }
+    }
+    if !taxonomy.equivalences().is_empty() {
+        eprintln!("Equivalent [{}]", taxonomy.equivalences().len());
+        for class in taxonomy.equivalences() {
+            let members: Vec<String> = class.iter().map(|&c| describe(&parse_result, c)).collect();
+            eprintln!("  {}", members.join(" = "));
+        }
+    }
+    if let Some(realization) = &realization {
+        let inconsistent = realization.inconsistent();
+        if !inconsistent.is_empty() {
+            eprintln!("Inconsistent individuals [{}]", inconsistent.len());
+            for &i in inconsistent {
+                eprintln!("  {}", describe_individual(&parse_result, i));
+            }
+        }
+    }
+    if args.print_roots {
+        println!("Roots [{}]", taxonomy.roots().len());
+        for &c in taxonomy.roots() {
+            println!("  {}", describe(&parse_result, c));
+        }
+    }
+    if args.print_leaves {
+        let leaves: Vec<ConceptId> = taxonomy.leaves().collect();
+        println!("Leaves [{}]", leaves.len());
+        for c in leaves {
+            println!("  {}", describe(&parse_result, c));
+        }
+    }
+
+    if let Some(path) = &args.output {
+        let summary = (args.format == OutputFormat::Json).then(|| {
+            let millis = |d: Duration| Value::from(d.as_secs_f64() * 1000.0);
+            let mut stats = vec![
+                ("concepts", Value::from(num_parsed - 2)),
+                ("roles", Value::from(num_roles)),
+                ("inferred_subsumptions", Value::from(inferred)),
+                ("root_concepts", Value::from(taxonomy.roots().len())),
+            ];
+            // Timings would make otherwise identical runs differ.
+            if !args.deterministic {
+                stats.extend([
+                    ("parse_ms", millis(parse_time)),
+                    ("normalize_ms", millis(build_time)),
+                    ("saturation_ms", millis(sat_time)),
+                    ("reduction_ms", millis(tax_time)),
+                    ("total_ms", millis(parse_time + build_time + sat_time + tax_time)),
+                ]);
+            }
+            if let (Some((footprint, store_bytes)), false) = (footprint, args.deterministic) {
+                let peaks = peaks.iter().map(|&(phase, peak)| (format!("{}_bytes", phase), peak.map_or(Value::Null, Value::from)));
+                stats.extend([
+                    ("peak_memory", Value::Object(peaks.collect())),
+                    ("context_bytes", Value::from(footprint.total())),
+                    ("store_bytes", Value::from(store_bytes)),
+                ]);
+            }
+            if realization.is_some() {
+                stats.insert(3, ("individuals", Value::from(parse_result.individuals.len())));
+            }
+            json_summary(&parse_result, &taxonomy, &unsat, stats)
+        });
+        let summary = summary.unwrap_or_default();
+        write_output(&args, path, &parse_result, &taxonomy, resolver.as_ref(), summary);
+    }
+    if let Some(path) = &args.stats_json {
+        let cpus = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let mut stats = vec![
+            ("input".to_string(), Value::from(input_path.as_str())),
+            ("version".to_string(), Value::from(env!("CARGO_PKG_VERSION"))),
+            ("os".to_string(), Value::from(env::consts::OS)),
+            ("arch".to_string(), Value::from(env::consts::ARCH)),
+            ("cpus".to_string(), Value::from(cpus)),
+            ("threads".to_string(), Value::from(args.threads.map_or(1, NonZeroUsize::get))),
+            ("worklist".to_string(), Value::from(args.worklist.name())),
+            ("concepts".to_string(), Value::from(num_parsed - 2)),
+            ("roles".to_string(), Value::from(num_roles)),
+            ("individuals".to_string(), Value::from(parse_result.individuals.len())),
+            ("inferred_subsumptions".to_string(), Value::from(inferred)),
+            ("unsatisfiable".to_string(), Value::from(unsat.len())),
+            ("equivalence_classes".to_string(), Value::from(taxonomy.equivalences().len())),
+            ("total_ms".to_string(), Value::from((parse_time + build_time + sat_time + tax_time).as_secs_f64() * 1000.0)),
+        ];
+        if let Value::Object(recorded) = recorder.to_json() {
+            stats.extend(recorded);
+        }
+        let written = open_sink(path).and_then(|mut sink| {
+            writeln!(sink, "{}", Value::Object(stats))?;
+            sink.flush()
+        });
+        if let Err(e) = written {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+    }
+    if let Some(path) = &args.types {
+        let written = open_sink(path).and_then(|mut sink| {
+            if let Some(realization) = &realization {
+                for i in 0..parse_result.individuals.len() {
+                    for &c in realization.direct_types_of(i) {
+                        let concept = &parse_result.concepts[c as usize];
+                        writeln!(sink, "{}\t{}", parse_result.individuals[i], concept)?;
+                    }
+                }
+            }
+            sink.flush()
+        });
+        if let Err(e) = written {
+            die(&format!("writing {}: {}", path.display(), e));
+        }
+    }
+}
+
+/// How the `arena` allocator has fared, for `--memory`.
+#[cfg(feature = "arena")]
+fn arena_report() -> Option<String> {
+    let stats = ALLOCATOR.stats();
+    let (reserved, live, abandoned) = (Bytes(stats.reserved), Bytes(stats.live), Bytes(stats.abandoned));
+    Some(format!("Arena: {} reserved, {} live, {} abandoned", reserved, live, abandoned))
+}
+
+#[cfg(not(feature = "arena"))]
+fn arena_report() -> Option<String> {
+    None
+}
+
+/// The members `--format json` adds after the concepts: equivalence
+/// classes, unsatisfiable concepts and `stats`.
+fn json_summary(
+    parsed: &ParseResult,
+    taxonomy: &Taxonomy,
+    unsat: &[ConceptId],
+    stats: Vec<(&str, Value)>,
+) -> Vec<(String, Value)> {
+    let name = |&c: &ConceptId| Value::from(parsed.concepts[c as usize].as_str());
+    let equivalences = taxonomy
+        .equivalences()
+        .iter()
+        .map(|class| Value::Array(class.iter().map(name).collect()));
+    let stats = stats.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    vec![
+        ("equivalences".to_string(), Value::Array(equivalences.collect())),
+        ("unsatisfiable".to_string(), Value::Array(unsat.iter().map(name).collect())),
+        ("stats".to_string(), Value::Object(stats)),
+    ]
+}
+
+/// Writes the classification in `--format` to `path`; `summary` ends a
+/// `json` document.
+fn write_output(
+    args: &Args,
+    path: &Path,
+    parsed: &ParseResult,
+    taxonomy: &Taxonomy,
+    resolver: &dyn IdResolver,
+    summary: Vec<(String, Value)>,
+) {
+    let depths = args.with_depth.then(|| taxonomy.depths());
+    let view = ClassificationView {
+        concepts: &parsed.concepts,
+        direct_parents: taxonomy.direct_parents(),
+        depths: depths.as_deref(),
+        labels: Some(&parsed.labels),
+    };
+    let root = args.subtree.as_ref().map(|term| {
+        let symbols = SymbolIndex::with_resolver(parsed, resolver);
+        symbols.concept(term).unwrap_or_else(|| die(&format!("unknown term '{}'", term)))
+    });
+    let written = open_sink(path).and_then(|mut sink| match args.format.writer() {
+        Some(_) if args.format == OutputFormat::Dot => write_dot(&view, taxonomy, root, &mut sink),
+        Some(_) if args.format == OutputFormat::Json => {
+            write_classification(&mut JsonWriter::with_summary(summary), &view, &mut sink)
+        }
+        Some(mut writer) => write_classification(writer.as_mut(), &view, &mut sink),
+        None => {
+            let source = BufReader::with_capacity(1024 * 1024, File::open(&args.input)?);
+            let added =
+                write_with_inferred(source, &mut sink, parsed, taxonomy.direct_parents(), &[], resolver)?;
+            eprintln!("Added {} inferred is_a lines", added);
+            Ok(())
+        }
+    });
+    if let Err(e) = written {
+        eprintln!("error: writing {}: {}", path.display(), e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,131 @@
+//! Where the memory goes: the heap footprint of the saturation structures,
+//! estimated from their capacities, and the peak resident size of the
+//! process per phase (`--memory`).
+//!
+//! Footprints count what the collections hold on to, spare capacity
+//! included, but not the allocator's own overhead; the peak resident size
+//! is what the kernel reports and includes everything.
+
+use std::fmt;
+use std::mem::{size_of, size_of_val};
+
+use crate::kernel::{AxiomStore, ConceptId, Context};
+
+/// Heap bytes of a hash table with room for `capacity` entries of `entry`
+/// bytes: a power-of-two bucket array at most 7/8 full, with a control
+/// byte per bucket and a group of spare control bytes.
+fn table_bytes(capacity: usize, entry: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    let buckets = (capacity * 8 / 7).next_power_of_two();
+    buckets * (entry + 1) + 16
+}
+
+fn vec_bytes<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+/// Per-role lists: the outer vector and every inner one.
+fn lists_bytes(lists: &Vec<Vec<ConceptId>>) -> usize {
+    vec_bytes(lists) + lists.iter().map(vec_bytes).sum::<usize>()
+}
+
+/// Estimated heap footprint of saturated contexts, by structure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Footprint {
+    /// The `Context` records themselves.
+    pub records: usize,
+    pub super_sets: usize,
+    pub link_maps: usize,
+    pub pred_maps: usize,
+}
+
+impl Footprint {
+    pub fn of(contexts: &[Context]) -> Self {
+        let mut footprint = Footprint { records: size_of_val(contexts), ..Self::default() };
+        for ctx in contexts {
+            footprint.super_sets += table_bytes(ctx.super_set.capacity(), size_of::<ConceptId>());
+            footprint.link_maps += lists_bytes(&ctx.link_map);
+            footprint.pred_maps += lists_bytes(&ctx.pred_map);
+        }
+        footprint
+    }
+
+    pub fn total(&self) -> usize {
+        self.records + self.super_sets + self.link_maps + self.pred_maps
+    }
+}
+
+/// `total (super sets …, link maps …, predecessor maps …, records …)`.
+impl fmt::Display for Footprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (super sets {}, link maps {}, predecessor maps {}, records {})",
+            Bytes(self.total()),
+            Bytes(self.super_sets),
+            Bytes(self.link_maps),
+            Bytes(self.pred_maps),
+            Bytes(self.records)
+        )
+    }
+}
+
+/// Estimated heap footprint of the normalized axioms.
+pub fn store_bytes(store: &AxiomStore) -> usize {
+    let entry = size_of::<(ConceptId, Vec<ConceptId>)>();
+    let indexes = store.conj_index.iter().chain(&store.exist_left).map(|index| {
+        table_bytes(index.capacity(), entry) + index.values().map(vec_bytes).sum::<usize>()
+    });
+    let existentials = store.exist_right.iter().map(vec_bytes);
+    let chains = store.chains_by_first.iter().chain(&store.chains_by_second);
+    let identities = [
+        &store.right_identities,
+        &store.right_identities_by_second,
+        &store.left_identities,
+        &store.left_identities_by_first,
+    ];
+    lists_bytes(&store.sub_to_sups)
+        + lists_bytes(&store.role_sub_to_sups)
+        + indexes.sum::<usize>()
+        + vec_bytes(&store.exist_right)
+        + existentials.sum::<usize>()
+        + chains.map(vec_bytes).sum::<usize>()
+        + identities.into_iter().map(lists_bytes).sum::<usize>()
+}
+
+/// A byte count in B, KiB, MiB or GiB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bytes(pub usize);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0 as f64;
+        for unit in ["B", "KiB", "MiB"] {
+            if value < 1024.0 {
+                return match unit {
+                    "B" => write!(f, "{} B", self.0),
+                    unit => write!(f, "{:.1} {}", value, unit),
+                };
+            }
+            value /= 1024.0;
+        }
+        write!(f, "{:.1} GiB", value)
+    }
+}
+
+/// The largest resident size of the process since it started or since the
+/// last [`reset_peak`] (`VmHWM`); `None` where the kernel doesn't say.
+pub fn peak_resident() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find_map(|l| l.strip_prefix("VmHWM:"))?;
+    let kib: usize = line.trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Starts a new peak for [`peak_resident`] at the current resident size,
+/// so each phase can report its own. Does nothing where unsupported.
+pub fn reset_peak() {
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
@@ -0,0 +1,130 @@
+//! Collapsing inferred equivalence groups to one canonical term each, for
+//! consumers that can only represent a tree/DAG of distinct terms.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::kernel::{ConceptId, Context, BOTTOM, TOP};
+use crate::taxonomy::equivalence_classes;
+
+/// Picks the canonical member of an equivalence group: the term whose
+/// prefix comes first in `prefixes`, then the smallest ID.
+pub struct Precedence<'a> {
+    prefixes: &'a [String],
+}
+
+impl<'a> Precedence<'a> {
+    pub fn new(prefixes: &'a [String]) -> Self {
+        Self { prefixes }
+    }
+
+    /// Position of `id`'s prefix in the precedence list; unlisted prefixes
+    /// rank last. A listed prefix matches the CURIE prefix (`GO` for
+    /// `GO:0005739`) or, for IRIs, the start of the IRI.
+    pub fn rank(&self, id: &str) -> usize {
+        let curie_prefix = id.split_once(':').map(|(p, _)| p);
+        let matches = |p: &String| {
+            curie_prefix == Some(p.as_str()) || (p.contains('/') && id.starts_with(p.as_str()))
+        };
+        self.prefixes.iter().position(matches).unwrap_or(self.prefixes.len())
+    }
+
+    fn pick(&self, group: &[ConceptId], names: &[String]) -> ConceptId {
+        let name = |c: ConceptId| names[c as usize].as_str();
+        *group
+            .iter()
+            .min_by(|&&a, &&b| (self.rank(name(a)), name(a)).cmp(&(self.rank(name(b)), name(b))))
+            .expect("equivalence groups are never empty")
+    }
+}
+
+pub struct EquivalenceMerge {
+    /// Concept → canonical member of its group (itself for singletons).
+    canonical: Vec<ConceptId>,
+    /// Reduced hierarchy over canonical concepts; merged-away concepts have
+    /// no parents.
+    direct_parents: Vec<Vec<ConceptId>>,
+    groups: usize,
+}
+
+impl EquivalenceMerge {
+    /// Merges the equivalent satisfiable concepts among the first
+    /// `num_concepts` named ones (`names[c]`) and reduces the hierarchy
+    /// between the canonical terms.
+    pub fn new(
+        contexts: &[Context],
+        num_concepts: usize,
+        names: &[String],
+        precedence: &Precedence,
+    ) -> Self {
+        let mut canonical: Vec<ConceptId> = (0..num_concepts as ConceptId).collect();
+        let classes = equivalence_classes(contexts, num_concepts);
+        for group in &classes {
+            let rep = precedence.pick(group, names);
+            for &m in group {
+                canonical[m as usize] = rep;
+            }
+        }
+        let groups = classes.len();
+
+        let mut direct_parents = vec![Vec::new(); num_concepts];
+        for c in 2..num_concepts {
+            if canonical[c] != c as ConceptId {
+                continue;
+            }
+            let mut candidates: Vec<ConceptId> = contexts[c]
+                .super_set
+                .iter()
+                .filter(|&&s| s != TOP && s != BOTTOM && (s as usize) < num_concepts)
+                .map(|&s| canonical[s as usize])
+                .filter(|&s| s != c as ConceptId)
+                .collect();
+            candidates.sort_unstable();
+            candidates.dedup();
+
+            let mut direct: Vec<ConceptId> = candidates
+                .iter()
+                .copied()
+                .filter(|&b| {
+                    !candidates
+                        .iter()
+                        .any(|&s| s != b && contexts[s as usize].super_set.contains(&b))
+                })
+                .collect();
+            if direct.is_empty() {
+                direct.push(TOP);
+            }
+            direct_parents[c] = direct;
+        }
+
+        Self { canonical, direct_parents, groups }
+    }
+
+    pub fn canonical(&self, c: ConceptId) -> ConceptId {
+        self.canonical[c as usize]
+    }
+
+    /// Number of equivalence groups that were collapsed.
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    /// `(merged, canonical)` for every concept merged into another, in
+    /// ascending order of the merged concept.
+    pub fn merged(&self) -> impl Iterator<Item = (ConceptId, ConceptId)> + '_ {
+        self.canonical
+            .iter()
+            .enumerate()
+            .filter(|&(c, &rep)| rep != c as ConceptId)
+            .map(|(c, &rep)| (c as ConceptId, rep))
+    }
+
+    pub fn direct_parents(&self) -> &[Vec<ConceptId>] {
+        &self.direct_parents
+    }
+
+    pub fn into_direct_parents(self) -> Vec<Vec<ConceptId>> {
+        self.direct_parents
+    }
+}
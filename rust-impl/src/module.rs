@@ -0,0 +1,252 @@
+//! Syntactic ⊥-locality modules over the normalized axiom store.
+//!
+//! An axiom is ⊥-local for a signature Σ when its left-hand side mentions a
+//! symbol outside Σ (the symbol can be read as empty, making the axiom
+//! trivially true). The module for Σ is the closure that keeps adding the
+//! non-local axioms and their symbols; it entails the same subsumptions
+//! between Σ-concepts as the whole store, so saturating it alone yields the
+//! exact super sets of the seed concepts.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::kernel::{saturate, AxiomStore, ConceptId, Context, RoleId, BOTTOM, TOP};
+
+pub struct Module {
+    concepts: Vec<bool>,
+    roles: Vec<bool>,
+    len: usize,
+}
+
+impl Module {
+    /// The module of `seed` plus TOP and BOTTOM, which every context sees.
+    pub fn extract(
+        store: &AxiomStore,
+        num_roles: usize,
+        seed: impl IntoIterator<Item = ConceptId>,
+    ) -> Self {
+        let mut module = Self {
+            concepts: vec![false; store.num_concepts()],
+            roles: vec![false; num_roles],
+            len: 0,
+        };
+        let mut worklist: Vec<ConceptId> = Vec::new();
+        // Nominals are never empty, so axioms on them are never local.
+        for c in [TOP, BOTTOM].into_iter().chain(store.nominals.iter().copied()).chain(seed) {
+            module.add_concept(c, &mut worklist);
+        }
+
+        // Reflexivity has no left-hand side to be empty, so it is never local.
+        let mut new_roles: Vec<RoleId> = Vec::new();
+        for &r in &store.reflexive_roles {
+            module.add_role(store, r, &mut new_roles);
+        }
+        while let Some(a) = worklist.pop() {
+            let a_idx = a as usize;
+            for &b in &store.sub_to_sups[a_idx] {
+                module.add_concept(b, &mut worklist);
+            }
+            for (&other, rights) in &store.conj_index[a_idx] {
+                if module.contains(other) {
+                    for &c in rights {
+                        module.add_concept(c, &mut worklist);
+                    }
+                }
+            }
+            for rf in &store.exist_right[a_idx] {
+                module.add_concept(rf.fill, &mut worklist);
+                module.add_role(store, rf.role, &mut new_roles);
+            }
+            for (r, by_fill) in store.exist_left.iter().enumerate() {
+                if module.roles[r] {
+                    if let Some(sups) = by_fill.get(&a) {
+                        for &c in sups {
+                            module.add_concept(c, &mut worklist);
+                        }
+                    }
+                }
+            }
+            // A role entering Σ makes NF4 axioms on earlier fillers non-local.
+            for r in new_roles.drain(..) {
+                for (&fill, sups) in &store.exist_left[r as usize] {
+                    if module.contains(fill) {
+                        for &c in sups {
+                            module.add_concept(c, &mut worklist);
+                        }
+                    }
+                }
+            }
+        }
+        module
+    }
+
+    /// Adds `r` and the super roles it makes non-local: those of `r ⊑ s`,
+    /// and of chains whose other role is already in Σ.
+    fn add_role(&mut self, store: &AxiomStore, r: RoleId, new_roles: &mut Vec<RoleId>) {
+        let mut pending = vec![r];
+        while let Some(r) = pending.pop() {
+            if self.roles[r as usize] {
+                continue;
+            }
+            self.roles[r as usize] = true;
+            new_roles.push(r);
+            pending.extend(&store.role_sub_to_sups[r as usize]);
+            let by_first = store.chains_by_first[r as usize].iter();
+            let by_second = store.chains_by_second[r as usize].iter();
+            for &(other, t) in by_first.chain(by_second) {
+                if self.roles[other as usize] {
+                    pending.push(t);
+                }
+            }
+        }
+    }
+
+    fn add_concept(&mut self, c: ConceptId, worklist: &mut Vec<ConceptId>) {
+        let slot = &mut self.concepts[c as usize];
+        if !*slot {
+            *slot = true;
+            self.len += 1;
+            worklist.push(c);
+        }
+    }
+
+    pub fn contains(&self, c: ConceptId) -> bool {
+        self.concepts.get(c as usize).copied().unwrap_or(false)
+    }
+
+    pub fn contains_role(&self, r: RoleId) -> bool {
+        self.roles.get(r as usize).copied().unwrap_or(false)
+    }
+
+    /// Number of concepts in the module's signature.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The store's axioms that are non-local for the module's signature.
+    pub fn restrict(&self, store: &AxiomStore) -> AxiomStore {
+        let mut sub = AxiomStore::new(store.num_concepts(), self.roles.len());
+        self.copy_axioms(store, &mut sub, |c| c);
+        sub
+    }
+
+    /// [`restrict`](Self::restrict) with the module's concepts renumbered
+    /// densely, in ascending order (TOP and BOTTOM keep their IDs), and the
+    /// original ID of each.
+    pub fn compact(&self, store: &AxiomStore) -> (AxiomStore, Vec<ConceptId>) {
+        let ids: Vec<ConceptId> =
+            (0..store.num_concepts() as ConceptId).filter(|&c| self.contains(c)).collect();
+        let mut dense = vec![0 as ConceptId; store.num_concepts()];
+        for (i, &c) in ids.iter().enumerate() {
+            dense[c as usize] = i as ConceptId;
+        }
+        let mut sub = AxiomStore::new(ids.len(), self.roles.len());
+        self.copy_axioms(store, &mut sub, |c| dense[c as usize]);
+        (sub, ids)
+    }
+
+    /// Adds the non-local axioms of `store` to `sub`, with concepts passed
+    /// through `id`.
+    fn copy_axioms(&self, store: &AxiomStore, sub: &mut AxiomStore, id: impl Fn(ConceptId) -> ConceptId) {
+        for a in (0..store.num_concepts() as ConceptId).filter(|&a| self.contains(a)) {
+            let a_idx = a as usize;
+            for &b in &store.sub_to_sups[a_idx] {
+                sub.add_subsumption(id(a), id(b));
+            }
+            for (&other, rights) in &store.conj_index[a_idx] {
+                // Stored under both conjuncts; re-add from the smaller one.
+                if other >= a && self.contains(other) {
+                    for &c in rights {
+                        sub.add_conjunction(id(a), id(other), id(c));
+                    }
+                }
+            }
+            for rf in &store.exist_right[a_idx] {
+                sub.add_exist_right(id(a), rf.role, id(rf.fill));
+            }
+        }
+        for (r, sups) in store.role_sub_to_sups.iter().enumerate() {
+            if self.roles[r] {
+                for &s in sups {
+                    sub.add_role_subsumption(r as RoleId, s);
+                }
+                for (_, second, t) in store.role_chains_from(r as RoleId) {
+                    if self.roles[second as usize] {
+                        sub.add_role_chain(r as RoleId, second, t);
+                    }
+                }
+            }
+        }
+        for &r in &store.reflexive_roles {
+            sub.add_reflexive(r);
+        }
+        for &a in &store.nominals {
+            sub.add_nominal(id(a));
+        }
+        for (r, by_fill) in store.exist_left.iter().enumerate() {
+            if !self.roles[r] {
+                continue;
+            }
+            for (&fill, sups) in by_fill {
+                if self.contains(fill) {
+                    for &c in sups {
+                        sub.add_exist_left(r as RoleId, id(fill), id(c));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether `sub ⊑ sup` follows from `store`, saturating only the module of
+/// `sub` instead of every concept. For one-off questions; classify the
+/// whole store when asking many.
+pub fn is_subsumed_by(store: &AxiomStore, sub: ConceptId, sup: ConceptId) -> bool {
+    if sub == sup || sup == TOP {
+        return true;
+    }
+    let num_roles = store.exist_left.len();
+    let module = Module::extract(store, num_roles, [sub]);
+    let (compact, ids) = module.compact(store);
+    let local = |c: ConceptId| ids.binary_search(&c).ok();
+    let Some(sub) = local(sub) else {
+        return false;
+    };
+    let contexts = saturate(&compact, ids.len(), num_roles);
+    let supers = &contexts[sub].super_set;
+    supers.contains(&BOTTOM) || local(sup).is_some_and(|s| supers.contains(&(s as ConceptId)))
+}
+
+/// Saturates the module of `seed` alone, for classifying part of a store:
+/// the super sets of the module's concepts come out exact, since the module
+/// is also the module of its own signature. The contexts cover every
+/// concept of `store`; those outside the module are left empty, and links
+/// and predecessors only reach concepts inside it.
+pub fn saturate_signature(
+    store: &AxiomStore,
+    num_roles: usize,
+    seed: impl IntoIterator<Item = ConceptId>,
+) -> (Vec<Context>, Module) {
+    let module = Module::extract(store, num_roles, seed);
+    let (compact, ids) = module.compact(store);
+    let original = |c: &ConceptId| ids[*c as usize];
+    let remap = |targets: Vec<Vec<ConceptId>>| -> Vec<Vec<ConceptId>> {
+        targets.into_iter().map(|t| t.iter().map(original).collect()).collect()
+    };
+    let mut contexts: Vec<Context> = (0..store.num_concepts() as ConceptId)
+        .map(|c| Context::with_capacity(c, num_roles, 0))
+        .collect();
+    for (ctx, &c) in saturate(&compact, ids.len(), num_roles).into_iter().zip(&ids) {
+        contexts[c as usize] = Context {
+            id: c,
+            super_set: ctx.super_set.iter().map(original).collect(),
+            link_map: remap(ctx.link_map),
+            pred_map: remap(ctx.pred_map),
+        };
+    }
+    (contexts, module)
+}
@@ -0,0 +1,329 @@
+//! Syntactic locality modules over the told axioms of a [`ParseResult`]
+//! (see [`crate::module`] for ⊥-modules of a normalized store).
+//!
+//! An axiom is ⊥-local for a signature Σ when reading every symbol outside
+//! Σ as empty makes it trivially true, and ⊤-local when reading concepts
+//! outside Σ as everything and roles outside Σ as the universal role does.
+//! The module for Σ keeps adding the axioms that are not local for Σ plus
+//! the symbols of the module so far; it entails everything the whole
+//! ontology entails over Σ. STAR modules alternate ⊥ and ⊤ extraction until
+//! neither removes anything, and are the smallest of the three.
+//!
+//! In EL, a left-hand side stays non-empty only when all its symbols are in
+//! the signature, and a right-hand side stops being everything as soon as
+//! one of its symbols is; so each axiom turns non-local once all (⊥) or any
+//! (⊤) of a fixed set of its symbols is reached, and extraction runs on
+//! counters instead of re-checking every axiom.
+
+use std::collections::HashSet;
+
+use crate::kernel::{ConceptId, RoleId, BOTTOM, TOP};
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::obo::ParseResult;
+
+/// A told axiom of a [`ParseResult`]: the table it is in and its index there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AxiomId {
+    /// `subsumptions[i]`.
+    IsA(usize),
+    /// `relations[i]`.
+    Relationship(usize),
+    /// `disjoint_pairs[i]`.
+    Disjoint(usize),
+    /// `definitions[i]`.
+    Definition(usize),
+    /// `class_axioms[i]`.
+    ClassAxiom(usize),
+    /// `role_subsumptions[i]`.
+    SubRole(usize),
+    /// `role_chains[i]`.
+    RoleChain(usize),
+    /// `reflexive_roles[i]`.
+    Reflexive(usize),
+    /// `role_domains[i]`.
+    Domain(usize),
+    /// `role_ranges[i]`.
+    Range(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locality {
+    Bottom,
+    Top,
+    Star,
+}
+
+/// A condition under which an axiom is not local: `need` of its `symbols`
+/// are in the signature.
+struct Unit {
+    axiom: AxiomId,
+    symbols: Vec<usize>,
+    need: usize,
+}
+
+/// [`Unit`]s of one locality, indexed by symbol.
+struct Units {
+    units: Vec<Unit>,
+    by_symbol: Vec<Vec<usize>>,
+}
+
+impl Units {
+    fn new(mut units: Vec<Unit>, num_symbols: usize) -> Self {
+        let mut by_symbol = vec![Vec::new(); num_symbols];
+        for (u, unit) in units.iter_mut().enumerate() {
+            unit.symbols.sort_unstable();
+            unit.symbols.dedup();
+            for &s in &unit.symbols {
+                by_symbol[s].push(u);
+            }
+        }
+        Self { units, by_symbol }
+    }
+}
+
+/// Extracts locality modules of one parse result; the indexes are built
+/// once, so extracting many modules only pays for their size.
+pub struct ModuleExtractor<'p> {
+    parsed: &'p ParseResult,
+    bottom: Units,
+    top: Units,
+}
+
+impl<'p> ModuleExtractor<'p> {
+    pub fn new(parsed: &'p ParseResult) -> Self {
+        let mut extractor = Self {
+            parsed,
+            bottom: Units { units: Vec::new(), by_symbol: Vec::new() },
+            top: Units { units: Vec::new(), by_symbol: Vec::new() },
+        };
+        let all = |axiom, symbols: Vec<usize>| Unit { axiom, need: symbols.len(), symbols };
+        let any = |axiom, mut symbols: Vec<usize>| {
+            // TOP never stops a right-hand side from being everything.
+            symbols.retain(|&s| s != TOP as usize);
+            Unit { axiom, symbols, need: 1 }
+        };
+        let x = &extractor;
+        let (mut bottom, mut top) = (Vec::new(), Vec::new());
+        for (i, &(sub, sup)) in parsed.subsumptions.iter().enumerate() {
+            bottom.push(all(AxiomId::IsA(i), vec![sub]));
+            top.push(any(AxiomId::IsA(i), vec![sup]));
+        }
+        for (i, &(sub, r, filler)) in parsed.relations.iter().enumerate() {
+            bottom.push(all(AxiomId::Relationship(i), vec![sub]));
+            top.push(any(AxiomId::Relationship(i), vec![x.role(r), filler]));
+        }
+        for (i, &(a, b)) in parsed.disjoint_pairs.iter().enumerate() {
+            bottom.push(all(AxiomId::Disjoint(i), vec![a, b]));
+            top.push(any(AxiomId::Disjoint(i), vec![BOTTOM as usize]));
+        }
+        for (i, definition) in parsed.definitions.iter().enumerate() {
+            let id = AxiomId::Definition(i);
+            bottom.push(all(id, vec![definition.term]));
+            bottom.push(all(id, x.symbols(&definition.expression())));
+            top.push(any(id, x.signature(id)));
+        }
+        for (i, axiom) in parsed.class_axioms.iter().enumerate() {
+            let id = AxiomId::ClassAxiom(i);
+            match axiom {
+                ClassAxiom::SubClassOf(sub, sup) => {
+                    bottom.push(all(id, x.symbols(sub)));
+                    top.push(any(id, x.symbols(sup)));
+                }
+                ClassAxiom::Equivalent(a, b) => {
+                    bottom.push(all(id, x.symbols(a)));
+                    bottom.push(all(id, x.symbols(b)));
+                    top.push(any(id, x.signature(id)));
+                }
+            }
+        }
+        for (i, &(sub, sup)) in parsed.role_subsumptions.iter().enumerate() {
+            bottom.push(all(AxiomId::SubRole(i), vec![x.role(sub)]));
+            top.push(any(AxiomId::SubRole(i), vec![x.role(sup)]));
+        }
+        for (i, &(first, second, sup)) in parsed.role_chains.iter().enumerate() {
+            bottom.push(all(AxiomId::RoleChain(i), vec![x.role(first), x.role(second)]));
+            top.push(any(AxiomId::RoleChain(i), vec![x.role(sup)]));
+        }
+        for (i, &r) in parsed.reflexive_roles.iter().enumerate() {
+            // Never ⊥-local: TOP, always in the signature, stands for the
+            // missing left-hand side.
+            bottom.push(all(AxiomId::Reflexive(i), vec![TOP as usize]));
+            top.push(any(AxiomId::Reflexive(i), vec![x.role(r)]));
+        }
+        for (i, &(r, domain)) in parsed.role_domains.iter().enumerate() {
+            bottom.push(all(AxiomId::Domain(i), vec![x.role(r)]));
+            top.push(any(AxiomId::Domain(i), vec![domain]));
+        }
+        for (i, &(r, range)) in parsed.role_ranges.iter().enumerate() {
+            bottom.push(all(AxiomId::Range(i), vec![x.role(r)]));
+            top.push(any(AxiomId::Range(i), vec![range]));
+        }
+
+        let num_symbols = parsed.concepts.len() + parsed.roles.len();
+        extractor.bottom = Units::new(bottom, num_symbols);
+        extractor.top = Units::new(top, num_symbols);
+        extractor
+    }
+
+    /// Symbols are concepts, then roles.
+    fn role(&self, r: usize) -> usize {
+        self.parsed.concepts.len() + r
+    }
+
+    fn symbols(&self, expr: &ClassExpr) -> Vec<usize> {
+        let mut out = Vec::new();
+        let mut stack = vec![expr];
+        while let Some(e) = stack.pop() {
+            match e {
+                ClassExpr::Named(c) => out.push(*c as usize),
+                ClassExpr::And(parts) => stack.extend(parts),
+                ClassExpr::Some(r, filler) => {
+                    out.push(self.role(*r as usize));
+                    stack.push(filler);
+                }
+            }
+        }
+        out
+    }
+
+    /// Every symbol of the told axiom `id`: concepts as their IDs, roles
+    /// after the last concept.
+    pub fn signature(&self, id: AxiomId) -> Vec<usize> {
+        let p = self.parsed;
+        match id {
+            AxiomId::IsA(i) => vec![p.subsumptions[i].0, p.subsumptions[i].1],
+            AxiomId::Relationship(i) => {
+                let (sub, r, filler) = p.relations[i];
+                vec![sub, self.role(r), filler]
+            }
+            AxiomId::Disjoint(i) => vec![p.disjoint_pairs[i].0, p.disjoint_pairs[i].1],
+            AxiomId::Definition(i) => {
+                let definition = &p.definitions[i];
+                let mut out = self.symbols(&definition.expression());
+                out.push(definition.term);
+                out
+            }
+            AxiomId::ClassAxiom(i) => {
+                let (ClassAxiom::SubClassOf(a, b) | ClassAxiom::Equivalent(a, b)) =
+                    &p.class_axioms[i];
+                let mut out = self.symbols(a);
+                out.extend(self.symbols(b));
+                out
+            }
+            AxiomId::SubRole(i) => {
+                let (sub, sup) = p.role_subsumptions[i];
+                vec![self.role(sub), self.role(sup)]
+            }
+            AxiomId::RoleChain(i) => {
+                let (first, second, sup) = p.role_chains[i];
+                vec![self.role(first), self.role(second), self.role(sup)]
+            }
+            AxiomId::Reflexive(i) => vec![self.role(p.reflexive_roles[i])],
+            AxiomId::Domain(i) => vec![self.role(p.role_domains[i].0), p.role_domains[i].1],
+            AxiomId::Range(i) => vec![self.role(p.role_ranges[i].0), p.role_ranges[i].1],
+        }
+    }
+
+    /// The axioms of `within` (all when `None`) that are not local for the
+    /// signature `seed` grown by the symbols of the axioms taken.
+    fn closure(
+        &self,
+        units: &Units,
+        within: Option<&HashSet<AxiomId>>,
+        seed: &[usize],
+    ) -> HashSet<AxiomId> {
+        let mut in_sigma = vec![false; units.by_symbol.len()];
+        let mut reached = vec![0usize; units.units.len()];
+        let mut taken: HashSet<AxiomId> = HashSet::new();
+        // Nominals can be read neither as empty nor as everything.
+        let nominals = self.parsed.nominals.iter().map(|&(c, _)| c);
+        let mut worklist: Vec<usize> = [TOP as usize, BOTTOM as usize]
+            .into_iter()
+            .chain(nominals)
+            .chain(seed.iter().copied())
+            .collect();
+        while let Some(s) = worklist.pop() {
+            if std::mem::replace(&mut in_sigma[s], true) {
+                continue;
+            }
+            for &u in &units.by_symbol[s] {
+                let unit = &units.units[u];
+                reached[u] += 1;
+                if reached[u] != unit.need || within.is_some_and(|w| !w.contains(&unit.axiom)) {
+                    continue;
+                }
+                if taken.insert(unit.axiom) {
+                    worklist.extend(self.signature(unit.axiom));
+                }
+            }
+        }
+        taken
+    }
+
+    /// The `locality` module of the signature made of `concepts` and
+    /// `roles`, in table order.
+    pub fn extract(
+        &self,
+        locality: Locality,
+        concepts: &[ConceptId],
+        roles: &[RoleId],
+    ) -> Vec<AxiomId> {
+        let seed: Vec<usize> = concepts
+            .iter()
+            .map(|&c| c as usize)
+            .chain(roles.iter().map(|&r| self.role(r as usize)))
+            .collect();
+        let module = match locality {
+            Locality::Bottom => self.closure(&self.bottom, None, &seed),
+            Locality::Top => self.closure(&self.top, None, &seed),
+            Locality::Star => {
+                let mut module = self.closure(&self.bottom, None, &seed);
+                loop {
+                    let top = self.closure(&self.top, Some(&module), &seed);
+                    let bottom = self.closure(&self.bottom, Some(&top), &seed);
+                    if bottom.len() == module.len() {
+                        break bottom;
+                    }
+                    module = bottom;
+                }
+            }
+        };
+        let mut module: Vec<AxiomId> = module.into_iter().collect();
+        module.sort_unstable();
+        module
+    }
+}
+
+/// A copy of `parsed` with only `axioms` (and every symbol, so IDs keep
+/// their meaning), for reasoning over a module.
+pub fn restrict(parsed: &ParseResult, axioms: &[AxiomId]) -> ParseResult {
+    let mut out = ParseResult {
+        concepts: parsed.concepts.clone(),
+        concept_idx: parsed.concept_idx.clone(),
+        roles: parsed.roles.clone(),
+        labels: parsed.labels.clone(),
+        declared: parsed.declared.clone(),
+        obsolete: parsed.obsolete.clone(),
+        role_labels: parsed.role_labels.clone(),
+        individuals: parsed.individuals.clone(),
+        individual_idx: parsed.individual_idx.clone(),
+        individual_labels: parsed.individual_labels.clone(),
+        nominals: parsed.nominals.clone(),
+        ..ParseResult::new()
+    };
+    for &id in axioms {
+        match id {
+            AxiomId::IsA(i) => out.subsumptions.push(parsed.subsumptions[i]),
+            AxiomId::Relationship(i) => out.relations.push(parsed.relations[i]),
+            AxiomId::Disjoint(i) => out.disjoint_pairs.push(parsed.disjoint_pairs[i]),
+            AxiomId::Definition(i) => out.definitions.push(parsed.definitions[i].clone()),
+            AxiomId::ClassAxiom(i) => out.class_axioms.push(parsed.class_axioms[i].clone()),
+            AxiomId::SubRole(i) => out.role_subsumptions.push(parsed.role_subsumptions[i]),
+            AxiomId::RoleChain(i) => out.role_chains.push(parsed.role_chains[i]),
+            AxiomId::Reflexive(i) => out.reflexive_roles.push(parsed.reflexive_roles[i]),
+            AxiomId::Domain(i) => out.role_domains.push(parsed.role_domains[i]),
+            AxiomId::Range(i) => out.role_ranges.push(parsed.role_ranges[i]),
+        }
+    }
+    out
+}
@@ -0,0 +1,327 @@
+//! Rewrites EL class expressions into the normal forms consumed by the
+//! saturation rules (NF1–NF4), introducing fresh concepts as needed.
+//!
+//! Right-hand sides are unfolded into NF1/NF3 axioms, left-hand sides into
+//! NF2/NF4 axioms, so general concept inclusions such as
+//! `∃part_of.(A ⊓ B) ⊑ C` need no special casing.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::kernel::{AxiomStore, ConceptId, RoleFiller, RoleId};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClassExpr {
+    Named(ConceptId),
+    And(Vec<ClassExpr>),
+    Some(RoleId, Box<ClassExpr>),
+}
+
+impl ClassExpr {
+    /// Top-level conjuncts, with nested conjunctions flattened.
+    pub fn conjuncts(&self) -> Vec<&ClassExpr> {
+        let mut out = Vec::new();
+        let mut stack = alloc::vec![self];
+        while let Some(e) = stack.pop() {
+            match e {
+                ClassExpr::And(parts) => stack.extend(parts.iter().rev()),
+                other => out.push(other),
+            }
+        }
+        out
+    }
+
+    /// Every concept the expression mentions, in order of appearance.
+    pub fn signature(&self) -> Vec<ConceptId> {
+        let mut out = Vec::new();
+        let mut stack = alloc::vec![self];
+        while let Some(e) = stack.pop() {
+            match e {
+                ClassExpr::Named(c) => out.push(*c),
+                ClassExpr::And(parts) => stack.extend(parts.iter().rev()),
+                ClassExpr::Some(_, filler) => stack.push(filler),
+            }
+        }
+        out
+    }
+
+    /// The expression with every concept passed through `f`, or `None` if
+    /// `f` drops one of them.
+    pub fn try_map(&self, f: &mut impl FnMut(ConceptId) -> Option<ConceptId>) -> Option<ClassExpr> {
+        Some(match self {
+            ClassExpr::Named(c) => ClassExpr::Named(f(*c)?),
+            ClassExpr::And(parts) => {
+                ClassExpr::And(parts.iter().map(|p| p.try_map(f)).collect::<Option<_>>()?)
+            }
+            ClassExpr::Some(r, filler) => ClassExpr::Some(*r, Box::new(filler.try_map(f)?)),
+        })
+    }
+
+    /// The expression with every role passed through `f`.
+    pub fn map_roles(&self, f: &mut impl FnMut(RoleId) -> RoleId) -> ClassExpr {
+        match self {
+            ClassExpr::Named(c) => ClassExpr::Named(*c),
+            ClassExpr::And(parts) => ClassExpr::And(parts.iter().map(|p| p.map_roles(f)).collect()),
+            ClassExpr::Some(r, filler) => ClassExpr::Some(f(*r), Box::new(filler.map_roles(f))),
+        }
+    }
+}
+
+/// An axiom over class expressions, as read by front-ends whose axioms
+/// don't fit the told `is_a`/relationship tables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClassAxiom {
+    /// `sub ⊑ sup`; `sub` may be complex (a general concept inclusion).
+    SubClassOf(ClassExpr, ClassExpr),
+    /// `a ≡ b`.
+    Equivalent(ClassExpr, ClassExpr),
+}
+
+impl ClassAxiom {
+    /// Every concept on either side.
+    pub fn signature(&self) -> Vec<ConceptId> {
+        let (ClassAxiom::SubClassOf(a, b) | ClassAxiom::Equivalent(a, b)) = self;
+        let mut out = a.signature();
+        out.extend(b.signature());
+        out
+    }
+
+    /// The axiom with every concept passed through `f`, or `None` if `f`
+    /// drops one of them.
+    pub fn try_map(&self, f: &mut impl FnMut(ConceptId) -> Option<ConceptId>) -> Option<ClassAxiom> {
+        Some(match self {
+            ClassAxiom::SubClassOf(a, b) => ClassAxiom::SubClassOf(a.try_map(f)?, b.try_map(f)?),
+            ClassAxiom::Equivalent(a, b) => ClassAxiom::Equivalent(a.try_map(f)?, b.try_map(f)?),
+        })
+    }
+
+    /// The axiom with every role passed through `f`.
+    pub fn map_roles(&self, f: &mut impl FnMut(RoleId) -> RoleId) -> ClassAxiom {
+        match self {
+            ClassAxiom::SubClassOf(a, b) => ClassAxiom::SubClassOf(a.map_roles(f), b.map_roles(f)),
+            ClassAxiom::Equivalent(a, b) => ClassAxiom::Equivalent(a.map_roles(f), b.map_roles(f)),
+        }
+    }
+}
+
+/// Adds normalized axioms to a store, allocating fresh concept IDs after
+/// `next_concept` (the store is grown to cover them). Fresh concepts have no
+/// name; callers extend their symbol tables up to [`Normalizer::num_concepts`].
+pub struct Normalizer<'a> {
+    store: &'a mut AxiomStore,
+    next_concept: ConceptId,
+    /// The fresh `X` with `A ⊓ B ⊑ X` for each pair, so conjunctions with a
+    /// common prefix share the start of their chains.
+    conjunctions: BTreeMap<(ConceptId, ConceptId), ConceptId>,
+}
+
+impl<'a> Normalizer<'a> {
+    pub fn new(store: &'a mut AxiomStore, num_concepts: usize) -> Self {
+        store.grow(num_concepts);
+        Self { store, next_concept: num_concepts as ConceptId, conjunctions: BTreeMap::new() }
+    }
+
+    pub fn num_concepts(&self) -> usize {
+        self.next_concept as usize
+    }
+
+    pub fn fresh(&mut self) -> ConceptId {
+        let id = self.next_concept;
+        self.next_concept += 1;
+        self.store.grow(self.next_concept as usize);
+        id
+    }
+
+    /// Introduces a fresh concept `Q ≡ expr` and returns it.
+    pub fn define(&mut self, expr: &ClassExpr) -> ConceptId {
+        let q = self.fresh();
+        self.add_equivalence(q, expr);
+        q
+    }
+
+    /// `a ⊑ expr`: `a` is subsumed by every conjunct.
+    pub fn add_subsumption(&mut self, a: ConceptId, expr: &ClassExpr) {
+        for conjunct in expr.conjuncts() {
+            match conjunct {
+                ClassExpr::Named(b) => self.store.add_subsumption(a, *b),
+                ClassExpr::Some(r, filler) => {
+                    let f = self.concept_for(filler);
+                    self.store.add_exist_right(a, *r, f);
+                }
+                ClassExpr::And(_) => unreachable!("conjuncts() flattens conjunctions"),
+            }
+        }
+    }
+
+    /// `sub ⊑ sup` for arbitrary expressions on both sides.
+    pub fn add_inclusion(&mut self, sub: &ClassExpr, sup: &ClassExpr) {
+        let a = self.lhs_concept(sub);
+        self.add_subsumption(a, sup);
+    }
+
+    pub fn add_axiom(&mut self, axiom: &ClassAxiom) {
+        match axiom {
+            ClassAxiom::SubClassOf(sub, sup) => self.add_inclusion(sub, sup),
+            ClassAxiom::Equivalent(ClassExpr::Named(a), expr)
+            | ClassAxiom::Equivalent(expr, ClassExpr::Named(a)) => self.add_equivalence(*a, expr),
+            ClassAxiom::Equivalent(a, b) => {
+                self.add_inclusion(a, b);
+                self.add_inclusion(b, a);
+            }
+        }
+    }
+
+    /// Role ranges as `(role, range)` pairs. Every `∃r.F` on the right of
+    /// an axiom in the store becomes `∃r.F'`, with a fresh `F'` subsumed by
+    /// `F` and by the ranges of `r` (see [`role_ranges`]), so the link rules
+    /// only ever reach contexts that hold the range. Existentials added
+    /// afterwards keep their filler.
+    pub fn add_ranges(&mut self, ranges: &[(RoleId, ConceptId)]) {
+        if ranges.is_empty() {
+            return;
+        }
+        let closed = role_ranges(self.store, ranges);
+        let mut fillers: BTreeMap<(RoleId, ConceptId), ConceptId> = BTreeMap::new();
+        for a in 0..self.store.num_concepts() {
+            for i in 0..self.store.exist_right[a].len() {
+                let RoleFiller { role, fill } = self.store.exist_right[a][i];
+                let range = &closed[role as usize];
+                if range.is_empty() {
+                    continue;
+                }
+                let ranged = match fillers.get(&(role, fill)) {
+                    Some(&f) => f,
+                    None => {
+                        let f = self.fresh();
+                        self.store.add_subsumption(f, fill);
+                        for &c in range {
+                            self.store.add_subsumption(f, c);
+                        }
+                        fillers.insert((role, fill), f);
+                        f
+                    }
+                };
+                self.store.exist_right[a][i].fill = ranged;
+            }
+        }
+    }
+
+    /// `a ≡ expr`: `a` is subsumed by every conjunct, and the conjunction of
+    /// all conjuncts is subsumed by `a`.
+    pub fn add_equivalence(&mut self, a: ConceptId, expr: &ClassExpr) {
+        let conjuncts = expr.conjuncts();
+        let mut atoms: Vec<ConceptId> = Vec::with_capacity(conjuncts.len());
+
+        for conjunct in conjuncts {
+            match conjunct {
+                ClassExpr::Named(b) => {
+                    self.store.add_subsumption(a, *b);
+                    atoms.push(*b);
+                }
+                ClassExpr::Some(r, filler) => {
+                    let f = self.concept_for(filler);
+                    self.store.add_exist_right(a, *r, f);
+                    // ∃r.F ⊑ X, with X standing in for the existential conjunct.
+                    let x = self.fresh();
+                    self.store.add_exist_left(*r, f, x);
+                    atoms.push(x);
+                }
+                ClassExpr::And(_) => unreachable!("conjuncts() flattens conjunctions"),
+            }
+        }
+
+        self.add_conjunction_chain(&atoms, a);
+    }
+
+    /// `atoms[0] ⊓ … ⊓ atoms[n-1] ⊑ target` as a chain of binary NF2 axioms
+    /// over fresh intermediate concepts: `A ⊓ B ⊓ C ⊑ T` becomes
+    /// `A ⊓ B ⊑ X` and `X ⊓ C ⊑ T`, and CR2 derives `X` before `T`. The
+    /// atoms are sorted and deduplicated first, so the same conjunction
+    /// written in any order reuses one chain.
+    fn add_conjunction_chain(&mut self, atoms: &[ConceptId], target: ConceptId) {
+        let mut atoms = atoms.to_vec();
+        atoms.sort_unstable();
+        atoms.dedup();
+        match atoms[..] {
+            [] => {}
+            [single] => self.store.add_subsumption(single, target),
+            [first, ref rest @ .., last] => {
+                let mut acc = first;
+                for &atom in rest {
+                    acc = match self.conjunctions.get(&(acc, atom)) {
+                        Some(&x) => x,
+                        None => {
+                            let x = self.fresh();
+                            self.store.add_conjunction(acc, atom, x);
+                            self.conjunctions.insert((acc, atom), x);
+                            x
+                        }
+                    };
+                }
+                self.store.add_conjunction(acc, last, target);
+            }
+        }
+    }
+
+    /// A concept that `expr` is subsumed by, for the left-hand side of an
+    /// axiom: named concepts stand for themselves, anything else gets a
+    /// fresh `X` with only `expr ⊑ X` (NF2/NF4 axioms, no NF3).
+    fn lhs_concept(&mut self, expr: &ClassExpr) -> ConceptId {
+        match expr {
+            ClassExpr::Named(c) => *c,
+            ClassExpr::Some(r, filler) => {
+                let f = self.lhs_concept(filler);
+                let x = self.fresh();
+                self.store.add_exist_left(*r, f, x);
+                x
+            }
+            ClassExpr::And(_) => {
+                let atoms: Vec<ConceptId> =
+                    expr.conjuncts().into_iter().map(|c| self.lhs_concept(c)).collect();
+                if let [single] = atoms[..] {
+                    return single;
+                }
+                let x = self.fresh();
+                self.add_conjunction_chain(&atoms, x);
+                x
+            }
+        }
+    }
+
+    /// Named concepts stand for themselves; anything else gets a fresh
+    /// concept defined as equivalent to it.
+    fn concept_for(&mut self, expr: &ClassExpr) -> ConceptId {
+        match expr {
+            ClassExpr::Named(b) => *b,
+            other => self.define(other),
+        }
+    }
+}
+
+/// The ranges of every role of `store`, its own from `ranges` and those of
+/// the roles it is told to be under, indexed by role.
+pub fn role_ranges(store: &AxiomStore, ranges: &[(RoleId, ConceptId)]) -> Vec<Vec<ConceptId>> {
+    let num_roles = store.role_sub_to_sups.len();
+    let mut own: Vec<Vec<ConceptId>> = alloc::vec![Vec::new(); num_roles];
+    for &(r, c) in ranges {
+        own[r as usize].push(c);
+    }
+    (0..num_roles)
+        .map(|r| {
+            let mut seen = alloc::vec![false; num_roles];
+            let mut stack = alloc::vec![r];
+            let mut out: Vec<ConceptId> = Vec::new();
+            while let Some(s) = stack.pop() {
+                if core::mem::replace(&mut seen[s], true) {
+                    continue;
+                }
+                out.extend(&own[s]);
+                stack.extend(store.role_sub_to_sups[s].iter().map(|&t| t as usize));
+            }
+            out.sort_unstable();
+            out.dedup();
+            out
+        })
+        .collect()
+}
@@ -0,0 +1,66 @@
+use std::io::{self, BufRead};
+use std::str;
+
+/// The lines of a [`BufRead`] as `&str`, without a `String` per line: a line
+/// that lies within the reader's buffer is borrowed from it, and only one
+/// straddling two fills is copied, into a buffer reused for every line. Over
+/// a byte slice nothing is copied at all.
+pub struct ByteLines<R> {
+    reader: R,
+    /// Bytes of a line that straddles fills.
+    carry: Vec<u8>,
+    /// Bytes of the line last returned, consumed on the next call.
+    pending: usize,
+}
+
+impl<R: BufRead> ByteLines<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, carry: Vec::new(), pending: 0 }
+    }
+
+    /// The next line without its `\n` or `\r\n`, like [`BufRead::lines`],
+    /// or `None` at the end of the input. A line that isn't UTF-8 is an
+    /// [`io::ErrorKind::InvalidData`] error, after which reading goes on.
+    pub fn next_line(&mut self) -> Option<io::Result<&str>> {
+        self.read_line().transpose()
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<&str>> {
+        self.reader.consume(std::mem::take(&mut self.pending));
+        self.carry.clear();
+        loop {
+            let (len, ended) = match self.reader.fill_buf() {
+                Ok([]) => break,
+                Ok(buf) => match memchr::memchr(b'\n', buf) {
+                    Some(i) => (i + 1, true),
+                    None => (buf.len(), false),
+                },
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if ended && self.carry.is_empty() {
+                // Still buffered, so this second fill does no I/O.
+                self.pending = len;
+                let buf = self.reader.fill_buf()?;
+                return text(&buf[..len]).map(Some);
+            }
+            self.carry.extend_from_slice(&self.reader.fill_buf()?[..len]);
+            self.reader.consume(len);
+            if ended {
+                break;
+            }
+        }
+        if self.carry.is_empty() {
+            return Ok(None);
+        }
+        text(&self.carry).map(Some)
+    }
+}
+
+fn text(line: &[u8]) -> io::Result<&str> {
+    let line = match line.strip_suffix(b"\n") {
+        Some(line) => line.strip_suffix(b"\r").unwrap_or(line),
+        None => line,
+    };
+    str::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
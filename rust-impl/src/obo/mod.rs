@@ -0,0 +1,1371 @@
+mod lines;
+mod stats;
+mod warning;
+
+pub use lines::ByteLines;
+pub use stats::{content_stats, ContentStats};
+pub use warning::{Location, ParseError, Warning, WarningKind};
+
+use crate::normalize::{ClassAxiom, ClassExpr, Normalizer};
+use crate::profile::Violation;
+use crate::query::Symbols;
+use crate::resolve::{AltIds, CurieMap, IdResolver, Verbatim};
+use crate::{AxiomStore, ConceptId, RoleId, BOTTOM, TOP};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+
+pub struct ParseResult {
+    pub concepts: Vec<String>,
+    pub roles: Vec<String>,
+    pub concept_idx: HashMap<String, usize>,
+    pub subsumptions: Vec<(usize, usize)>,
+    pub relations: Vec<(usize, usize, usize)>,
+    /// `name:` of each concept that has one.
+    pub labels: HashMap<usize, String>,
+    /// Concepts with their own `[Term]` stanza (the rest are only referenced).
+    pub declared: HashSet<usize>,
+    pub obsolete: HashSet<usize>,
+    /// `(term, replacement)` from `replaced_by:` lines (OWL: `IAO:0100001`),
+    /// the replacement by name.
+    pub replaced_by: Vec<(usize, String)>,
+    /// `(term, alternative)` from `consider:` lines, by name.
+    pub consider: Vec<(usize, String)>,
+    /// `name:` of each role declared in a `[Typedef]` stanza.
+    pub role_labels: HashMap<usize, String>,
+    /// `(sub, sup)` role pairs from `is_a:` lines of `[Typedef]` stanzas.
+    pub role_subsumptions: Vec<(usize, usize)>,
+    /// `(first, second, sup)` role chains from `transitive_over:`,
+    /// `holds_over_chain:` and `is_transitive:` Typedef tags.
+    pub role_chains: Vec<(usize, usize, usize)>,
+    /// Roles with `is_reflexive: true`.
+    pub reflexive_roles: Vec<usize>,
+    /// `(role, concept)` pairs from `domain:` Typedef tags: `∃role.⊤ ⊑ concept`.
+    pub role_domains: Vec<(usize, usize)>,
+    /// `(role, concept)` pairs from `range:` Typedef tags: every `role`
+    /// successor is a `concept`.
+    pub role_ranges: Vec<(usize, usize)>,
+    /// Logical definitions from `intersection_of:` lines.
+    pub definitions: Vec<Definition>,
+    /// `(term, other)` pairs from `disjoint_from:` lines.
+    pub disjoint_pairs: Vec<(usize, usize)>,
+    /// Axioms over nested class expressions (OWL front-ends only).
+    pub class_axioms: Vec<ClassAxiom>,
+    /// Axioms left out for constructs outside EL; see [`crate::profile`].
+    pub violations: Vec<Violation>,
+    /// Individuals from `[Instance]` stanzas (OWL: named individuals).
+    pub individuals: Vec<String>,
+    pub individual_idx: HashMap<String, usize>,
+    /// `name:` of each individual that has one.
+    pub individual_labels: HashMap<usize, String>,
+    /// `(individual, concept)` from `instance_of:` lines.
+    pub class_assertions: Vec<(usize, usize)>,
+    /// `(individual, role, individual)` from `relationship:` lines of
+    /// `[Instance]` stanzas.
+    pub role_assertions: Vec<(usize, usize, usize)>,
+    /// `(concept, individual)`: concepts `{individual}` with that one
+    /// instance, from term axioms that point at an `[Instance]`.
+    pub nominals: Vec<(usize, usize)>,
+    /// `import:` header lines, as written, with where each was; see
+    /// [`crate::input::read_with_imports`].
+    pub imports: Vec<(String, Location)>,
+    /// Prefixes from `idspace:` header lines and OWL prefix declarations,
+    /// over [`CurieMap::standard`].
+    pub prefixes: CurieMap,
+}
+
+impl ParseResult {
+    /// No axioms, with only `owl:Thing` and `owl:Nothing` interned.
+    pub fn new() -> Self {
+        let concepts = vec!["owl:Thing".to_string(), "owl:Nothing".to_string()];
+        let concept_idx = concepts.iter().enumerate().map(|(i, c)| (c.clone(), i)).collect();
+        Self {
+            concepts,
+            roles: Vec::new(),
+            concept_idx,
+            subsumptions: Vec::new(),
+            relations: Vec::new(),
+            labels: HashMap::new(),
+            declared: HashSet::new(),
+            obsolete: HashSet::new(),
+            replaced_by: Vec::new(),
+            consider: Vec::new(),
+            role_labels: HashMap::new(),
+            role_subsumptions: Vec::new(),
+            role_chains: Vec::new(),
+            reflexive_roles: Vec::new(),
+            role_domains: Vec::new(),
+            role_ranges: Vec::new(),
+            definitions: Vec::new(),
+            disjoint_pairs: Vec::new(),
+            class_axioms: Vec::new(),
+            violations: Vec::new(),
+            individuals: Vec::new(),
+            individual_idx: HashMap::new(),
+            individual_labels: HashMap::new(),
+            class_assertions: Vec::new(),
+            role_assertions: Vec::new(),
+            nominals: Vec::new(),
+            imports: Vec::new(),
+            prefixes: CurieMap::standard(),
+        }
+    }
+
+    /// Whether each concept has a name, for [`Taxonomy::among`]: unnamed
+    /// ones, such as the complements of an approximation, stay out of the
+    /// hierarchy.
+    ///
+    /// [`Taxonomy::among`]: crate::Taxonomy::among
+    pub fn named(&self) -> Vec<bool> {
+        self.concepts.iter().enumerate().map(|(c, name)| c < 2 || !name.is_empty()).collect()
+    }
+
+    /// The individual named `name`, interned on first use.
+    pub fn individual(&mut self, name: &str) -> usize {
+        let individuals = &mut self.individuals;
+        *self.individual_idx.entry(name.to_string()).or_insert_with_key(|name| {
+            individuals.push(name.clone());
+            individuals.len() - 1
+        })
+    }
+
+    /// The nominal `{individual}`, a concept named after the individual in
+    /// braces, interned on first use.
+    pub fn nominal(&mut self, individual: usize) -> usize {
+        let name = format!("{{{}}}", self.individuals[individual]);
+        if let Some(&c) = self.concept_idx.get(&name) {
+            return c;
+        }
+        let c = self.concepts.len();
+        self.concepts.push(name.clone());
+        self.concept_idx.insert(name, c);
+        self.nominals.push((c, individual));
+        c
+    }
+
+    /// Makes the concepts named `a` and `b` equivalent, as told subsumptions
+    /// both ways, interning either on first use.
+    pub fn add_equivalence(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.intern_concept(a), self.intern_concept(b));
+        if a != b {
+            self.subsumptions.push((a, b));
+            self.subsumptions.push((b, a));
+        }
+    }
+
+    fn intern_concept(&mut self, name: &str) -> usize {
+        if let Some(&c) = self.concept_idx.get(name) {
+            return c;
+        }
+        self.concepts.push(name.to_string());
+        self.concept_idx.insert(name.to_string(), self.concepts.len() - 1);
+        self.concepts.len() - 1
+    }
+
+    /// Adds the axioms of `other`, matching concepts, roles and individuals
+    /// by name; those new to `self` are numbered after its own, and unnamed
+    /// concepts are always new. Labels of `self` win over those of `other`.
+    pub fn merge(&mut self, other: ParseResult) {
+        let concepts: Vec<usize> = other
+            .concepts
+            .iter()
+            .map(|c| {
+                if !c.is_empty() {
+                    return self.intern_concept(c);
+                }
+                self.concepts.push(String::new());
+                self.concepts.len() - 1
+            })
+            .collect();
+        let mut role_idx: HashMap<String, usize> =
+            self.roles.iter().enumerate().map(|(r, name)| (name.clone(), r)).collect();
+        let roles: Vec<usize> = other
+            .roles
+            .iter()
+            .map(|name| {
+                *role_idx.entry(name.clone()).or_insert_with(|| {
+                    self.roles.push(name.clone());
+                    self.roles.len() - 1
+                })
+            })
+            .collect();
+        let individuals: Vec<usize> = other.individuals.iter().map(|a| self.individual(a)).collect();
+        let (c, r, a) = (|i: usize| concepts[i], |i: usize| roles[i], |i: usize| individuals[i]);
+
+        self.subsumptions.extend(other.subsumptions.iter().map(|&(x, y)| (c(x), c(y))));
+        self.relations.extend(other.relations.iter().map(|&(x, s, y)| (c(x), r(s), c(y))));
+        for (x, label) in other.labels {
+            self.labels.entry(c(x)).or_insert(label);
+        }
+        self.declared.extend(other.declared.iter().map(|&x| c(x)));
+        self.obsolete.extend(other.obsolete.iter().map(|&x| c(x)));
+        self.replaced_by.extend(other.replaced_by.into_iter().map(|(x, name)| (c(x), name)));
+        self.consider.extend(other.consider.into_iter().map(|(x, name)| (c(x), name)));
+        for (s, label) in other.role_labels {
+            self.role_labels.entry(r(s)).or_insert(label);
+        }
+        self.role_subsumptions.extend(other.role_subsumptions.iter().map(|&(s, t)| (r(s), r(t))));
+        let chains = other.role_chains.iter().map(|&(s, t, u)| (r(s), r(t), r(u)));
+        self.role_chains.extend(chains);
+        self.reflexive_roles.extend(other.reflexive_roles.iter().map(|&s| r(s)));
+        self.role_domains.extend(other.role_domains.iter().map(|&(s, x)| (r(s), c(x))));
+        self.role_ranges.extend(other.role_ranges.iter().map(|&(s, x)| (r(s), c(x))));
+        self.definitions.extend(other.definitions.iter().map(|d| Definition {
+            term: c(d.term),
+            genus: d.genus.iter().map(|&g| c(g)).collect(),
+            differentia: d.differentia.iter().map(|&(s, f)| (r(s), c(f))).collect(),
+        }));
+        self.disjoint_pairs.extend(other.disjoint_pairs.iter().map(|&(x, y)| (c(x), c(y))));
+        let mut axiom = |axiom: &ClassAxiom| {
+            let axiom = axiom.try_map(&mut |x| Some(c(x as usize) as ConceptId));
+            let axiom = axiom.expect("every concept is mapped");
+            axiom.map_roles(&mut |s| r(s as usize) as RoleId)
+        };
+        self.class_axioms.extend(other.class_axioms.iter().map(&mut axiom));
+        self.violations.extend(other.violations.iter().map(|v| Violation {
+            approximation: v.approximation.iter().map(&mut axiom).collect(),
+            ..v.clone()
+        }));
+        for (i, label) in other.individual_labels {
+            self.individual_labels.entry(a(i)).or_insert(label);
+        }
+        self.class_assertions.extend(other.class_assertions.iter().map(|&(i, x)| (a(i), c(x))));
+        let asserted = other.role_assertions.iter().map(|&(i, s, j)| (a(i), r(s), a(j)));
+        self.role_assertions.extend(asserted);
+        for &(x, i) in &other.nominals {
+            if !self.nominals.iter().any(|&(n, _)| n == c(x)) {
+                self.nominals.push((c(x), a(i)));
+            }
+        }
+        self.imports.extend(other.imports);
+        self.prefixes.merge(&other.prefixes);
+    }
+
+    /// Renames concepts, roles and individuals to the IRIs their CURIEs
+    /// expand to under [`prefixes`](Self::prefixes), for output in full
+    /// IRIs. Names without a known prefix, full IRIs among them, stay.
+    pub fn expand_names(&mut self) {
+        let prefixes = &self.prefixes;
+        for name in self.concepts.iter_mut().chain(&mut self.roles).chain(&mut self.individuals) {
+            if let Cow::Owned(iri) = prefixes.expand(name) {
+                *name = iri;
+            }
+        }
+        for &(c, a) in &self.nominals {
+            self.concepts[c] = format!("{{{}}}", self.individuals[a]);
+        }
+        let index = |names: &[String]| names.iter().enumerate().map(|(i, n)| (n.clone(), i)).collect();
+        self.concept_idx = index(&self.concepts);
+        self.individual_idx = index(&self.individuals);
+    }
+}
+
+impl Default for ParseResult {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `term ≡ genus ⊓ … ⊓ ∃relation.filler ⊓ …`, from the `intersection_of:`
+/// lines of one stanza.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Definition {
+    pub term: usize,
+    pub genus: Vec<usize>,
+    /// `(relation, filler)` pairs.
+    pub differentia: Vec<(usize, usize)>,
+}
+
+impl Definition {
+    pub fn expression(&self) -> ClassExpr {
+        let genus = self.genus.iter().map(|&g| ClassExpr::Named(g as ConceptId));
+        let differentia = self.differentia.iter().map(|&(r, f)| {
+            ClassExpr::Some(r as RoleId, Box::new(ClassExpr::Named(f as ConceptId)))
+        });
+        ClassExpr::And(genus.chain(differentia).collect())
+    }
+
+    /// Concepts whose axioms the definition extends: the term and every
+    /// concept on the right-hand side.
+    pub fn signature(&self) -> impl Iterator<Item = usize> + '_ {
+        let fillers = self.differentia.iter().map(|&(_, f)| f);
+        std::iter::once(self.term).chain(self.genus.iter().copied()).chain(fillers)
+    }
+}
+
+/// Logical `[Term]` tags outside EL, recorded as [`Violation`]s.
+const SKIPPED_TAGS: &[&str] = &["union_of", "complement_of"];
+
+/// `[Term]` tags that carry axioms; a misspelt one loses its axiom.
+const LOGICAL_TAGS: &[&str] = &[
+    "is_a",
+    "intersection_of",
+    "union_of",
+    "complement_of",
+    "equivalent_to",
+    "disjoint_from",
+    "relationship",
+];
+
+/// The other `[Term]` tags of OBO 1.4, and those OBO 1.2 had besides.
+const ANNOTATION_TAGS: &[&str] = &[
+    "id",
+    "is_anonymous",
+    "name",
+    "namespace",
+    "alt_id",
+    "def",
+    "comment",
+    "subset",
+    "synonym",
+    "xref",
+    "builtin",
+    "property_value",
+    "created_by",
+    "creation_date",
+    "is_obsolete",
+    "replaced_by",
+    "consider",
+    "exact_synonym",
+    "narrow_synonym",
+    "broad_synonym",
+    "related_synonym",
+    "xref_analog",
+    "xref_unknown",
+    "use_term",
+];
+
+/// The logical tag an unknown `tag` is probably a misspelling of: the same
+/// up to case and `-`/`_`, or within an edit or two (one for short tags).
+fn logical_lookalike(tag: &str) -> Option<&'static str> {
+    if ANNOTATION_TAGS.contains(&tag) || LOGICAL_TAGS.contains(&tag) {
+        return None;
+    }
+    let tag = tag.to_ascii_lowercase().replace('-', "_");
+    LOGICAL_TAGS
+        .iter()
+        .copied()
+        .find(|logical| edit_distance(&tag, logical) <= if logical.len() <= 4 { 1 } else { 2 })
+}
+
+/// Levenshtein distance in bytes.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.bytes().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.bytes().enumerate() {
+            let substituted = diagonal + (x != y) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Parses the EL fragment of an OBO document. Anything ignored along the way
+/// is reported in the returned warnings rather than dropped silently; only a
+/// failing reader stops the parse.
+pub fn parse_obo<R: BufRead>(reader: R) -> Result<(ParseResult, Vec<Warning>), ParseError> {
+    parse_obo_with(reader, &Verbatim)
+}
+
+/// [`parse_obo`] with every concept ID passed through `resolver` first.
+pub fn parse_obo_with<R: BufRead>(
+    reader: R,
+    resolver: &dyn IdResolver,
+) -> Result<(ParseResult, Vec<Warning>), ParseError> {
+    parse_obo_observed(reader, resolver, &mut |_| {})
+}
+
+/// The axioms of one `[Term]` stanza, reported as soon as the stanza ends.
+pub struct Stanza<'a> {
+    pub term: usize,
+    pub subsumptions: &'a [(usize, usize)],
+    pub relations: &'a [(usize, usize, usize)],
+    /// Concepts and roles numbered so far.
+    pub num_concepts: usize,
+    pub num_roles: usize,
+}
+
+/// [`parse_obo_with`], calling `on_stanza` at the end of every `[Term]`
+/// stanza so consumers can start on a term before the whole file is read.
+pub fn parse_obo_observed<R: BufRead>(
+    reader: R,
+    resolver: &dyn IdResolver,
+    on_stanza: &mut dyn FnMut(Stanza),
+) -> Result<(ParseResult, Vec<Warning>), ParseError> {
+    let mut concepts: Vec<String> = vec!["owl:Thing".to_string(), "owl:Nothing".to_string()];
+    let mut roles: Vec<String> = Vec::new();
+    let mut concept_idx: HashMap<String, usize> = HashMap::new();
+    let mut role_idx: HashMap<String, usize> = HashMap::new();
+    
+    concept_idx.insert("owl:Thing".to_string(), 0);
+    concept_idx.insert("owl:Nothing".to_string(), 1);
+
+    let mut subsumptions: Vec<(usize, usize)> = Vec::new();
+    let mut relations: Vec<(usize, usize, usize)> = Vec::new();
+    let mut labels: HashMap<usize, String> = HashMap::new();
+    let mut declared: HashSet<usize> = HashSet::new();
+    let mut obsolete: HashSet<usize> = HashSet::new();
+    let mut replaced_by: Vec<(usize, String)> = Vec::new();
+    let mut consider: Vec<(usize, String)> = Vec::new();
+    let mut role_labels: HashMap<usize, String> = HashMap::new();
+    let mut role_subsumptions: Vec<(usize, usize)> = Vec::new();
+    let mut role_chains: Vec<(usize, usize, usize)> = Vec::new();
+    let mut reflexive_roles: Vec<usize> = Vec::new();
+    let mut role_domains: Vec<(usize, usize)> = Vec::new();
+    let mut role_ranges: Vec<(usize, usize)> = Vec::new();
+    let mut definitions: Vec<Definition> = Vec::new();
+    let mut disjoint_pairs: Vec<(usize, usize)> = Vec::new();
+    let mut violations: Vec<Violation> = Vec::new();
+    // `[Instance]` stanzas, kept in a result of their own until the end.
+    let mut abox = ParseResult::new();
+    let mut warnings: Vec<Warning> = Vec::new();
+    let mut warn = |kind, location, message: String| {
+        warnings.push(Warning { kind, location, message })
+    };
+    // Relations declared by a Typedef, and where each relation is first used.
+    let mut typedef_roles: HashSet<usize> = HashSet::new();
+    let mut role_first_use: HashMap<usize, (usize, Location)> = HashMap::new();
+    // Where each term's stanza starts, and where each class is first a target.
+    let mut declared_at: HashMap<usize, Location> = HashMap::new();
+    let mut target_first_use: HashMap<usize, (usize, Location)> = HashMap::new();
+
+    let mut imports: Vec<(String, Location)> = Vec::new();
+    let mut prefixes = CurieMap::standard();
+    // Before the first stanza, where `import:` lines go.
+    let mut in_header = true;
+    let mut current_id: Option<usize> = None;
+    let mut current_role: Option<usize> = None;
+    // `intersection_of:` lines of the current stanza, and where the first was.
+    let mut intersection: Vec<(Option<usize>, usize)> = Vec::new();
+    let mut intersection_at = Location::default();
+    let mut is_obsolete = false;
+    let mut stanza_axioms = 0usize;
+    let mut in_term = false;
+    let mut in_typedef = false;
+    let mut in_instance = false;
+    let mut current_individual: Option<usize> = None;
+    // Start of the current stanza's axioms in `subsumptions`/`relations`.
+    let mut stanza_start = (0usize, 0usize);
+    let mut end_stanza = |term: Option<usize>,
+                          subsumptions: &[(usize, usize)],
+                          relations: &[(usize, usize, usize)],
+                          num_concepts: usize,
+                          num_roles: usize,
+                          start: &mut (usize, usize)| {
+        if let Some(term) = term {
+            on_stanza(Stanza {
+                term,
+                subsumptions: &subsumptions[start.0..],
+                relations: &relations[start.1..],
+                num_concepts,
+                num_roles,
+            });
+        }
+        *start = (subsumptions.len(), relations.len());
+    };
+
+    let mut lines = ByteLines::new(reader);
+    let mut line_no = 0;
+    while let Some(line) = lines.next_line() {
+        line_no += 1;
+        let mut at = Location { line: line_no, column: 1 };
+        let line = match line {
+            Ok(l) => l,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                warn(WarningKind::MalformedLine, at, format!("unreadable line: {}", e));
+                continue;
+            }
+            Err(source) => return Err(ParseError { location: at, source }),
+        };
+        let indent = line.len() - line.trim_start().len();
+        at.column += line[..indent].chars().count();
+        let line = line.trim();
+        
+        if line.is_empty() {
+            continue;
+        }
+
+        if in_header && !line.starts_with('[') {
+            if let Some(rest) = line.strip_prefix("import:") {
+                imports.push((tag_target(rest).to_string(), at));
+            } else if let Some((prefix, base)) = line.strip_prefix("idspace:").and_then(idspace) {
+                prefixes.add_prefix(prefix, base);
+            }
+            continue;
+        }
+        in_header = false;
+
+        if line.starts_with('[') && in_term {
+            let (nc, nr) = (concepts.len(), roles.len());
+            end_stanza(current_id, &subsumptions, &relations, nc, nr, &mut stanza_start);
+            let parts = std::mem::take(&mut intersection);
+            let term = current_id.filter(|_| !is_obsolete);
+            definitions.extend(take_definition(term, parts, intersection_at, &mut warn));
+        }
+
+        if line == "[Term]" {
+            in_term = true;
+            in_typedef = false;
+            in_instance = false;
+            current_id = None;
+            is_obsolete = false;
+            stanza_axioms = 0;
+            continue;
+        }
+
+        if line.starts_with("[Typedef]") {
+            in_term = false;
+            in_typedef = true;
+            in_instance = false;
+            current_id = None;
+            current_role = None;
+            continue;
+        }
+
+        if line.starts_with('[') {
+            in_term = false;
+            in_typedef = false;
+            in_instance = line == "[Instance]";
+            current_individual = None;
+            continue;
+        }
+
+        if in_instance {
+            let Some((tag, rest)) = line.split_once(':') else {
+                continue;
+            };
+            if tag == "id" {
+                current_individual = Some(abox.individual(&resolver.resolve(rest.trim())));
+                continue;
+            }
+            let Some(a) = current_individual else {
+                if matches!(tag, "instance_of" | "relationship") {
+                    warn(WarningKind::MalformedLine, at, format!("{} before id", tag));
+                }
+                continue;
+            };
+            match tag {
+                "name" => {
+                    abox.individual_labels.insert(a, rest.trim().to_string());
+                }
+                "instance_of" => {
+                    let target = resolver.resolve(tag_target(rest));
+                    let target = target.as_ref();
+                    if target.is_empty() {
+                        warn(WarningKind::MalformedLine, at, "instance_of without a target".to_string());
+                        continue;
+                    }
+                    let c = *concept_idx.entry(target.to_string()).or_insert_with(|| {
+                        concepts.push(target.to_string());
+                        concepts.len() - 1
+                    });
+                    abox.class_assertions.push((a, c));
+                }
+                "relationship" => {
+                    let [role_name, target, ..] = rest.split_whitespace().collect::<Vec<_>>()[..] else {
+                        let message = "relationship needs a relation and a target";
+                        warn(WarningKind::MalformedLine, at, message.to_string());
+                        continue;
+                    };
+                    let r = *role_idx.entry(role_name.to_string()).or_insert_with(|| {
+                        roles.push(role_name.to_string());
+                        roles.len() - 1
+                    });
+                    let b = abox.individual(&resolver.resolve(target));
+                    abox.role_assertions.push((a, r, b));
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if in_typedef {
+            let mut role = |name: &str| {
+                *role_idx.entry(name.to_string()).or_insert_with(|| {
+                    roles.push(name.to_string());
+                    roles.len() - 1
+                })
+            };
+            if let Some(rest) = line.strip_prefix("id:") {
+                let r = role(rest.trim());
+                typedef_roles.insert(r);
+                current_role = Some(r);
+            } else if let (Some(rest), Some(r)) = (line.strip_prefix("name:"), current_role) {
+                role_labels.insert(r, rest.trim().to_string());
+            } else if let Some((tag, rest)) = line.split_once(':') {
+                let value = rest.split(['{', '!']).next().unwrap_or("");
+                let targets: Vec<&str> = value.split_whitespace().collect();
+                if tag == "inverse_of" {
+                    let role = current_role.map_or("a relation", |r| roles[r].as_str());
+                    warn(WarningKind::SkippedTag, at, format!("inverse_of on {} is not supported", role));
+                    let construct = tag.to_string();
+                    violations.push(Violation { construct, location: at, approximation: Vec::new() });
+                    continue;
+                }
+                let arity = match tag {
+                    "is_a" | "transitive_over" | "domain" | "range" => 1,
+                    "holds_over_chain" => 2,
+                    "is_transitive" | "is_reflexive" => 0,
+                    _ => continue,
+                };
+                let Some(r) = current_role else {
+                    warn(WarningKind::MalformedLine, at, format!("{} before id", tag));
+                    continue;
+                };
+                if arity == 0 {
+                    match (tag, value.trim() == "true") {
+                        ("is_transitive", true) => role_chains.push((r, r, r)),
+                        (_, true) => reflexive_roles.push(r),
+                        _ => {}
+                    }
+                } else if targets.len() != arity {
+                    let expected = match tag {
+                        "domain" | "range" => "a class",
+                        _ if arity == 1 => "a relation",
+                        _ => "two relations",
+                    };
+                    warn(WarningKind::MalformedLine, at, format!("{} needs {}", tag, expected));
+                } else if tag == "domain" || tag == "range" {
+                    let target = resolver.resolve(targets[0]);
+                    let target = target.as_ref();
+                    let c = if let Some(&idx) = concept_idx.get(target) {
+                        idx
+                    } else {
+                        concepts.push(target.to_string());
+                        concept_idx.insert(target.to_string(), concepts.len() - 1);
+                        concepts.len() - 1
+                    };
+                    if tag == "domain" {
+                        role_domains.push((r, c));
+                    } else {
+                        role_ranges.push((r, c));
+                    }
+                } else if tag == "is_a" {
+                    role_subsumptions.push((r, role(targets[0])));
+                } else if tag == "transitive_over" {
+                    role_chains.push((r, role(targets[0]), r));
+                } else {
+                    role_chains.push((role(targets[0]), role(targets[1]), r));
+                }
+            }
+            continue;
+        }
+
+        if !in_term || line.starts_with('!') {
+            continue;
+        }
+
+        let Some((tag, _)) = line.split_once(':') else {
+            warn(WarningKind::MalformedLine, at, "expected `tag: value`".to_string());
+            continue;
+        };
+
+        if let Some(rest) = line.strip_prefix("id:") {
+            let id = resolver.resolve(rest.trim());
+            let id = id.as_ref();
+            if let Some(&existing_idx) = concept_idx.get(id) {
+                current_id = Some(existing_idx);
+            } else {
+                let idx = concepts.len();
+                concepts.push(id.to_string());
+                concept_idx.insert(id.to_string(), idx);
+                current_id = Some(idx);
+            }
+            if let Some(idx) = current_id {
+                if let Some(first) = declared_at.get(&idx) {
+                    let message = format!("{} is already declared on line {}", concepts[idx], first.line);
+                    warn(WarningKind::DuplicateId, at, message);
+                } else {
+                    declared_at.insert(idx, at);
+                }
+                declared.insert(idx);
+            }
+            continue;
+        }
+
+        if line.starts_with("is_obsolete:") {
+            is_obsolete = line.contains("true");
+            if let (true, Some(idx)) = (is_obsolete, current_id) {
+                obsolete.insert(idx);
+                if stanza_axioms > 0 {
+                    warn(
+                        WarningKind::ObsoleteWithAxioms,
+                        at,
+                        format!("obsolete term {} has {} is_a/relationship lines", concepts[idx], stanza_axioms),
+                    );
+                }
+            }
+            continue;
+        }
+
+        if let (Some(rest), Some(idx)) = (line.strip_prefix("name:"), current_id) {
+            labels.insert(idx, rest.trim().to_string());
+            continue;
+        }
+
+        if let ("replaced_by" | "consider", Some(idx)) = (tag, current_id) {
+            let target = resolver.resolve(tag_target(&line[tag.len() + 1..])).into_owned();
+            if target.is_empty() {
+                warn(WarningKind::MalformedLine, at, format!("{} without a target", tag));
+            } else if tag == "replaced_by" {
+                replaced_by.push((idx, target));
+            } else {
+                consider.push((idx, target));
+            }
+            continue;
+        }
+
+        let is_axiom =
+            matches!(tag, "is_a" | "equivalent_to" | "relationship" | "intersection_of" | "disjoint_from");
+        if is_obsolete {
+            if is_axiom && stanza_axioms == 0 {
+                if let Some(idx) = current_id {
+                    warn(
+                        WarningKind::ObsoleteWithAxioms,
+                        at,
+                        format!("obsolete term {} has is_a/relationship lines", concepts[idx]),
+                    );
+                }
+            }
+            stanza_axioms += is_axiom as usize;
+            continue;
+        }
+
+        let Some(sub_idx) = current_id else {
+            if is_axiom {
+                warn(WarningKind::MalformedLine, at, format!("{} before id", tag));
+            }
+            continue;
+        };
+
+        if SKIPPED_TAGS.contains(&tag) {
+            warn(WarningKind::SkippedTag, at, format!("{} on {} is not supported", tag, concepts[sub_idx]));
+            // `C ≡ A ⊔ …` gives `A ⊑ C`; `C ≡ ¬A` gives `C ⊓ A ⊑ ⊥`.
+            let value = line[tag.len() + 1..].split(['{', '!']).next().unwrap_or("");
+            let part = match value.split_whitespace().collect::<Vec<_>>()[..] {
+                [class] => Some((None, class)),
+                [relation, class] if tag == "union_of" => {
+                    let r = *role_idx.entry(relation.to_string()).or_insert_with(|| {
+                        roles.push(relation.to_string());
+                        roles.len() - 1
+                    });
+                    Some((Some(r as RoleId), class))
+                }
+                _ => None,
+            };
+            let approximation = part.map(|(relation, class)| {
+                let class = resolver.resolve(class);
+                let class = class.as_ref();
+                let a = match concept_idx.get(class) {
+                    Some(&idx) => idx,
+                    None => {
+                        concepts.push(class.to_string());
+                        concept_idx.insert(class.to_string(), concepts.len() - 1);
+                        concepts.len() - 1
+                    }
+                };
+                let (a, c) = (ClassExpr::Named(a as ConceptId), ClassExpr::Named(sub_idx as ConceptId));
+                match relation {
+                    Some(r) => ClassAxiom::SubClassOf(ClassExpr::Some(r, Box::new(a)), c),
+                    None if tag == "union_of" => ClassAxiom::SubClassOf(a, c),
+                    None => ClassAxiom::SubClassOf(ClassExpr::And(vec![c, a]), ClassExpr::Named(BOTTOM)),
+                }
+            });
+            let construct = tag.to_string();
+            violations.push(Violation { construct, location: at, approximation: approximation.into_iter().collect() });
+            continue;
+        }
+        stanza_axioms += is_axiom as usize;
+
+        if let Some(rest) = line.strip_prefix("is_a:") {
+            let target = resolver.resolve(tag_target(rest));
+            let target = target.as_ref();
+            let sup_idx = if let Some(&idx) = concept_idx.get(target) {
+                idx
+            } else if !target.is_empty() {
+                let idx = concepts.len();
+                concepts.push(target.to_string());
+                concept_idx.insert(target.to_string(), idx);
+                idx
+            } else {
+                warn(WarningKind::MalformedLine, at, "is_a without a target".to_string());
+                continue;
+            };
+            target_first_use.entry(sup_idx).or_insert((sub_idx, at));
+            subsumptions.push((sub_idx, sup_idx));
+        } else if let Some(rest) = line.strip_prefix("equivalent_to:") {
+            // Subsumption both ways; the taxonomy groups the two.
+            let target = resolver.resolve(tag_target(rest));
+            let target = target.as_ref();
+            let other = if let Some(&idx) = concept_idx.get(target) {
+                idx
+            } else if !target.is_empty() {
+                concepts.push(target.to_string());
+                concept_idx.insert(target.to_string(), concepts.len() - 1);
+                concepts.len() - 1
+            } else {
+                warn(WarningKind::MalformedLine, at, "equivalent_to without a target".to_string());
+                continue;
+            };
+            target_first_use.entry(other).or_insert((sub_idx, at));
+            subsumptions.push((sub_idx, other));
+            subsumptions.push((other, sub_idx));
+        } else if let Some(rest) = line.strip_prefix("disjoint_from:") {
+            let target = resolver.resolve(tag_target(rest));
+            let target = target.as_ref();
+            let other = if let Some(&idx) = concept_idx.get(target) {
+                idx
+            } else if !target.is_empty() {
+                concepts.push(target.to_string());
+                concept_idx.insert(target.to_string(), concepts.len() - 1);
+                concepts.len() - 1
+            } else {
+                warn(WarningKind::MalformedLine, at, "disjoint_from without a target".to_string());
+                continue;
+            };
+            target_first_use.entry(other).or_insert((sub_idx, at));
+            disjoint_pairs.push((sub_idx, other));
+        } else if let Some(rest) = line.strip_prefix("intersection_of:") {
+            let value = rest.split(['{', '!']).next().unwrap_or("");
+            let part = match value.split_whitespace().collect::<Vec<_>>()[..] {
+                [genus] => (None, genus),
+                [relation, filler] => {
+                    let r = *role_idx.entry(relation.to_string()).or_insert_with(|| {
+                        roles.push(relation.to_string());
+                        roles.len() - 1
+                    });
+                    role_first_use.entry(r).or_insert((sub_idx, at));
+                    (Some(r), filler)
+                }
+                _ => {
+                    let message = "intersection_of needs a class or a relation and a class";
+                    warn(WarningKind::MalformedLine, at, message.to_string());
+                    continue;
+                }
+            };
+            let target = resolver.resolve(part.1);
+            let target = target.as_ref();
+            let target_idx = match concept_idx.get(target) {
+                Some(&idx) => idx,
+                None => {
+                    concepts.push(target.to_string());
+                    concept_idx.insert(target.to_string(), concepts.len() - 1);
+                    concepts.len() - 1
+                }
+            };
+            target_first_use.entry(target_idx).or_insert((sub_idx, at));
+            if intersection.is_empty() {
+                intersection_at = at;
+            }
+            intersection.push((part.0, target_idx));
+        } else if let Some(rest) = line.strip_prefix("relationship:") {
+            // Qualifiers and the trailing comment aren't part of the axiom.
+            let value = rest.split(['{', '!']).next().unwrap_or("");
+            let mut parts = value.split_whitespace();
+            if let (Some(role_name), Some(target), None) = (parts.next(), parts.next(), parts.next()) {
+                let target = resolver.resolve(target);
+                let target = target.as_ref();
+
+                let role_idx_val = if let Some(&idx) = role_idx.get(role_name) {
+                    idx
+                } else {
+                    let idx = roles.len();
+                    roles.push(role_name.to_string());
+                    role_idx.insert(role_name.to_string(), idx);
+                    idx
+                };
+
+                let target_idx = if let Some(&idx) = concept_idx.get(target) {
+                    idx
+                } else {
+                    let idx = concepts.len();
+                    concepts.push(target.to_string());
+                    concept_idx.insert(target.to_string(), idx);
+                    idx
+                };
+
+                role_first_use.entry(role_idx_val).or_insert((sub_idx, at));
+                target_first_use.entry(target_idx).or_insert((sub_idx, at));
+                relations.push((sub_idx, role_idx_val, target_idx));
+            } else {
+                warn(
+                    WarningKind::MalformedLine,
+                    at,
+                    "relationship needs a relation and a single target".to_string(),
+                );
+            }
+        } else if let Some(meant) = logical_lookalike(tag) {
+            let message = format!("unknown tag {} on {} (did you mean {}?)", tag, concepts[sub_idx], meant);
+            warn(WarningKind::UnknownTag, at, message);
+        }
+    }
+
+    if in_term {
+        let (nc, nr) = (concepts.len(), roles.len());
+        end_stanza(current_id, &subsumptions, &relations, nc, nr, &mut stanza_start);
+        let parts = std::mem::take(&mut intersection);
+        let term = current_id.filter(|_| !is_obsolete);
+        definitions.extend(take_definition(term, parts, intersection_at, &mut warn));
+    }
+
+    let mut undeclared: Vec<(Location, usize, usize)> = role_first_use
+        .into_iter()
+        .filter(|(r, _)| !typedef_roles.contains(r))
+        .map(|(r, (term, at))| (at, r, term))
+        .collect();
+    undeclared.sort_unstable();
+    for (at, r, term) in undeclared {
+        warn(
+            WarningKind::UnknownRelation,
+            at,
+            format!("relation {} (first used on {}) has no [Typedef]", roles[r], concepts[term]),
+        );
+    }
+
+    // Without imports, a target nothing declares is most likely a typo.
+    if imports.is_empty() {
+        let mut missing: Vec<(Location, usize, usize)> = target_first_use
+            .into_iter()
+            .filter(|&(c, _)| c > BOTTOM as usize && !declared.contains(&c))
+            .filter(|&(c, _)| !abox.individual_idx.contains_key(concepts[c].as_str()))
+            .map(|(c, (term, at))| (at, c, term))
+            .collect();
+        missing.sort_unstable();
+        for (at, c, term) in missing {
+            warn(
+                WarningKind::MissingTarget,
+                at,
+                format!("{} (first used on {}) has no [Term] stanza", concepts[c], concepts[term]),
+            );
+        }
+    }
+
+    // Term axioms pointing at an `[Instance]` are about the nominal `{id}`.
+    let mut nominals: Vec<(usize, usize)> = Vec::new();
+    for (c, name) in concepts.iter_mut().enumerate().skip(2) {
+        if declared.contains(&c) {
+            continue;
+        }
+        if let Some(&a) = abox.individual_idx.get(name.as_str()) {
+            concept_idx.remove(name.as_str());
+            *name = format!("{{{}}}", name);
+            concept_idx.insert(name.clone(), c);
+            nominals.push((c, a));
+        }
+    }
+
+    warnings.sort_by_key(|w| w.location);
+
+    let result = ParseResult {
+        concepts,
+        roles,
+        concept_idx,
+        subsumptions,
+        relations,
+        labels,
+        declared,
+        obsolete,
+        replaced_by,
+        consider,
+        role_labels,
+        role_subsumptions,
+        role_chains,
+        reflexive_roles,
+        role_domains,
+        role_ranges,
+        definitions,
+        disjoint_pairs,
+        class_axioms: Vec::new(),
+        violations,
+        individuals: abox.individuals,
+        individual_idx: abox.individual_idx,
+        individual_labels: abox.individual_labels,
+        class_assertions: abox.class_assertions,
+        role_assertions: abox.role_assertions,
+        nominals,
+        imports,
+        prefixes,
+    };
+    Ok((result, warnings))
+}
+
+/// The normalized axioms of a parse result. Definitions are normalized last,
+/// so their fresh concepts are numbered from `result.concepts.len()` up to
+/// `num_concepts()` of the returned store.
+pub fn build_axiom_store(result: &ParseResult) -> AxiomStore {
+    let mut store = build_told_axiom_store(result);
+    add_definitions(result, &mut store);
+    store
+}
+
+/// [`build_axiom_store`] without the definitions, for callers that number
+/// concepts of their own before [`add_definitions`].
+pub fn build_told_axiom_store(result: &ParseResult) -> AxiomStore {
+    let mut store = AxiomStore::new(result.concepts.len(), result.roles.len());
+
+    for (sub, sup) in &result.subsumptions {
+        store.add_subsumption(*sub as u32, *sup as u32);
+    }
+
+    for (sub, role, target) in &result.relations {
+        store.add_exist_right(*sub as u32, *role as u32, *target as u32);
+    }
+
+    for (a, b) in &result.disjoint_pairs {
+        store.add_conjunction(*a as u32, *b as u32, BOTTOM);
+    }
+
+    for (sub, sup) in &result.role_subsumptions {
+        store.add_role_subsumption(*sub as u32, *sup as u32);
+    }
+
+    for (first, second, sup) in &result.role_chains {
+        store.add_role_chain(*first as u32, *second as u32, *sup as u32);
+    }
+
+    add_nominals(result, &mut store);
+
+    for role in &result.reflexive_roles {
+        store.add_reflexive(*role as u32);
+    }
+
+    for (role, domain) in &result.role_domains {
+        store.add_exist_left(*role as u32, TOP, *domain as u32);
+    }
+
+    store
+}
+
+/// Marks the nominals of `result` in `store`, with the assertions about
+/// their individuals, which are TBox axioms about `{a}`: `{a} ⊑ C` and,
+/// when `b` is a nominal too, `{a} ⊑ ∃r.{b}`.
+pub fn add_nominals(result: &ParseResult, store: &mut AxiomStore) {
+    if result.nominals.is_empty() {
+        return;
+    }
+    let mut nominal_of: HashMap<usize, ConceptId> = HashMap::new();
+    for &(c, a) in &result.nominals {
+        store.add_nominal(c as ConceptId);
+        nominal_of.insert(a, c as ConceptId);
+    }
+    for (a, c) in &result.class_assertions {
+        if let Some(&n) = nominal_of.get(a) {
+            store.add_subsumption(n, *c as ConceptId);
+        }
+    }
+    for (a, r, b) in &result.role_assertions {
+        if let (Some(&n), Some(&m)) = (nominal_of.get(a), nominal_of.get(b)) {
+            store.add_exist_right(n, *r as RoleId, m);
+        }
+    }
+}
+
+/// Normalizes the definitions and class axioms of `result` into `store`,
+/// with fresh concepts after those the store already covers, then applies
+/// the role ranges to every existential in the store.
+pub fn add_definitions(result: &ParseResult, store: &mut AxiomStore) {
+    let num_concepts = store.num_concepts();
+    let mut normalizer = Normalizer::new(store, num_concepts);
+    for definition in &result.definitions {
+        normalizer.add_equivalence(definition.term as ConceptId, &definition.expression());
+    }
+    for axiom in &result.class_axioms {
+        normalizer.add_axiom(axiom);
+    }
+    let ranges: Vec<(RoleId, ConceptId)> =
+        result.role_ranges.iter().map(|&(r, c)| (r as RoleId, c as ConceptId)).collect();
+    normalizer.add_ranges(&ranges);
+}
+
+/// Turns the `intersection_of:` lines of a finished stanza into a
+/// definition; OBO requires at least two of them.
+fn take_definition(
+    term: Option<usize>,
+    parts: Vec<(Option<usize>, usize)>,
+    at: Location,
+    warn: &mut dyn FnMut(WarningKind, Location, String),
+) -> Option<Definition> {
+    let term = term?;
+    match parts.len() {
+        0 => return None,
+        1 => {
+            let message = "a single intersection_of line is not a definition";
+            warn(WarningKind::MalformedLine, at, message.to_string());
+            return None;
+        }
+        _ => {}
+    }
+    let mut definition = Definition { term, genus: Vec::new(), differentia: Vec::new() };
+    for (relation, target) in parts {
+        match relation {
+            Some(r) => definition.differentia.push((r, target)),
+            None => definition.genus.push(target),
+        }
+    }
+    Some(definition)
+}
+
+/// Resolves query names against a parse result: IDs first, then labels
+/// (case-insensitively).
+pub struct SymbolIndex<'a> {
+    parsed: &'a ParseResult,
+    resolver: &'a dyn IdResolver,
+    concept_labels: HashMap<String, usize>,
+    role_ids: HashMap<&'a str, usize>,
+    role_labels: HashMap<String, usize>,
+}
+
+impl<'a> SymbolIndex<'a> {
+    pub fn new(parsed: &'a ParseResult) -> Self {
+        Self::with_resolver(parsed, &Verbatim)
+    }
+
+    /// Resolves concept IDs through `resolver` (the one used for parsing)
+    /// before looking them up.
+    pub fn with_resolver(parsed: &'a ParseResult, resolver: &'a dyn IdResolver) -> Self {
+        let lower = |(&idx, label): (&usize, &String)| (label.to_lowercase(), idx);
+        Self {
+            parsed,
+            resolver,
+            concept_labels: parsed.labels.iter().map(lower).collect(),
+            role_ids: parsed.roles.iter().enumerate().map(|(i, r)| (r.as_str(), i)).collect(),
+            role_labels: parsed.role_labels.iter().map(lower).collect(),
+        }
+    }
+}
+
+impl SymbolIndex<'_> {
+    /// `name` as written, then as a CURIE and as a full IRI under the
+    /// prefixes of the parse result, so either form finds the other.
+    fn spellings<'n>(&self, name: &'n str) -> [Cow<'n, str>; 3] {
+        let prefixes = &self.parsed.prefixes;
+        [Cow::Borrowed(name), prefixes.resolve(name), prefixes.expand(name)]
+    }
+}
+
+impl Symbols for SymbolIndex<'_> {
+    fn concept(&self, name: &str) -> Option<ConceptId> {
+        let resolved = self.resolver.resolve(name);
+        let by_id = self.spellings(&resolved).into_iter().find_map(|n| self.parsed.concept_idx.get(n.as_ref()));
+        by_id.or_else(|| self.concept_labels.get(&name.to_lowercase())).map(|&idx| idx as ConceptId)
+    }
+
+    fn role(&self, name: &str) -> Option<RoleId> {
+        self.spellings(name)
+            .into_iter()
+            .find_map(|n| self.role_ids.get(n.as_ref()))
+            .or_else(|| self.role_labels.get(&name.to_lowercase()))
+            .map(|&idx| idx as RoleId)
+    }
+}
+
+/// Collects `alt_id:` → `id:` pairs from `[Term]` stanzas, for resolving
+/// references to merged terms in a second pass.
+pub fn scan_alt_ids<R: BufRead>(reader: R) -> io::Result<AltIds> {
+    let mut alt_ids = AltIds::new();
+    let mut in_term = false;
+    let mut id: Option<String> = None;
+    let mut pending: Vec<String> = Vec::new();
+    let mut flush = |id: &mut Option<String>, pending: &mut Vec<String>| {
+        if let Some(primary) = id.take() {
+            for alt in pending.iter() {
+                alt_ids.insert(alt, &primary);
+            }
+        }
+        pending.clear();
+    };
+
+    let mut lines = ByteLines::new(reader);
+    while let Some(line) = lines.next_line() {
+        let line = line?.trim();
+        if line.starts_with('[') {
+            flush(&mut id, &mut pending);
+            in_term = line == "[Term]";
+        } else if !in_term {
+            continue;
+        } else if let Some(rest) = line.strip_prefix("id:") {
+            id = Some(tag_target(rest).to_string());
+        } else if let Some(rest) = line.strip_prefix("alt_id:") {
+            pending.push(tag_target(rest).to_string());
+        }
+    }
+    flush(&mut id, &mut pending);
+    Ok(alt_ids)
+}
+
+/// Collects `(id, xref)` pairs from `[Term]` stanzas whose `xref:` has one
+/// of `prefixes` (`UBERON` for `xref: UBERON:0000948`), for treating those
+/// cross-references as equivalences.
+pub fn scan_xrefs<R: BufRead>(reader: R, prefixes: &[String]) -> io::Result<Vec<(String, String)>> {
+    let mut xrefs = Vec::new();
+    let mut in_term = false;
+    let mut id: Option<String> = None;
+    let mut lines = ByteLines::new(reader);
+    while let Some(line) = lines.next_line() {
+        let line = line?.trim();
+        if line.starts_with('[') {
+            in_term = line == "[Term]";
+            id = None;
+        } else if !in_term {
+            continue;
+        } else if let Some(rest) = line.strip_prefix("id:") {
+            id = Some(tag_target(rest).to_string());
+        } else if let (Some(rest), Some(id)) = (line.strip_prefix("xref:"), &id) {
+            let xref = tag_target(rest);
+            let wanted = xref.split_once(':').is_some_and(|(prefix, _)| prefixes.iter().any(|p| p == prefix));
+            if wanted {
+                xrefs.push((id.clone(), xref.to_string()));
+            }
+        }
+    }
+    Ok(xrefs)
+}
+
+/// Reads the `idspace:` header lines, as `(prefix, base IRI)` pairs, and
+/// stops at the first stanza.
+pub fn scan_idspaces<R: BufRead>(reader: R) -> io::Result<Vec<(String, String)>> {
+    let mut idspaces = Vec::new();
+    let mut lines = ByteLines::new(reader);
+    while let Some(line) = lines.next_line() {
+        let line = line?.trim();
+        if line.starts_with('[') {
+            break;
+        }
+        if let Some((prefix, base)) = line.strip_prefix("idspace:").and_then(idspace) {
+            idspaces.push((prefix.to_string(), base.to_string()));
+        }
+    }
+    Ok(idspaces)
+}
+
+/// `(prefix, base IRI)` of an `idspace:` value, `GO http://purl.obolibrary.org/obo/GO_ "…"`.
+fn idspace(rest: &str) -> Option<(&str, &str)> {
+    let mut tokens = rest.split_whitespace();
+    Some((tokens.next()?, tokens.next()?))
+}
+
+/// First token of a tag value, dropping `{qualifiers}` and `! comments`.
+fn tag_target(rest: &str) -> &str {
+    rest.split(|ch: char| ch.is_whitespace() || ch == '{' || ch == '!')
+        .find(|tok| !tok.is_empty())
+        .unwrap_or("")
+}
+
+/// Copies an OBO document byte-for-byte, appending to each `[Term]` stanza
+/// the inferred direct parents that aren't already asserted, as
+/// `is_a: X {is_inferred="true"}` lines, and likewise the other members of
+/// its group in `equivalences` as `equivalent_to:` lines. Header, comments,
+/// tag order and non-Term stanzas are untouched, so the output diffs
+/// minimally against the source. Returns the number of lines added.
+/// `resolver` must be the one `result` was parsed with.
+pub fn write_with_inferred<R: BufRead, W: Write>(
+    mut source: R,
+    out: &mut W,
+    result: &ParseResult,
+    direct_parents: &[Vec<ConceptId>],
+    equivalences: &[Vec<ConceptId>],
+    resolver: &dyn IdResolver,
+) -> io::Result<usize> {
+    let group: HashMap<ConceptId, &[ConceptId]> =
+        equivalences.iter().flat_map(|class| class.iter().map(move |&c| (c, class.as_slice()))).collect();
+    let mut line: Vec<u8> = Vec::with_capacity(256);
+    let mut held_blanks: Vec<u8> = Vec::new();
+    let mut newline: &[u8] = b"\n";
+    let mut first_line = true;
+    // Whether the last line copied ended in a line break; the source's last
+    // line may not, and inferred lines mustn't be glued onto it.
+    let mut line_ended = true;
+
+    let mut in_term = false;
+    let mut term: Option<usize> = None;
+    let mut obsolete = false;
+    let mut told: HashSet<String> = HashSet::new();
+    let mut told_equivalents: HashSet<String> = HashSet::new();
+    let mut added = 0;
+
+    loop {
+        line.clear();
+        let eof = source.read_until(b'\n', &mut line)? == 0;
+        if first_line && line.ends_with(b"\r\n") {
+            newline = b"\r\n";
+        }
+        first_line = false;
+
+        let text = String::from_utf8_lossy(&line);
+        let trimmed = text.trim();
+        let ends_stanza = eof || trimmed.starts_with('[');
+
+        if ends_stanza && in_term {
+            if let (Some(c), false) = (term, obsolete) {
+                for &p in &direct_parents[c] {
+                    let parent = &result.concepts[p as usize];
+                    if p == TOP || told.contains(parent.as_str()) {
+                        continue;
+                    }
+                    if !line_ended {
+                        out.write_all(newline)?;
+                        line_ended = true;
+                    }
+                    out.write_all(b"is_a: ")?;
+                    out.write_all(parent.as_bytes())?;
+                    out.write_all(b" {is_inferred=\"true\"}")?;
+                    out.write_all(newline)?;
+                    added += 1;
+                }
+                let equivalents = group.get(&(c as ConceptId)).copied().unwrap_or_default();
+                for &e in equivalents.iter().filter(|&&e| e != c as ConceptId) {
+                    let equivalent = &result.concepts[e as usize];
+                    if equivalent.is_empty() || told_equivalents.contains(equivalent.as_str()) {
+                        continue;
+                    }
+                    if !line_ended {
+                        out.write_all(newline)?;
+                        line_ended = true;
+                    }
+                    out.write_all(b"equivalent_to: ")?;
+                    out.write_all(equivalent.as_bytes())?;
+                    out.write_all(b" {is_inferred=\"true\"}")?;
+                    out.write_all(newline)?;
+                    added += 1;
+                }
+            }
+        }
+        if eof {
+            out.write_all(&held_blanks)?;
+            break;
+        }
+
+        if trimmed.is_empty() {
+            held_blanks.extend_from_slice(&line);
+            continue;
+        }
+        out.write_all(&held_blanks)?;
+        held_blanks.clear();
+        out.write_all(&line)?;
+        line_ended = line.ends_with(b"\n");
+
+        if ends_stanza {
+            in_term = trimmed == "[Term]";
+            term = None;
+            obsolete = false;
+            told.clear();
+            told_equivalents.clear();
+        } else if in_term {
+            if let Some(rest) = trimmed.strip_prefix("id:") {
+                term = result.concept_idx.get(resolver.resolve(rest.trim()).as_ref()).copied();
+            } else if let Some(rest) = trimmed.strip_prefix("is_a:") {
+                told.insert(resolver.resolve(tag_target(rest)).into_owned());
+            } else if let Some(rest) = trimmed.strip_prefix("equivalent_to:") {
+                told_equivalents.insert(resolver.resolve(tag_target(rest)).into_owned());
+            } else if let Some(rest) = trimmed.strip_prefix("is_obsolete:") {
+                obsolete = rest.contains("true");
+            }
+        }
+    }
+
+    out.flush()?;
+    Ok(added)
+}
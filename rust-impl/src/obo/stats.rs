@@ -0,0 +1,129 @@
+//! Content statistics over an OBO document, independent of classification.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use super::ByteLines;
+
+#[derive(Clone, Debug, Default)]
+pub struct ContentStats {
+    pub terms: usize,
+    pub typedefs: usize,
+    pub obsolete: usize,
+    /// Non-obsolete terms with a `def:` line.
+    pub defined: usize,
+    pub terms_per_namespace: BTreeMap<String, usize>,
+    pub is_a: usize,
+    pub relationships: BTreeMap<String, usize>,
+    pub intersection_of: usize,
+    pub union_of: usize,
+    pub disjoint_from: usize,
+    pub equivalent_to: usize,
+}
+
+impl ContentStats {
+    /// Fraction of non-obsolete terms carrying a definition.
+    pub fn definition_coverage(&self) -> f64 {
+        let live = self.terms - self.obsolete;
+        if live == 0 {
+            0.0
+        } else {
+            self.defined as f64 / live as f64
+        }
+    }
+}
+
+/// Scans a document once, tallying terms and logical tags per stanza.
+pub fn content_stats<R: BufRead>(reader: R) -> io::Result<ContentStats> {
+    let mut stats = ContentStats::default();
+    let mut in_term = false;
+    let mut namespace: Option<String> = None;
+    let mut has_def = false;
+    let mut obsolete = false;
+
+    let mut lines = ByteLines::new(reader);
+    while let Some(line) = lines.next_line() {
+        let line = line?.trim();
+        if line.starts_with('[') {
+            if in_term {
+                finish_term(&mut stats, namespace.take(), has_def, obsolete);
+            }
+            in_term = line == "[Term]";
+            if in_term {
+                stats.terms += 1;
+            } else if line == "[Typedef]" {
+                stats.typedefs += 1;
+            }
+            has_def = false;
+            obsolete = false;
+            continue;
+        }
+        if !in_term {
+            continue;
+        }
+
+        let Some((tag, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        match tag {
+            "namespace" => namespace = Some(value.to_string()),
+            "def" => has_def = true,
+            "is_obsolete" => obsolete = value.starts_with("true"),
+            "is_a" => stats.is_a += 1,
+            "relationship" => {
+                if let Some(rel) = value.split_whitespace().next() {
+                    *stats.relationships.entry(rel.to_string()).or_insert(0) += 1;
+                }
+            }
+            "intersection_of" => stats.intersection_of += 1,
+            "union_of" => stats.union_of += 1,
+            "disjoint_from" => stats.disjoint_from += 1,
+            "equivalent_to" => stats.equivalent_to += 1,
+            _ => {}
+        }
+    }
+    if in_term {
+        finish_term(&mut stats, namespace, has_def, obsolete);
+    }
+
+    Ok(stats)
+}
+
+fn finish_term(stats: &mut ContentStats, namespace: Option<String>, has_def: bool, obsolete: bool) {
+    let ns = namespace.unwrap_or_else(|| "(none)".to_string());
+    *stats.terms_per_namespace.entry(ns).or_insert(0) += 1;
+    if obsolete {
+        stats.obsolete += 1;
+    } else if has_def {
+        stats.defined += 1;
+    }
+}
+
+impl fmt::Display for ContentStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== Content Stats ===")?;
+        writeln!(f, "Terms: {}", self.terms)?;
+        writeln!(f, "Obsolete terms: {}", self.obsolete)?;
+        writeln!(f, "Typedefs: {}", self.typedefs)?;
+        writeln!(
+            f,
+            "Definition coverage: {:.1}% ({} of {} live terms)",
+            self.definition_coverage() * 100.0,
+            self.defined,
+            self.terms - self.obsolete
+        )?;
+        writeln!(f, "\nTerms per namespace:")?;
+        for (ns, count) in &self.terms_per_namespace {
+            writeln!(f, "  {}: {}", ns, count)?;
+        }
+        writeln!(f, "\nAxioms:")?;
+        writeln!(f, "  is_a: {}", self.is_a)?;
+        for (rel, count) in &self.relationships {
+            writeln!(f, "  relationship {}: {}", rel, count)?;
+        }
+        writeln!(f, "  intersection_of: {}", self.intersection_of)?;
+        writeln!(f, "  union_of: {}", self.union_of)?;
+        writeln!(f, "  disjoint_from: {}", self.disjoint_from)?;
+        write!(f, "  equivalent_to: {}", self.equivalent_to)
+    }
+}
@@ -0,0 +1,89 @@
+//! Problems noticed while parsing a document: [`Warning`]s about single
+//! lines, which are skipped, and the [`ParseError`] that stops reading.
+
+use std::{fmt, io};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WarningKind {
+    /// A logical tag the reasoner does not (yet) translate, e.g. `union_of`.
+    SkippedTag,
+    /// A `relationship:` whose relation has no `[Typedef]` stanza.
+    UnknownRelation,
+    /// An obsolete term that still carries `is_a`/`relationship` lines.
+    ObsoleteWithAxioms,
+    /// A line that isn't a well-formed `tag: value` pair for its tag.
+    MalformedLine,
+    /// An `import:` with no local file to read.
+    UnresolvedImport,
+    /// A second `[Term]` stanza with the same `id:`; the two are merged.
+    DuplicateId,
+    /// An `is_a`/`relationship`/… target without a `[Term]` stanza in a
+    /// document that has no imports to define it.
+    MissingTarget,
+    /// An unknown `[Term]` tag that looks like a misspelt logical one, so
+    /// an axiom may be missing.
+    UnknownTag,
+}
+
+impl WarningKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            WarningKind::SkippedTag => "skipped-tag",
+            WarningKind::UnknownRelation => "unknown-relation",
+            WarningKind::ObsoleteWithAxioms => "obsolete-with-axioms",
+            WarningKind::MalformedLine => "malformed-line",
+            WarningKind::UnresolvedImport => "unresolved-import",
+            WarningKind::DuplicateId => "duplicate-id",
+            WarningKind::MissingTarget => "missing-target",
+            WarningKind::UnknownTag => "unknown-tag",
+        }
+    }
+}
+
+/// 1-based line and column (in characters) within the source document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub location: Location,
+    pub message: String,
+}
+
+/// `line:column: kind: message`, ready to prefix with the file name.
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.location, self.kind.name(), self.message)
+    }
+}
+
+/// A read failure that stopped parsing; unlike a [`Warning`], nothing after
+/// `location` was seen. Undecodable lines are warnings, not errors.
+#[derive(Debug)]
+pub struct ParseError {
+    pub location: Location,
+    pub source: io::Error,
+}
+
+/// `line:column: message`, ready to prefix with the file name.
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: read failed: {}", self.location, self.source)
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
@@ -0,0 +1,211 @@
+//! What to do with obsolete terms before reasoning (`--obsolete`).
+//!
+//! An obsolete term loses its own axioms when parsed, but other terms may
+//! still refer to it. `Drop` removes those axioms too; `Replace` rewrites
+//! them to the term's `replaced_by:` target, or its `consider:` target when
+//! that is the only one, following replacements that are obsolete in turn.
+//! Either way the obsolete terms are left unnamed, so they appear in no
+//! output.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::impact::rewrite_definition;
+use crate::obo::ParseResult;
+use crate::ConceptId;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ObsoleteMode {
+    /// Leave obsolete terms as they were parsed.
+    #[default]
+    Keep,
+    Drop,
+    Replace,
+}
+
+impl ObsoleteMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(ObsoleteMode::Keep),
+            "drop" => Some(ObsoleteMode::Drop),
+            "replace" => Some(ObsoleteMode::Replace),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObsoleteSummary {
+    pub obsolete: usize,
+    /// Obsolete terms whose references now point at their replacement.
+    pub replaced: usize,
+    /// Obsolete terms removed with every axiom that mentions them.
+    pub dropped: usize,
+    pub axioms_dropped: usize,
+    pub axioms_rewritten: usize,
+}
+
+impl fmt::Display for ObsoleteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} obsolete terms: {} replaced, {} dropped ({} axioms rewritten, {} dropped)",
+            self.obsolete, self.replaced, self.dropped, self.axioms_rewritten, self.axioms_dropped
+        )
+    }
+}
+
+/// The single replacement of each obsolete term: its `replaced_by:` target
+/// if it has exactly one, else its only `consider:` target.
+fn replacements(parsed: &ParseResult) -> HashMap<usize, usize> {
+    let mut replaced_by: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut consider: HashMap<usize, Vec<usize>> = HashMap::new();
+    let targets = [(&parsed.replaced_by, &mut replaced_by), (&parsed.consider, &mut consider)];
+    for (pairs, map) in targets {
+        for (term, target) in pairs {
+            if let Some(&target) = parsed.concept_idx.get(target) {
+                let list = map.entry(*term).or_default();
+                if !list.contains(&target) && target != *term {
+                    list.push(target);
+                }
+            }
+        }
+    }
+    parsed
+        .obsolete
+        .iter()
+        .filter_map(|&c| {
+            let single = |map: &HashMap<usize, Vec<usize>>| match map.get(&c).map(Vec::as_slice) {
+                Some(&[target]) => Some(target),
+                _ => None,
+            };
+            Some((c, single(&replaced_by).or_else(|| single(&consider))?))
+        })
+        .collect()
+}
+
+/// Applies `mode` to `parsed` in place.
+pub fn apply(parsed: &mut ParseResult, mode: ObsoleteMode) -> ObsoleteSummary {
+    let mut summary = ObsoleteSummary { obsolete: parsed.obsolete.len(), ..Default::default() };
+    if mode == ObsoleteMode::Keep || parsed.obsolete.is_empty() {
+        return summary;
+    }
+    let direct = match mode {
+        ObsoleteMode::Replace => replacements(parsed),
+        _ => HashMap::new(),
+    };
+    // Where each obsolete term ends up: a live term, or `None`.
+    let mut fate: HashMap<usize, Option<usize>> = HashMap::new();
+    for &c in &parsed.obsolete {
+        let mut seen = vec![c];
+        let mut at = c;
+        let end = loop {
+            match direct.get(&at) {
+                None => break None,
+                Some(&next) if seen.contains(&next) => break None,
+                Some(&next) if !parsed.obsolete.contains(&next) => break Some(next),
+                Some(&next) => {
+                    seen.push(next);
+                    at = next;
+                }
+            }
+        };
+        match end {
+            Some(_) => summary.replaced += 1,
+            None => summary.dropped += 1,
+        }
+        fate.insert(c, end);
+    }
+
+    let rewrite = |c: usize| match fate.get(&c) {
+        None => Some(c),
+        Some(&end) => end,
+    };
+    let mut rewritten = 0;
+    let mut dropped = 0;
+    let mut count = |before: bool, after: bool| {
+        if !after {
+            dropped += 1;
+        } else if before {
+            rewritten += 1;
+        }
+    };
+    let touches = |cs: &[usize]| cs.iter().any(|c| fate.contains_key(c));
+
+    let retain_pairs = |pairs: &mut Vec<(usize, usize)>, count: &mut dyn FnMut(bool, bool)| {
+        *pairs = pairs
+            .iter()
+            .filter_map(|&(a, b)| {
+                // A pair merged into one term says nothing (or, if disjoint, too much).
+                let touched = touches(&[a, b]);
+                let pair = rewrite(a).zip(rewrite(b)).filter(|(a, b)| !touched || a != b);
+                count(touched, pair.is_some());
+                pair
+            })
+            .collect();
+    };
+    retain_pairs(&mut parsed.subsumptions, &mut count);
+    retain_pairs(&mut parsed.disjoint_pairs, &mut count);
+
+    parsed.relations = parsed
+        .relations
+        .iter()
+        .filter_map(|&(a, r, b)| {
+            let relation = rewrite(a).zip(rewrite(b)).map(|(a, b)| (a, r, b));
+            count(touches(&[a, b]), relation.is_some());
+            relation
+        })
+        .collect();
+    for ends in [&mut parsed.role_domains, &mut parsed.role_ranges] {
+        *ends = ends
+            .iter()
+            .filter_map(|&(r, c)| {
+                let end = rewrite(c).map(|c| (r, c));
+                count(touches(&[c]), end.is_some());
+                end
+            })
+            .collect();
+    }
+    parsed.definitions = parsed
+        .definitions
+        .iter()
+        .filter_map(|d| {
+            let fillers = d.differentia.iter().map(|&(_, f)| f);
+            let mentioned: Vec<usize> = d.genus.iter().copied().chain(fillers).chain([d.term]).collect();
+            let definition = rewrite_definition(d, |c| rewrite(c).map(|c| c as ConceptId));
+            count(touches(&mentioned), definition.is_some());
+            definition
+        })
+        .collect();
+    parsed.class_axioms = parsed
+        .class_axioms
+        .iter()
+        .filter_map(|axiom| {
+            let mut mentions = false;
+            let axiom = axiom.try_map(&mut |c| {
+                mentions |= fate.contains_key(&(c as usize));
+                rewrite(c as usize).map(|c| c as ConceptId)
+            });
+            count(mentions, axiom.is_some());
+            axiom
+        })
+        .collect();
+    parsed.class_assertions = parsed
+        .class_assertions
+        .iter()
+        .filter_map(|&(i, c)| {
+            let assertion = rewrite(c).map(|c| (i, c));
+            count(touches(&[c]), assertion.is_some());
+            assertion
+        })
+        .collect();
+    summary.axioms_rewritten = rewritten;
+    summary.axioms_dropped = dropped;
+
+    for &c in fate.keys() {
+        let name = std::mem::take(&mut parsed.concepts[c]);
+        parsed.concept_idx.remove(&name);
+        parsed.labels.remove(&c);
+    }
+    summary
+}
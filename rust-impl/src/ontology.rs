@@ -0,0 +1,563 @@
+//! A name-based front door to the reasoner: concepts and roles are interned
+//! by name, axioms are added between the returned IDs, and [`classify`]
+//! runs normalization, saturation and taxonomy construction in one step.
+//! [`into_classified`] does the same into a [`ClassifiedOntology`] that
+//! threads can share.
+//!
+//! The axioms are kept in a [`ParseResult`], so an ontology read by any
+//! front-end converts into one and back without copying.
+//!
+//! [`classify`]: Ontology::classify
+//! [`into_classified`]: Ontology::into_classified
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use tracing::info_span;
+
+use crate::dangling::undeclared;
+use crate::error::ReasonerError;
+use crate::explain::{AxiomId, Explainer};
+use crate::export::relation_closure;
+use crate::input::{parse_document, read_with_imports, InputFormat};
+use crate::modules::{restrict, Locality, ModuleExtractor};
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::obo::{build_axiom_store, ParseResult, Warning};
+use crate::query::Symbols;
+use crate::resolve::Verbatim;
+use crate::{
+    is_subsumed_by, realize, saturate, saturate_signature, AxiomStore, ConceptId, Context, Descriptions, Realization,
+    Reasoner, RoleId, Taxonomy, BOTTOM,
+};
+
+#[derive(Default)]
+pub struct Ontology {
+    parsed: ParseResult,
+    role_idx: HashMap<String, RoleId>,
+}
+
+impl Ontology {
+    /// An empty ontology; `owl:Thing` and `owl:Nothing` are TOP and BOTTOM.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the ontology at `path` and the documents it imports, each in
+    /// the format its extension or content suggests. The warnings are about
+    /// lines that were skipped, those of `path` first.
+    pub fn read(path: &Path) -> Result<(Self, Vec<Warning>), ReasonerError> {
+        let mut warnings = Vec::new();
+        let parsed = read_with_imports(
+            &[path],
+            |path: &Path| {
+                let format = InputFormat::detect(path)?.unwrap_or(InputFormat::Obo);
+                let reader = BufReader::with_capacity(1024 * 1024, File::open(path)?);
+                parse_document(reader, format, &Verbatim, false)
+            },
+            |_: &Path, found| warnings.extend(found),
+        )?;
+        Ok((Self::from(parsed), warnings))
+    }
+
+    /// The concept named `name`, interned on first use.
+    pub fn concept(&mut self, name: &str) -> ConceptId {
+        if let Some(&idx) = self.parsed.concept_idx.get(name) {
+            return idx as ConceptId;
+        }
+        let idx = self.parsed.concepts.len();
+        self.parsed.concepts.push(name.to_string());
+        self.parsed.concept_idx.insert(name.to_string(), idx);
+        idx as ConceptId
+    }
+
+    /// The role named `name`, interned on first use.
+    pub fn role(&mut self, name: &str) -> RoleId {
+        if let Some(&r) = self.role_idx.get(name) {
+            return r;
+        }
+        let r = self.parsed.roles.len() as RoleId;
+        self.parsed.roles.push(name.to_string());
+        self.role_idx.insert(name.to_string(), r);
+        r
+    }
+
+    /// The individual named `name`, interned on first use.
+    pub fn individual(&mut self, name: &str) -> usize {
+        self.parsed.individual(name)
+    }
+
+    /// The nominal `{individual}`, a concept with that one instance.
+    pub fn nominal(&mut self, individual: usize) -> ConceptId {
+        self.parsed.nominal(individual) as ConceptId
+    }
+
+    pub fn find_concept(&self, name: &str) -> Option<ConceptId> {
+        self.parsed.concept_idx.get(name).map(|&idx| idx as ConceptId)
+    }
+
+    pub fn find_role(&self, name: &str) -> Option<RoleId> {
+        self.role_idx.get(name).copied()
+    }
+
+    pub fn concept_name(&self, c: ConceptId) -> &str {
+        &self.parsed.concepts[c as usize]
+    }
+
+    /// Classes that axioms refer to but nothing declares, in ID order; see
+    /// [`dangling_references`](crate::dangling::dangling_references).
+    pub fn undeclared(&self) -> Vec<&str> {
+        let mut names: Vec<&str> =
+            undeclared(&self.parsed).into_iter().map(|c| self.parsed.concepts[c].as_str()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn role_name(&self, r: RoleId) -> &str {
+        &self.parsed.roles[r as usize]
+    }
+
+    pub fn num_concepts(&self) -> usize {
+        self.parsed.concepts.len()
+    }
+
+    pub fn num_roles(&self) -> usize {
+        self.parsed.roles.len()
+    }
+
+    pub fn set_label(&mut self, c: ConceptId, label: &str) {
+        self.parsed.labels.insert(c as usize, label.to_string());
+    }
+
+    /// The `name:` or `rdfs:label` of `c`, if it has one.
+    pub fn label(&self, c: ConceptId) -> Option<&str> {
+        self.parsed.labels.get(&(c as usize)).map(String::as_str)
+    }
+
+    /// The concepts labelled `label`, ignoring case, in ID order; labels
+    /// need not be unique. Scans every label, so resolve names once rather
+    /// than per query.
+    pub fn lookup_by_label(&self, label: &str) -> Vec<ConceptId> {
+        let label = label.to_lowercase();
+        let mut found: Vec<ConceptId> = self
+            .parsed
+            .labels
+            .iter()
+            .filter(|(_, l)| l.to_lowercase() == label)
+            .map(|(&c, _)| c as ConceptId)
+            .collect();
+        found.sort_unstable();
+        found
+    }
+
+    /// `sub ⊑ sup`.
+    pub fn add_subclass_of(&mut self, sub: ConceptId, sup: ConceptId) {
+        self.parsed.subsumptions.push((sub as usize, sup as usize));
+    }
+
+    /// `sub ⊑ ∃role.filler`.
+    pub fn add_existential(&mut self, sub: ConceptId, role: RoleId, filler: ConceptId) {
+        self.parsed.relations.push((sub as usize, role as usize, filler as usize));
+    }
+
+    /// `c ≡ expr`.
+    pub fn add_equivalent(&mut self, c: ConceptId, expr: ClassExpr) {
+        self.add_axiom(ClassAxiom::Equivalent(ClassExpr::Named(c), expr));
+    }
+
+    /// `a ⊓ b ⊑ ⊥`.
+    pub fn add_disjoint(&mut self, a: ConceptId, b: ConceptId) {
+        self.parsed.disjoint_pairs.push((a as usize, b as usize));
+    }
+
+    /// An axiom over arbitrary EL class expressions.
+    pub fn add_axiom(&mut self, axiom: ClassAxiom) {
+        self.parsed.class_axioms.push(axiom);
+    }
+
+    /// `sub ⊑ sup` between roles.
+    pub fn add_sub_role(&mut self, sub: RoleId, sup: RoleId) {
+        self.parsed.role_subsumptions.push((sub as usize, sup as usize));
+    }
+
+    /// `first ∘ second ⊑ sup`.
+    pub fn add_role_chain(&mut self, first: RoleId, second: RoleId, sup: RoleId) {
+        self.parsed.role_chains.push((first as usize, second as usize, sup as usize));
+    }
+
+    pub fn add_transitive(&mut self, role: RoleId) {
+        self.add_role_chain(role, role, role);
+    }
+
+    pub fn add_reflexive(&mut self, role: RoleId) {
+        self.parsed.reflexive_roles.push(role as usize);
+    }
+
+    /// `∃role.⊤ ⊑ domain`.
+    pub fn add_domain(&mut self, role: RoleId, domain: ConceptId) {
+        self.parsed.role_domains.push((role as usize, domain as usize));
+    }
+
+    /// Every `role` successor is a `range`.
+    pub fn add_range(&mut self, role: RoleId, range: ConceptId) {
+        self.parsed.role_ranges.push((role as usize, range as usize));
+    }
+
+    /// `individual : c`.
+    pub fn add_class_assertion(&mut self, individual: usize, c: ConceptId) {
+        self.parsed.class_assertions.push((individual, c as usize));
+    }
+
+    /// `role(subject, object)`.
+    pub fn add_role_assertion(&mut self, subject: usize, role: RoleId, object: usize) {
+        self.parsed.role_assertions.push((subject, role as usize, object));
+    }
+
+    pub fn parse_result(&self) -> &ParseResult {
+        &self.parsed
+    }
+
+    pub fn into_parse_result(self) -> ParseResult {
+        self.parsed
+    }
+
+    /// The normalized axioms; fresh concepts follow [`num_concepts`](Self::num_concepts).
+    pub fn axiom_store(&self) -> AxiomStore {
+        build_axiom_store(&self.parsed)
+    }
+
+    /// Saturated contexts of every concept, fresh ones included.
+    pub fn saturate(&self) -> Vec<Context> {
+        let store = self.axiom_store();
+        saturate(&store, store.num_concepts(), self.num_roles())
+    }
+
+    /// A [`Reasoner`] saturated for the axioms so far, for adding more
+    /// without starting over.
+    pub fn reasoner(&self) -> Reasoner {
+        Reasoner::new(self.axiom_store(), self.num_roles()).with_named_concepts(self.num_concepts())
+    }
+
+    fn known_concept(&self, name: &str) -> Result<ConceptId, ReasonerError> {
+        self.find_concept(name).ok_or_else(|| ReasonerError::UnknownTerm(name.to_string()))
+    }
+
+    /// Whether `sub ⊑ sup`, saturating only what `sub` depends on; see
+    /// [`is_subsumed_by`].
+    pub fn is_subsumed_by(&self, sub: &str, sup: &str) -> Result<bool, ReasonerError> {
+        let (sub, sup) = (self.known_concept(sub)?, self.known_concept(sup)?);
+        Ok(is_subsumed_by(&self.axiom_store(), sub, sup))
+    }
+
+    /// Justifications of `sub ⊑ sup` over the axioms added so far; see
+    /// [`Explainer`].
+    pub fn explain(&self, sub: &str, sup: &str) -> Result<Vec<Vec<AxiomId>>, ReasonerError> {
+        let (sub, sup) = (self.known_concept(sub)?, self.known_concept(sup)?);
+        Ok(Explainer::new(&self.parsed).explain(sub, sup))
+    }
+
+    /// The `locality` module of the named concepts, as an ontology of its
+    /// own with the same IDs; unknown names are left out of the signature.
+    pub fn module(&self, locality: Locality, names: &[&str]) -> Ontology {
+        let seed: Vec<ConceptId> = names.iter().filter_map(|n| self.find_concept(n)).collect();
+        let axioms = ModuleExtractor::new(&self.parsed).extract(locality, &seed, &[]);
+        Ontology { parsed: restrict(&self.parsed, &axioms), role_idx: self.role_idx.clone() }
+    }
+
+    /// Every inferred `c ⊑ ∃role.d` between named concepts, by name; see
+    /// [`relation_closure`].
+    pub fn relation_closure(&self, role: &str) -> Result<Vec<(&str, &str)>, ReasonerError> {
+        let r = self.find_role(role).ok_or_else(|| ReasonerError::UnknownTerm(role.to_string()))?;
+        let pairs = relation_closure(&self.saturate(), self.num_concepts(), r);
+        Ok(pairs.into_iter().map(|(c, d)| (self.concept_name(c), self.concept_name(d))).collect())
+    }
+
+    /// The least common subsumer of two named concepts as a class
+    /// expression, up to [`Descriptions::DEFAULT_DEPTH`].
+    pub fn least_common_subsumer(&self, a: &str, b: &str) -> Result<ClassExpr, ReasonerError> {
+        let (a, b) = (self.known_concept(a)?, self.known_concept(b)?);
+        let store = self.axiom_store();
+        let contexts = saturate(&store, store.num_concepts(), self.num_roles());
+        Ok(Descriptions::new(&store, &contexts, self.num_concepts()).least_common_subsumer(a, b))
+    }
+
+    /// The most specific concept of the individual named `name`, up to
+    /// [`Descriptions::DEFAULT_DEPTH`].
+    pub fn most_specific_concept(&self, name: &str) -> Result<ClassExpr, ReasonerError> {
+        let Some(&a) = self.parsed.individual_idx.get(name) else {
+            return Err(ReasonerError::UnknownTerm(name.to_string()));
+        };
+        let mut store = self.axiom_store();
+        let mut contexts = saturate(&store, store.num_concepts(), self.num_roles());
+        let first = store.num_concepts();
+        realize(&self.parsed, &mut store, &mut contexts);
+        let descriptions = Descriptions::new(&store, &contexts, self.num_concepts());
+        Ok(descriptions.most_specific_concept((first + a) as ConceptId))
+    }
+
+    /// Types of the individuals and instances of the concepts.
+    pub fn realize(&self) -> Realization {
+        let mut store = self.axiom_store();
+        let mut contexts = saturate(&store, store.num_concepts(), self.num_roles());
+        realize(&self.parsed, &mut store, &mut contexts)
+    }
+
+    /// Runs in `normalize`, `saturate` and `reduce` spans; see
+    /// [`crate::telemetry`].
+    pub fn classify(&self) -> Classification<'_> {
+        Classification { ontology: self, hierarchy: self.hierarchy() }
+    }
+
+    /// [`classify`](Self::classify), keeping the results together with the
+    /// ontology so they can be shared between threads.
+    pub fn into_classified(self) -> ClassifiedOntology {
+        let hierarchy = self.hierarchy();
+        ClassifiedOntology { ontology: self, hierarchy }
+    }
+
+    fn hierarchy(&self) -> Hierarchy {
+        let store = info_span!("normalize").in_scope(|| self.axiom_store());
+        let mut contexts =
+            info_span!("saturate").in_scope(|| saturate(&store, store.num_concepts(), self.num_roles()));
+        contexts.truncate(self.num_concepts());
+        let taxonomy = info_span!("reduce").in_scope(|| Taxonomy::among(&contexts, &self.parse_result().named()));
+        Hierarchy { contexts, taxonomy }
+    }
+
+    /// [`classify`](Self::classify) restricted to the named concepts and
+    /// what they depend on (their ⊥-locality module); unknown names are left
+    /// out. Superclasses of the classified concepts are exact, subclasses
+    /// only those classified too, and every other concept is subsumed by
+    /// nothing.
+    pub fn classify_signature(&self, names: &[&str]) -> Classification<'_> {
+        let seed: Vec<ConceptId> = names.iter().filter_map(|n| self.find_concept(n)).collect();
+        let store = self.axiom_store();
+        let (mut contexts, module) = saturate_signature(&store, self.num_roles(), seed);
+        contexts.truncate(self.num_concepts());
+        let named: Vec<bool> = (0..self.num_concepts() as ConceptId).map(|c| module.contains(c)).collect();
+        let taxonomy = Taxonomy::among(&contexts, &named);
+        Classification { ontology: self, hierarchy: Hierarchy { contexts, taxonomy } }
+    }
+}
+
+impl From<ParseResult> for Ontology {
+    fn from(parsed: ParseResult) -> Self {
+        let role_idx = parsed
+            .roles
+            .iter()
+            .enumerate()
+            .map(|(r, name)| (name.clone(), r as RoleId))
+            .collect();
+        Self { parsed, role_idx }
+    }
+}
+
+impl Symbols for Ontology {
+    fn concept(&self, name: &str) -> Option<ConceptId> {
+        self.find_concept(name)
+    }
+
+    fn role(&self, name: &str) -> Option<RoleId> {
+        self.find_role(name)
+    }
+}
+
+/// The saturated contexts of the named concepts and the hierarchy reduced
+/// from them, queried by name against the ontology they were classified
+/// from.
+struct Hierarchy {
+    /// Contexts of the named concepts only.
+    contexts: Vec<Context>,
+    taxonomy: Taxonomy,
+}
+
+impl Hierarchy {
+    fn names(ontology: &Ontology, ids: impl Iterator<Item = ConceptId>) -> Vec<&str> {
+        ids.map(|c| ontology.concept_name(c)).collect()
+    }
+
+    fn is_subclass_of(&self, ontology: &Ontology, sub: &str, sup: &str) -> bool {
+        let (Some(sub), Some(sup)) = (ontology.find_concept(sub), ontology.find_concept(sup)) else {
+            return false;
+        };
+        let supers = &self.contexts[sub as usize].super_set;
+        sub == sup || supers.contains(&sup) || supers.contains(&BOTTOM)
+    }
+
+    fn superclasses<'a>(&self, ontology: &'a Ontology, name: &str) -> Vec<&'a str> {
+        let Some(c) = ontology.find_concept(name) else {
+            return Vec::new();
+        };
+        let mut supers: Vec<ConceptId> = self.contexts[c as usize]
+            .super_set
+            .iter()
+            .copied()
+            .filter(|&s| s != c && (s as usize) < self.contexts.len())
+            .collect();
+        supers.sort_unstable();
+        Self::names(ontology, supers.into_iter())
+    }
+
+    fn direct_superclasses<'a>(&self, ontology: &'a Ontology, name: &str) -> Vec<&'a str> {
+        match ontology.find_concept(name) {
+            Some(c) => Self::names(ontology, self.taxonomy.parents(c).iter().copied()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Equivalents share their representative's place in the hierarchy.
+    fn subclasses<'a>(&self, ontology: &'a Ontology, name: &str) -> Vec<&'a str> {
+        let Some(c) = ontology.find_concept(name) else {
+            return Vec::new();
+        };
+        let descendants = self.taxonomy.descendants(self.taxonomy.representative(c));
+        Self::names(ontology, descendants.into_iter())
+    }
+
+    fn direct_subclasses<'a>(&self, ontology: &'a Ontology, name: &str) -> Vec<&'a str> {
+        let Some(c) = ontology.find_concept(name) else {
+            return Vec::new();
+        };
+        let children = self.taxonomy.children(self.taxonomy.representative(c));
+        Self::names(ontology, children.iter().copied())
+    }
+
+    fn equivalents<'a>(&self, ontology: &'a Ontology, name: &str) -> Vec<&'a str> {
+        let Some(c) = ontology.find_concept(name) else {
+            return Vec::new();
+        };
+        let class = self.taxonomy.equivalences().iter().find(|class| class.contains(&c));
+        let members = class.map(|class| class.as_slice()).unwrap_or_default();
+        Self::names(ontology, members.iter().copied().filter(|&m| m != c))
+    }
+
+    fn unsatisfiable<'a>(&self, ontology: &'a Ontology) -> Vec<&'a str> {
+        Self::names(ontology, crate::unsatisfiable_concepts(&self.contexts).into_iter())
+    }
+}
+
+/// The classified hierarchy of an [`Ontology`], queried by name. Unknown
+/// names have no superclasses and are subsumed by nothing.
+pub struct Classification<'o> {
+    ontology: &'o Ontology,
+    hierarchy: Hierarchy,
+}
+
+impl<'o> Classification<'o> {
+    pub fn contexts(&self) -> &[Context] {
+        &self.hierarchy.contexts
+    }
+
+    pub fn taxonomy(&self) -> &Taxonomy {
+        &self.hierarchy.taxonomy
+    }
+
+    pub fn is_subclass_of(&self, sub: &str, sup: &str) -> bool {
+        self.hierarchy.is_subclass_of(self.ontology, sub, sup)
+    }
+
+    /// Every named superclass of `name` other than itself, in concept order.
+    pub fn superclasses(&self, name: &str) -> Vec<&'o str> {
+        self.hierarchy.superclasses(self.ontology, name)
+    }
+
+    pub fn direct_superclasses(&self, name: &str) -> Vec<&'o str> {
+        self.hierarchy.direct_superclasses(self.ontology, name)
+    }
+
+    /// Every named subclass of `name` other than its equivalents, in
+    /// concept order.
+    pub fn subclasses(&self, name: &str) -> Vec<&'o str> {
+        self.hierarchy.subclasses(self.ontology, name)
+    }
+
+    pub fn direct_subclasses(&self, name: &str) -> Vec<&'o str> {
+        self.hierarchy.direct_subclasses(self.ontology, name)
+    }
+
+    /// Named concepts equivalent to `name`, itself excluded.
+    pub fn equivalents(&self, name: &str) -> Vec<&'o str> {
+        self.hierarchy.equivalents(self.ontology, name)
+    }
+
+    /// False when the axioms have no model; see [`crate::is_consistent`].
+    pub fn is_consistent(&self) -> bool {
+        crate::is_consistent(&self.hierarchy.contexts)
+    }
+
+    pub fn unsatisfiable(&self) -> Vec<&'o str> {
+        self.hierarchy.unsatisfiable(self.ontology)
+    }
+}
+
+/// An [`Ontology`] classified once and owning its results, for answering
+/// queries from many threads: it is `Send` and `Sync` and every query
+/// takes `&self`, so an `Arc<ClassifiedOntology>` can be handed to each
+/// worker instead of a copy of the contexts or a lock around them.
+pub struct ClassifiedOntology {
+    ontology: Ontology,
+    hierarchy: Hierarchy,
+}
+
+const _: () = {
+    const fn shared<T: Send + Sync>() {}
+    shared::<ClassifiedOntology>()
+};
+
+impl ClassifiedOntology {
+    /// The ontology classified, for names, labels and told axioms.
+    pub fn ontology(&self) -> &Ontology {
+        &self.ontology
+    }
+
+    pub fn contexts(&self) -> &[Context] {
+        &self.hierarchy.contexts
+    }
+
+    pub fn taxonomy(&self) -> &Taxonomy {
+        &self.hierarchy.taxonomy
+    }
+
+    pub fn is_subclass_of(&self, sub: &str, sup: &str) -> bool {
+        self.hierarchy.is_subclass_of(&self.ontology, sub, sup)
+    }
+
+    /// Every named superclass of `name` other than itself, in concept order.
+    pub fn superclasses(&self, name: &str) -> Vec<&str> {
+        self.hierarchy.superclasses(&self.ontology, name)
+    }
+
+    pub fn direct_superclasses(&self, name: &str) -> Vec<&str> {
+        self.hierarchy.direct_superclasses(&self.ontology, name)
+    }
+
+    /// Every named subclass of `name` other than its equivalents, in
+    /// concept order.
+    pub fn subclasses(&self, name: &str) -> Vec<&str> {
+        self.hierarchy.subclasses(&self.ontology, name)
+    }
+
+    pub fn direct_subclasses(&self, name: &str) -> Vec<&str> {
+        self.hierarchy.direct_subclasses(&self.ontology, name)
+    }
+
+    /// Named concepts equivalent to `name`, itself excluded.
+    pub fn equivalents(&self, name: &str) -> Vec<&str> {
+        self.hierarchy.equivalents(&self.ontology, name)
+    }
+
+    /// False when the axioms have no model; see [`crate::is_consistent`].
+    pub fn is_consistent(&self) -> bool {
+        crate::is_consistent(&self.hierarchy.contexts)
+    }
+
+    pub fn unsatisfiable(&self) -> Vec<&str> {
+        self.hierarchy.unsatisfiable(&self.ontology)
+    }
+
+    /// Gives the ontology back, for adding axioms and classifying again.
+    pub fn into_ontology(self) -> Ontology {
+        self.ontology
+    }
+}
@@ -0,0 +1,281 @@
+//! Streaming writers for classification results.
+//!
+//! Every format is driven through the same [`FormatWriter`] callbacks, one
+//! concept at a time, into a large `BufWriter`. Nothing builds the whole
+//! document in memory, so SNOMED-sized closures stream out in fixed-size
+//! chunks to a file, stdout, or any wrapping (e.g. compressing) `Write`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::kernel::{ConceptId, TOP};
+use crate::taxonomy::{DepthRange, Taxonomy};
+
+/// Size of the chunks handed to the underlying sink.
+pub const OUTPUT_CHUNK: usize = 256 * 1024;
+
+/// Borrowed view of a classification handed to format writers.
+pub struct ClassificationView<'a> {
+    pub concepts: &'a [String],
+    pub direct_parents: &'a [Vec<ConceptId>],
+    /// Per-concept depths, for formats that can include them.
+    pub depths: Option<&'a [DepthRange]>,
+    /// Human-readable names, for formats that can include them.
+    pub labels: Option<&'a HashMap<usize, String>>,
+}
+
+impl<'a> ClassificationView<'a> {
+    pub fn name(&self, c: ConceptId) -> &'a str {
+        &self.concepts[c as usize]
+    }
+
+    /// Named concepts in output order (TOP and BOTTOM excluded).
+    pub fn concept_ids(&self) -> impl Iterator<Item = ConceptId> + 'a {
+        let concepts = self.concepts;
+        (2..concepts.len() as ConceptId).filter(move |&c| !concepts[c as usize].is_empty())
+    }
+}
+
+pub trait FormatWriter {
+    fn header(&mut self, _out: &mut dyn Write, _view: &ClassificationView) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn concept(
+        &mut self,
+        out: &mut dyn Write,
+        view: &ClassificationView,
+        c: ConceptId,
+    ) -> io::Result<()>;
+
+    fn footer(&mut self, _out: &mut dyn Write, _view: &ClassificationView) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Tsv,
+    /// The source OBO document with inferred `is_a` lines appended.
+    Obo,
+    /// GraphViz digraph of the reduced hierarchy.
+    Dot,
+    /// One JSON document; see [`JsonWriter`].
+    Json,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tsv" => Some(OutputFormat::Tsv),
+            "obo" => Some(OutputFormat::Obo),
+            "dot" => Some(OutputFormat::Dot),
+            "json" => Some(OutputFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Per-concept writer for this format, or `None` for formats that are
+    /// driven by re-reading the source document instead (`obo`).
+    pub fn writer(self) -> Option<Box<dyn FormatWriter>> {
+        match self {
+            OutputFormat::Tsv => Some(Box::new(TsvWriter)),
+            OutputFormat::Obo => None,
+            OutputFormat::Dot => Some(Box::new(DotWriter::default())),
+            OutputFormat::Json => Some(Box::new(JsonWriter::default())),
+        }
+    }
+}
+
+/// One `concept<TAB>direct_parent` row per edge of the reduced hierarchy,
+/// followed by `min_depth<TAB>max_depth` of the concept when the view has
+/// depths.
+pub struct TsvWriter;
+
+impl FormatWriter for TsvWriter {
+    fn header(&mut self, out: &mut dyn Write, view: &ClassificationView) -> io::Result<()> {
+        match view.depths {
+            Some(_) => writeln!(out, "concept\tdirect_parent\tmin_depth\tmax_depth"),
+            None => writeln!(out, "concept\tdirect_parent"),
+        }
+    }
+
+    fn concept(
+        &mut self,
+        out: &mut dyn Write,
+        view: &ClassificationView,
+        c: ConceptId,
+    ) -> io::Result<()> {
+        let name = view.name(c);
+        for &p in &view.direct_parents[c as usize] {
+            match view.depths {
+                Some(depths) => {
+                    let d = depths[c as usize];
+                    writeln!(out, "{}\t{}\t{}\t{}", name, view.name(p), d.min, d.max)?
+                }
+                None => writeln!(out, "{}\t{}", name, view.name(p))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A GraphViz `digraph` with an edge from each concept to each direct
+/// parent, drawn parents on top. Nodes show the concept ID and, when the
+/// view has labels, its label; edges to TOP are left out.
+#[derive(Default)]
+pub struct DotWriter {
+    /// Concepts to draw; all when `None`.
+    keep: Option<Vec<bool>>,
+}
+
+impl DotWriter {
+    /// Draws only `root` and its descendants.
+    pub fn subtree(taxonomy: &Taxonomy, root: ConceptId) -> Self {
+        let mut keep = vec![false; taxonomy.len()];
+        for c in taxonomy.descendants(root).into_iter().chain([root]) {
+            keep[c as usize] = true;
+        }
+        Self { keep: Some(keep) }
+    }
+
+    fn keeps(&self, c: ConceptId) -> bool {
+        self.keep.as_ref().is_none_or(|keep| keep[c as usize])
+    }
+}
+
+/// `text` for the inside of a double-quoted DOT string.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl FormatWriter for DotWriter {
+    fn header(&mut self, out: &mut dyn Write, _view: &ClassificationView) -> io::Result<()> {
+        writeln!(out, "digraph taxonomy {{")?;
+        writeln!(out, "  rankdir=BT;")?;
+        writeln!(out, "  node [shape=box];")
+    }
+
+    fn concept(
+        &mut self,
+        out: &mut dyn Write,
+        view: &ClassificationView,
+        c: ConceptId,
+    ) -> io::Result<()> {
+        if !self.keeps(c) {
+            return Ok(());
+        }
+        let name = dot_escape(view.name(c));
+        match view.labels.and_then(|labels| labels.get(&(c as usize))) {
+            Some(label) => writeln!(out, "  \"{0}\" [label=\"{0}\\n{1}\"];", name, dot_escape(label))?,
+            None => writeln!(out, "  \"{}\";", name)?,
+        }
+        for &p in &view.direct_parents[c as usize] {
+            if p != TOP && self.keeps(p) {
+                writeln!(out, "  \"{}\" -> \"{}\";", name, dot_escape(view.name(p)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn footer(&mut self, out: &mut dyn Write, _view: &ClassificationView) -> io::Result<()> {
+        writeln!(out, "}}")
+    }
+}
+
+/// A JSON object whose `concepts` member lists every concept as
+/// `{"id", "label", "parents", "min_depth", "max_depth"}`, label and depths
+/// only when the view has them, one concept per line; the summary members
+/// (equivalence classes, timings, …) follow it.
+#[derive(Default)]
+pub struct JsonWriter {
+    summary: Vec<(String, Value)>,
+    written: usize,
+}
+
+impl JsonWriter {
+    /// Ends the document with `summary`'s members after `concepts`.
+    pub fn with_summary(summary: Vec<(String, Value)>) -> Self {
+        Self { summary, written: 0 }
+    }
+}
+
+impl FormatWriter for JsonWriter {
+    fn header(&mut self, out: &mut dyn Write, _view: &ClassificationView) -> io::Result<()> {
+        write!(out, "{{\"concepts\":[")
+    }
+
+    fn concept(
+        &mut self,
+        out: &mut dyn Write,
+        view: &ClassificationView,
+        c: ConceptId,
+    ) -> io::Result<()> {
+        let mut members = vec![("id".to_string(), Value::from(view.name(c)))];
+        if let Some(label) = view.labels.and_then(|labels| labels.get(&(c as usize))) {
+            members.push(("label".to_string(), Value::from(label.as_str())));
+        }
+        let parents = view.direct_parents[c as usize].iter().map(|&p| Value::from(view.name(p)));
+        members.push(("parents".to_string(), Value::Array(parents.collect())));
+        if let Some(depths) = view.depths {
+            let d = depths[c as usize];
+            members.push(("min_depth".to_string(), Value::from(d.min as usize)));
+            members.push(("max_depth".to_string(), Value::from(d.max as usize)));
+        }
+        let separator = if self.written == 0 { "" } else { "," };
+        self.written += 1;
+        write!(out, "{}\n{}", separator, Value::Object(members))
+    }
+
+    fn footer(&mut self, out: &mut dyn Write, _view: &ClassificationView) -> io::Result<()> {
+        write!(out, "\n]")?;
+        for (key, value) in &self.summary {
+            let mut member = String::from(",\n");
+            json::write_escaped(&mut member, key).expect("writing to a String");
+            write!(out, "{}:{}", member, value)?;
+        }
+        writeln!(out, "}}")
+    }
+}
+
+/// Opens `path` (or stdout for `-`) as a chunked output sink.
+pub fn open_sink(path: &Path) -> io::Result<BufWriter<Box<dyn Write>>> {
+    let inner: Box<dyn Write> = if path.as_os_str() == "-" {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(File::create(path)?)
+    };
+    Ok(BufWriter::with_capacity(OUTPUT_CHUNK, inner))
+}
+
+/// Streams `view` through `format` into `sink`, flushing at the end.
+pub fn write_classification<W: Write>(
+    format: &mut dyn FormatWriter,
+    view: &ClassificationView,
+    sink: &mut W,
+) -> io::Result<()> {
+    format.header(sink, view)?;
+    for c in view.concept_ids() {
+        format.concept(sink, view, c)?;
+    }
+    format.footer(sink, view)?;
+    sink.flush()
+}
+
+/// Writes `view` as a GraphViz digraph, only `root` and its descendants in
+/// `taxonomy` when a root is given.
+pub fn write_dot<W: Write>(
+    view: &ClassificationView,
+    taxonomy: &Taxonomy,
+    root: Option<ConceptId>,
+    sink: &mut W,
+) -> io::Result<()> {
+    let mut writer = match root {
+        Some(root) => DotWriter::subtree(taxonomy, root),
+        None => DotWriter::default(),
+    };
+    write_classification(&mut writer, view, sink)
+}
@@ -0,0 +1,198 @@
+//! EL approximations of class axioms outside EL, for
+//! [`crate::profile::Violation::approximation`].
+//!
+//! The right of `sub ⊑ sup` is replaced by an EL expression that contains
+//! it and the left by EL expressions it contains, so every axiom produced
+//! is entailed by the original: `A ⊑ B ⊓ ∀r.C` keeps `A ⊑ B`,
+//! `A ⊔ B ⊑ C` becomes `A ⊑ C` and `B ⊑ C`, and `A ⊑ B ⊔ C` keeps what
+//! `B` and `C` have in common.
+//!
+//! A complement `¬X` inside an expression becomes a fresh concept `N` with
+//! `N ⊓ X ⊑ ⊥`. Reading `N` as `¬X` satisfies every axiom made with it, so
+//! nothing is entailed about the named concepts that the original axioms
+//! don't entail, and `A ⊑ ¬X` and `¬X ⊑ B` still give `A ⊑ B`.
+
+use alloc::boxed::Box;
+
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::{ConceptId, RoleId, BOTTOM, TOP};
+
+/// Left-hand alternatives kept from one expression; more are dropped,
+/// which only loses axioms.
+const MAX_ALTERNATIVES: usize = 16;
+
+/// An OWL class expression, as far as approximation cares.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Owl {
+    El(ClassExpr),
+    And(Vec<Owl>),
+    Or(Vec<Owl>),
+    Not(Box<Owl>),
+    /// `∃role.filler`; `None` for an inverse or otherwise unsupported role.
+    Some(Option<RoleId>, Box<Owl>),
+    /// `≥n role.filler`.
+    Min(u32, Option<RoleId>, Box<Owl>),
+    /// Universal and maximum restrictions and the like: nothing EL can
+    /// express on either side.
+    Other,
+}
+
+/// Fresh concepts standing for complements, one per complemented
+/// expression of a document.
+pub(super) trait Complements {
+    /// The concept for `¬x`, and whether it was made just now.
+    fn complement(&mut self, x: &Owl) -> (ConceptId, bool);
+}
+
+/// One approximation in progress: the complements it may use and the
+/// disjointness axioms of those new to the document.
+struct Approximation<'c> {
+    complements: &'c mut dyn Complements,
+    axioms: Vec<ClassAxiom>,
+}
+
+impl Approximation<'_> {
+    /// The concept for `¬x`, disjoint from every EL expression `x` contains.
+    fn complement(&mut self, x: &Owl) -> ClassExpr {
+        let (n, new) = self.complements.complement(x);
+        if new {
+            for s in self.strengthen(x) {
+                let both = ClassExpr::And(vec![ClassExpr::Named(n), s]);
+                self.axioms.push(ClassAxiom::SubClassOf(both, ClassExpr::Named(BOTTOM)));
+            }
+        }
+        ClassExpr::Named(n)
+    }
+
+    /// An EL expression containing `e`.
+    fn weaken(&mut self, e: &Owl) -> ClassExpr {
+        match e {
+            Owl::El(e) => e.clone(),
+            Owl::And(parts) => conjunction(parts.iter().map(|p| self.weaken(p)).collect()),
+            Owl::Or(parts) => {
+                let mut parts = parts.iter().map(|p| self.weaken(p));
+                let first = parts.next().unwrap_or(ClassExpr::Named(TOP));
+                parts.fold(first, |common, part| in_common(&common, &part))
+            }
+            Owl::Not(x) => self.complement(x),
+            Owl::Some(Some(r), filler) | Owl::Min(1.., Some(r), filler) => {
+                ClassExpr::Some(*r, Box::new(self.weaken(filler)))
+            }
+            _ => ClassExpr::Named(TOP),
+        }
+    }
+
+    /// EL expressions contained in `e`, none of them when EL can't express
+    /// any part of it.
+    fn strengthen(&mut self, e: &Owl) -> Vec<ClassExpr> {
+        let mut out = match e {
+            Owl::El(e) => vec![e.clone()],
+            Owl::Or(parts) => parts.iter().flat_map(|p| self.strengthen(p)).collect(),
+            Owl::And(parts) => {
+                let mut combinations = vec![Vec::new()];
+                for part in parts {
+                    let alternatives = self.strengthen(part);
+                    combinations = combinations
+                        .iter()
+                        .flat_map(|c| alternatives.iter().map(move |a| [c.clone(), vec![a.clone()]].concat()))
+                        .take(MAX_ALTERNATIVES)
+                        .collect();
+                }
+                combinations
+                    .into_iter()
+                    .map(|mut c| if c.len() == 1 { c.remove(0) } else { ClassExpr::And(c) })
+                    .collect()
+            }
+            Owl::Not(x) => vec![self.complement(x)],
+            Owl::Some(Some(r), filler) | Owl::Min(1, Some(r), filler) => {
+                self.strengthen(filler).into_iter().map(|f| ClassExpr::Some(*r, Box::new(f))).collect()
+            }
+            Owl::Min(0, ..) => vec![ClassExpr::Named(TOP)],
+            _ => Vec::new(),
+        };
+        out.truncate(MAX_ALTERNATIVES);
+        out
+    }
+
+    fn subclass_of(&mut self, sub: &Owl, sup: &Owl) {
+        let subs = self.strengthen(sub);
+        let conjuncts = match sup {
+            Owl::And(parts) => parts.iter().collect(),
+            sup => vec![sup],
+        };
+        for conjunct in conjuncts {
+            let sup = self.weaken(conjunct);
+            if sup != ClassExpr::Named(TOP) {
+                self.axioms.extend(subs.iter().map(|s| ClassAxiom::SubClassOf(s.clone(), sup.clone())));
+            }
+        }
+    }
+}
+
+/// `parts` as one expression, without TOP unless nothing else is left.
+fn conjunction(parts: Vec<ClassExpr>) -> ClassExpr {
+    let top = ClassExpr::Named(TOP);
+    let mut parts: Vec<ClassExpr> = parts.into_iter().filter(|p| *p != top).collect();
+    match parts.len() {
+        0 => top,
+        1 => parts.remove(0),
+        _ => ClassExpr::And(parts),
+    }
+}
+
+/// An EL expression containing both `a` and `b`, from the conjuncts they
+/// share: named ones as they are, existentials along the same role with
+/// what their fillers have in common.
+fn in_common(a: &ClassExpr, b: &ClassExpr) -> ClassExpr {
+    let theirs = b.conjuncts();
+    let mut common: Vec<ClassExpr> = Vec::new();
+    for ours in a.conjuncts() {
+        let shared: Vec<ClassExpr> = match ours {
+            ClassExpr::Named(_) => theirs.iter().filter(|&&t| t == ours).map(|&t| t.clone()).collect(),
+            ClassExpr::Some(r, f) => theirs
+                .iter()
+                .filter_map(|t| match t {
+                    ClassExpr::Some(s, g) if s == r => Some(ClassExpr::Some(*r, Box::new(in_common(f, g)))),
+                    _ => None,
+                })
+                .collect(),
+            ClassExpr::And(_) => Vec::new(),
+        };
+        for e in shared {
+            if !common.contains(&e) {
+                common.push(e);
+            }
+        }
+    }
+    conjunction(common)
+}
+
+fn approximate(complements: &mut dyn Complements, f: impl FnOnce(&mut Approximation<'_>)) -> Vec<ClassAxiom> {
+    let mut approximation = Approximation { complements, axioms: Vec::new() };
+    f(&mut approximation);
+    approximation.axioms
+}
+
+/// EL axioms entailed by `sub ⊑ sup`.
+pub(super) fn subclass_of(sub: &Owl, sup: &Owl, complements: &mut dyn Complements) -> Vec<ClassAxiom> {
+    approximate(complements, |a| a.subclass_of(sub, sup))
+}
+
+/// EL axioms entailed by `a ≡ b`.
+pub(super) fn equivalent(a: &Owl, b: &Owl, complements: &mut dyn Complements) -> Vec<ClassAxiom> {
+    approximate(complements, |x| {
+        x.subclass_of(a, b);
+        x.subclass_of(b, a);
+    })
+}
+
+/// EL axioms entailed by the pairwise disjointness of `classes`.
+pub(super) fn disjoint(classes: &[Owl], complements: &mut dyn Complements) -> Vec<ClassAxiom> {
+    approximate(complements, |x| {
+        for (i, a) in classes.iter().enumerate() {
+            for b in &classes[i + 1..] {
+                x.subclass_of(a, &Owl::Not(Box::new(b.clone())));
+            }
+        }
+    })
+}
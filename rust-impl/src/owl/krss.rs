@@ -0,0 +1,368 @@
+//! KRSS / KRSS2 front-end: the Lisp-style syntax of the classic DL
+//! benchmark suites and of SNOMED CT's KRSS distribution.
+//!
+//! Like the functional-syntax reader, each top-level form becomes axioms
+//! before the next is read. Names are kept as written, with `|…|` quotes
+//! stripped; keywords match in any case. Forms and constructors outside
+//! the EL fragment are skipped with a warning.
+
+use std::io::{self, BufRead};
+
+use super::approximate::{self, Owl};
+use super::Builder;
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::obo::{Location, ParseError, ParseResult, Warning, WarningKind};
+use crate::resolve::IdResolver;
+use crate::{ConceptId, RoleId, BOTTOM, TOP};
+
+#[derive(Clone, Debug)]
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    /// The keyword heading a list, lowercased.
+    fn head(&self) -> Option<String> {
+        match self {
+            Sexp::List(items) => match items.first() {
+                Some(Sexp::Atom(head)) => Some(head.to_ascii_lowercase()),
+                _ => None,
+            },
+            Sexp::Atom(_) => None,
+        }
+    }
+
+    fn keyword(&self) -> Option<String> {
+        match self {
+            Sexp::Atom(atom) => Some(atom.to_ascii_lowercase()),
+            Sexp::List(_) => None,
+        }
+    }
+}
+
+struct Reader<'r> {
+    builder: Builder<'r>,
+}
+
+impl Reader<'_> {
+    fn role(&mut self, sexp: &Sexp) -> Result<RoleId, String> {
+        match sexp {
+            Sexp::Atom(name) => Ok(self.builder.role(name) as RoleId),
+            list => Err(list.head().unwrap_or_else(|| "a nested list".to_string())),
+        }
+    }
+
+    fn concept(&mut self, name: &str) -> ConceptId {
+        match name.to_ascii_lowercase().as_str() {
+            "top" | "*top*" | "thing" => TOP,
+            "bottom" | "*bottom*" => BOTTOM,
+            _ => self.builder.concept(name) as ConceptId,
+        }
+    }
+
+    /// The class expression of `sexp`, or the name of the first constructor
+    /// in it that the reasoner doesn't support.
+    fn class(&mut self, sexp: &Sexp) -> Result<ClassExpr, String> {
+        let items = match sexp {
+            Sexp::Atom(name) => return Ok(ClassExpr::Named(self.concept(name))),
+            Sexp::List(items) => items,
+        };
+        let head = sexp.head().unwrap_or_else(|| "a nested list".to_string());
+        match (head.as_str(), items.get(1..).unwrap_or_default()) {
+            ("and", [part]) => self.class(part),
+            ("and", parts) if !parts.is_empty() => {
+                Ok(ClassExpr::And(parts.iter().map(|p| self.class(p)).collect::<Result<_, _>>()?))
+            }
+            ("some", [role, filler]) => {
+                let role = self.role(role)?;
+                Ok(ClassExpr::Some(role, Box::new(self.class(filler)?)))
+            }
+            _ => Err(head),
+        }
+    }
+
+    /// `sexp` for approximation: EL where [`Reader::class`] reads it.
+    fn owl(&mut self, sexp: &Sexp) -> Owl {
+        if let Ok(e) = self.class(sexp) {
+            return Owl::El(e);
+        }
+        let Sexp::List(items) = sexp else {
+            return Owl::Other;
+        };
+        match (sexp.head().as_deref(), items.get(1..).unwrap_or_default()) {
+            (Some("and"), parts) => Owl::And(parts.iter().map(|p| self.owl(p)).collect()),
+            (Some("or"), parts) => Owl::Or(parts.iter().map(|p| self.owl(p)).collect()),
+            (Some("not"), [x]) => Owl::Not(Box::new(self.owl(x))),
+            (Some("some"), [role, filler]) => Owl::Some(self.role(role).ok(), Box::new(self.owl(filler))),
+            (Some(head @ ("at-least" | "exactly")), [Sexp::Atom(n), role, rest @ ..]) => {
+                let filler = match rest {
+                    [filler] => self.owl(filler),
+                    _ => Owl::El(ClassExpr::Named(TOP)),
+                };
+                let min = Owl::Min(n.parse().unwrap_or(0), self.role(role).ok(), Box::new(filler));
+                match head {
+                    "at-least" => min,
+                    _ => Owl::And(vec![min, Owl::Other]),
+                }
+            }
+            _ => Owl::Other,
+        }
+    }
+
+    /// EL axioms entailed by the form `head`, which is outside EL.
+    fn approximate(&mut self, head: &str, args: &[Sexp]) -> Vec<ClassAxiom> {
+        let members: Vec<Owl> = args.iter().map(|a| self.owl(a)).collect();
+        match (head, &members[..]) {
+            ("define-primitive-concept" | "implies", [sub, sup]) => approximate::subclass_of(sub, sup, &mut self.builder),
+            ("define-concept" | "equivalent", [a, b]) => approximate::equivalent(a, b, &mut self.builder),
+            ("disjoint", members) => approximate::disjoint(members, &mut self.builder),
+            _ => Vec::new(),
+        }
+    }
+
+    fn skip(&mut self, at: Location, message: String) {
+        self.builder.warn(WarningKind::SkippedTag, at, message);
+    }
+
+    fn form(&mut self, items: Vec<Sexp>, at: Location) {
+        let Some(Sexp::Atom(head)) = items.first() else {
+            return self.skip(at, "a form without a keyword".to_string());
+        };
+        let head = head.to_ascii_lowercase();
+        let args = &items[1..];
+        if let Err(construct) = self.logical_form(&head, args, at) {
+            self.skip(at, format!("{} in {} is not supported", construct, head));
+            let approximation = self.approximate(&head, args);
+            self.builder.violation(&construct, at, approximation);
+        }
+    }
+
+    fn logical_form(&mut self, head: &str, args: &[Sexp], at: Location) -> Result<(), String> {
+        match (head, args) {
+            ("define-primitive-concept", [Sexp::Atom(name), rest @ ..]) if rest.len() <= 1 => {
+                let sup = rest.first().map(|sup| self.class(sup)).transpose()?;
+                let c = self.concept(name);
+                self.builder.result.declared.insert(c as usize);
+                if let Some(sup) = sup {
+                    self.builder.subclass_of(ClassExpr::Named(c), sup);
+                }
+            }
+            ("define-concept", [Sexp::Atom(name), definition]) => {
+                let definition = self.class(definition)?;
+                let c = self.concept(name);
+                self.builder.result.declared.insert(c as usize);
+                self.builder.equivalent(ClassExpr::Named(c), definition);
+            }
+            ("implies", [sub, sup]) => {
+                let (sub, sup) = (self.class(sub)?, self.class(sup)?);
+                self.builder.subclass_of(sub, sup);
+            }
+            ("equivalent", [a, b]) => {
+                let (a, b) = (self.class(a)?, self.class(b)?);
+                // Relate the definition to the named side when there is one.
+                match (a, b) {
+                    (a, b @ ClassExpr::Named(_)) => self.builder.equivalent(b, a),
+                    (a, b) => self.builder.equivalent(a, b),
+                }
+            }
+            ("disjoint", members) if members.len() >= 2 => {
+                let classes = members.iter().map(|m| self.class(m)).collect::<Result<_, _>>()?;
+                self.builder.disjoint(classes);
+            }
+            ("define-primitive-role" | "define-primitive-attribute", [Sexp::Atom(name), options @ ..]) => {
+                let r = self.builder.role(name);
+                self.role_options(r, options)?;
+                if head == "define-primitive-attribute" {
+                    return Err("a functional role".to_string());
+                }
+            }
+            ("implies-role" | "role-inclusion", [sub, sup]) => {
+                let sup = self.role(sup)? as usize;
+                match sub {
+                    Sexp::List(items) if sub.head().as_deref() == Some("compose") => {
+                        let [_, first, second] = &items[..] else {
+                            return Err(format!("a chain of {} roles", items.len() - 1));
+                        };
+                        let (first, second) = (self.role(first)? as usize, self.role(second)? as usize);
+                        self.builder.result.role_chains.push((first, second, sup));
+                    }
+                    sub => {
+                        let sub = self.role(sub)? as usize;
+                        self.builder.result.role_subsumptions.push((sub, sup));
+                    }
+                }
+            }
+            ("transitive", [role]) => {
+                let r = self.role(role)? as usize;
+                self.builder.result.role_chains.push((r, r, r));
+            }
+            ("reflexive", [role]) => {
+                let r = self.role(role)? as usize;
+                self.builder.result.reflexive_roles.push(r);
+            }
+            ("instance", [Sexp::Atom(individual), class]) => {
+                let ClassExpr::Named(c) = self.class(class)? else {
+                    // Complex types would need a concept of their own per assertion.
+                    return Err(class.head().unwrap_or_default());
+                };
+                let a = self.builder.individual(individual);
+                self.builder.class_assertion(a, c as usize);
+            }
+            ("related", [Sexp::Atom(subject), Sexp::Atom(object), role]) => {
+                let role = self.role(role)? as usize;
+                let (a, b) = (self.builder.individual(subject), self.builder.individual(object));
+                self.builder.role_assertion(a, role, b);
+            }
+            _ => {
+                let message = format!("{} forms are not supported", head);
+                self.builder.warn(WarningKind::SkippedTag, at, message);
+                self.builder.violation(head, at, Vec::new());
+            }
+        }
+        Ok(())
+    }
+
+    /// The `:keyword value` options of `define-primitive-role` for `r`.
+    fn role_options(&mut self, r: usize, options: &[Sexp]) -> Result<(), String> {
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            let Some(keyword) = option.keyword() else {
+                return Err("an option without a keyword".to_string());
+            };
+            let Some(value) = options.next() else {
+                return Err(format!("{} without a value", keyword));
+            };
+            let set = || matches!(value.keyword().as_deref(), Some("t" | "true"));
+            match keyword.as_str() {
+                ":parent" => {
+                    let s = self.role(value)? as usize;
+                    self.builder.result.role_subsumptions.push((r, s));
+                }
+                ":parents" => {
+                    let parents = match value {
+                        Sexp::List(parents) => parents.as_slice(),
+                        atom => core::slice::from_ref(atom),
+                    };
+                    for parent in parents {
+                        let s = self.role(parent)? as usize;
+                        self.builder.result.role_subsumptions.push((r, s));
+                    }
+                }
+                ":transitive" if set() => self.builder.result.role_chains.push((r, r, r)),
+                ":reflexive" if set() => self.builder.result.reflexive_roles.push(r),
+                ":transitive" | ":reflexive" => {}
+                ":right-identity" => {
+                    let s = self.role(value)? as usize;
+                    self.builder.result.role_chains.push((r, s, r));
+                }
+                ":left-identity" => {
+                    let s = self.role(value)? as usize;
+                    self.builder.result.role_chains.push((s, r, r));
+                }
+                ":domain" => match self.class(value)? {
+                    ClassExpr::Named(c) => self.builder.result.role_domains.push((r, c as usize)),
+                    class => {
+                        let some = ClassExpr::Some(r as RoleId, Box::new(ClassExpr::Named(TOP)));
+                        self.builder.subclass_of(some, class);
+                    }
+                },
+                ":range" => match self.class(value)? {
+                    ClassExpr::Named(c) => self.builder.result.role_ranges.push((r, c as usize)),
+                    // A complex range would need a concept of its own.
+                    _ => return Err("a complex range".to_string()),
+                },
+                ":feature" | ":functional" if set() => return Err("a functional role".to_string()),
+                ":feature" | ":functional" => {}
+                keyword => return Err(keyword.to_string()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the EL fragment of a KRSS document. Everything skipped is
+/// reported in the returned warnings.
+pub fn parse_krss<R: BufRead>(
+    mut input: R,
+    resolver: &dyn IdResolver,
+) -> Result<(ParseResult, Vec<Warning>), ParseError> {
+    let mut reader = Reader { builder: Builder::new(resolver) };
+    let malformed = |reader: &mut Reader, location, message: &str| {
+        reader.builder.warn(WarningKind::MalformedLine, location, message.to_string());
+    };
+    // Lists being read, innermost last, each with where it opened.
+    let mut open: Vec<(Vec<Sexp>, Location)> = Vec::new();
+    let mut line = String::new();
+    let mut line_no = 0;
+    loop {
+        line.clear();
+        match input.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => line_no += 1,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                line_no += 1;
+                malformed(&mut reader, Location { line: line_no, column: 1 }, &format!("unreadable line: {}", e));
+                continue;
+            }
+            Err(source) => return Err(ParseError { location: Location { line: line_no + 1, column: 1 }, source }),
+        }
+
+        let mut chars = line.char_indices().peekable();
+        let mut column = 0;
+        while let Some((i, c)) = chars.next() {
+            column += 1;
+            let at = Location { line: line_no, column };
+            let atom = match c {
+                ';' => break,
+                c if c.is_whitespace() => continue,
+                '(' => {
+                    open.push((Vec::new(), at));
+                    continue;
+                }
+                ')' => {
+                    let Some((items, opened)) = open.pop() else {
+                        malformed(&mut reader, at, "unbalanced parentheses");
+                        continue;
+                    };
+                    match open.last_mut() {
+                        Some((parent, _)) => parent.push(Sexp::List(items)),
+                        None => reader.form(items, opened),
+                    }
+                    continue;
+                }
+                '|' | '"' => {
+                    let Some(len) = line[i + 1..].find(c) else {
+                        malformed(&mut reader, at, "unterminated name");
+                        break;
+                    };
+                    let name = line[i + 1..i + 1 + len].to_string();
+                    while chars.next_if(|&(j, _)| j <= i + 1 + len).is_some() {
+                        column += 1;
+                    }
+                    name
+                }
+                _ => {
+                    let mut end = line.len();
+                    while let Some(&(j, c)) = chars.peek() {
+                        if c.is_whitespace() || matches!(c, '(' | ')' | ';' | '|' | '"') {
+                            end = j;
+                            break;
+                        }
+                        chars.next();
+                        column += 1;
+                    }
+                    line[i..end].to_string()
+                }
+            };
+            match open.last_mut() {
+                Some((items, _)) => items.push(Sexp::Atom(atom)),
+                None => malformed(&mut reader, at, &format!("unexpected '{}' outside a form", atom)),
+            }
+        }
+    }
+    if let Some(&(_, opened)) = open.first() {
+        malformed(&mut reader, opened, "unbalanced parentheses");
+    }
+    Ok(reader.builder.finish())
+}
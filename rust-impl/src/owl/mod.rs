@@ -0,0 +1,244 @@
+//! OWL front-ends. Each reads the EL fragment of one OWL syntax, or of
+//! KRSS, into the same [`ParseResult`] the OBO parser produces, so the rest
+//! of the pipeline doesn't care where an ontology came from.
+
+mod approximate;
+pub mod krss;
+pub mod ofn;
+#[cfg(feature = "serve")]
+pub mod owllink;
+mod rdf;
+pub mod rdfxml;
+pub mod turtle;
+mod xml;
+
+pub use krss::parse_krss;
+pub use ofn::{parse_ofn, write_ofn, write_ofn_with_inferred};
+pub use rdfxml::parse_rdfxml;
+pub use turtle::parse_turtle;
+
+pub use crate::resolve::{OWL, RDF, RDFS, XSD};
+
+/// IAO "term replaced by", the OWL form of OBO's `replaced_by:`.
+pub(crate) const REPLACED_BY: &str = "http://purl.obolibrary.org/obo/IAO_0100001";
+/// OBO's `consider:` in OWL.
+pub(crate) const CONSIDER: &str = "http://www.geneontology.org/formats/oboInOwl#consider";
+
+use std::collections::HashMap;
+
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::obo::{Location, ParseResult, Warning, WarningKind};
+use crate::profile::Violation;
+use crate::resolve::{CurieMap, IdResolver};
+use crate::{ConceptId, BOTTOM, TOP};
+
+
+/// Interns the IRIs of an OWL document into a [`ParseResult`].
+///
+/// OBO PURLs are contracted to CURIEs (`GO:0008150`) before `resolver`
+/// sees them, so OWL and OBO releases of an ontology share identifiers;
+/// other IRIs are kept whole. `owl:Thing` and `owl:Nothing` map to TOP and
+/// BOTTOM.
+pub(crate) struct Builder<'r> {
+    pub result: ParseResult,
+    pub warnings: Vec<Warning>,
+    resolver: &'r dyn IdResolver,
+    purls: CurieMap,
+    role_idx: HashMap<String, usize>,
+    /// Annotations whose subject may turn out to be a class or a property.
+    labels: Vec<(String, String)>,
+    deprecated: Vec<String>,
+    /// `(subject, replacement, replaced_by)`: `replaced_by` or `consider`.
+    replacements: Vec<(String, String, bool)>,
+    /// Complemented expressions and the unnamed concepts standing for them.
+    complements: Vec<(approximate::Owl, usize)>,
+}
+
+impl approximate::Complements for Builder<'_> {
+    fn complement(&mut self, x: &approximate::Owl) -> (ConceptId, bool) {
+        if let Some(&(_, n)) = self.complements.iter().find(|(y, _)| y == x) {
+            return (n as ConceptId, false);
+        }
+        // Unnamed, so it stays out of the output and never meets a named
+        // concept of another document.
+        self.result.concepts.push(String::new());
+        let n = self.result.concepts.len() - 1;
+        self.complements.push((x.clone(), n));
+        (n as ConceptId, true)
+    }
+}
+
+impl<'r> Builder<'r> {
+    pub fn new(resolver: &'r dyn IdResolver) -> Self {
+        Self {
+            result: ParseResult::new(),
+            warnings: Vec::new(),
+            resolver,
+            purls: CurieMap::obo(),
+            role_idx: HashMap::new(),
+            labels: Vec::new(),
+            deprecated: Vec::new(),
+            replacements: Vec::new(),
+            complements: Vec::new(),
+        }
+    }
+
+    pub fn warn(&mut self, kind: WarningKind, location: Location, message: String) {
+        self.warnings.push(Warning { kind, location, message });
+    }
+
+    /// Records a prefix the document declares; names stay as resolved.
+    pub fn prefix(&mut self, prefix: &str, base: &str) {
+        self.result.prefixes.add_prefix(prefix, base);
+    }
+
+    /// Records an axiom left out for `construct`, after its warning.
+    pub fn violation(&mut self, construct: &str, location: Location, approximation: Vec<ClassAxiom>) {
+        let construct = construct.to_string();
+        self.result.violations.push(Violation { construct, location, approximation });
+    }
+
+    fn name(&self, iri: &str) -> String {
+        let curie = self.purls.resolve(iri);
+        self.resolver.resolve(&curie).into_owned()
+    }
+
+    pub fn concept(&mut self, iri: &str) -> usize {
+        match iri.strip_prefix(OWL) {
+            Some("Thing") => return TOP as usize,
+            Some("Nothing") => return BOTTOM as usize,
+            _ => {}
+        }
+        let name = self.name(iri);
+        let result = &mut self.result;
+        *result.concept_idx.entry(name).or_insert_with_key(|name| {
+            result.concepts.push(name.clone());
+            result.concepts.len() - 1
+        })
+    }
+
+    pub fn role(&mut self, iri: &str) -> usize {
+        let name = self.name(iri);
+        let roles = &mut self.result.roles;
+        *self.role_idx.entry(name).or_insert_with_key(|name| {
+            roles.push(name.clone());
+            roles.len() - 1
+        })
+    }
+
+    pub fn individual(&mut self, iri: &str) -> usize {
+        let name = self.name(iri);
+        self.result.individual(&name)
+    }
+
+    /// The nominal `{iri}`.
+    pub fn nominal(&mut self, iri: &str) -> usize {
+        let a = self.individual(iri);
+        self.result.nominal(a)
+    }
+
+    pub fn class_assertion(&mut self, individual: usize, class: usize) {
+        self.result.class_assertions.push((individual, class));
+    }
+
+    pub fn role_assertion(&mut self, subject: usize, role: usize, object: usize) {
+        self.result.role_assertions.push((subject, role, object));
+    }
+
+    pub fn label(&mut self, subject: &str, label: &str) {
+        self.labels.push((subject.to_string(), label.to_string()));
+    }
+
+    pub fn deprecate(&mut self, subject: &str) {
+        self.deprecated.push(subject.to_string());
+    }
+
+    /// Records a `REPLACED_BY` or `CONSIDER` annotation; `target` is an IRI
+    /// or a CURIE.
+    pub fn replacement(&mut self, subject: &str, property: &str, target: &str) {
+        let replaced_by = match property {
+            REPLACED_BY => true,
+            CONSIDER => false,
+            _ => return,
+        };
+        self.replacements.push((subject.to_string(), target.to_string(), replaced_by));
+    }
+
+    /// Records `sub ⊑ sup`, in the told tables when it fits them.
+    pub fn subclass_of(&mut self, sub: ClassExpr, sup: ClassExpr) {
+        let ClassExpr::Named(a) = sub else {
+            return self.result.class_axioms.push(ClassAxiom::SubClassOf(sub, sup));
+        };
+        let a = a as usize;
+        let sup = match sup {
+            ClassExpr::Named(b) => return self.result.subsumptions.push((a, b as usize)),
+            ClassExpr::Some(r, filler) => match *filler {
+                ClassExpr::Named(f) => {
+                    return self.result.relations.push((a, r as usize, f as usize))
+                }
+                filler => ClassExpr::Some(r, Box::new(filler)),
+            },
+            sup => sup,
+        };
+        self.result.class_axioms.push(ClassAxiom::SubClassOf(ClassExpr::Named(a as ConceptId), sup));
+    }
+
+    /// Records `a ≡ b`.
+    pub fn equivalent(&mut self, a: ClassExpr, b: ClassExpr) {
+        match (a, b) {
+            (ClassExpr::Named(a), ClassExpr::Named(b)) => {
+                self.result.subsumptions.push((a as usize, b as usize));
+                self.result.subsumptions.push((b as usize, a as usize));
+            }
+            (a, b) => self.result.class_axioms.push(ClassAxiom::Equivalent(a, b)),
+        }
+    }
+
+    /// Records that `classes` are pairwise disjoint.
+    pub fn disjoint(&mut self, classes: Vec<ClassExpr>) {
+        for (i, a) in classes.iter().enumerate() {
+            for b in &classes[i + 1..] {
+                match (a, b) {
+                    (ClassExpr::Named(a), ClassExpr::Named(b)) => {
+                        self.result.disjoint_pairs.push((*a as usize, *b as usize))
+                    }
+                    (a, b) => {
+                        let both = ClassExpr::And(vec![a.clone(), b.clone()]);
+                        let axiom = ClassAxiom::SubClassOf(both, ClassExpr::Named(BOTTOM));
+                        self.result.class_axioms.push(axiom);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolves the annotations collected along the way and returns the
+    /// parse result, with warnings in document order.
+    pub fn finish(mut self) -> (ParseResult, Vec<Warning>) {
+        for (subject, label) in std::mem::take(&mut self.labels) {
+            let name = self.name(&subject);
+            if let Some(&c) = self.result.concept_idx.get(&name) {
+                self.result.labels.insert(c, label);
+            } else if let Some(&r) = self.role_idx.get(&name) {
+                self.result.role_labels.insert(r, label);
+            } else if let Some(&i) = self.result.individual_idx.get(&name) {
+                self.result.individual_labels.insert(i, label);
+            }
+        }
+        for subject in std::mem::take(&mut self.deprecated) {
+            if let Some(&c) = self.result.concept_idx.get(&self.name(&subject)) {
+                self.result.obsolete.insert(c);
+            }
+        }
+        for (subject, target, replaced_by) in std::mem::take(&mut self.replacements) {
+            let Some(&c) = self.result.concept_idx.get(&self.name(&subject)) else { continue };
+            let target = self.name(&target);
+            match replaced_by {
+                true => self.result.replaced_by.push((c, target)),
+                false => self.result.consider.push((c, target)),
+            }
+        }
+        self.warnings.sort_by_key(|w| w.location);
+        (self.result, self.warnings)
+    }
+}
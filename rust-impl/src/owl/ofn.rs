@@ -0,0 +1,779 @@
+//! OWL 2 Functional-Style Syntax (`.ofn`) front-end.
+//!
+//! The document is read one axiom at a time: `Ontology(` only opens the
+//! axiom list, so nothing larger than a single axiom is held in memory.
+//! Axioms and class constructors outside the supported EL fragment are
+//! skipped with a warning.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+
+use super::approximate::{self, Owl};
+use super::{Builder, OWL, RDF, RDFS, XSD};
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::obo::{Location, ParseError, ParseResult, Warning, WarningKind};
+use crate::resolve::{IdResolver, OBO_PURL};
+use crate::{ConceptId, RoleId, TOP};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum Token {
+    Open,
+    Close,
+    /// `<full IRI>`, brackets stripped.
+    Iri(String),
+    /// Prefixed name, `prefix:=` of a prefix declaration, or keyword.
+    Name(String),
+    /// Lexical form of a literal; datatype and language tag are dropped.
+    Literal(String),
+}
+
+#[derive(Clone, Debug)]
+pub(super) enum Node {
+    Atom(Token),
+    List(String, Vec<Node>),
+}
+
+/// Tokens of a document read line by line; literals may span lines.
+struct Lexer<R> {
+    reader: R,
+    line: String,
+    pos: usize,
+    line_no: usize,
+    peeked: Option<(Token, Location)>,
+    errors: Vec<Warning>,
+    /// Set when the reader fails; the input ends there.
+    failed: Option<ParseError>,
+}
+
+impl<R: BufRead> Lexer<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            pos: 0,
+            line_no: 0,
+            peeked: None,
+            errors: Vec::new(),
+            failed: None,
+        }
+    }
+
+    fn location(&self) -> Location {
+        Location { line: self.line_no, column: self.line[..self.pos].chars().count() + 1 }
+    }
+
+    fn error(&mut self, at: Location, message: String) {
+        self.errors.push(Warning { kind: WarningKind::MalformedLine, location: at, message });
+    }
+
+    /// Moves to the next line; false at the end of the input.
+    fn next_line(&mut self) -> bool {
+        self.line.clear();
+        self.pos = 0;
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => false,
+            Ok(_) => {
+                self.line_no += 1;
+                true
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                self.line_no += 1;
+                let at = Location { line: self.line_no, column: 1 };
+                self.error(at, format!("unreadable line: {}", e));
+                self.line.clear();
+                true
+            }
+            Err(source) => {
+                let location = Location { line: self.line_no + 1, column: 1 };
+                self.failed = Some(ParseError { location, source });
+                false
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = self.scan();
+        }
+        self.peeked.as_ref().map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<(Token, Location)> {
+        self.peeked.take().or_else(|| self.scan())
+    }
+
+    fn scan(&mut self) -> Option<(Token, Location)> {
+        loop {
+            let rest = &self.line[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                if !self.next_line() {
+                    return None;
+                }
+                continue;
+            }
+            let at = self.location();
+            let first = trimmed.chars().next()?;
+            return Some(match first {
+                '(' => {
+                    self.pos += 1;
+                    (Token::Open, at)
+                }
+                ')' => {
+                    self.pos += 1;
+                    (Token::Close, at)
+                }
+                '<' => match trimmed.find('>') {
+                    Some(end) => {
+                        let iri = trimmed[1..end].to_string();
+                        self.pos += end + 1;
+                        (Token::Iri(iri), at)
+                    }
+                    None => {
+                        self.error(at, "unterminated IRI".to_string());
+                        self.pos = self.line.len();
+                        continue;
+                    }
+                },
+                '"' => {
+                    self.pos += 1;
+                    let text = self.literal(at)?;
+                    (Token::Literal(text), at)
+                }
+                _ => {
+                    let end = trimmed
+                        .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '"' | '<'))
+                        .unwrap_or(trimmed.len());
+                    let name = trimmed[..end].to_string();
+                    self.pos += end;
+                    (Token::Name(name), at)
+                }
+            });
+        }
+    }
+
+    /// Reads a quoted literal after its opening quote, then drops a trailing
+    /// `^^datatype` or `@lang`.
+    fn literal(&mut self, at: Location) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            let mut chars = self.line[self.pos..].char_indices();
+            let mut closed = false;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            text.push(escaped);
+                        }
+                    }
+                    '"' => {
+                        self.pos += i + 1;
+                        closed = true;
+                        break;
+                    }
+                    c => text.push(c),
+                }
+            }
+            if closed {
+                break;
+            }
+            if !self.next_line() {
+                self.error(at, "unterminated literal".to_string());
+                return None;
+            }
+        }
+        let rest = &self.line[self.pos..];
+        if rest.starts_with("^^") {
+            self.pos += 2;
+            self.scan();
+        } else if let Some(tag) = rest.strip_prefix('@') {
+            let end = tag.find(|c: char| c.is_whitespace() || c == ')').unwrap_or(tag.len());
+            self.pos += 1 + end;
+        }
+        Some(text)
+    }
+
+    /// The arguments of a list whose `head (` was just read, up to the
+    /// matching `)`.
+    fn arguments(&mut self, head_at: Location) -> Option<Vec<Node>> {
+        let mut args = Vec::new();
+        loop {
+            let Some((token, at)) = self.next() else {
+                self.error(head_at, "unbalanced parentheses".to_string());
+                return None;
+            };
+            match token {
+                Token::Close => return Some(args),
+                Token::Open => {
+                    self.error(at, "unexpected '('".to_string());
+                    return None;
+                }
+                Token::Name(head) if self.peek() == Some(&Token::Open) => {
+                    self.next();
+                    args.push(Node::List(head, self.arguments(at)?));
+                }
+                token => args.push(Node::Atom(token)),
+            }
+        }
+    }
+}
+
+/// Axioms that carry no logical content for classification.
+const IGNORED: &[&str] = &[
+    "Import",
+    "Annotation",
+    "SubAnnotationPropertyOf",
+    "AnnotationPropertyDomain",
+    "AnnotationPropertyRange",
+];
+
+struct Reader<'r> {
+    builder: Builder<'r>,
+    prefixes: HashMap<String, String>,
+}
+
+impl Reader<'_> {
+    fn iri(&self, token: &Token) -> Option<String> {
+        match token {
+            Token::Iri(iri) => Some(iri.clone()),
+            Token::Name(name) => {
+                let (prefix, local) = name.split_once(':')?;
+                let base = match self.prefixes.get(prefix) {
+                    Some(base) => base.as_str(),
+                    None => match prefix {
+                        "owl" => OWL,
+                        "rdf" => RDF,
+                        "rdfs" => RDFS,
+                        "xsd" => XSD,
+                        _ => return Some(name.clone()),
+                    },
+                };
+                Some(format!("{}{}", base, local))
+            }
+            _ => None,
+        }
+    }
+
+    fn entity(&self, node: &Node) -> Option<String> {
+        match node {
+            Node::Atom(token) => self.iri(token),
+            Node::List(..) => None,
+        }
+    }
+
+    fn role(&mut self, node: &Node) -> Result<RoleId, String> {
+        match node {
+            Node::Atom(token) => match self.iri(token) {
+                Some(iri) => Ok(self.builder.role(&iri) as RoleId),
+                None => Err("a literal".to_string()),
+            },
+            Node::List(head, _) => Err(head.clone()),
+        }
+    }
+
+    fn individual(&mut self, node: &Node) -> Result<usize, String> {
+        match self.entity(node) {
+            Some(iri) => Ok(self.builder.individual(&iri)),
+            None => Err("an anonymous individual".to_string()),
+        }
+    }
+
+    fn nominal(&mut self, node: &Node) -> Result<ConceptId, String> {
+        let a = self.individual(node)?;
+        Ok(self.builder.result.nominal(a) as ConceptId)
+    }
+
+    /// The class expression of `node`, or the name of the first construct
+    /// in it that the reasoner doesn't support.
+    fn class(&mut self, node: &Node) -> Result<ClassExpr, String> {
+        match node {
+            Node::Atom(token) => match self.iri(token) {
+                Some(iri) => Ok(ClassExpr::Named(self.builder.concept(&iri) as u32)),
+                None => Err("a literal".to_string()),
+            },
+            Node::List(head, args) => match (head.as_str(), &args[..]) {
+                ("ObjectIntersectionOf", parts) if parts.len() >= 2 => {
+                    Ok(ClassExpr::And(parts.iter().map(|p| self.class(p)).collect::<Result<_, _>>()?))
+                }
+                ("ObjectSomeValuesFrom", [role, filler]) => {
+                    let role = self.role(role)?;
+                    Ok(ClassExpr::Some(role, Box::new(self.class(filler)?)))
+                }
+                // More than one individual would be a union.
+                ("ObjectOneOf", [individual]) => Ok(ClassExpr::Named(self.nominal(individual)?)),
+                ("ObjectHasValue", [role, individual]) => {
+                    let role = self.role(role)?;
+                    let nominal = ClassExpr::Named(self.nominal(individual)?);
+                    Ok(ClassExpr::Some(role, Box::new(nominal)))
+                }
+                (head, _) => Err(head.to_string()),
+            },
+        }
+    }
+
+    /// `node` for approximation: EL where [`Reader::class`] reads it.
+    fn owl(&mut self, node: &Node) -> Owl {
+        if let Ok(e) = self.class(node) {
+            return Owl::El(e);
+        }
+        let Node::List(head, args) = node else {
+            return Owl::Other;
+        };
+        match (head.as_str(), &args[..]) {
+            ("ObjectIntersectionOf", parts) => Owl::And(parts.iter().map(|p| self.owl(p)).collect()),
+            ("ObjectUnionOf", parts) => Owl::Or(parts.iter().map(|p| self.owl(p)).collect()),
+            ("ObjectComplementOf", [x]) => Owl::Not(Box::new(self.owl(x))),
+            ("ObjectSomeValuesFrom", [role, filler]) => Owl::Some(self.role(role).ok(), Box::new(self.owl(filler))),
+            ("ObjectOneOf", individuals) => Owl::Or(
+                individuals
+                    .iter()
+                    .map(|a| self.nominal(a).map_or(Owl::Other, |c| Owl::El(ClassExpr::Named(c))))
+                    .collect(),
+            ),
+            ("ObjectMinCardinality" | "ObjectExactCardinality", [Node::Atom(Token::Name(n) | Token::Literal(n)), role, rest @ ..]) => {
+                let filler = match rest {
+                    [filler] => self.owl(filler),
+                    _ => Owl::El(ClassExpr::Named(TOP)),
+                };
+                let min = Owl::Min(n.parse().unwrap_or(0), self.role(role).ok(), Box::new(filler));
+                match head.as_str() {
+                    "ObjectMinCardinality" => min,
+                    _ => Owl::And(vec![min, Owl::Other]),
+                }
+            }
+            _ => Owl::Other,
+        }
+    }
+
+    /// EL axioms entailed by the class axiom `head(args)`, which is outside EL.
+    fn approximate(&mut self, head: &str, args: &[&Node]) -> Vec<ClassAxiom> {
+        let members: Vec<Owl> = args.iter().map(|a| self.owl(a)).collect();
+        match (head, &members[..]) {
+            ("SubClassOf", [sub, sup]) => approximate::subclass_of(sub, sup, &mut self.builder),
+            ("EquivalentClasses", [first, rest @ ..]) => {
+                rest.iter().flat_map(|m| approximate::equivalent(first, m, &mut self.builder)).collect()
+            }
+            ("DisjointClasses", members) => approximate::disjoint(members, &mut self.builder),
+            _ => Vec::new(),
+        }
+    }
+
+    fn skip(&mut self, at: Location, message: String) {
+        self.builder.warn(WarningKind::SkippedTag, at, message);
+    }
+
+    fn axiom(&mut self, head: &str, args: &[Node], at: Location) {
+        if IGNORED.contains(&head) {
+            return;
+        }
+        let args: Vec<&Node> = args
+            .iter()
+            .skip_while(|a| matches!(a, Node::List(h, _) if h == "Annotation"))
+            .collect();
+        if let Err(construct) = self.logical_axiom(head, &args, at) {
+            self.skip(at, format!("{} in {} is not supported", construct, head));
+            let approximation = self.approximate(head, &args);
+            self.builder.violation(&construct, at, approximation);
+        }
+    }
+
+    fn logical_axiom(&mut self, head: &str, args: &[&Node], at: Location) -> Result<(), String> {
+        match (head, args) {
+            ("Prefix", [Node::Atom(Token::Name(prefix)), Node::Atom(Token::Iri(iri))]) => {
+                let prefix = prefix.strip_suffix(":=").unwrap_or(prefix);
+                self.builder.prefix(prefix, iri);
+                self.prefixes.insert(prefix.to_string(), iri.clone());
+            }
+            ("Declaration", [Node::List(kind, entity)]) => {
+                let Some(iri) = entity.first().and_then(|e| self.entity(e)) else {
+                    return Err("an anonymous entity".to_string());
+                };
+                match kind.as_str() {
+                    "Class" => {
+                        let c = self.builder.concept(&iri);
+                        self.builder.result.declared.insert(c);
+                    }
+                    "ObjectProperty" => {
+                        self.builder.role(&iri);
+                    }
+                    "NamedIndividual" => {
+                        self.builder.individual(&iri);
+                    }
+                    _ => {}
+                }
+            }
+            ("SubClassOf", [sub, sup]) => {
+                let (sub, sup) = (self.class(sub)?, self.class(sup)?);
+                self.builder.subclass_of(sub, sup);
+            }
+            ("EquivalentClasses", members) if members.len() >= 2 => {
+                let mut exprs: Vec<ClassExpr> =
+                    members.iter().map(|m| self.class(m)).collect::<Result<_, _>>()?;
+                // Relate everything to a named member when there is one.
+                if let Some(i) = exprs.iter().position(|e| matches!(e, ClassExpr::Named(_))) {
+                    exprs.swap(0, i);
+                }
+                let first = exprs.remove(0);
+                for e in exprs {
+                    if e != first {
+                        self.builder.equivalent(first.clone(), e);
+                    }
+                }
+            }
+            ("DisjointClasses", members) if members.len() >= 2 => {
+                let classes = members.iter().map(|m| self.class(m)).collect::<Result<_, _>>()?;
+                self.builder.disjoint(classes);
+            }
+            ("SubObjectPropertyOf", [Node::List(chain, roles), sup])
+                if chain == "ObjectPropertyChain" =>
+            {
+                let [first, second] = &roles[..] else {
+                    return Err(format!("a chain of {} properties", roles.len()));
+                };
+                let (first, second, sup) = (self.role(first)?, self.role(second)?, self.role(sup)?);
+                let chain = (first as usize, second as usize, sup as usize);
+                self.builder.result.role_chains.push(chain);
+            }
+            ("SubObjectPropertyOf", [sub, sup]) => {
+                let (sub, sup) = (self.role(sub)?, self.role(sup)?);
+                self.builder.result.role_subsumptions.push((sub as usize, sup as usize));
+            }
+            ("EquivalentObjectProperties", members) if members.len() >= 2 => {
+                let roles: Vec<RoleId> =
+                    members.iter().map(|m| self.role(m)).collect::<Result<_, _>>()?;
+                for pair in roles.windows(2) {
+                    let (r, s) = (pair[0] as usize, pair[1] as usize);
+                    self.builder.result.role_subsumptions.extend([(r, s), (s, r)]);
+                }
+            }
+            ("TransitiveObjectProperty", [role]) => {
+                let r = self.role(role)? as usize;
+                self.builder.result.role_chains.push((r, r, r));
+            }
+            ("ReflexiveObjectProperty", [role]) => {
+                let r = self.role(role)? as usize;
+                self.builder.result.reflexive_roles.push(r);
+            }
+            ("ObjectPropertyDomain", [role, class]) => {
+                let (r, class) = (self.role(role)?, self.class(class)?);
+                match class {
+                    ClassExpr::Named(c) => {
+                        self.builder.result.role_domains.push((r as usize, c as usize));
+                    }
+                    class => {
+                        let some = ClassExpr::Some(r, Box::new(ClassExpr::Named(TOP)));
+                        self.builder.subclass_of(some, class);
+                    }
+                }
+            }
+            ("ObjectPropertyRange", [role, class]) => {
+                let (r, class) = (self.role(role)?, self.class(class)?);
+                // A complex range would need a concept of its own.
+                let ClassExpr::Named(c) = class else {
+                    return Err("a complex class expression".to_string());
+                };
+                self.builder.result.role_ranges.push((r as usize, c as usize));
+            }
+            ("ClassAssertion", [Node::Atom(class), individual]) => {
+                let Some(iri) = self.iri(class) else {
+                    return Err("a literal".to_string());
+                };
+                let c = self.builder.concept(&iri);
+                let a = self.individual(individual)?;
+                self.builder.class_assertion(a, c);
+            }
+            // Complex types would need a concept of their own per assertion.
+            ("ClassAssertion", [Node::List(head, _), _]) => return Err(head.clone()),
+            ("ObjectPropertyAssertion", [role, subject, object]) => {
+                let role = self.role(role)?;
+                let (a, b) = (self.individual(subject)?, self.individual(object)?);
+                self.builder.role_assertion(a, role as usize, b);
+            }
+            ("AnnotationAssertion", [property, Node::Atom(subject), Node::Atom(value)]) => {
+                let property = self.entity(property).unwrap_or_default();
+                let Some(subject) = self.iri(subject) else {
+                    return Ok(());
+                };
+                let Token::Literal(value) = value else {
+                    if let Some(target) = self.iri(value) {
+                        self.builder.replacement(&subject, &property, &target);
+                    }
+                    return Ok(());
+                };
+                if property == format!("{}label", RDFS) {
+                    self.builder.label(&subject, value);
+                } else if property == format!("{}deprecated", OWL) && value == "true" {
+                    self.builder.deprecate(&subject);
+                } else {
+                    self.builder.replacement(&subject, &property, value);
+                }
+            }
+            ("AnnotationAssertion", _) => {}
+            _ => {
+                let message = format!("{} axioms are not supported", head);
+                self.builder.warn(WarningKind::SkippedTag, at, message);
+                self.builder.violation(head, at, Vec::new());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the EL fragment of an OWL 2 functional-syntax document.
+/// Everything skipped is reported in the returned warnings.
+pub fn parse_ofn<R: BufRead>(
+    reader: R,
+    resolver: &dyn IdResolver,
+) -> Result<(ParseResult, Vec<Warning>), ParseError> {
+    let mut lexer = Lexer::new(reader);
+    let mut reader = Reader { builder: Builder::new(resolver), prefixes: HashMap::new() };
+
+    while let Some((token, at)) = lexer.next() {
+        match token {
+            Token::Name(head) if lexer.peek() == Some(&Token::Open) => {
+                lexer.next();
+                // Axioms follow `Ontology(` directly; its `)` is skipped below.
+                if head == "Ontology" {
+                    continue;
+                }
+                if let Some(args) = lexer.arguments(at) {
+                    reader.axiom(&head, &args, at);
+                }
+            }
+            // The ontology and version IRIs, and the ontology's closing `)`.
+            Token::Iri(_) | Token::Close => {}
+            token => lexer.error(at, format!("unexpected {:?}", token)),
+        }
+    }
+
+    if let Some(error) = lexer.failed {
+        return Err(error);
+    }
+    reader.builder.warnings.append(&mut lexer.errors);
+    Ok(reader.builder.finish())
+}
+
+/// Reads axioms already split into trees, as the OWLlink adapter gets them
+/// from OWL/XML; otherwise like [`parse_ofn`].
+#[cfg(feature = "serve")]
+pub(super) fn read_axioms(
+    axioms: impl IntoIterator<Item = (Node, Location)>,
+    resolver: &dyn IdResolver,
+) -> (ParseResult, Vec<Warning>) {
+    let mut reader = Reader { builder: Builder::new(resolver), prefixes: HashMap::new() };
+    for (axiom, at) in axioms {
+        match axiom {
+            Node::List(head, args) => reader.axiom(&head, &args, at),
+            Node::Atom(token) => reader.skip(at, format!("unexpected {:?}", token)),
+        }
+    }
+    reader.builder.finish()
+}
+
+/// `name` as an OFN IRI: OBO CURIEs as `obo:` PURLs, which [`parse_ofn`]
+/// reads back as the same CURIE, anything else as a full IRI.
+pub(super) fn iri(name: &str) -> String {
+    if name == "owl:Thing" || name == "owl:Nothing" {
+        return name.to_string();
+    }
+    let word = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric());
+    match name.split_once(':') {
+        Some((prefix, local))
+            if word(prefix) && local.split(['_', '-', '.']).all(|part| word(part) || part.is_empty()) =>
+        {
+            format!("obo:{}_{}", prefix, local)
+        }
+        _ => format!("<{}>", name),
+    }
+}
+
+fn literal(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `oboInOwl:is_inferred`, as ROBOT's `reason --annotate-inferred-axioms`
+/// marks the axioms it adds.
+const INFERRED: &str = "Annotation(oboInOwl:is_inferred \"true\"^^xsd:string)";
+
+const OBO_IN_OWL: &str = "http://www.geneontology.org/formats/oboInOwl#";
+
+/// Direct parents and equivalence groups to add as inferred axioms.
+struct Inferred<'p> {
+    direct_parents: &'p [Vec<ConceptId>],
+    equivalences: &'p [Vec<ConceptId>],
+}
+
+struct Writer<'p, W> {
+    parsed: &'p ParseResult,
+    out: W,
+    inferred: Option<Inferred<'p>>,
+}
+
+impl<W: Write> Writer<'_, W> {
+    fn concept(&self, c: usize) -> String {
+        match self.parsed.nominals.iter().find(|&&(n, _)| n == c) {
+            Some(&(_, a)) => format!("ObjectOneOf({})", iri(&self.parsed.individuals[a])),
+            None => iri(&self.parsed.concepts[c]),
+        }
+    }
+
+    fn role(&self, r: usize) -> String {
+        iri(&self.parsed.roles[r])
+    }
+
+    fn class(&self, expr: &ClassExpr) -> String {
+        match expr {
+            ClassExpr::Named(c) => self.concept(*c as usize),
+            ClassExpr::And(parts) if parts.len() == 1 => self.class(&parts[0]),
+            ClassExpr::And(parts) => {
+                let parts: Vec<String> = parts.iter().map(|p| self.class(p)).collect();
+                format!("ObjectIntersectionOf({})", parts.join(" "))
+            }
+            ClassExpr::Some(r, filler) => {
+                format!("ObjectSomeValuesFrom({} {})", self.role(*r as usize), self.class(filler))
+            }
+        }
+    }
+
+    fn label(&mut self, subject: String, label: &str) -> io::Result<()> {
+        writeln!(self.out, "AnnotationAssertion(rdfs:label {} {})", subject, literal(label))
+    }
+
+    fn write(&mut self) -> io::Result<()> {
+        let p = self.parsed;
+        writeln!(self.out, "Prefix(obo:=<{}>)", OBO_PURL)?;
+        writeln!(self.out, "Prefix(owl:=<{}>)", OWL)?;
+        writeln!(self.out, "Prefix(rdfs:=<{}>)", RDFS)?;
+        writeln!(self.out, "Prefix(xsd:=<{}>)", XSD)?;
+        if self.inferred.is_some() {
+            writeln!(self.out, "Prefix(oboInOwl:=<{}>)", OBO_IN_OWL)?;
+        }
+        writeln!(self.out, "Ontology(")?;
+
+        let nominal = |c: usize| p.nominals.iter().any(|&(n, _)| n == c);
+        for c in (2..p.concepts.len()).filter(|&c| !p.concepts[c].is_empty() && !nominal(c)) {
+            writeln!(self.out, "Declaration(Class({}))", self.concept(c))?;
+            if let Some(label) = p.labels.get(&c) {
+                self.label(self.concept(c), label)?;
+            }
+            if p.obsolete.contains(&c) {
+                let subject = self.concept(c);
+                writeln!(self.out, "AnnotationAssertion(owl:deprecated {} \"true\"^^xsd:boolean)", subject)?;
+            }
+        }
+        for r in 0..p.roles.len() {
+            writeln!(self.out, "Declaration(ObjectProperty({}))", self.role(r))?;
+            if let Some(label) = p.role_labels.get(&r) {
+                self.label(self.role(r), label)?;
+            }
+        }
+        for (i, name) in p.individuals.iter().enumerate() {
+            writeln!(self.out, "Declaration(NamedIndividual({}))", iri(name))?;
+            if let Some(label) = p.individual_labels.get(&i) {
+                self.label(iri(name), label)?;
+            }
+        }
+
+        for &(a, b) in &p.subsumptions {
+            writeln!(self.out, "SubClassOf({} {})", self.concept(a), self.concept(b))?;
+        }
+        for &(a, r, b) in &p.relations {
+            let some = format!("ObjectSomeValuesFrom({} {})", self.role(r), self.concept(b));
+            writeln!(self.out, "SubClassOf({} {})", self.concept(a), some)?;
+        }
+        for &(a, b) in &p.disjoint_pairs {
+            writeln!(self.out, "DisjointClasses({} {})", self.concept(a), self.concept(b))?;
+        }
+        for definition in &p.definitions {
+            let expr = self.class(&definition.expression());
+            writeln!(self.out, "EquivalentClasses({} {})", self.concept(definition.term), expr)?;
+        }
+        for axiom in &p.class_axioms {
+            let (head, a, b) = match axiom {
+                ClassAxiom::SubClassOf(a, b) => ("SubClassOf", a, b),
+                ClassAxiom::Equivalent(a, b) => ("EquivalentClasses", a, b),
+            };
+            writeln!(self.out, "{}({} {})", head, self.class(a), self.class(b))?;
+        }
+        for &(r, s) in &p.role_subsumptions {
+            writeln!(self.out, "SubObjectPropertyOf({} {})", self.role(r), self.role(s))?;
+        }
+        for &(r, s, t) in &p.role_chains {
+            if r == s && s == t {
+                writeln!(self.out, "TransitiveObjectProperty({})", self.role(r))?;
+            } else {
+                let chain = format!("ObjectPropertyChain({} {})", self.role(r), self.role(s));
+                writeln!(self.out, "SubObjectPropertyOf({} {})", chain, self.role(t))?;
+            }
+        }
+        for &r in &p.reflexive_roles {
+            writeln!(self.out, "ReflexiveObjectProperty({})", self.role(r))?;
+        }
+        for &(r, c) in &p.role_domains {
+            writeln!(self.out, "ObjectPropertyDomain({} {})", self.role(r), self.concept(c))?;
+        }
+        for &(r, c) in &p.role_ranges {
+            writeln!(self.out, "ObjectPropertyRange({} {})", self.role(r), self.concept(c))?;
+        }
+        for &(a, c) in &p.class_assertions {
+            writeln!(self.out, "ClassAssertion({} {})", self.concept(c), iri(&p.individuals[a]))?;
+        }
+        for &(a, r, b) in &p.role_assertions {
+            let (a, b) = (iri(&p.individuals[a]), iri(&p.individuals[b]));
+            writeln!(self.out, "ObjectPropertyAssertion({} {} {})", self.role(r), a, b)?;
+        }
+        if let Some(inferred) = self.inferred.take() {
+            self.write_inferred(inferred)?;
+        }
+
+        writeln!(self.out, ")")?;
+        self.out.flush()
+    }
+
+    /// Inferred direct subsumptions not already told, and equivalence
+    /// groups, between named classes.
+    fn write_inferred(&mut self, Inferred { direct_parents, equivalences }: Inferred) -> io::Result<()> {
+        let p = self.parsed;
+        let nominal = |c: usize| p.nominals.iter().any(|&(n, _)| n == c);
+        let named = |c: ConceptId| c as usize >= 2 && !p.concepts[c as usize].is_empty() && !nominal(c as usize);
+        let told: HashSet<(usize, usize)> = p.subsumptions.iter().copied().collect();
+        for (c, parents) in direct_parents.iter().enumerate() {
+            if !named(c as ConceptId) || p.obsolete.contains(&c) {
+                continue;
+            }
+            for &d in parents.iter().filter(|&&d| named(d)) {
+                if !told.contains(&(c, d as usize)) {
+                    let (sub, sup) = (self.concept(c), self.concept(d as usize));
+                    writeln!(self.out, "SubClassOf({} {} {})", INFERRED, sub, sup)?;
+                }
+            }
+        }
+        for class in equivalences {
+            let members: Vec<String> = class.iter().filter(|&&c| named(c)).map(|&c| self.concept(c as usize)).collect();
+            if members.len() > 1 {
+                writeln!(self.out, "EquivalentClasses({} {})", INFERRED, members.join(" "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes every axiom of `parsed` as an OWL 2 functional-syntax document
+/// that [`parse_ofn`] reads back to the same axioms.
+pub fn write_ofn<W: Write>(parsed: &ParseResult, out: W) -> io::Result<()> {
+    Writer { parsed, out, inferred: None }.write()
+}
+
+/// [`write_ofn`] followed by the inferred direct subsumptions that aren't
+/// told and the inferred equivalence groups, annotated
+/// `oboInOwl:is_inferred "true"`. `equivalences` may be empty to leave
+/// them out.
+pub fn write_ofn_with_inferred<W: Write>(
+    parsed: &ParseResult,
+    direct_parents: &[Vec<ConceptId>],
+    equivalences: &[Vec<ConceptId>],
+    out: W,
+) -> io::Result<()> {
+    Writer { parsed, out, inferred: Some(Inferred { direct_parents, equivalences }) }.write()
+}
@@ -0,0 +1,491 @@
+//! An OWLlink adapter (the XML binding over HTTP), so OWLlink clients such
+//! as Protégé's OWLlink plugin can use this reasoner. `el-reasoner serve`
+//! answers it on `POST /` and `POST /owllink`.
+//!
+//! Supported requests: `GetDescription`, `CreateKB`, `ReleaseKB`, `Tell`
+//! (OWL/XML axioms, read like functional syntax), `LoadOntologies` (local
+//! files or `file:` IRIs, in any input format), `Classify`,
+//! `IsKBSatisfiable`, `IsClassSatisfiable`, `IsEntailed` and
+//! `IsEntailedDirect` (`SubClassOf` between classes), `GetAllClasses`,
+//! `GetSubClasses`, `GetSuperClasses`, `GetEquivalentClasses` and
+//! `GetUnsatisfiableClasses`. Others get an `Error` response.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use super::ofn::{self, Node, Token};
+use super::xml::{Event, XmlReader};
+use super::OWL;
+use crate::obo::{build_axiom_store, Location, ParseResult};
+use crate::ontology::Ontology;
+use crate::resolve::{CurieMap, IdResolver, Verbatim, OBO_PURL};
+use crate::{is_consistent, saturate, unsatisfiable_concepts, ConceptId, Taxonomy, BOTTOM, TOP};
+
+pub const OWLLINK: &str = "http://www.owllink.org/owllink#";
+
+/// An element of a request, with namespaces dropped from its name.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+    at: Location,
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(['#', '/']).next().unwrap_or(name)
+}
+
+/// The document element of `request`.
+fn read_document(request: &[u8]) -> Result<Element, String> {
+    let mut xml = XmlReader::new(request);
+    let mut stack: Vec<Element> = Vec::new();
+    while let Some((event, at)) = xml.next()? {
+        match event {
+            Event::Start { name, attrs } => {
+                let attrs = attrs.into_iter().map(|(n, v)| (local_name(&n).to_string(), v)).collect();
+                stack.push(Element { name: local_name(&name).to_string(), attrs, at, ..Element::default() });
+            }
+            Event::End => {
+                let element = stack.pop().ok_or("unbalanced end tag")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+            }
+        }
+    }
+    Err("no document element".to_string())
+}
+
+/// `element` as the functional-syntax tree it stands for: entities become
+/// IRIs, everything else a list headed by its element name.
+fn node(element: &Element) -> Node {
+    if let Some(iri) = element.attr("IRI") {
+        return Node::Atom(Token::Iri(iri.to_string()));
+    }
+    if let Some(name) = element.attr("abbreviatedIRI").or(element.attr("nodeID")) {
+        return Node::Atom(Token::Name(name.to_string()));
+    }
+    let text = element.text.trim().to_string();
+    match element.name.as_str() {
+        "IRI" => Node::Atom(Token::Iri(text)),
+        "AbbreviatedIRI" => Node::Atom(Token::Name(text)),
+        "Literal" => Node::Atom(Token::Literal(element.text.clone())),
+        // The one place an entity keeps its kind.
+        "Declaration" => {
+            let entities = element.children.iter().map(|e| Node::List(e.name.clone(), vec![node(e)]));
+            Node::List(element.name.clone(), entities.collect())
+        }
+        _ => Node::List(element.name.clone(), element.children.iter().map(node).collect()),
+    }
+}
+
+/// A knowledge base and, once classified, its taxonomy.
+struct Kb {
+    /// `Prefix` declarations of `CreateKB`, for abbreviated IRIs.
+    prefixes: Vec<(String, String)>,
+    parsed: ParseResult,
+    classified: Option<Classified>,
+}
+
+struct Classified {
+    taxonomy: Taxonomy,
+    /// See [`Taxonomy::representative`], per concept.
+    representative: Vec<ConceptId>,
+    /// Sorted.
+    unsatisfiable: Vec<ConceptId>,
+    consistent: bool,
+}
+
+impl Kb {
+    fn classified(&mut self) -> &Classified {
+        let parsed = &self.parsed;
+        self.classified.get_or_insert_with(|| {
+            let n = parsed.concepts.len();
+            let store = build_axiom_store(parsed);
+            let mut contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+            contexts.truncate(n);
+            let taxonomy = Taxonomy::new(&contexts, n);
+            let mut representative: Vec<ConceptId> = (0..n as ConceptId).collect();
+            for class in taxonomy.equivalences() {
+                for &c in class {
+                    representative[c as usize] = class[0];
+                }
+            }
+            Classified {
+                taxonomy,
+                representative,
+                unsatisfiable: unsatisfiable_concepts(&contexts),
+                consistent: is_consistent(&contexts),
+            }
+        })
+    }
+
+    fn add(&mut self, parsed: ParseResult) {
+        self.parsed.merge(parsed);
+        self.classified = None;
+    }
+
+    fn expand(&self, name: &str) -> String {
+        let Some((prefix, local)) = name.split_once(':') else {
+            return name.to_string();
+        };
+        match self.prefixes.iter().find(|(p, _)| p == prefix) {
+            Some((_, base)) => format!("{}{}", base, local),
+            None if prefix == "owl" => format!("{}{}", OWL, local),
+            None => name.to_string(),
+        }
+    }
+
+    /// The concept an `owl:Class` element names, as the front-ends name it.
+    fn class(&self, element: &Element) -> Result<ConceptId, String> {
+        if element.name != "Class" {
+            return Err(format!("{} is not supported here; name a class", element.name));
+        }
+        let iri = match (element.attr("IRI"), element.attr("abbreviatedIRI")) {
+            (Some(iri), _) => iri.to_string(),
+            (None, Some(name)) => self.expand(name),
+            (None, None) => return Err("a class without an IRI".to_string()),
+        };
+        match iri.strip_prefix(OWL) {
+            Some("Thing") => return Ok(TOP),
+            Some("Nothing") => return Ok(BOTTOM),
+            _ => {}
+        }
+        let name = CurieMap::obo().resolve(&iri).into_owned();
+        match self.parsed.concept_idx.get(&name) {
+            Some(&c) => Ok(c as ConceptId),
+            None => Err(format!("unknown class {}", iri)),
+        }
+    }
+}
+
+/// Sets of equivalent classes, as OWLlink reports hierarchy answers.
+type Synsets = Vec<Vec<ConceptId>>;
+
+impl Classified {
+    fn is_unsatisfiable(&self, c: ConceptId) -> bool {
+        c == BOTTOM || self.unsatisfiable.binary_search(&c).is_ok()
+    }
+
+    fn nothing(&self) -> Vec<ConceptId> {
+        let mut synset = vec![BOTTOM];
+        synset.extend(&self.unsatisfiable);
+        synset
+    }
+
+    fn equivalents(&self, c: ConceptId) -> Vec<ConceptId> {
+        if self.is_unsatisfiable(c) {
+            return self.nothing();
+        }
+        let representative = self.representative[c as usize];
+        let equivalences = self.taxonomy.equivalences();
+        match equivalences.binary_search_by_key(&representative, |class| class[0]) {
+            Ok(i) => equivalences[i].clone(),
+            Err(_) => vec![c],
+        }
+    }
+
+    /// `ids` grouped by equivalence, each satisfiable one once.
+    fn synsets(&self, ids: impl IntoIterator<Item = ConceptId>) -> Synsets {
+        let mut seen = HashSet::new();
+        let mut synsets = Vec::new();
+        for c in ids {
+            if !self.is_unsatisfiable(c) && seen.insert(self.representative[c as usize]) {
+                synsets.push(self.equivalents(c));
+            }
+        }
+        synsets
+    }
+
+    fn superclasses(&self, c: ConceptId, direct: bool) -> Synsets {
+        let taxonomy = &self.taxonomy;
+        if self.is_unsatisfiable(c) {
+            // Everything is above owl:Nothing.
+            let all = (0..taxonomy.len() as ConceptId).filter(|&d| d != BOTTOM);
+            if !direct {
+                return self.synsets(all);
+            }
+            let leaves = self.synsets(taxonomy.leaves());
+            return if leaves.is_empty() { vec![vec![TOP]] } else { leaves };
+        }
+        let c = self.representative[c as usize];
+        match direct {
+            true => self.synsets(taxonomy.parents(c).iter().copied()),
+            false => self.synsets(taxonomy.ancestors(c)),
+        }
+    }
+
+    fn subclasses(&self, c: ConceptId, direct: bool) -> Synsets {
+        if self.is_unsatisfiable(c) {
+            return Vec::new();
+        }
+        let c = self.representative[c as usize];
+        let mut synsets = match direct {
+            true => self.synsets(self.taxonomy.children(c).iter().copied()),
+            false => self.synsets(self.taxonomy.descendants(c)),
+        };
+        if !direct || synsets.is_empty() {
+            synsets.push(self.nothing());
+        }
+        synsets
+    }
+}
+
+/// The knowledge bases of one server.
+#[derive(Default)]
+pub struct OwlLink {
+    kbs: HashMap<String, Kb>,
+    created: usize,
+}
+
+/// One response element, written by [`OwlLink::respond`].
+enum Answer {
+    Ok(Option<String>),
+    Kb(String),
+    Boolean(bool),
+    Classes(Vec<ConceptId>),
+    Synset(Vec<ConceptId>),
+    Synsets(Synsets),
+    Description,
+    Error(&'static str, String),
+}
+
+fn error(message: impl Into<String>) -> Answer {
+    Answer::Error("Error", message.into())
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The IRI of a concept named `name`: OBO CURIEs as PURLs, as in the OWL
+/// front-ends.
+fn iri(name: &str) -> String {
+    if let Some(local) = name.strip_prefix("owl:") {
+        return format!("{}{}", OWL, local);
+    }
+    match ofn::iri(name).strip_prefix("obo:") {
+        Some(purl) => format!("{}{}", OBO_PURL, purl),
+        None => name.to_string(),
+    }
+}
+
+impl OwlLink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `ResponseMessage` to a `RequestMessage` document, one response
+    /// per request in order.
+    pub fn respond(&mut self, request: &[u8]) -> String {
+        let mut out = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ResponseMessage xmlns=\"{}\" xmlns:owl=\"{}\">\n",
+            OWLLINK, OWL
+        );
+        match read_document(request) {
+            Ok(message) if message.name == "RequestMessage" => {
+                for request in &message.children {
+                    let answer = self.answer(request);
+                    // Classes are named after the KB, which is gone after a ReleaseKB.
+                    let kb = request.attr("kb").and_then(|kb| self.kbs.get(kb));
+                    write_answer(&mut out, &answer, kb.map_or(&[][..], |kb| &kb.parsed.concepts));
+                }
+            }
+            Ok(other) => {
+                let answer = error(format!("expected a RequestMessage, not {}", other.name));
+                write_answer(&mut out, &answer, &[]);
+            }
+            Err(e) => write_answer(&mut out, &Answer::Error("SyntaxError", e), &[]),
+        }
+        out.push_str("</ResponseMessage>\n");
+        out
+    }
+
+    fn kb(&mut self, request: &Element) -> Result<&mut Kb, Answer> {
+        let name = request.attr("kb").unwrap_or_default();
+        self.kbs.get_mut(name).ok_or_else(|| Answer::Error("KBError", format!("unknown KB '{}'", name)))
+    }
+
+    fn answer(&mut self, request: &Element) -> Answer {
+        let result = match request.name.as_str() {
+            "GetDescription" => Ok(Answer::Description),
+            "CreateKB" => Ok(self.create_kb(request)),
+            "ReleaseKB" => match self.kbs.remove(request.attr("kb").unwrap_or_default()) {
+                Some(_) => Ok(Answer::Ok(None)),
+                None => Err(Answer::Error("KBError", "unknown KB".to_string())),
+            },
+            _ => self.kb(request).map(|kb| kb_answer(kb, request)),
+        };
+        result.unwrap_or_else(|answer| answer)
+    }
+
+    fn create_kb(&mut self, request: &Element) -> Answer {
+        let name = match request.attr("kb") {
+            Some(name) if self.kbs.contains_key(name) => {
+                return Answer::Error("KBError", format!("KB '{}' exists", name));
+            }
+            Some(name) => name.to_string(),
+            None => {
+                self.created += 1;
+                format!("http://el-reasoner/kb/{}", self.created)
+            }
+        };
+        let prefixes = request
+            .children
+            .iter()
+            .filter(|e| e.name == "Prefix")
+            .filter_map(|e| Some((e.attr("name")?.to_string(), e.attr("fullIRI")?.to_string())))
+            .collect();
+        let kb = Kb { prefixes, parsed: ParseResult::new(), classified: None };
+        self.kbs.insert(name.clone(), kb);
+        Answer::Kb(name)
+    }
+}
+
+/// Writes `answer`, naming classes after `concepts`.
+fn write_answer(out: &mut String, answer: &Answer, concepts: &[String]) {
+    let class = |out: &mut String, c: ConceptId| {
+        let _ = write!(out, "<owl:Class IRI=\"{}\"/>", escape(&iri(&concepts[c as usize])));
+    };
+    let synset = |out: &mut String, synset: &[ConceptId]| {
+        out.push_str("<ClassSynset>");
+        synset.iter().for_each(|&c| class(out, c));
+        out.push_str("</ClassSynset>");
+    };
+    out.push_str("  ");
+    match answer {
+        Answer::Ok(None) => out.push_str("<OK/>"),
+        Answer::Ok(Some(warning)) => {
+            let _ = write!(out, "<OK warning=\"{}\"/>", escape(warning));
+        }
+        Answer::Kb(kb) => {
+            let _ = write!(out, "<ResponseKB kb=\"{}\"/>", escape(kb));
+        }
+        Answer::Boolean(result) => {
+            let _ = write!(out, "<BooleanResponse result=\"{}\"/>", result);
+        }
+        Answer::Classes(classes) => {
+            out.push_str("<SetOfClasses>");
+            classes.iter().for_each(|&c| class(out, c));
+            out.push_str("</SetOfClasses>");
+        }
+        Answer::Synset(classes) => synset(out, classes),
+        Answer::Synsets(synsets) => {
+            out.push_str("<SetOfClassSynsets>");
+            synsets.iter().for_each(|s| synset(out, s));
+            out.push_str("</SetOfClassSynsets>");
+        }
+        Answer::Description => {
+            let _ = write!(
+                out,
+                "<Description name=\"el-reasoner\"><ProtocolVersion major=\"1\" minor=\"0\"/>\
+                 <ReasonerVersion major=\"0\" minor=\"1\" build=\"0\"/></Description>"
+            );
+        }
+        Answer::Error(kind, message) => {
+            let _ = write!(out, "<{} error=\"{}\"/>", kind, escape(message));
+        }
+    }
+    out.push('\n');
+}
+
+fn tell(kb: &mut Kb, request: &Element) -> Answer {
+    let prefixes = kb.prefixes.iter().map(|(name, base)| {
+        let declaration = vec![Node::Atom(Token::Name(format!("{}:=", name))), Node::Atom(Token::Iri(base.clone()))];
+        (Node::List("Prefix".to_string(), declaration), request.at)
+    });
+    let axioms: Vec<(Node, Location)> =
+        prefixes.chain(request.children.iter().map(|axiom| (node(axiom), axiom.at))).collect();
+    let (parsed, warnings) = ofn::read_axioms(axioms, &Verbatim);
+    kb.add(parsed);
+    match warnings.first() {
+        None => Answer::Ok(None),
+        Some(first) => Answer::Ok(Some(format!("{} axioms skipped: {}", warnings.len(), first.message))),
+    }
+}
+
+fn load_ontologies(kb: &mut Kb, request: &Element) -> Answer {
+    for ontology in &request.children {
+        let Some(iri) = ontology.attr("IRI") else { continue };
+        let path = Path::new(iri.strip_prefix("file://").unwrap_or(iri));
+        if !path.exists() {
+            return error(format!("cannot load {}; only local files are supported", iri));
+        }
+        match Ontology::read(path) {
+            Ok((ontology, _)) => kb.add(ontology.into_parse_result()),
+            Err(e) => return error(format!("{}: {}", iri, e)),
+        }
+    }
+    Answer::Ok(None)
+}
+
+/// Answers the requests about one KB.
+fn kb_answer(kb: &mut Kb, request: &Element) -> Answer {
+    let direct = request.attr("direct") == Some("true");
+    let class = |kb: &Kb| match request.children.first() {
+        Some(element) => kb.class(element),
+        None => Err(format!("{} needs a class", request.name)),
+    };
+    let result = match request.name.as_str() {
+        "Tell" => return tell(kb, request),
+        "LoadOntologies" => return load_ontologies(kb, request),
+        "Classify" => {
+            kb.classified();
+            Ok(Answer::Ok(None))
+        }
+        "IsKBSatisfiable" => Ok(Answer::Boolean(kb.classified().consistent)),
+        "GetAllClasses" => Ok(Answer::Classes((0..kb.parsed.concepts.len() as ConceptId).collect())),
+        "GetUnsatisfiableClasses" => Ok(Answer::Synset(kb.classified().nothing())),
+        "IsClassSatisfiable" => class(kb).map(|c| Answer::Boolean(!kb.classified().is_unsatisfiable(c))),
+        "GetEquivalentClasses" => class(kb).map(|c| Answer::Synset(kb.classified().equivalents(c))),
+        "GetSubClasses" => class(kb).map(|c| Answer::Synsets(kb.classified().subclasses(c, direct))),
+        "GetSuperClasses" => class(kb).map(|c| Answer::Synsets(kb.classified().superclasses(c, direct))),
+        "IsEntailed" | "IsEntailedDirect" => entailed(kb, request),
+        other => Err(format!("{} is not supported", other)),
+    };
+    result.unwrap_or_else(error)
+}
+
+/// Whether a `SubClassOf` between classes holds, directly for
+/// `IsEntailedDirect`.
+fn entailed(kb: &mut Kb, request: &Element) -> Result<Answer, String> {
+    let axiom = request.children.first().ok_or("IsEntailed needs an axiom")?;
+    let [sub, sup] = &axiom.children[..] else {
+        return Err(format!("{} is not supported; ask about SubClassOf", axiom.name));
+    };
+    if axiom.name != "SubClassOf" {
+        return Err(format!("{} is not supported; ask about SubClassOf", axiom.name));
+    }
+    let (sub, sup) = (kb.class(sub)?, kb.class(sup)?);
+    let direct = request.name == "IsEntailedDirect";
+    let classified = kb.classified();
+    let holds = classified.superclasses(sub, direct).iter().any(|synset| synset.contains(&sup))
+        || (!direct && classified.equivalents(sub).contains(&sup))
+        || (!direct && (sup == TOP || classified.is_unsatisfiable(sub)));
+    Ok(Answer::Boolean(holds))
+}
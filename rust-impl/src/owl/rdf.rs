@@ -0,0 +1,502 @@
+//! Reads the EL fragment of OWL out of RDF triples, whichever syntax they
+//! were written in.
+//!
+//! Triples between named resources (`X rdfs:subClassOf Y`, labels,
+//! declarations) are applied as they arrive. Triples about blank nodes are
+//! kept until the document is complete, because the restrictions and lists
+//! they describe can be spread anywhere in it.
+
+use std::collections::{HashMap, HashSet};
+
+use super::approximate::{self, Owl};
+use super::{Builder, OWL, RDF, RDFS};
+use crate::normalize::ClassExpr;
+use crate::obo::{Location, ParseResult, Warning, WarningKind};
+use crate::resolve::IdResolver;
+use crate::{RoleId, TOP};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Term {
+    Iri(String),
+    Blank(String),
+    /// Lexical form; datatype and language are dropped.
+    Literal(String),
+}
+
+/// Nesting limit for class expressions, against cyclic blank nodes.
+const MAX_DEPTH: usize = 64;
+
+type Properties = Vec<(String, Term, Location)>;
+
+pub(crate) struct Graph<'r> {
+    builder: Builder<'r>,
+    blanks: HashMap<String, Properties>,
+    /// `(subject, predicate, blank)` from a named subject to a blank node.
+    pending: Vec<(String, String, String, Location)>,
+    /// `(predicate, subject, object)` of `rdfs:subPropertyOf`,
+    /// `owl:equivalentProperty`, `rdfs:domain` and `rdfs:range` with named
+    /// objects, kept until the object properties are known.
+    property_axioms: Vec<(String, String, String)>,
+    object_properties: HashSet<String>,
+    individuals: HashSet<String>,
+    /// `(subject, predicate, object)` between named resources whose meaning
+    /// depends on the subject being an individual: `rdf:type` with a class
+    /// and property assertions.
+    assertions: Vec<(String, String, String)>,
+    fresh: usize,
+}
+
+pub(crate) fn rdf(local: &str) -> String {
+    format!("{}{}", RDF, local)
+}
+
+/// `reference` against `base`: enough of RFC 3986 for `rdf:about="#x"`,
+/// `rdf:ID` and relative file names.
+pub(crate) fn resolve_iri(base: &str, reference: &str) -> String {
+    let absolute = reference.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    if absolute || base.is_empty() {
+        return reference.to_string();
+    }
+    let base = base.split('#').next().unwrap_or(base);
+    if reference.is_empty() || reference.starts_with('#') {
+        return format!("{}{}", base, reference);
+    }
+    match base.rfind('/') {
+        Some(i) => format!("{}{}", &base[..=i], reference),
+        None => reference.to_string(),
+    }
+}
+
+fn local<'a>(iri: &'a str, namespace: &str) -> Option<&'a str> {
+    iri.strip_prefix(namespace)
+}
+
+impl<'r> Graph<'r> {
+    pub fn new(resolver: &'r dyn IdResolver) -> Self {
+        Self {
+            builder: Builder::new(resolver),
+            blanks: HashMap::new(),
+            pending: Vec::new(),
+            property_axioms: Vec::new(),
+            object_properties: HashSet::new(),
+            individuals: HashSet::new(),
+            assertions: Vec::new(),
+            fresh: 0,
+        }
+    }
+
+    pub fn warn(&mut self, kind: WarningKind, location: Location, message: String) {
+        self.builder.warn(kind, location, message);
+    }
+
+    pub fn prefix(&mut self, prefix: &str, base: &str) {
+        self.builder.prefix(prefix, base);
+    }
+
+    /// A blank node no label in the document can collide with.
+    pub fn fresh_blank(&mut self) -> Term {
+        self.fresh += 1;
+        Term::Blank(format!(" {}", self.fresh))
+    }
+
+    pub fn triple(&mut self, subject: Term, predicate: &str, object: Term, at: Location) {
+        match subject {
+            Term::Iri(s) => self.named(s, predicate, object, at),
+            Term::Blank(b) => {
+                self.blanks
+                    .entry(b)
+                    .or_default()
+                    .push((predicate.to_string(), object, at))
+            }
+            Term::Literal(_) => {}
+        }
+    }
+
+    fn named(&mut self, s: String, predicate: &str, object: Term, at: Location) {
+        let b = &mut self.builder;
+        let o = match object {
+            Term::Blank(o) => return self.pending.push((s, predicate.to_string(), o, at)),
+            Term::Literal(value) => {
+                if predicate == format!("{}label", RDFS) {
+                    b.label(&s, &value);
+                } else if predicate == format!("{}deprecated", OWL) && value == "true" {
+                    b.deprecate(&s);
+                } else {
+                    b.replacement(&s, predicate, &value);
+                }
+                return;
+            }
+            Term::Iri(o) => o,
+        };
+        b.replacement(&s, predicate, &o);
+        if predicate == rdf("type") {
+            match local(&o, OWL) {
+                Some("Class") => {
+                    let c = b.concept(&s);
+                    b.result.declared.insert(c);
+                }
+                Some("ObjectProperty") => {
+                    b.role(&s);
+                    self.object_properties.insert(s);
+                }
+                Some("TransitiveProperty") => {
+                    let r = b.role(&s);
+                    b.result.role_chains.push((r, r, r));
+                    self.object_properties.insert(s);
+                }
+                Some("ReflexiveProperty") => {
+                    let r = b.role(&s);
+                    b.result.reflexive_roles.push(r);
+                    self.object_properties.insert(s);
+                }
+                Some("NamedIndividual") => {
+                    b.individual(&s);
+                    self.individuals.insert(s);
+                }
+                Some(_) => {}
+                None if local(&o, RDFS).is_some() || local(&o, RDF).is_some() => {}
+                None => self.assertions.push((s, predicate.to_string(), o)),
+            }
+            return;
+        }
+        match (local(predicate, RDFS), local(predicate, OWL)) {
+            (Some("subClassOf"), _) => {
+                let (sub, sup) = (b.concept(&s), b.concept(&o));
+                b.result.subsumptions.push((sub, sup));
+            }
+            (_, Some("equivalentClass")) => {
+                let (a, e) = (b.concept(&s), b.concept(&o));
+                b.equivalent(ClassExpr::Named(a as u32), ClassExpr::Named(e as u32));
+            }
+            (_, Some("disjointWith")) => {
+                let (x, y) = (b.concept(&s), b.concept(&o));
+                b.result.disjoint_pairs.push((x, y));
+            }
+            (Some("subPropertyOf" | "domain" | "range"), _) | (_, Some("equivalentProperty")) => {
+                self.property_axioms.push((predicate.to_string(), s, o));
+            }
+            (None, None) => self.assertions.push((s, predicate.to_string(), o)),
+            _ => {}
+        }
+    }
+
+    /// Resolves the blank-node structures and returns the parse result.
+    pub fn finish(mut self) -> (ParseResult, Vec<Warning>) {
+        let blanks = std::mem::take(&mut self.blanks);
+        let mut expr = Expressions {
+            blanks: &blanks,
+            builder: &mut self.builder,
+            object_properties: &mut self.object_properties,
+        };
+
+        for (s, predicate, o, at) in std::mem::take(&mut self.pending) {
+            expr.axiom(&Term::Iri(s), &predicate, &Term::Blank(o), at);
+        }
+
+        for (b, properties) in &blanks {
+            let all_disjoint = Term::Iri(format!("{}AllDisjointClasses", OWL));
+            if properties
+                .iter()
+                .any(|(p, t, _)| *p == rdf("type") && *t == all_disjoint)
+            {
+                let at = properties[0].2;
+                let members = find(properties, &format!("{}members", OWL))
+                    .ok_or_else(|| "a missing owl:members".to_string());
+                if let Err(construct) = members.clone().and_then(|m| expr.disjoint(m)) {
+                    let message = format!("{} in AllDisjointClasses is not supported", construct);
+                    expr.builder.warn(WarningKind::SkippedTag, at, message);
+                    let classes: Vec<Owl> = match members.and_then(|m| expr.list(m)) {
+                        Ok(items) => items.iter().map(|m| expr.owl(m, 0)).collect(),
+                        Err(_) => Vec::new(),
+                    };
+                    let approximation = approximate::disjoint(&classes, expr.builder);
+                    expr.builder.violation(&construct, at, approximation);
+                }
+            }
+            // General concept inclusions: axioms about an anonymous class.
+            for (p, o, at) in properties {
+                expr.axiom(&Term::Blank(b.clone()), p, o, *at);
+            }
+        }
+
+        for (predicate, sub, sup) in std::mem::take(&mut self.property_axioms) {
+            let kind = local(&predicate, RDFS);
+            if matches!(kind, Some("domain" | "range")) {
+                if !self.object_properties.contains(&sub) {
+                    continue;
+                }
+                let (r, c) = (self.builder.role(&sub), self.builder.concept(&sup));
+                if kind == Some("domain") {
+                    self.builder.result.role_domains.push((r, c));
+                } else {
+                    self.builder.result.role_ranges.push((r, c));
+                }
+                continue;
+            }
+            if !self.object_properties.contains(&sub) && !self.object_properties.contains(&sup) {
+                continue;
+            }
+            let (r, s) = (self.builder.role(&sub), self.builder.role(&sup));
+            self.builder.result.role_subsumptions.push((r, s));
+            if local(&predicate, OWL) == Some("equivalentProperty") {
+                self.builder.result.role_subsumptions.push((s, r));
+            }
+        }
+
+        for (s, predicate, o) in std::mem::take(&mut self.assertions) {
+            if !self.individuals.contains(&s) {
+                continue;
+            }
+            let a = self.builder.individual(&s);
+            if predicate == rdf("type") {
+                let c = self.builder.concept(&o);
+                self.builder.class_assertion(a, c);
+            } else if self.object_properties.contains(&predicate) && self.individuals.contains(&o) {
+                let (r, b) = (self.builder.role(&predicate), self.builder.individual(&o));
+                self.builder.role_assertion(a, r, b);
+            }
+        }
+        self.builder.finish()
+    }
+}
+
+fn find<'p>(properties: &'p Properties, predicate: &str) -> Option<&'p Term> {
+    properties
+        .iter()
+        .find(|(p, _, _)| p == predicate)
+        .map(|(_, o, _)| o)
+}
+
+/// Class expressions and lists read from the blank nodes of a document.
+struct Expressions<'g, 'r> {
+    blanks: &'g HashMap<String, Properties>,
+    builder: &'g mut Builder<'r>,
+    object_properties: &'g mut HashSet<String>,
+}
+
+impl<'g> Expressions<'g, '_> {
+    fn properties(&self, b: &str) -> &'g Properties {
+        static NONE: Properties = Vec::new();
+        self.blanks.get(b).unwrap_or(&NONE)
+    }
+
+    /// The members of the `rdf:first`/`rdf:rest` list at `term`.
+    fn list(&self, term: &Term) -> Result<Vec<Term>, String> {
+        let mut items = Vec::new();
+        let mut node = term;
+        while let Term::Blank(b) = node {
+            let properties = self.properties(b);
+            let (Some(first), Some(rest)) = (
+                find(properties, &rdf("first")),
+                find(properties, &rdf("rest")),
+            ) else {
+                return Err("a malformed list".to_string());
+            };
+            if items.len() > self.blanks.len() {
+                return Err("a cyclic list".to_string());
+            }
+            items.push(first.clone());
+            node = rest;
+        }
+        match node {
+            Term::Iri(nil) if *nil == rdf("nil") => Ok(items),
+            _ => Err("a malformed list".to_string()),
+        }
+    }
+
+    fn role(&mut self, term: &Term) -> Result<RoleId, String> {
+        match term {
+            Term::Iri(iri) => {
+                self.object_properties.insert(iri.clone());
+                Ok(self.builder.role(iri) as RoleId)
+            }
+            _ => Err("an anonymous property".to_string()),
+        }
+    }
+
+    /// The class expression at `term`, or the name of the first construct
+    /// in it that the reasoner doesn't support.
+    fn class(&mut self, term: &Term, depth: usize) -> Result<ClassExpr, String> {
+        let b = match term {
+            Term::Iri(iri) => return Ok(ClassExpr::Named(self.builder.concept(iri) as u32)),
+            Term::Literal(_) => return Err("a literal".to_string()),
+            Term::Blank(b) if depth < MAX_DEPTH => b,
+            Term::Blank(_) => return Err("a cyclic class expression".to_string()),
+        };
+        let properties = self.properties(b);
+        if let Some(list) = find(properties, &format!("{}intersectionOf", OWL)) {
+            let parts = self.list(list)?;
+            return match parts.len() {
+                0 => Err("an empty intersectionOf".to_string()),
+                1 => self.class(&parts[0], depth + 1),
+                _ => Ok(ClassExpr::And(
+                    parts
+                        .iter()
+                        .map(|p| self.class(p, depth + 1))
+                        .collect::<Result<_, _>>()?,
+                )),
+            };
+        }
+        if let Some(list) = find(properties, &format!("{}oneOf", OWL)) {
+            // More than one individual would be a union.
+            return match &self.list(list)?[..] {
+                [Term::Iri(iri)] => Ok(ClassExpr::Named(self.builder.nominal(iri) as u32)),
+                _ => Err("oneOf".to_string()),
+            };
+        }
+        let on_property = find(properties, &format!("{}onProperty", OWL));
+        let some = find(properties, &format!("{}someValuesFrom", OWL));
+        if let (Some(property), Some(filler)) = (on_property, some) {
+            let role = self.role(property)?;
+            return Ok(ClassExpr::Some(
+                role,
+                Box::new(self.class(filler, depth + 1)?),
+            ));
+        }
+        let value = find(properties, &format!("{}hasValue", OWL));
+        if let (Some(property), Some(Term::Iri(iri))) = (on_property, value) {
+            let role = self.role(property)?;
+            let nominal = ClassExpr::Named(self.builder.nominal(iri) as u32);
+            return Ok(ClassExpr::Some(role, Box::new(nominal)));
+        }
+        let construct = properties
+            .iter()
+            .filter_map(|(p, _, _)| local(p, OWL))
+            .find(|&p| p != "onProperty")
+            .unwrap_or("an empty class expression");
+        Err(construct.to_string())
+    }
+
+    /// `term` for approximation: EL where [`Expressions::class`] reads it.
+    fn owl(&mut self, term: &Term, depth: usize) -> Owl {
+        if let Ok(e) = self.class(term, depth) {
+            return Owl::El(e);
+        }
+        let Term::Blank(b) = term else {
+            return Owl::Other;
+        };
+        if depth >= MAX_DEPTH {
+            return Owl::Other;
+        }
+        let properties = self.properties(b);
+        let owl = |p: &str| find(properties, &format!("{}{}", OWL, p));
+        let members = |expr: &mut Self, list: &Term| -> Vec<Owl> {
+            let items = expr.list(list).unwrap_or_default();
+            items.iter().map(|m| expr.owl(m, depth + 1)).collect()
+        };
+        if let Some(list) = owl("intersectionOf") {
+            return Owl::And(members(self, list));
+        }
+        if let Some(list) = owl("unionOf") {
+            return Owl::Or(members(self, list));
+        }
+        if let Some(list) = owl("oneOf") {
+            return Owl::Or(members(self, list));
+        }
+        if let Some(x) = owl("complementOf") {
+            return Owl::Not(Box::new(self.owl(x, depth + 1)));
+        }
+        let Some(property) = owl("onProperty") else {
+            return Owl::Other;
+        };
+        let role = self.role(property).ok();
+        if let Some(filler) = owl("someValuesFrom") {
+            return Owl::Some(role, Box::new(self.owl(filler, depth + 1)));
+        }
+        let filler = match owl("onClass") {
+            Some(filler) => self.owl(filler, depth + 1),
+            None => Owl::El(ClassExpr::Named(TOP)),
+        };
+        let count = |p: &str| match owl(p) {
+            Some(Term::Literal(n)) => n.parse().ok(),
+            _ => None,
+        };
+        if let Some(n) = count("minCardinality").or_else(|| count("minQualifiedCardinality")) {
+            return Owl::Min(n, role, Box::new(filler));
+        }
+        if let Some(n) = count("cardinality").or_else(|| count("qualifiedCardinality")) {
+            return Owl::And(vec![Owl::Min(n, role, Box::new(filler)), Owl::Other]);
+        }
+        Owl::Other
+    }
+
+    fn chain(&mut self, sup: &str, list: &Term) -> Result<(), String> {
+        let roles = self.list(list)?;
+        let [first, second] = &roles[..] else {
+            return Err(format!("a chain of {} properties", roles.len()));
+        };
+        let (first, second) = (self.role(first)?, self.role(second)?);
+        let sup = self.role(&Term::Iri(sup.to_string()))?;
+        self.builder
+            .result
+            .role_chains
+            .push((first as usize, second as usize, sup as usize));
+        Ok(())
+    }
+
+    fn disjoint(&mut self, members: &Term) -> Result<(), String> {
+        let classes = self
+            .list(members)?
+            .iter()
+            .map(|m| self.class(m, 0))
+            .collect::<Result<_, _>>()?;
+        self.builder.disjoint(classes);
+        Ok(())
+    }
+
+    /// Applies the class axiom `subject predicate object`, if it is one,
+    /// warning when it uses a construct outside EL.
+    fn axiom(&mut self, subject: &Term, predicate: &str, object: &Term, at: Location) {
+        let class_axiom = matches!(local(predicate, RDFS), Some("subClassOf"))
+            || matches!(local(predicate, OWL), Some("equivalentClass" | "disjointWith"));
+        let applied = if class_axiom {
+            self.class(subject, 0).and_then(|a| {
+                let b = self.class(object, 0)?;
+                match local(predicate, OWL) {
+                    Some("equivalentClass") => self.builder.equivalent(a, b),
+                    Some("disjointWith") => self.builder.disjoint(vec![a, b]),
+                    _ => self.builder.subclass_of(a, b),
+                }
+                Ok(())
+            })
+        } else {
+            match (subject, local(predicate, OWL), local(predicate, RDFS)) {
+                (Term::Iri(s), Some("propertyChainAxiom"), _) => self.chain(s, object),
+                (_, _, Some("domain")) => self.role(subject).and_then(|r| {
+                    let some = ClassExpr::Some(r, Box::new(ClassExpr::Named(TOP)));
+                    let domain = self.class(object, 0)?;
+                    self.builder.subclass_of(some, domain);
+                    Ok(())
+                }),
+                // A complex range would need a concept of its own.
+                (_, _, Some("range")) => Err("a complex class expression".to_string()),
+                _ => Ok(()),
+            }
+        };
+        if let Err(construct) = applied {
+            let name = predicate.rsplit(['#', '/']).next().unwrap_or(predicate);
+            let shown = match subject {
+                Term::Iri(s) => s.as_str(),
+                _ => "an anonymous class",
+            };
+            let message = format!("{} in {} of {} is not supported", construct, name, shown);
+            self.builder.warn(WarningKind::SkippedTag, at, message);
+            let approximation = if class_axiom {
+                let (a, b) = (self.owl(subject, 0), self.owl(object, 0));
+                match local(predicate, OWL) {
+                    Some("equivalentClass") => approximate::equivalent(&a, &b, self.builder),
+                    Some("disjointWith") => approximate::disjoint(&[a, b], self.builder),
+                    _ => approximate::subclass_of(&a, &b, self.builder),
+                }
+            } else {
+                Vec::new()
+            };
+            self.builder.violation(&construct, at, approximation);
+        }
+    }
+}
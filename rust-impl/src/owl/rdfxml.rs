@@ -0,0 +1,271 @@
+//! RDF/XML front-end: the usual `.owl` download of OBO Foundry and
+//! BioPortal ontologies.
+//!
+//! The XML is read as a stream of events and turned into triples on the
+//! fly (node and property elements, `rdf:parseType="Resource"`,
+//! `"Collection"` and `"Literal"`, property attributes); the triples go to
+//! the same [`Graph`] as every other RDF syntax.
+
+use std::io::BufRead;
+
+use super::rdf::{rdf, resolve_iri, Graph, Term};
+use super::xml::{Event, XmlReader, XML};
+use crate::obo::{Location, ParseError, ParseResult, Warning, WarningKind};
+use crate::resolve::IdResolver;
+
+enum Frame {
+    /// The `rdf:RDF` root.
+    Root,
+    Node(Term),
+    Property {
+        subject: Term,
+        predicate: String,
+        text: String,
+        object: bool,
+    },
+    Collection {
+        subject: Term,
+        predicate: String,
+        items: Vec<Term>,
+    },
+    /// `rdf:parseType="Literal"` content, skipped up to its end.
+    Literal {
+        depth: usize,
+    },
+}
+
+struct Reader<'g, 'r> {
+    graph: &'g mut Graph<'r>,
+    frames: Vec<Frame>,
+    /// `xml:base` of each open element, if it sets one.
+    bases: Vec<Option<String>>,
+}
+
+impl Reader<'_, '_> {
+    fn base(&self) -> &str {
+        self.bases
+            .iter()
+            .rev()
+            .find_map(|b| b.as_deref())
+            .unwrap_or("")
+    }
+
+    fn take(attrs: &mut Vec<(String, String)>, name: &str) -> Option<String> {
+        let i = attrs.iter().position(|(k, _)| k == name)?;
+        Some(attrs.remove(i).1)
+    }
+
+    /// The resource named by `rdf:about`, `rdf:ID`, `rdf:nodeID` or
+    /// `rdf:resource`, removing that attribute.
+    fn resource(&mut self, attrs: &mut Vec<(String, String)>, keys: &[&str]) -> Option<Term> {
+        for key in keys {
+            if let Some(value) = Self::take(attrs, &rdf(key)) {
+                return Some(match *key {
+                    "nodeID" => Term::Blank(format!("n{}", value)),
+                    "ID" => Term::Iri(resolve_iri(self.base(), &format!("#{}", value))),
+                    _ => Term::Iri(resolve_iri(self.base(), &value)),
+                });
+            }
+        }
+        None
+    }
+
+    /// Property attributes on a node (or empty property) element.
+    fn property_attributes(&mut self, subject: &Term, attrs: Vec<(String, String)>, at: Location) {
+        for (key, value) in attrs {
+            if key.starts_with(XML) || !key.contains(':') {
+                continue;
+            }
+            let object = if key == rdf("type") {
+                Term::Iri(resolve_iri(self.base(), &value))
+            } else {
+                Term::Literal(value)
+            };
+            self.graph.triple(subject.clone(), &key, object, at);
+        }
+    }
+
+    fn node(&mut self, name: String, mut attrs: Vec<(String, String)>, at: Location) -> Term {
+        let subject = self
+            .resource(&mut attrs, &["about", "ID", "nodeID"])
+            .unwrap_or_else(|| self.graph.fresh_blank());
+        if name != rdf("Description") {
+            self.graph
+                .triple(subject.clone(), &rdf("type"), Term::Iri(name), at);
+        }
+        self.property_attributes(&subject, attrs, at);
+        subject
+    }
+
+    fn start(&mut self, name: String, mut attrs: Vec<(String, String)>, at: Location) {
+        let base = Self::take(&mut attrs, &format!("{}base", XML));
+        let base = base.map(|b| resolve_iri(self.base(), &b));
+        self.bases.push(base);
+        attrs.retain(|(k, _)| !k.starts_with(XML));
+
+        let frame = match self.frames.last_mut() {
+            None if name == rdf("RDF") => Frame::Root,
+            Some(Frame::Literal { depth }) => {
+                *depth += 1;
+                self.bases.pop();
+                return;
+            }
+            Some(Frame::Node(subject)) => {
+                let subject = subject.clone();
+                self.property(subject, name, attrs, at)
+            }
+            None | Some(Frame::Root) => Frame::Node(self.node(name, attrs, at)),
+            Some(Frame::Collection { .. }) => {
+                let item = self.node(name, attrs, at);
+                if let Some(Frame::Collection { items, .. }) = self.frames.last_mut() {
+                    items.push(item.clone());
+                }
+                Frame::Node(item)
+            }
+            Some(Frame::Property { .. }) => {
+                let object = self.node(name, attrs, at);
+                if let Some(Frame::Property {
+                    subject,
+                    predicate,
+                    object: filled,
+                    ..
+                }) = self.frames.last_mut()
+                {
+                    *filled = true;
+                    let (subject, predicate) = (subject.clone(), predicate.clone());
+                    self.graph.triple(subject, &predicate, object.clone(), at);
+                }
+                Frame::Node(object)
+            }
+        };
+        self.frames.push(frame);
+    }
+
+    fn property(
+        &mut self,
+        subject: Term,
+        predicate: String,
+        mut attrs: Vec<(String, String)>,
+        at: Location,
+    ) -> Frame {
+        Self::take(&mut attrs, &rdf("ID"));
+        Self::take(&mut attrs, &rdf("datatype"));
+        match Self::take(&mut attrs, &rdf("parseType")).as_deref() {
+            Some("Resource") => {
+                let object = self.graph.fresh_blank();
+                self.graph.triple(subject, &predicate, object.clone(), at);
+                return Frame::Node(object);
+            }
+            Some("Collection") => {
+                return Frame::Collection {
+                    subject,
+                    predicate,
+                    items: Vec::new(),
+                }
+            }
+            Some(_) => return Frame::Literal { depth: 0 },
+            None => {}
+        }
+        let object = self.resource(&mut attrs, &["resource", "nodeID"]);
+        let has_properties = attrs.iter().any(|(k, _)| k.contains(':'));
+        let object = match object {
+            Some(object) => Some(object),
+            None if has_properties => Some(self.graph.fresh_blank()),
+            None => None,
+        };
+        if let Some(object) = &object {
+            self.graph
+                .triple(subject.clone(), &predicate, object.clone(), at);
+            self.property_attributes(object, attrs, at);
+        }
+        Frame::Property {
+            subject,
+            predicate,
+            text: String::new(),
+            object: object.is_some(),
+        }
+    }
+
+    fn end(&mut self, at: Location) {
+        if let Some(Frame::Literal { depth }) = self.frames.last_mut() {
+            if *depth > 0 {
+                *depth -= 1;
+                return;
+            }
+        }
+        self.bases.pop();
+        match self.frames.pop() {
+            Some(Frame::Property {
+                subject,
+                predicate,
+                text,
+                object: false,
+            }) => {
+                self.graph
+                    .triple(subject, &predicate, Term::Literal(text), at);
+            }
+            Some(Frame::Collection {
+                subject,
+                predicate,
+                items,
+            }) => {
+                let mut list = Term::Iri(rdf("nil"));
+                for item in items.into_iter().rev() {
+                    let node = self.graph.fresh_blank();
+                    self.graph.triple(node.clone(), &rdf("first"), item, at);
+                    self.graph.triple(node.clone(), &rdf("rest"), list, at);
+                    list = node;
+                }
+                self.graph.triple(subject, &predicate, list, at);
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: String) {
+        if let Some(Frame::Property { text: value, .. }) = self.frames.last_mut() {
+            value.push_str(&text);
+        }
+    }
+}
+
+/// Parses the EL fragment of an RDF/XML document. Everything skipped is
+/// reported in the returned warnings; malformed XML ends the parse with a
+/// warning at the point it was noticed.
+pub fn parse_rdfxml<R: BufRead>(
+    reader: R,
+    resolver: &dyn IdResolver,
+) -> Result<(ParseResult, Vec<Warning>), ParseError> {
+    let mut graph = Graph::new(resolver);
+    let mut xml = XmlReader::new(reader);
+    let mut reader = Reader {
+        graph: &mut graph,
+        frames: Vec::new(),
+        bases: Vec::new(),
+    };
+    loop {
+        match xml.next() {
+            Ok(Some((Event::Start { name, attrs }, at))) => {
+                // The root's namespaces are the document's prefixes.
+                if reader.frames.is_empty() {
+                    for (prefix, ns) in &xml.namespaces()[1..] {
+                        reader.graph.prefix(prefix, ns);
+                    }
+                }
+                reader.start(name, attrs, at)
+            }
+            Ok(Some((Event::End, at))) => reader.end(at),
+            Ok(Some((Event::Text(text), _))) => reader.text(text),
+            Ok(None) => break,
+            Err(message) => {
+                let at = xml.location();
+                reader.graph.warn(WarningKind::MalformedLine, at, message);
+                break;
+            }
+        }
+    }
+    if let Some(error) = xml.failed {
+        return Err(error);
+    }
+    Ok(graph.finish())
+}
@@ -0,0 +1,415 @@
+//! Turtle (`.ttl`) front-end, e.g. for ontologies saved from Protégé.
+//!
+//! Statements are parsed one at a time and their triples handed to the
+//! same [`Graph`] as RDF/XML. A malformed statement is reported and skipped
+//! up to the next `.`.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use super::rdf::{rdf, resolve_iri, Graph, Term};
+use super::RDF;
+use crate::obo::{Location, ParseError, ParseResult, Warning, WarningKind};
+use crate::resolve::IdResolver;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    /// `<...>`, brackets stripped and not yet resolved against the base.
+    Iri(String),
+    /// `prefix:local`.
+    Name(String),
+    /// `_:label`.
+    Blank(String),
+    Literal(String),
+    /// `a`, `@prefix`, `@base`, `PREFIX`, `BASE`, `true`, `false`.
+    Keyword(String),
+    Punct(char),
+}
+
+struct Lexer<R> {
+    reader: R,
+    line: String,
+    pos: usize,
+    line_no: usize,
+    peeked: Option<(Token, Location)>,
+    /// Set when the reader fails; the input ends there.
+    failed: Option<ParseError>,
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "<>\"'{}|^`,;()[]#".contains(c)
+}
+
+impl<R: BufRead> Lexer<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            pos: 0,
+            line_no: 0,
+            peeked: None,
+            failed: None,
+        }
+    }
+
+    fn location(&self) -> Location {
+        Location {
+            line: self.line_no,
+            column: self.line[..self.pos].chars().count() + 1,
+        }
+    }
+
+    fn next_line(&mut self) -> Result<bool, String> {
+        self.line.clear();
+        self.pos = 0;
+        let n = match self.reader.read_line(&mut self.line) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => return Err(e.to_string()),
+            Err(source) => {
+                let location = Location { line: self.line_no + 1, column: 1 };
+                self.failed = Some(ParseError { location, source });
+                0
+            }
+        };
+        self.line_no += 1;
+        Ok(n > 0)
+    }
+
+    /// Line and byte offset of the next character to scan.
+    fn position(&self) -> (usize, usize) {
+        (self.line_no, self.pos)
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>, String> {
+        if self.peeked.is_none() {
+            self.peeked = self.scan()?;
+        }
+        Ok(self.peeked.as_ref().map(|(t, _)| t))
+    }
+
+    fn next(&mut self) -> Result<Option<(Token, Location)>, String> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.scan(),
+        }
+    }
+
+    /// The next token; after an error the rest of the line is dropped.
+    fn scan(&mut self) -> Result<Option<(Token, Location)>, String> {
+        let token = self.scan_token();
+        if token.is_err() {
+            self.pos = self.line.len();
+        }
+        token
+    }
+
+    fn scan_token(&mut self) -> Result<Option<(Token, Location)>, String> {
+        loop {
+            let rest = &self.line[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                if !self.next_line()? {
+                    return Ok(None);
+                }
+                continue;
+            }
+            let at = self.location();
+            let first = trimmed.chars().next().unwrap_or(' ');
+            let token = match first {
+                '<' => {
+                    let end = trimmed.find('>').ok_or("unterminated IRI")?;
+                    let iri = trimmed[1..end].to_string();
+                    self.pos += end + 1;
+                    Token::Iri(iri)
+                }
+                '"' | '\'' => Token::Literal(self.string(first)?),
+                ',' | ';' | '(' | ')' | '[' | ']' => {
+                    self.pos += 1;
+                    Token::Punct(first)
+                }
+                '.' if !trimmed[1..].starts_with(|c: char| c.is_ascii_digit()) => {
+                    self.pos += 1;
+                    Token::Punct('.')
+                }
+                _ => {
+                    let mut end = trimmed.find(is_delimiter).unwrap_or(trimmed.len());
+                    if end == 0 {
+                        self.pos += first.len_utf8();
+                        return Err(format!("unexpected '{}'", first));
+                    }
+                    // A name can't end in `.`; that's the end of the statement.
+                    while end > 1 && trimmed[..end].ends_with('.') {
+                        end -= 1;
+                    }
+                    let word = trimmed[..end].to_string();
+                    self.pos += end;
+                    if let Some(label) = word.strip_prefix("_:") {
+                        Token::Blank(label.to_string())
+                    } else if word.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) {
+                        Token::Literal(word)
+                    } else if word.contains(':') {
+                        Token::Name(word)
+                    } else {
+                        Token::Keyword(word)
+                    }
+                }
+            };
+            return Ok(Some((token, at)));
+        }
+    }
+
+    /// A quoted string starting at the current position; a trailing
+    /// `@lang` or `^^datatype` is dropped.
+    fn string(&mut self, quote: char) -> Result<String, String> {
+        let long: String = [quote; 3].iter().collect();
+        let is_long = self.line[self.pos..].starts_with(&long);
+        self.pos += if is_long { 3 } else { 1 };
+        let mut text = String::new();
+        loop {
+            let rest = &self.line[self.pos..];
+            let mut chars = rest.char_indices();
+            while let Some((i, c)) = chars.next() {
+                if c == '\\' {
+                    let escaped = match chars.next() {
+                        Some((_, 't')) => '\t',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((j, u @ ('u' | 'U'))) => {
+                            let len = if u == 'u' { 4 } else { 8 };
+                            let hex = rest.get(j + 1..j + 1 + len).ok_or("truncated escape")?;
+                            for _ in 0..len {
+                                chars.next();
+                            }
+                            u32::from_str_radix(hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or("invalid escape")?
+                        }
+                        Some((_, c)) => c,
+                        None => '\\',
+                    };
+                    text.push(escaped);
+                } else if is_long && rest[i..].starts_with(&long) {
+                    self.pos += i + 3;
+                    return self.suffix(text);
+                } else if !is_long && c == quote {
+                    self.pos += i + 1;
+                    return self.suffix(text);
+                } else {
+                    text.push(c);
+                }
+            }
+            if !is_long || !self.next_line()? {
+                return Err("unterminated string".to_string());
+            }
+        }
+    }
+
+    fn suffix(&mut self, text: String) -> Result<String, String> {
+        let rest = &self.line[self.pos..];
+        if let Some(tag) = rest.strip_prefix('@') {
+            let end = tag.find(|c| is_delimiter(c) || c == '.');
+            self.pos += 1 + end.unwrap_or(tag.len());
+        } else if rest.starts_with("^^") {
+            self.pos += 2;
+            self.scan()?;
+        }
+        Ok(text)
+    }
+}
+
+struct Parser<'g, 'r, R> {
+    lexer: Lexer<R>,
+    graph: &'g mut Graph<'r>,
+    prefixes: HashMap<String, String>,
+    base: String,
+}
+
+impl<R: BufRead> Parser<'_, '_, R> {
+    fn expect(&mut self, punct: char) -> Result<(), String> {
+        match self.lexer.next()? {
+            Some((Token::Punct(p), _)) if p == punct => Ok(()),
+            Some((token, _)) => Err(format!("expected '{}', found {:?}", punct, token)),
+            None => Err(format!("expected '{}'", punct)),
+        }
+    }
+
+    fn peek_punct(&mut self, punct: char) -> Result<bool, String> {
+        Ok(self.lexer.peek()? == Some(&Token::Punct(punct)))
+    }
+
+    fn iri(&self, token: Token) -> Result<String, String> {
+        match token {
+            Token::Iri(iri) => Ok(resolve_iri(&self.base, &iri)),
+            Token::Name(name) => {
+                let (prefix, local) = name.split_once(':').unwrap_or(("", &name));
+                let namespace = self
+                    .prefixes
+                    .get(prefix)
+                    .ok_or_else(|| format!("undeclared prefix '{}:'", prefix))?;
+                Ok(format!("{}{}", namespace, local.replace('\\', "")))
+            }
+            token => Err(format!("expected an IRI, found {:?}", token)),
+        }
+    }
+
+    /// A subject or object; `[ ... ]` and `( ... )` emit their own triples.
+    fn term(&mut self, token: Token, at: Location) -> Result<Term, String> {
+        Ok(match token {
+            Token::Blank(label) => Term::Blank(format!("n{}", label)),
+            Token::Literal(value) => Term::Literal(value),
+            Token::Keyword(word) if word == "true" || word == "false" => Term::Literal(word),
+            Token::Punct('[') => {
+                let node = self.graph.fresh_blank();
+                if !self.peek_punct(']')? {
+                    self.predicate_objects(&node)?;
+                }
+                self.expect(']')?;
+                node
+            }
+            Token::Punct('(') => {
+                let mut items = Vec::new();
+                while !self.peek_punct(')')? {
+                    let (token, at) = self.lexer.next()?.ok_or("unterminated collection")?;
+                    items.push(self.term(token, at)?);
+                }
+                self.expect(')')?;
+                let mut list = Term::Iri(rdf("nil"));
+                for item in items.into_iter().rev() {
+                    let node = self.graph.fresh_blank();
+                    self.graph.triple(node.clone(), &rdf("first"), item, at);
+                    self.graph.triple(node.clone(), &rdf("rest"), list, at);
+                    list = node;
+                }
+                list
+            }
+            token => Term::Iri(self.iri(token)?),
+        })
+    }
+
+    fn predicate_objects(&mut self, subject: &Term) -> Result<(), String> {
+        loop {
+            let (token, _) = self.lexer.next()?.ok_or("expected a predicate")?;
+            let predicate = match token {
+                Token::Keyword(a) if a == "a" => format!("{}type", RDF),
+                token => self.iri(token)?,
+            };
+            loop {
+                let (token, at) = self.lexer.next()?.ok_or("expected an object")?;
+                let object = self.term(token, at)?;
+                self.graph.triple(subject.clone(), &predicate, object, at);
+                if !self.peek_punct(',')? {
+                    break;
+                }
+                self.lexer.next()?;
+            }
+            // `;` may repeat and may end the list.
+            if !self.peek_punct(';')? {
+                return Ok(());
+            }
+            while self.peek_punct(';')? {
+                self.lexer.next()?;
+            }
+            if matches!(self.lexer.peek()?, Some(Token::Punct('.' | ']')) | None) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn directive(&mut self, keyword: &str) -> Result<(), String> {
+        let sparql = !keyword.starts_with('@');
+        match keyword.to_ascii_lowercase().trim_start_matches('@') {
+            "prefix" => {
+                let (token, _) = self.lexer.next()?.ok_or("expected a prefix")?;
+                let Token::Name(prefix) = token else {
+                    return Err(format!("expected a prefix, found {:?}", token));
+                };
+                let prefix = prefix
+                    .strip_suffix(':')
+                    .ok_or("expected a prefix")?
+                    .to_string();
+                let (token, _) = self.lexer.next()?.ok_or("expected an IRI")?;
+                let iri = self.iri(token)?;
+                self.graph.prefix(&prefix, &iri);
+                self.prefixes.insert(prefix, iri);
+            }
+            "base" => {
+                let (token, _) = self.lexer.next()?.ok_or("expected an IRI")?;
+                self.base = self.iri(token)?;
+            }
+            _ => return Err(format!("unexpected '{}'", keyword)),
+        }
+        if !sparql {
+            self.expect('.')?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, token: Token, at: Location) -> Result<(), String> {
+        match token {
+            Token::Keyword(k)
+                if k.starts_with('@')
+                    || k.eq_ignore_ascii_case("prefix")
+                    || k.eq_ignore_ascii_case("base") =>
+            {
+                self.directive(&k)
+            }
+            token => {
+                let bare = token == Token::Punct('[');
+                let subject = self.term(token, at)?;
+                // `[ ... ] .` on its own is a complete statement.
+                if !(bare && self.peek_punct('.')?) {
+                    self.predicate_objects(&subject)?;
+                }
+                self.expect('.')
+            }
+        }
+    }
+
+    /// Skips to just after the next `.`, to resynchronize after an error.
+    fn recover(&mut self) {
+        let mut at = self.lexer.position();
+        while let Ok(Some((token, _))) = self.lexer.next() {
+            if token == Token::Punct('.') || self.lexer.position() == at {
+                return;
+            }
+            at = self.lexer.position();
+        }
+    }
+}
+
+/// Parses the EL fragment of a Turtle document. Everything skipped is
+/// reported in the returned warnings.
+pub fn parse_turtle<R: BufRead>(
+    reader: R,
+    resolver: &dyn IdResolver,
+) -> Result<(ParseResult, Vec<Warning>), ParseError> {
+    let mut graph = Graph::new(resolver);
+    let mut parser = Parser {
+        lexer: Lexer::new(reader),
+        graph: &mut graph,
+        prefixes: HashMap::new(),
+        base: String::new(),
+    };
+    loop {
+        let (token, at) = match parser.lexer.next() {
+            Ok(Some(next)) => next,
+            Ok(None) => break,
+            Err(message) => {
+                let at = parser.lexer.location();
+                parser.graph.warn(WarningKind::MalformedLine, at, message);
+                parser.recover();
+                continue;
+            }
+        };
+        if let Err(message) = parser.statement(token, at) {
+            parser.graph.warn(WarningKind::MalformedLine, at, message);
+            parser.recover();
+        }
+    }
+    if let Some(error) = parser.lexer.failed {
+        return Err(error);
+    }
+    Ok(graph.finish())
+}
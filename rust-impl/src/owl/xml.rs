@@ -0,0 +1,305 @@
+//! A small pull parser for the XML that RDF/XML documents are written in.
+//!
+//! Elements and attributes come out with their namespaces expanded
+//! (`rdf:about` becomes `http://www.w3.org/1999/02/22-rdf-syntax-ns#about`);
+//! unprefixed attributes keep their bare name. Entities declared in the
+//! DOCTYPE internal subset (`<!ENTITY obo "...">`, as the OWL API writes
+//! them) are expanded. Validation, external entities and processing
+//! instructions are out of scope.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::obo::{Location, ParseError};
+
+pub const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    Start {
+        name: String,
+        attrs: Vec<(String, String)>,
+    },
+    /// Closes the innermost element; also emitted right after `<empty/>`.
+    End,
+    Text(String),
+}
+
+pub(crate) struct XmlReader<R> {
+    reader: R,
+    line: usize,
+    buf: Vec<u8>,
+    /// A `<` was consumed at the end of the last text run.
+    in_tag: bool,
+    /// The last start tag was self-closing.
+    pending_end: bool,
+    entities: HashMap<String, String>,
+    /// `(prefix, namespace)` declarations in scope, innermost last.
+    namespaces: Vec<(String, String)>,
+    /// Length of `namespaces` outside each open element.
+    scopes: Vec<usize>,
+    /// Set when the reader fails; the input ends there.
+    pub failed: Option<ParseError>,
+}
+
+impl<R: BufRead> XmlReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: 1,
+            buf: Vec::new(),
+            in_tag: false,
+            pending_end: false,
+            entities: HashMap::new(),
+            namespaces: vec![("xml".to_string(), XML.to_string())],
+            scopes: Vec::new(),
+            failed: None,
+        }
+    }
+
+    /// `(prefix, namespace)` declarations in scope, innermost last.
+    pub fn namespaces(&self) -> &[(String, String)] {
+        &self.namespaces
+    }
+
+    pub fn location(&self) -> Location {
+        Location {
+            line: self.line,
+            column: 1,
+        }
+    }
+
+    /// Appends input up to and including `end` to `buf`; false at the end
+    /// of the input.
+    fn read_until(&mut self, end: u8) -> Result<bool, String> {
+        let start = self.buf.len();
+        let n = match self.reader.read_until(end, &mut self.buf) {
+            Ok(n) => n,
+            Err(source) => {
+                let location = self.location();
+                let message = source.to_string();
+                self.failed = Some(ParseError { location, source });
+                return Err(message);
+            }
+        };
+        self.line += self.buf[start..].iter().filter(|&&b| b == b'\n').count();
+        Ok(n > 0 && self.buf.last() == Some(&end))
+    }
+
+    /// Reads into `buf` until it ends with `terminator`.
+    fn read_through(&mut self, terminator: &[u8]) -> Result<(), String> {
+        let last = *terminator.last().unwrap();
+        while !self.buf.ends_with(terminator) {
+            if !self.read_until(last)? {
+                return Err(format!(
+                    "expected '{}'",
+                    String::from_utf8_lossy(terminator)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The next event, `None` at the end of the document.
+    pub fn next(&mut self) -> Result<Option<(Event, Location)>, String> {
+        loop {
+            if self.pending_end {
+                self.pending_end = false;
+                return Ok(Some((self.end(), self.location())));
+            }
+            let at = self.location();
+            self.buf.clear();
+            if !self.in_tag {
+                self.in_tag = self.read_until(b'<')?;
+                if self.in_tag {
+                    self.buf.pop();
+                }
+                if self.buf.is_empty() {
+                    if !self.in_tag {
+                        return Ok(None);
+                    }
+                    continue;
+                }
+                let text = String::from_utf8_lossy(&self.buf).into_owned();
+                return Ok(Some((Event::Text(self.unescape(&text)), at)));
+            }
+            self.in_tag = false;
+            if !self.read_until(b'>')? {
+                return Err("unterminated tag".to_string());
+            }
+            match self.buf[0] {
+                b'?' => self.read_through(b"?>")?,
+                b'!' if self.buf.starts_with(b"!--") => self.read_through(b"-->")?,
+                b'!' if self.buf.starts_with(b"![CDATA[") => {
+                    self.read_through(b"]]>")?;
+                    let text = &self.buf[8..self.buf.len() - 3];
+                    return Ok(Some((
+                        Event::Text(String::from_utf8_lossy(text).into_owned()),
+                        at,
+                    )));
+                }
+                b'!' => self.doctype()?,
+                b'/' => return Ok(Some((self.end(), at))),
+                _ => {
+                    while !quotes_balanced(&self.buf) {
+                        if !self.read_until(b'>')? {
+                            return Err("unterminated tag".to_string());
+                        }
+                    }
+                    return self.start().map(|e| Some((e, at)));
+                }
+            }
+        }
+    }
+
+    fn end(&mut self) -> Event {
+        if let Some(len) = self.scopes.pop() {
+            self.namespaces.truncate(len);
+        }
+        Event::End
+    }
+
+    /// Reads a `<!DOCTYPE ...>` declaration, keeping its entities.
+    fn doctype(&mut self) -> Result<(), String> {
+        let open = self.buf.iter().position(|&b| b == b'[');
+        if open.is_some_and(|i| !self.buf[i..].contains(&b']')) {
+            self.read_through(b"]>")?;
+        }
+        let text = String::from_utf8_lossy(&self.buf).into_owned();
+        let mut rest = text.as_str();
+        while let Some(i) = rest.find("<!ENTITY") {
+            rest = rest[i + 8..].trim_start();
+            let name_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let name = rest[..name_end].to_string();
+            rest = rest[name_end..].trim_start();
+            let Some(quote) = rest.chars().next().filter(|&q| q == '"' || q == '\'') else {
+                continue;
+            };
+            let Some(len) = rest[1..].find(quote) else {
+                break;
+            };
+            let value = self.unescape(&rest[1..1 + len]);
+            self.entities.insert(name, value);
+            rest = &rest[1 + len..];
+        }
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<Event, String> {
+        let tag = String::from_utf8_lossy(&self.buf[..self.buf.len() - 1]).into_owned();
+        let (tag, empty) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag.as_str(), false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = &tag[..name_end];
+
+        let mut raw = Vec::new();
+        let mut rest = tag[name_end..].trim_start();
+        while !rest.is_empty() {
+            let (key, value) = rest
+                .split_once('=')
+                .ok_or_else(|| format!("malformed attribute in <{}>", name))?;
+            let value = value.trim_start();
+            let quote = value.chars().next().filter(|&q| q == '"' || q == '\'');
+            let Some(quote) = quote else {
+                return Err(format!("unquoted attribute in <{}>", name));
+            };
+            let len = value[1..]
+                .find(quote)
+                .ok_or_else(|| format!("unterminated attribute in <{}>", name))?;
+            raw.push((key.trim().to_string(), self.unescape(&value[1..1 + len])));
+            rest = value[len + 2..].trim_start();
+        }
+
+        self.scopes.push(self.namespaces.len());
+        for (key, value) in &raw {
+            if key == "xmlns" {
+                self.namespaces.push((String::new(), value.clone()));
+            } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+                self.namespaces.push((prefix.to_string(), value.clone()));
+            }
+        }
+        let name = self.expand(name, true)?;
+        let mut attrs = Vec::with_capacity(raw.len());
+        for (key, value) in raw {
+            if key != "xmlns" && !key.starts_with("xmlns:") {
+                attrs.push((self.expand(&key, false)?, value));
+            }
+        }
+        self.pending_end = empty;
+        Ok(Event::Start { name, attrs })
+    }
+
+    /// `prefix:local` with the prefix's namespace in place of the prefix.
+    /// Unprefixed element names take the default namespace.
+    fn expand(&self, name: &str, element: bool) -> Result<String, String> {
+        let (prefix, local) = match name.split_once(':') {
+            Some((prefix, local)) => (prefix, local),
+            None if element => ("", name),
+            None => return Ok(name.to_string()),
+        };
+        match self.namespaces.iter().rev().find(|(p, _)| p == prefix) {
+            Some((_, ns)) => Ok(format!("{}{}", ns, local)),
+            None if prefix.is_empty() => Ok(name.to_string()),
+            None => Err(format!("undeclared namespace prefix '{}'", prefix)),
+        }
+    }
+
+    fn unescape(&self, text: &str) -> String {
+        if !text.contains('&') {
+            return text.to_string();
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('&') {
+            out.push_str(&rest[..i]);
+            rest = &rest[i..];
+            let Some(end) = rest.find(';') else {
+                break;
+            };
+            let entity = &rest[1..end];
+            let expanded = match entity {
+                "lt" => Some('<'.to_string()),
+                "gt" => Some('>'.to_string()),
+                "amp" => Some('&'.to_string()),
+                "quot" => Some('"'.to_string()),
+                "apos" => Some('\''.to_string()),
+                _ => match entity.strip_prefix('#') {
+                    Some(code) => {
+                        let code = match code.strip_prefix('x') {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                            None => code.parse().ok(),
+                        };
+                        code.and_then(char::from_u32).map(String::from)
+                    }
+                    None => self.entities.get(entity).cloned(),
+                },
+            };
+            match expanded {
+                Some(expanded) => {
+                    out.push_str(&expanded);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+fn quotes_balanced(tag: &[u8]) -> bool {
+    let mut quote = None;
+    for &b in tag {
+        match quote {
+            Some(q) if b == q => quote = None,
+            None if b == b'"' || b == b'\'' => quote = Some(b),
+            _ => {}
+        }
+    }
+    quote.is_none()
+}
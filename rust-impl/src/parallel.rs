@@ -0,0 +1,440 @@
+//! Saturation on several threads, ELK-style: every context belongs to one
+//! worker (concept `c` to worker `c % threads`) and only that worker changes
+//! it. A rule whose conclusion lands in another worker's context is sent
+//! there as a message:
+//!
+//! - a new super concept of a context (CR1–CR5 across a link);
+//! - a link to be made from another worker's context (CR10, CR11);
+//! - a link just made, for the worker owning its target, which records the
+//!   predecessor and applies the rules that read the target (CR4 forward,
+//!   CR5, and CR11 with the link as first half).
+//!
+//! Every rule reading two contexts runs in the worker owning the one it
+//! reads the most recent change of, so each combination of facts is seen by
+//! whichever side learns about it second. The super sets and links come out
+//! as those of [`saturate`]; the order of links and predecessors may differ.
+
+use std::mem;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::kernel::{report_derived, saturate, AxiomStore, ConceptId, Context, RoleId, Rule, BOTTOM, TOP};
+use crate::taxonomy::Reducer;
+
+/// Messages buffered per destination before they are handed over.
+const BATCH: usize = 256;
+
+/// Concepts a taxonomy worker takes at a time.
+const REDUCTION_BLOCK: usize = 1 << 12;
+
+enum Message {
+    /// `added` belongs in the super set of `concept`, by `rule`.
+    Super { concept: ConceptId, added: ConceptId, rule: Rule },
+    /// `source -role-> target` follows by `rule`; made by the owner of
+    /// `source`.
+    Link { source: ConceptId, role: RoleId, target: ConceptId, rule: Rule },
+    /// `source -role-> target` was made; for the owner of `target`.
+    Pred { source: ConceptId, role: RoleId, target: ConceptId },
+}
+
+struct Shared {
+    inboxes: Vec<Mutex<Vec<Message>>>,
+    /// Messages handed over but not yet taken, plus busy workers; saturation
+    /// is done when it reaches zero.
+    pending: AtomicUsize,
+}
+
+struct Worker<'a> {
+    id: usize,
+    threads: usize,
+    store: &'a AxiomStore,
+    num_roles: usize,
+    track_bottom: bool,
+    /// Contexts of concepts `id`, `id + threads`, `id + 2 * threads`, …
+    contexts: Vec<Context>,
+    worklist: Vec<(ConceptId, ConceptId)>,
+    link_worklist: Vec<(ConceptId, RoleId, ConceptId)>,
+    outboxes: Vec<Vec<Message>>,
+    shared: &'a Shared,
+    /// The rule being applied, and the new facts by [`Rule`] discriminant
+    /// this worker's contexts got.
+    rule: Rule,
+    derived: [usize; Rule::ALL.len()],
+}
+
+impl<'a> Worker<'a> {
+    fn owner(&self, c: ConceptId) -> usize {
+        c as usize % self.threads
+    }
+
+    fn ctx(&mut self, c: ConceptId) -> &mut Context {
+        &mut self.contexts[c as usize / self.threads]
+    }
+
+    fn send(&mut self, to: usize, message: Message) {
+        self.outboxes[to].push(message);
+        if self.outboxes[to].len() >= BATCH {
+            self.flush(to);
+        }
+    }
+
+    fn flush(&mut self, to: usize) {
+        let messages = &mut self.outboxes[to];
+        if messages.is_empty() {
+            return;
+        }
+        self.shared.pending.fetch_add(messages.len(), Ordering::SeqCst);
+        self.shared.inboxes[to].lock().unwrap().append(messages);
+    }
+
+    fn derive(&mut self, concept: ConceptId, added: ConceptId) {
+        let owner = self.owner(concept);
+        if owner != self.id {
+            self.send(owner, Message::Super { concept, added, rule: self.rule });
+        } else if self.ctx(concept).super_set.insert(added) {
+            self.worklist.push((concept, added));
+            self.derived[self.rule as usize] += 1;
+        }
+    }
+
+    fn link(&mut self, source: ConceptId, role: RoleId, target: ConceptId) {
+        let owner = self.owner(source);
+        if owner != self.id {
+            self.send(owner, Message::Link { source, role, target, rule: self.rule });
+            return;
+        }
+        let targets = &mut self.ctx(source).link_map[role as usize];
+        if !targets.contains(&target) {
+            targets.push(target);
+            self.link_worklist.push((source, role, target));
+            self.derived[self.rule as usize] += 1;
+        }
+    }
+
+    fn receive(&mut self, message: Message) {
+        match message {
+            Message::Super { concept, added, rule } => {
+                self.rule = rule;
+                self.derive(concept, added)
+            }
+            Message::Link { source, role, target, rule } => {
+                self.rule = rule;
+                self.link(source, role, target)
+            }
+            Message::Pred { source, role, target } => self.pred(source, role, target),
+        }
+    }
+
+    fn process(&mut self, c: ConceptId, d: ConceptId) {
+        let store = self.store;
+        let d_usize = d as usize;
+
+        // CR1
+        self.rule = Rule::CR1;
+        if d_usize < store.sub_to_sups.len() {
+            for &e in &store.sub_to_sups[d_usize] {
+                self.derive(c, e);
+            }
+        }
+
+        // CR2
+        self.rule = Rule::CR2;
+        if d_usize < store.conj_index.len() {
+            for (&d2, results) in &store.conj_index[d_usize] {
+                if self.ctx(c).super_set.contains(&d2) {
+                    for &e in results {
+                        self.derive(c, e);
+                    }
+                }
+            }
+        }
+
+        // CR3
+        self.rule = Rule::CR3;
+        if d_usize < store.exist_right.len() {
+            for rf in &store.exist_right[d_usize] {
+                self.link(c, rf.role, rf.fill);
+            }
+        }
+
+        // CR12
+        self.rule = Rule::CR12;
+        if d == c {
+            for &r in &store.reflexive_roles {
+                self.link(c, r, c);
+            }
+        }
+
+        // CR5 backward
+        self.rule = Rule::CR5;
+        if d == BOTTOM && self.track_bottom {
+            for r in 0..self.num_roles {
+                let preds = self.ctx(c).pred_map[r].clone();
+                for pred in preds {
+                    self.derive(pred, BOTTOM);
+                }
+            }
+        }
+
+        // CR4 backward
+        self.rule = Rule::CR4;
+        for r in 0..self.num_roles {
+            let Some(sups) = store.exist_left.get(r).and_then(|by_fill| by_fill.get(&d)) else {
+                continue;
+            };
+            let preds = self.ctx(c).pred_map[r].clone();
+            for pred in preds {
+                for &f in sups {
+                    self.derive(pred, f);
+                }
+            }
+        }
+    }
+
+    /// A link just made from the local context `c`.
+    fn process_link(&mut self, c: ConceptId, r: RoleId, e: ConceptId) {
+        let store = self.store;
+        let r_usize = r as usize;
+
+        let owner = self.owner(e);
+        if owner == self.id {
+            self.pred(c, r, e);
+        } else {
+            self.send(owner, Message::Pred { source: c, role: r, target: e });
+        }
+
+        // CR10
+        self.rule = Rule::CR10;
+        if r_usize < store.role_sub_to_sups.len() {
+            for &s in &store.role_sub_to_sups[r_usize] {
+                self.link(c, s, e);
+            }
+        }
+
+        // CR11, with this link as the second half
+        self.rule = Rule::CR11;
+        if r_usize < store.chains_by_second.len() {
+            for &(q, t) in &store.chains_by_second[r_usize] {
+                let preds = self.ctx(c).pred_map[q as usize].clone();
+                for p in preds {
+                    self.link(p, t, e);
+                }
+            }
+        }
+        if r_usize < store.right_identities_by_second.len() {
+            for &q in &store.right_identities_by_second[r_usize] {
+                let preds = self.ctx(c).pred_map[q as usize].clone();
+                for p in preds {
+                    self.link(p, q, e);
+                }
+            }
+            for &s in &store.left_identities[r_usize] {
+                let preds = self.ctx(c).pred_map[s as usize].clone();
+                for p in preds {
+                    self.link(p, r, e);
+                }
+            }
+        }
+    }
+
+    /// `c -r-> e` was made, `e` being local.
+    fn pred(&mut self, c: ConceptId, r: RoleId, e: ConceptId) {
+        let store = self.store;
+        let r_usize = r as usize;
+        self.ctx(e).pred_map[r_usize].push(c);
+
+        // CR4 forward
+        self.rule = Rule::CR4;
+        if r_usize < store.exist_left.len() && !store.exist_left[r_usize].is_empty() {
+            let supers: Vec<ConceptId> = self.ctx(e).super_set.iter().copied().collect();
+            for f in supers {
+                if let Some(sups) = store.exist_left[r_usize].get(&f) {
+                    for &g in sups {
+                        self.derive(c, g);
+                    }
+                }
+            }
+        }
+
+        // CR5
+        self.rule = Rule::CR5;
+        if self.track_bottom && self.ctx(e).super_set.contains(&BOTTOM) {
+            self.derive(c, BOTTOM);
+        }
+
+        // CR11, with this link as the first half
+        self.rule = Rule::CR11;
+        if r_usize < store.chains_by_first.len() {
+            for &(s, t) in &store.chains_by_first[r_usize] {
+                let targets = self.ctx(e).link_map[s as usize].clone();
+                for x in targets {
+                    self.link(c, t, x);
+                }
+            }
+        }
+        if r_usize < store.right_identities.len() {
+            for &s in &store.right_identities[r_usize] {
+                let targets = self.ctx(e).link_map[s as usize].clone();
+                for x in targets {
+                    self.link(c, r, x);
+                }
+            }
+            for &t in &store.left_identities_by_first[r_usize] {
+                let targets = self.ctx(e).link_map[t as usize].clone();
+                for x in targets {
+                    self.link(c, t, x);
+                }
+            }
+        }
+    }
+
+    fn run(&mut self) {
+        let shared = self.shared;
+        loop {
+            while !self.worklist.is_empty() || !self.link_worklist.is_empty() {
+                while let Some((c, d)) = self.worklist.pop() {
+                    self.process(c, d);
+                }
+                while let Some((c, r, e)) = self.link_worklist.pop() {
+                    self.process_link(c, r, e);
+                }
+            }
+            for to in 0..self.threads {
+                self.flush(to);
+            }
+
+            let messages = mem::take(&mut *shared.inboxes[self.id].lock().unwrap());
+            if !messages.is_empty() {
+                let taken = messages.len();
+                for message in messages {
+                    self.receive(message);
+                }
+                shared.pending.fetch_sub(taken, Ordering::SeqCst);
+                continue;
+            }
+
+            // Idle: done once nothing is in flight and no worker is busy.
+            shared.pending.fetch_sub(1, Ordering::SeqCst);
+            loop {
+                if shared.pending.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                if !shared.inboxes[self.id].lock().unwrap().is_empty() {
+                    shared.pending.fetch_add(1, Ordering::SeqCst);
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+    }
+}
+
+/// [`saturate`] on `threads` worker threads.
+pub fn saturate_parallel(
+    store: &AxiomStore,
+    num_concepts: usize,
+    num_roles: usize,
+    threads: NonZeroUsize,
+) -> Vec<Context> {
+    let threads = threads.get().min(num_concepts.max(1));
+    // Nominal merging reads every context at once; leave it to one thread.
+    if threads == 1 || !store.nominals.is_empty() {
+        return saturate(store, num_concepts, num_roles);
+    }
+
+    let shared = Shared {
+        inboxes: (0..threads).map(|_| Mutex::new(Vec::new())).collect(),
+        pending: AtomicUsize::new(threads),
+    };
+    let track_bottom = store.derives_bottom();
+    let shards: Vec<(Vec<Context>, [usize; Rule::ALL.len()])> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|id| {
+                let shared = &shared;
+                scope.spawn(move || {
+                    let mut worker = Worker {
+                        id,
+                        threads,
+                        store,
+                        num_roles,
+                        track_bottom,
+                        contexts: Vec::new(),
+                        worklist: Vec::new(),
+                        link_worklist: Vec::new(),
+                        outboxes: (0..threads).map(|_| Vec::new()).collect(),
+                        shared,
+                        rule: Rule::Init,
+                        derived: [0; Rule::ALL.len()],
+                    };
+                    for c in (id..num_concepts).step_by(threads) {
+                        let c = c as ConceptId;
+                        let mut ctx = Context::new(c, num_roles);
+                        ctx.super_set.insert(c);
+                        ctx.super_set.insert(TOP);
+                        worker.contexts.push(ctx);
+                        worker.worklist.push((c, c));
+                        worker.worklist.push((c, TOP));
+                    }
+                    worker.run();
+                    (worker.contexts, worker.derived)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut derived = [0; Rule::ALL.len()];
+    for (_, shard) in &shards {
+        for (total, n) in derived.iter_mut().zip(shard) {
+            *total += n;
+        }
+    }
+    report_derived(&derived);
+
+    let mut shards: Vec<_> = shards.into_iter().map(|(contexts, _)| contexts.into_iter()).collect();
+    (0..num_concepts).map(|c| shards[c % threads].next().unwrap()).collect()
+}
+
+/// [`build_taxonomy_with`](crate::build_taxonomy_with) on `threads`
+/// threads, each taking blocks of concepts until none are left.
+pub fn build_taxonomy_parallel(
+    contexts: &[Context],
+    num_concepts: usize,
+    equivalences: &[Vec<ConceptId>],
+    threads: NonZeroUsize,
+) -> Vec<Vec<ConceptId>> {
+    build_taxonomy_parallel_among(contexts, &vec![true; num_concepts], equivalences, threads)
+}
+
+/// [`build_taxonomy_parallel`] over the concepts `c` with `named[c]` set;
+/// see [`build_taxonomy_among`](crate::build_taxonomy_among).
+pub fn build_taxonomy_parallel_among(
+    contexts: &[Context],
+    named: &[bool],
+    equivalences: &[Vec<ConceptId>],
+    threads: NonZeroUsize,
+) -> Vec<Vec<ConceptId>> {
+    let num_concepts = named.len();
+    let reducer = Reducer::new(contexts, named, equivalences);
+    let mut direct_parents: Vec<Vec<ConceptId>> = vec![Vec::new(); num_concepts];
+    let blocks = Mutex::new(direct_parents.chunks_mut(REDUCTION_BLOCK).enumerate());
+    thread::scope(|scope| {
+        for _ in 0..threads.get().min(num_concepts.div_ceil(REDUCTION_BLOCK).max(1)) {
+            let (reducer, blocks) = (&reducer, &blocks);
+            scope.spawn(move || {
+                let mut covered = vec![0; num_concepts];
+                loop {
+                    let Some((i, block)) = blocks.lock().unwrap().next() else { break };
+                    let start = i * REDUCTION_BLOCK;
+                    for (c, parents) in (start..).zip(block).filter(|&(c, _)| c >= 2) {
+                        *parents = reducer.direct_parents(c as ConceptId, &mut covered);
+                    }
+                }
+            });
+        }
+    });
+    reducer.share_parents(&mut direct_parents);
+    direct_parents
+}
@@ -0,0 +1,245 @@
+//! Parsing and saturation overlapped on two threads.
+//!
+//! The parser streams each `[Term]` stanza's axioms to a saturation thread,
+//! which saturates with whatever has been read so far. In OBO every axiom
+//! sits in the stanza of its left-hand term, so when a stanza arrives the
+//! only contexts it can extend are those already holding that term (it was
+//! referenced before it was declared); the thread remembers those holders
+//! and revisits them. Saturation is monotone, so the end result equals
+//! saturating the finished store.
+//!
+//! `intersection_of` definitions are normalized once parsing is done, and
+//! every context holding a concept they mention is revisited then.
+//!
+//! A term with two stanzas, the first axiom that can derive BOTTOM after
+//! saturation has started (`disjoint_from` included), or `[Typedef]` role inclusions and chains (which
+//! apply to links already made) break that bookkeeping; the early work is then
+//! discarded and the whole store is saturated after parsing.
+
+use std::io::BufRead;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::kernel::{
+    resume, saturate, AxiomStore, ConceptId, Context, RoleId, TraceEvent, BOTTOM, TOP,
+};
+use crate::obo::{
+    add_definitions, add_nominals, parse_obo_observed, ParseError, ParseResult, Warning,
+};
+use crate::resolve::IdResolver;
+
+/// Stanzas sent to the saturation thread per message.
+const BATCH: usize = 4096;
+
+pub struct Pipelined {
+    pub parsed: ParseResult,
+    pub warnings: Vec<Warning>,
+    pub store: AxiomStore,
+    pub contexts: Vec<Context>,
+    /// Worklist items processed from streamed stanzas, ahead of the final pass.
+    pub early: usize,
+}
+
+struct OwnedStanza {
+    term: usize,
+    subsumptions: Vec<(usize, usize)>,
+    relations: Vec<(usize, usize, usize)>,
+}
+
+struct Batch {
+    stanzas: Vec<OwnedStanza>,
+    num_concepts: usize,
+    num_roles: usize,
+}
+
+/// Saturation-side state: the growing store, and for every term whose
+/// stanza has not arrived yet, the contexts that already hold it.
+struct Saturator {
+    store: AxiomStore,
+    contexts: Vec<Context>,
+    num_roles: usize,
+    stanza_done: Vec<bool>,
+    holders: Vec<Vec<ConceptId>>,
+    derives_bottom: bool,
+    invalidated: bool,
+    early: usize,
+}
+
+impl Saturator {
+    fn new() -> Self {
+        Self {
+            store: AxiomStore::new(0, 0),
+            contexts: Vec::new(),
+            num_roles: 0,
+            stanza_done: Vec::new(),
+            holders: Vec::new(),
+            derives_bottom: false,
+            invalidated: false,
+            early: 0,
+        }
+    }
+
+    /// Makes room for new concepts and roles; returns the new concepts.
+    fn grow(&mut self, num_concepts: usize, num_roles: usize) -> std::ops::Range<ConceptId> {
+        if num_roles > self.num_roles {
+            self.store.grow_roles(num_roles);
+            for ctx in &mut self.contexts {
+                ctx.link_map.resize_with(num_roles, Vec::new);
+                ctx.pred_map.resize_with(num_roles, Vec::new);
+            }
+            self.num_roles = num_roles;
+        }
+        let from = self.contexts.len();
+        if num_concepts > from {
+            self.store.grow(num_concepts);
+            self.contexts
+                .extend((from..num_concepts).map(|c| Context::new(c as ConceptId, self.num_roles)));
+            self.stanza_done.resize(num_concepts, false);
+            self.holders.resize_with(num_concepts, Vec::new);
+        }
+        from as ConceptId..self.contexts.len() as ConceptId
+    }
+
+    fn add(&mut self, batch: Batch) {
+        let new_concepts = self.grow(batch.num_concepts, batch.num_roles);
+        let mut revisit: Vec<(ConceptId, ConceptId)> = Vec::new();
+        for stanza in batch.stanzas {
+            let t = stanza.term;
+            let foreign = stanza.subsumptions.iter().any(|&(sub, _)| sub != t)
+                || stanza.relations.iter().any(|&(sub, _, _)| sub != t);
+            if self.stanza_done[t] || foreign {
+                self.invalidated = true;
+            }
+            self.stanza_done[t] = true;
+            for &(sub, sup) in &stanza.subsumptions {
+                self.store.add_subsumption(sub as ConceptId, sup as ConceptId);
+                self.derives_bottom |= sup == BOTTOM as usize;
+            }
+            for &(sub, role, target) in &stanza.relations {
+                self.store.add_exist_right(sub as ConceptId, role as RoleId, target as ConceptId);
+                self.derives_bottom |= target == BOTTOM as usize;
+            }
+            let holders = std::mem::take(&mut self.holders[t]);
+            revisit.extend(holders.into_iter().map(|c| (c, t as ConceptId)));
+        }
+        if self.derives_bottom && self.early > 0 {
+            // CR5 was skipped for the links made so far.
+            self.invalidated = true;
+        }
+        if self.invalidated {
+            return;
+        }
+        self.run(new_concepts, revisit);
+    }
+
+    fn run(&mut self, seeds: std::ops::Range<ConceptId>, revisit: Vec<(ConceptId, ConceptId)>) {
+        let contexts = std::mem::take(&mut self.contexts);
+        let (stanza_done, holders) = (&self.stanza_done, &mut self.holders);
+        let mut processed = 0;
+        self.contexts = resume(&self.store, contexts, self.num_roles, seeds, revisit, |event| {
+            processed += 1;
+            if let TraceEvent::Concept { concept, added } = event {
+                if !stanza_done[added as usize] {
+                    holders[added as usize].push(concept);
+                }
+            }
+        });
+        self.early += processed;
+    }
+
+    fn finish(mut self, parsed: &ParseResult) -> (AxiomStore, Vec<Context>, usize) {
+        let (num_concepts, num_roles) = (parsed.concepts.len(), parsed.roles.len());
+        let new_concepts = self.grow(num_concepts, num_roles);
+        for &(sub, sup) in &parsed.role_subsumptions {
+            self.store.add_role_subsumption(sub as RoleId, sup as RoleId);
+            self.invalidated |= self.early > 0;
+        }
+        for &(first, second, sup) in &parsed.role_chains {
+            self.store.add_role_chain(first as RoleId, second as RoleId, sup as RoleId);
+            self.invalidated |= self.early > 0;
+        }
+        for &(a, b) in &parsed.disjoint_pairs {
+            self.store.add_conjunction(a as ConceptId, b as ConceptId, BOTTOM);
+            self.invalidated |= self.early > 0;
+        }
+        for &role in &parsed.reflexive_roles {
+            self.store.add_reflexive(role as RoleId);
+            self.invalidated |= self.early > 0;
+        }
+        add_nominals(parsed, &mut self.store);
+        self.invalidated |= !parsed.nominals.is_empty() && self.early > 0;
+        for &(role, domain) in &parsed.role_domains {
+            self.store.add_exist_left(role as RoleId, TOP, domain as ConceptId);
+            self.invalidated |= self.early > 0;
+        }
+        // Ranges replace the fillers of existentials saturated so far.
+        self.invalidated |= !parsed.role_ranges.is_empty() && self.early > 0;
+        // Definitions are only normalized now, after every told axiom, so
+        // contexts holding any concept they mention are revisited.
+        add_definitions(parsed, &mut self.store);
+        let fresh = self.grow(self.store.num_concepts(), num_roles);
+        let mut touched = vec![false; fresh.end as usize];
+        for c in parsed.definitions.iter().flat_map(|d| d.signature()) {
+            touched[c] = true;
+            self.invalidated |= c == BOTTOM as usize && self.early > 0;
+        }
+        if self.invalidated {
+            let contexts = saturate(&self.store, self.store.num_concepts(), num_roles);
+            return (self.store, contexts, 0);
+        }
+        let mut revisit: Vec<(ConceptId, ConceptId)> = Vec::new();
+        if !parsed.definitions.is_empty() {
+            for ctx in &self.contexts[..new_concepts.start as usize] {
+                let held = ctx.super_set.iter().filter(|&&c| touched[c as usize]);
+                revisit.extend(held.map(|&c| (ctx.id, c)));
+            }
+        }
+        // Terms never declared keep their holders: they have no axioms.
+        let early = self.early;
+        self.run(new_concepts.start..fresh.end, revisit);
+        (self.store, self.contexts, early)
+    }
+}
+
+/// Parses `reader` on the calling thread while saturating what has been read
+/// so far on a second one. The store and contexts equal those of `build_axiom_store`
+/// followed by `saturate` on the parse result.
+pub fn parse_and_saturate<R: BufRead>(
+    reader: R,
+    resolver: &dyn IdResolver,
+) -> Result<Pipelined, ParseError> {
+    let (sender, receiver) = mpsc::sync_channel::<Batch>(4);
+    thread::scope(|scope| {
+        let worker = scope.spawn(move || {
+            let mut saturator = Saturator::new();
+            for batch in receiver {
+                saturator.add(batch);
+            }
+            saturator
+        });
+
+        let mut pending: Vec<OwnedStanza> = Vec::with_capacity(BATCH);
+        let mut counts = (2, 0);
+        let parsed = parse_obo_observed(reader, resolver, &mut |stanza| {
+            pending.push(OwnedStanza {
+                term: stanza.term,
+                subsumptions: stanza.subsumptions.to_vec(),
+                relations: stanza.relations.to_vec(),
+            });
+            counts = (stanza.num_concepts, stanza.num_roles);
+            if pending.len() == BATCH {
+                let stanzas = std::mem::replace(&mut pending, Vec::with_capacity(BATCH));
+                let batch = Batch { stanzas, num_concepts: counts.0, num_roles: counts.1 };
+                // A failed send means the worker panicked; join reports it.
+                let _ = sender.send(batch);
+            }
+        });
+        let _ = sender.send(Batch { stanzas: pending, num_concepts: counts.0, num_roles: counts.1 });
+        drop(sender);
+
+        let saturator = worker.join().expect("saturation thread panicked");
+        let (parsed, warnings) = parsed?;
+        let (store, contexts, early) = saturator.finish(&parsed);
+        Ok(Pipelined { parsed, warnings, store, contexts, early })
+    })
+}
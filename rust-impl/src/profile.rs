@@ -0,0 +1,145 @@
+//! Axioms outside OWL 2 EL: what the front-ends left out of a
+//! [`ParseResult`], what to do about them (`--non-el`) and a report of them
+//! by construct.
+//!
+//! Every [`Violation`] also has a warning of its own. With
+//! [`NonEl::Approximate`], the EL axioms it entails are added instead of
+//! nothing: `union_of: A` on `C` still gives `A ⊑ C`, a minimum cardinality
+//! of one or more on the right still gives the existential. Approximations
+//! only ever weaken, so they can miss subsumptions but never add one the
+//! original axioms don't entail. [`write_approximations`] lists what they
+//! added.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::normalize::ClassAxiom;
+use crate::obo::{Location, ParseResult};
+use crate::query::render_class_expression;
+
+/// An axiom left out for a construct outside the supported EL fragment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The first unsupported construct, as the front-end names it
+    /// (`union_of`, `ObjectUnionOf`, `allValuesFrom`, …).
+    pub construct: String,
+    pub location: Location,
+    /// EL axioms the left-out axiom entails; empty when there are none.
+    pub approximation: Vec<ClassAxiom>,
+}
+
+/// How to treat [`Violation`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonEl {
+    /// Reject the document with a [`ProfileReport`].
+    Error,
+    /// Leave the axioms out, with a warning for each.
+    #[default]
+    Skip,
+    /// Add their approximations in their place.
+    Approximate,
+}
+
+impl NonEl {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(NonEl::Error),
+            "skip" => Some(NonEl::Skip),
+            "approximate" => Some(NonEl::Approximate),
+            _ => None,
+        }
+    }
+}
+
+/// Violations of one document, grouped by construct.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    /// Per construct: how many axioms, where the first was and how many
+    /// have an approximation.
+    pub constructs: BTreeMap<String, ConstructCount>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConstructCount {
+    pub axioms: usize,
+    pub first: Location,
+    pub approximable: usize,
+}
+
+impl ProfileReport {
+    pub fn new(violations: &[Violation]) -> Self {
+        let mut constructs: BTreeMap<String, ConstructCount> = BTreeMap::new();
+        for v in violations {
+            let count = constructs
+                .entry(v.construct.clone())
+                .or_insert(ConstructCount { first: v.location, ..ConstructCount::default() });
+            count.axioms += 1;
+            count.first = count.first.min(v.location);
+            count.approximable += !v.approximation.is_empty() as usize;
+        }
+        Self { constructs }
+    }
+
+    pub fn axioms(&self) -> usize {
+        self.constructs.values().map(|c| c.axioms).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constructs.is_empty()
+    }
+}
+
+/// `N axioms outside OWL 2 EL` and a line per construct.
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} axioms outside OWL 2 EL", self.axioms())?;
+        for (construct, count) in &self.constructs {
+            write!(f, "\n  {}: {} (first at {}", construct, count.axioms, count.first)?;
+            if count.approximable > 0 {
+                write!(f, ", {} approximable", count.approximable)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Deals with the violations in `parsed` as `mode` says, taking them out
+/// of it. Returns how many axioms approximations added, or the report if
+/// `mode` is [`NonEl::Error`] and there were any.
+pub fn apply(parsed: &mut ParseResult, mode: NonEl) -> Result<usize, ProfileReport> {
+    let violations = std::mem::take(&mut parsed.violations);
+    match mode {
+        NonEl::Error if !violations.is_empty() => Err(ProfileReport::new(&violations)),
+        NonEl::Error | NonEl::Skip => Ok(0),
+        NonEl::Approximate => {
+            let before = parsed.class_axioms.len();
+            parsed.class_axioms.extend(violations.into_iter().flat_map(|v| v.approximation));
+            Ok(parsed.class_axioms.len() - before)
+        }
+    }
+}
+
+/// Writes a line per EL axiom the approximations in `parsed` add:
+/// `document:line:column`, the construct and the axiom, tab-separated.
+/// Unnamed concepts, such as those standing for complements, show as
+/// `_:c<id>`.
+pub fn write_approximations(out: &mut dyn Write, document: &str, parsed: &ParseResult) -> io::Result<()> {
+    let concept = |c| match parsed.concepts[c as usize].as_str() {
+        "" => format!("_:c{}", c),
+        name => name.to_string(),
+    };
+    let role = |r| parsed.roles[r as usize].clone();
+    for v in &parsed.violations {
+        for axiom in &v.approximation {
+            let (a, op, b) = match axiom {
+                ClassAxiom::SubClassOf(a, b) => (a, "⊑", b),
+                ClassAxiom::Equivalent(a, b) => (a, "≡", b),
+            };
+            let (a, b) = (render_class_expression(a, &concept, &role), render_class_expression(b, &concept, &role));
+            writeln!(out, "{}:{}\t{}\t{} {} {}", document, v.location, v.construct, a, op, b)?;
+        }
+    }
+    Ok(())
+}
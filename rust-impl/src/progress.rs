@@ -0,0 +1,107 @@
+//! Progress reporting for long-running steps. Entry points that take a
+//! [`ProgressSink`] announce their [`Phase`] and then, every so often, how
+//! far into it they are; the sink decides what (if anything) to show.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Parse,
+    Saturate,
+    Taxonomy,
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Saturate => "saturate",
+            Phase::Taxonomy => "taxonomy",
+        }
+    }
+}
+
+pub trait ProgressSink {
+    fn on_phase(&mut self, _phase: Phase) {}
+
+    /// `processed` of `total` units of the current phase: bytes when
+    /// parsing, concepts when building the taxonomy, and worklist items when
+    /// saturating, where `total` is the work known so far and grows as
+    /// rules derive more. The last call of a phase has `processed == total`.
+    fn on_progress(&mut self, _processed: usize, _total: usize) {}
+}
+
+/// Ignores everything.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {}
+
+#[cfg(feature = "std")]
+pub use reader::ProgressReader;
+
+#[cfg(feature = "std")]
+mod reader {
+    use std::io::{self, BufRead, Read};
+
+    use super::{Phase, ProgressSink};
+
+    /// Bytes between two reports.
+    const STEP: usize = 1 << 20;
+
+    /// A reader that reports the bytes consumed so far as [`Phase::Parse`]
+    /// progress, for any front-end reading a document of known length.
+    pub struct ProgressReader<'s, R> {
+        inner: R,
+        sink: &'s mut dyn ProgressSink,
+        read: usize,
+        reported: usize,
+        total: usize,
+        finished: bool,
+    }
+
+    impl<'s, R: BufRead> ProgressReader<'s, R> {
+        /// Wraps `inner`, `total` bytes long.
+        pub fn new(inner: R, total: usize, sink: &'s mut dyn ProgressSink) -> Self {
+            sink.on_phase(Phase::Parse);
+            Self { inner, sink, read: 0, reported: 0, total, finished: false }
+        }
+
+        fn advance(&mut self, n: usize) {
+            self.read += n;
+            if n == 0 || self.read >= self.total {
+                self.finish();
+            } else if self.read - self.reported >= STEP {
+                self.reported = self.read;
+                self.sink.on_progress(self.read, self.total);
+            }
+        }
+
+        fn finish(&mut self) {
+            if !std::mem::replace(&mut self.finished, true) {
+                let total = self.read.max(self.total);
+                self.sink.on_progress(total, total);
+            }
+        }
+    }
+
+    impl<R: BufRead> Read for ProgressReader<'_, R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.advance(n);
+            Ok(n)
+        }
+    }
+
+    impl<R: BufRead> BufRead for ProgressReader<'_, R> {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            // Nothing gets consumed at the end of the input, so report it here.
+            if self.inner.fill_buf()?.is_empty() {
+                self.finish();
+            }
+            self.inner.fill_buf()
+        }
+
+        fn consume(&mut self, amt: usize) {
+            self.inner.consume(amt);
+            self.advance(amt);
+        }
+    }
+}
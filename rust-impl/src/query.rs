@@ -0,0 +1,554 @@
+//! DL-query mini-language: Manchester-style EL class expressions such as
+//! `'has part' some 'mitochondrion' and 'cell'`, answered by defining a fresh
+//! query concept equivalent to the expression and reading its place in the
+//! saturated hierarchy.
+//!
+//! Retrieval queries build on them: a class expression stands for the named
+//! classes it subsumes, `descendants(…)`, `ancestors(…)`, `children(…)`,
+//! `parents(…)` and `equivalents(…)` for those related to a class
+//! expression, and `and`, `or` and `minus` combine the sets, as in
+//! `descendants(CHEBI:33917) and part_of some CHEBI:16646`.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::{ConceptId, ConceptSet, Context, Reasoner, RoleId, BOTTOM, TOP};
+
+/// Resolves names in a query (IDs or labels) to concepts and roles.
+pub trait Symbols {
+    fn concept(&self, name: &str) -> Option<ConceptId>;
+    fn role(&self, name: &str) -> Option<RoleId>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    /// Byte offset into the query text.
+    pub offset: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at offset {})", self.message, self.offset)
+    }
+}
+
+impl Error for QueryError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, ch)) = chars.peek() {
+        match ch {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((Token::Open, start));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((Token::Close, start));
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, c)) if c == ch => break,
+                        Some((_, c)) => name.push(c),
+                        None => {
+                            return Err(QueryError {
+                                message: "unterminated quoted name".to_string(),
+                                offset: start,
+                            })
+                        }
+                    }
+                }
+                tokens.push((Token::Name(name), start));
+            }
+            _ => {
+                let mut name = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push((Token::Name(name), start));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+    symbols: &'a dyn Symbols,
+}
+
+impl Parser<'_> {
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |t| t.1)
+    }
+
+    fn error(&self, message: String) -> QueryError {
+        QueryError { message, offset: self.offset() }
+    }
+
+    fn peek_keyword(&self, offset: usize) -> Option<String> {
+        match self.tokens.get(self.pos + offset) {
+            Some((Token::Name(n), _)) => Some(n.to_ascii_lowercase()),
+            _ => None,
+        }
+    }
+
+    // query := group (("or" | "minus") group)*
+    fn query(&mut self) -> Result<Retrieval, QueryError> {
+        let mut query = self.group()?;
+        while let Some(kw) = self.peek_keyword(0) {
+            match kw.as_str() {
+                "or" => {
+                    self.pos += 1;
+                    query = match (query, self.group()?) {
+                        (Retrieval::Or(mut parts), next) => {
+                            parts.push(next);
+                            Retrieval::Or(parts)
+                        }
+                        (first, next) => Retrieval::Or(vec![first, next]),
+                    };
+                }
+                "minus" => {
+                    self.pos += 1;
+                    query = Retrieval::Minus(Box::new(query), Box::new(self.group()?));
+                }
+                _ => break,
+            }
+        }
+        Ok(query)
+    }
+
+    // group := unit ("and" unit)*
+    fn group(&mut self) -> Result<Retrieval, QueryError> {
+        let mut parts = vec![self.unit()?];
+        while self.peek_keyword(0).as_deref() == Some("and") {
+            self.pos += 1;
+            parts.push(self.unit()?);
+        }
+        if parts.len() == 1 {
+            return Ok(parts.pop().unwrap());
+        }
+        // A conjunction of class expressions is one class expression.
+        let classes: Vec<ClassExpr> = parts
+            .iter()
+            .map_while(|p| match p {
+                Retrieval::Class(e) => Some(e.clone()),
+                _ => None,
+            })
+            .collect();
+        Ok(match classes.len() == parts.len() {
+            true => Retrieval::Class(ClassExpr::And(classes)),
+            false => Retrieval::And(parts),
+        })
+    }
+
+    // unit := RELATION "(" expr ")" | "(" query ")" | conj
+    fn unit(&mut self) -> Result<Retrieval, QueryError> {
+        let relation = self.peek_keyword(0).and_then(|kw| Relation::from_name(&kw));
+        let open = |offset: usize| matches!(self.tokens.get(self.pos + offset), Some((Token::Open, _)));
+        if let (Some(relation), true) = (relation, open(1)) {
+            self.pos += 2;
+            let e = self.expr()?;
+            self.close()?;
+            return Ok(Retrieval::Related(relation, e));
+        }
+        if open(0) {
+            self.pos += 1;
+            let q = self.query()?;
+            self.close()?;
+            return Ok(q);
+        }
+        Ok(Retrieval::Class(self.conj()?))
+    }
+
+    fn close(&mut self) -> Result<(), QueryError> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Close, _)) => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.error("expected ')'".to_string())),
+        }
+    }
+
+    // expr := conj ("and" conj)*
+    fn expr(&mut self) -> Result<ClassExpr, QueryError> {
+        let mut parts = vec![self.conj()?];
+        while let Some(kw) = self.peek_keyword(0) {
+            match kw.as_str() {
+                "and" => {
+                    self.pos += 1;
+                    parts.push(self.conj()?);
+                }
+                "or" => return Err(self.error("'or' (union) is outside OWL EL".to_string())),
+                _ => break,
+            }
+        }
+        Ok(if parts.len() == 1 { parts.pop().unwrap() } else { ClassExpr::And(parts) })
+    }
+
+    // conj := ROLE "some" conj | "(" expr ")" | NAME
+    fn conj(&mut self) -> Result<ClassExpr, QueryError> {
+        match self.tokens.get(self.pos).cloned() {
+            Some((Token::Open, _)) => {
+                self.pos += 1;
+                let e = self.expr()?;
+                self.close()?;
+                Ok(e)
+            }
+            Some((Token::Name(name), _)) => {
+                if let Some(kw) = self.peek_keyword(1) {
+                    match kw.as_str() {
+                        "some" => {
+                            let role = self.symbols.role(&name).ok_or_else(|| {
+                                self.error(format!("unknown relation '{}'", name))
+                            })?;
+                            self.pos += 2;
+                            let filler = self.conj()?;
+                            return Ok(ClassExpr::Some(role, Box::new(filler)));
+                        }
+                        "only" | "value" | "min" | "max" | "exactly" => {
+                            self.pos += 1;
+                            return Err(self.error(format!("'{}' is outside OWL EL", kw)));
+                        }
+                        _ => {}
+                    }
+                }
+                match name.to_ascii_lowercase().as_str() {
+                    "not" => return Err(self.error("'not' (complement) is outside OWL EL".to_string())),
+                    "and" | "or" | "some" => {
+                        return Err(self.error(format!("unexpected '{}'", name)))
+                    }
+                    _ => {}
+                }
+                let c = self
+                    .symbols
+                    .concept(&name)
+                    .ok_or_else(|| self.error(format!("unknown class '{}'", name)))?;
+                self.pos += 1;
+                Ok(ClassExpr::Named(c))
+            }
+            Some((Token::Close, _)) => Err(self.error("unexpected ')'".to_string())),
+            None => Err(self.error("unexpected end of query".to_string())),
+        }
+    }
+}
+
+/// Parses a Manchester-style EL class expression (`and`, `some`, parentheses,
+/// quoted labels or bare IDs).
+pub fn parse_class_expression(text: &str, symbols: &dyn Symbols) -> Result<ClassExpr, QueryError> {
+    let tokens = tokenize(text)?;
+    let mut parser = Parser { tokens, pos: 0, end: text.len(), symbols };
+    let expr = parser.expr()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.error("trailing input".to_string()));
+    }
+    Ok(expr)
+}
+
+/// `expr` in the syntax [`parse_class_expression`] reads, with names quoted
+/// where they would not read back as one.
+pub fn render_class_expression(
+    expr: &ClassExpr,
+    concept: &dyn Fn(ConceptId) -> String,
+    role: &dyn Fn(RoleId) -> String,
+) -> String {
+    match expr {
+        ClassExpr::Named(c) => quote(&concept(*c)),
+        ClassExpr::And(parts) => {
+            let parts: Vec<String> = parts.iter().map(|p| render_class_expression(p, concept, role)).collect();
+            parts.join(" and ")
+        }
+        ClassExpr::Some(r, filler) => {
+            let rendered = render_class_expression(filler, concept, role);
+            match **filler {
+                ClassExpr::And(_) => format!("{} some ({})", quote(&role(*r)), rendered),
+                _ => format!("{} some {}", quote(&role(*r)), rendered),
+            }
+        }
+    }
+}
+
+fn quote(name: &str) -> String {
+    let keyword = ["and", "or", "some", "not", "only", "value", "min", "max", "exactly"]
+        .contains(&name.to_ascii_lowercase().as_str());
+    let plain = !name.is_empty()
+        && !keyword
+        && !name.starts_with(['\'', '"'])
+        && !name.contains(|c: char| c.is_whitespace() || c == '(' || c == ')');
+    if plain {
+        name.to_string()
+    } else if name.contains('\'') {
+        format!("\"{}\"", name)
+    } else {
+        format!("'{}'", name)
+    }
+}
+
+/// Classes related to a class expression, for [`Retrieval::Related`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    Descendants,
+    Ancestors,
+    /// Direct subclasses.
+    Children,
+    /// Direct superclasses.
+    Parents,
+    Equivalents,
+}
+
+impl Relation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "descendants" => Some(Relation::Descendants),
+            "ancestors" => Some(Relation::Ancestors),
+            "children" => Some(Relation::Children),
+            "parents" => Some(Relation::Parents),
+            "equivalents" => Some(Relation::Equivalents),
+            _ => None,
+        }
+    }
+}
+
+/// A retrieval query: a set of named classes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Retrieval {
+    /// The classes the expression subsumes, its equivalents included.
+    Class(ClassExpr),
+    Related(Relation, ClassExpr),
+    And(Vec<Retrieval>),
+    Or(Vec<Retrieval>),
+    Minus(Box<Retrieval>, Box<Retrieval>),
+}
+
+impl Retrieval {
+    fn expressions<'a>(&'a self, out: &mut Vec<&'a ClassExpr>) {
+        match self {
+            Retrieval::Class(e) | Retrieval::Related(_, e) => out.push(e),
+            Retrieval::And(parts) | Retrieval::Or(parts) => parts.iter().for_each(|p| p.expressions(out)),
+            Retrieval::Minus(a, b) => {
+                a.expressions(out);
+                b.expressions(out);
+            }
+        }
+    }
+}
+
+/// Parses a retrieval query; a plain class expression parses as
+/// [`Retrieval::Class`].
+pub fn parse_retrieval(text: &str, symbols: &dyn Symbols) -> Result<Retrieval, QueryError> {
+    let tokens = tokenize(text)?;
+    let mut parser = Parser { tokens, pos: 0, end: text.len(), symbols };
+    let query = parser.query()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.error("trailing input".to_string()));
+    }
+    Ok(query)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct QueryAnswer {
+    pub equivalents: Vec<ConceptId>,
+    pub superclasses: Vec<ConceptId>,
+    pub subclasses: Vec<ConceptId>,
+}
+
+/// Reads the super/sub/equivalent classes of query concept `q` from the
+/// saturated contexts. `is_named` filters out fresh concepts introduced by
+/// normalization. With `direct`, only the nearest super/subclasses are kept.
+pub fn answer_query(
+    contexts: &[Context],
+    q: ConceptId,
+    direct: bool,
+    is_named: impl Fn(ConceptId) -> bool,
+) -> QueryAnswer {
+    let supers = &contexts[q as usize].super_set;
+    let mut answer = QueryAnswer::default();
+
+    for &s in supers {
+        if s == q || s == BOTTOM || !is_named(s) {
+            continue;
+        }
+        if contexts[s as usize].super_set.contains(&q) {
+            answer.equivalents.push(s);
+        } else {
+            answer.superclasses.push(s);
+        }
+    }
+    for (c, ctx) in contexts.iter().enumerate() {
+        let c = c as ConceptId;
+        if c == q || c == TOP || !is_named(c) || !ctx.super_set.contains(&q) {
+            continue;
+        }
+        if !supers.contains(&c) {
+            answer.subclasses.push(c);
+        }
+    }
+
+    if direct {
+        // `u` strictly subsumes `t`: u ∈ S(t) but not the other way round.
+        let strictly_above = |t: ConceptId, u: ConceptId| {
+            u != t && !contexts[u as usize].super_set.contains(&t)
+        };
+        let mut indirect = ConceptSet::default();
+        for &t in &answer.superclasses {
+            indirect.extend(
+                contexts[t as usize].super_set.iter().copied().filter(|&u| strictly_above(t, u)),
+            );
+        }
+        answer.superclasses.retain(|s| !indirect.contains(s));
+
+        let subs: ConceptSet = answer.subclasses.iter().copied().collect();
+        answer.subclasses.retain(|&b| {
+            !contexts[b as usize]
+                .super_set
+                .iter()
+                .any(|&c| subs.contains(&c) && strictly_above(b, c))
+        });
+    }
+
+    answer.equivalents.sort_unstable();
+    answer.superclasses.sort_unstable();
+    answer.subclasses.sort_unstable();
+    answer
+}
+
+/// A standing query registered as an auxiliary named concept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamedQuery {
+    pub name: String,
+    pub expression: String,
+}
+
+/// Reads `name = expression` lines; blank lines and `#` comments are skipped.
+pub fn read_named_queries<R: BufRead>(reader: R) -> io::Result<Vec<NamedQuery>> {
+    let mut queries = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((name, expression)) if !name.trim().is_empty() && !expression.trim().is_empty() => {
+                queries.push(NamedQuery {
+                    name: name.trim().to_string(),
+                    expression: expression.trim().to_string(),
+                });
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected `name = expression`", i + 1),
+                ))
+            }
+        }
+    }
+    Ok(queries)
+}
+
+/// Answers queries against a saturated [`Reasoner`]. Each class expression
+/// is defined once as a fresh concept and the reasoner resaturated
+/// incrementally, so a session of queries costs one saturation.
+pub struct QueryEngine {
+    reasoner: Reasoner,
+    is_named: Box<dyn Fn(ConceptId) -> bool>,
+    defined: Vec<(ClassExpr, ConceptId)>,
+}
+
+impl QueryEngine {
+    /// `is_named` tells the classes that may appear in answers from fresh
+    /// and hidden ones.
+    pub fn new(reasoner: Reasoner, is_named: impl Fn(ConceptId) -> bool + 'static) -> Self {
+        Self { reasoner, is_named: Box::new(is_named), defined: Vec::new() }
+    }
+
+    pub fn reasoner(&self) -> &Reasoner {
+        &self.reasoner
+    }
+
+    /// A fresh concept defined as equivalent to `expr`.
+    fn concept(&mut self, expr: &ClassExpr) -> ConceptId {
+        if let Some(&(_, q)) = self.defined.iter().find(|(e, _)| e == expr) {
+            return q;
+        }
+        let q = self.reasoner.add_concept();
+        self.reasoner.add_axiom(&ClassAxiom::Equivalent(ClassExpr::Named(q), expr.clone()));
+        self.defined.push((expr.clone(), q));
+        q
+    }
+
+    /// [`answer_query`] for a class expression.
+    pub fn answer(&mut self, expr: &ClassExpr, direct: bool) -> QueryAnswer {
+        let q = self.concept(expr);
+        self.reasoner.resaturate();
+        answer_query(self.reasoner.contexts(), q, direct, &self.is_named)
+    }
+
+    /// The named classes `query` retrieves, in ID order.
+    pub fn retrieve(&mut self, query: &Retrieval) -> Vec<ConceptId> {
+        let mut exprs = Vec::new();
+        query.expressions(&mut exprs);
+        for expr in exprs {
+            self.concept(expr);
+        }
+        self.reasoner.resaturate();
+        let mut found: Vec<ConceptId> = self.evaluate(query).into_iter().collect();
+        found.sort_unstable();
+        found
+    }
+
+    fn evaluate(&mut self, query: &Retrieval) -> ConceptSet {
+        match query {
+            Retrieval::Class(expr) => {
+                let q = self.concept(expr);
+                let answer = answer_query(self.reasoner.contexts(), q, false, &self.is_named);
+                answer.subclasses.into_iter().chain(answer.equivalents).collect()
+            }
+            Retrieval::Related(relation, expr) => {
+                let q = self.concept(expr);
+                let direct = matches!(relation, Relation::Children | Relation::Parents);
+                let answer = answer_query(self.reasoner.contexts(), q, direct, &self.is_named);
+                let found = match relation {
+                    Relation::Descendants | Relation::Children => answer.subclasses,
+                    Relation::Ancestors | Relation::Parents => answer.superclasses,
+                    Relation::Equivalents => answer.equivalents,
+                };
+                found.into_iter().collect()
+            }
+            Retrieval::And(parts) => {
+                let mut sets = parts.iter().map(|p| self.evaluate(p)).collect::<Vec<_>>().into_iter();
+                let first = sets.next().unwrap_or_default();
+                sets.fold(first, |acc, set| acc.intersection(&set).copied().collect())
+            }
+            Retrieval::Or(parts) => parts.iter().flat_map(|p| self.evaluate(p)).collect(),
+            Retrieval::Minus(a, b) => {
+                let (a, b) = (self.evaluate(a), self.evaluate(b));
+                a.difference(&b).copied().collect()
+            }
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! Saturation state kept across edits. Axioms added to a [`Reasoner`] after
+//! it has saturated are applied by [`Reasoner::resaturate`], which only
+//! processes their consequences instead of saturating the whole store again.
+//!
+//! Contexts holding a concept from the left-hand side of a new axiom are
+//! revisited with that concept; EL saturation is monotone, so this reaches
+//! the saturation of the grown store. New concepts, fresh ones from
+//! normalization included, are saturated from scratch. Role inclusions and
+//! chains also apply to links made before them, so adding or removing one
+//! makes the next `resaturate` start over.
+//!
+//! Axioms added through the reasoner can be removed again, delete-and-
+//! rederive style: every context that holds a left-hand concept of a removed axiom is
+//! emptied, along with every context linking to an emptied one (its
+//! conclusions may have come through that link), and the emptied contexts
+//! are saturated again against the store without the axiom. The remaining
+//! contexts never used it.
+//!
+//! The usual reasoner tasks ([`Reasoner::is_satisfiable`],
+//! [`Reasoner::super_classes`] and so on) resaturate first when there are
+//! pending edits, and answer direct queries from a taxonomy classified on
+//! first use and kept until the next edit.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::{io, path::Path, time::Duration};
+
+use crate::kernel::{resume, saturate, AxiomStore, ConceptId, Context, RoleId, BOTTOM};
+use crate::normalize::{ClassAxiom, ClassExpr, Normalizer};
+use crate::taxonomy::Taxonomy;
+
+/// Concepts a context must hold for the normalized `axiom` to apply in it:
+/// those on its left-hand side.
+fn premises(axiom: &ClassAxiom) -> Vec<ConceptId> {
+    match axiom {
+        ClassAxiom::SubClassOf(sub, _) => sub.signature(),
+        ClassAxiom::Equivalent(..) => axiom.signature(),
+    }
+}
+
+/// A class axiom added through the reasoner, and the fresh concepts its
+/// normalization took.
+struct Added {
+    axiom: ClassAxiom,
+    fresh: Range<ConceptId>,
+}
+
+pub struct Reasoner {
+    /// The store passed to [`Reasoner::new`], to rebuild from after removals.
+    base: AxiomStore,
+    store: AxiomStore,
+    contexts: Vec<Context>,
+    num_roles: usize,
+    added: Vec<Added>,
+    role_subsumptions: Vec<(RoleId, RoleId)>,
+    role_chains: Vec<(RoleId, RoleId, RoleId)>,
+    reflexive_roles: Vec<RoleId>,
+    /// Left-hand concepts of axioms added since the last saturation.
+    touched: Vec<ConceptId>,
+    /// Axioms removed since the last saturation.
+    removed: Vec<Added>,
+    /// A role axiom was added or removed since the last saturation.
+    restart: bool,
+    /// Concepts the reasoner tasks may answer with; fresh concepts from
+    /// normalization are not.
+    named: Vec<bool>,
+    /// The named concepts classified, once asked for since the last saturation.
+    taxonomy: Option<Taxonomy>,
+}
+
+impl Reasoner {
+    /// Saturates every concept `store` covers.
+    pub fn new(store: AxiomStore, num_roles: usize) -> Self {
+        let contexts = saturate(&store, store.num_concepts(), num_roles);
+        Self::from_contexts(store, num_roles, contexts)
+    }
+
+    /// A reasoner whose `contexts` are the saturation of `store`.
+    fn from_contexts(store: AxiomStore, num_roles: usize, contexts: Vec<Context>) -> Self {
+        let store_concepts = store.num_concepts();
+        Self {
+            base: store.clone(),
+            store,
+            contexts,
+            num_roles,
+            added: Vec::new(),
+            role_subsumptions: Vec::new(),
+            role_chains: Vec::new(),
+            reflexive_roles: Vec::new(),
+            touched: Vec::new(),
+            removed: Vec::new(),
+            restart: false,
+            named: vec![true; store_concepts],
+            taxonomy: None,
+        }
+    }
+
+    /// Treats only the first `n` concepts of the store (and those added
+    /// later with [`add_concept`](Self::add_concept)) as named, leaving the
+    /// fresh ones from normalization out of task answers.
+    pub fn with_named_concepts(mut self, n: usize) -> Self {
+        for (c, named) in self.named.iter_mut().enumerate() {
+            *named = c < n;
+        }
+        self
+    }
+
+    pub fn store(&self) -> &AxiomStore {
+        &self.store
+    }
+
+    /// Contexts as of the last saturation.
+    pub fn contexts(&self) -> &[Context] {
+        &self.contexts
+    }
+
+    pub fn into_contexts(self) -> Vec<Context> {
+        self.contexts
+    }
+
+    pub fn num_concepts(&self) -> usize {
+        self.store.num_concepts()
+    }
+
+    pub fn num_roles(&self) -> usize {
+        self.num_roles
+    }
+
+    /// A new concept, numbered after every concept so far (fresh ones
+    /// included).
+    pub fn add_concept(&mut self) -> ConceptId {
+        let c = self.store.num_concepts();
+        self.store.grow(c + 1);
+        self.named.resize(c, false);
+        self.named.push(true);
+        c as ConceptId
+    }
+
+    /// A new role.
+    pub fn add_role(&mut self) -> RoleId {
+        let r = self.num_roles;
+        self.num_roles += 1;
+        self.store.grow_roles(self.num_roles);
+        for ctx in &mut self.contexts {
+            ctx.link_map.resize_with(self.num_roles, Vec::new);
+            ctx.pred_map.resize_with(self.num_roles, Vec::new);
+        }
+        r as RoleId
+    }
+
+    pub fn add_axiom(&mut self, axiom: &ClassAxiom) {
+        let start = self.store.num_concepts();
+        let mut normalizer = Normalizer::new(&mut self.store, start);
+        normalizer.add_axiom(axiom);
+        let fresh = start as ConceptId..normalizer.num_concepts() as ConceptId;
+        self.touched.extend(premises(axiom));
+        self.added.push(Added { axiom: axiom.clone(), fresh });
+    }
+
+    /// Removes an axiom added with [`add_axiom`](Self::add_axiom); false if
+    /// there is none equal to `axiom`. Axioms of the store the reasoner was
+    /// created with stay.
+    pub fn remove_axiom(&mut self, axiom: &ClassAxiom) -> bool {
+        match self.added.iter().position(|a| a.axiom == *axiom) {
+            Some(i) => {
+                self.removed.push(self.added.remove(i));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `sub ⊑ sup`; removed as `SubClassOf(Named(sub), Named(sup))`.
+    pub fn add_subsumption(&mut self, sub: ConceptId, sup: ConceptId) {
+        self.add_axiom(&ClassAxiom::SubClassOf(ClassExpr::Named(sub), ClassExpr::Named(sup)));
+    }
+
+    /// `sub ⊑ ∃role.filler`; removed as
+    /// `SubClassOf(Named(sub), Some(role, Named(filler)))`.
+    pub fn add_existential(&mut self, sub: ConceptId, role: RoleId, filler: ConceptId) {
+        let sup = ClassExpr::Some(role, Box::new(ClassExpr::Named(filler)));
+        self.add_axiom(&ClassAxiom::SubClassOf(ClassExpr::Named(sub), sup));
+    }
+
+    /// `a ⊓ b ⊑ ⊥`; removed as
+    /// `SubClassOf(And([Named(a), Named(b)]), Named(BOTTOM))`.
+    pub fn add_disjoint(&mut self, a: ConceptId, b: ConceptId) {
+        let both = ClassExpr::And(vec![ClassExpr::Named(a), ClassExpr::Named(b)]);
+        self.add_axiom(&ClassAxiom::SubClassOf(both, ClassExpr::Named(BOTTOM)));
+    }
+
+    pub fn add_role_subsumption(&mut self, sub: RoleId, sup: RoleId) {
+        self.store.add_role_subsumption(sub, sup);
+        self.role_subsumptions.push((sub, sup));
+        self.restart = true;
+    }
+
+    pub fn add_role_chain(&mut self, first: RoleId, second: RoleId, sup: RoleId) {
+        self.store.add_role_chain(first, second, sup);
+        self.role_chains.push((first, second, sup));
+        self.restart = true;
+    }
+
+    pub fn add_reflexive(&mut self, role: RoleId) {
+        self.store.add_reflexive(role);
+        self.reflexive_roles.push(role);
+        self.restart = true;
+    }
+
+    pub fn remove_role_subsumption(&mut self, sub: RoleId, sup: RoleId) -> bool {
+        let Some(i) = self.role_subsumptions.iter().position(|&p| p == (sub, sup)) else {
+            return false;
+        };
+        self.role_subsumptions.remove(i);
+        self.restart = true;
+        self.store = self.rebuild();
+        true
+    }
+
+    pub fn remove_role_chain(&mut self, first: RoleId, second: RoleId, sup: RoleId) -> bool {
+        let Some(i) = self.role_chains.iter().position(|&c| c == (first, second, sup)) else {
+            return false;
+        };
+        self.role_chains.remove(i);
+        self.restart = true;
+        self.store = self.rebuild();
+        true
+    }
+
+    pub fn remove_reflexive(&mut self, role: RoleId) -> bool {
+        let Some(i) = self.reflexive_roles.iter().position(|&r| r == role) else {
+            return false;
+        };
+        self.reflexive_roles.remove(i);
+        self.restart = true;
+        self.store = self.rebuild();
+        true
+    }
+
+    /// The base store plus every axiom still added. Class axioms are
+    /// normalized with the fresh concepts they had, so concept IDs in the
+    /// contexts keep their meaning; those of removed axioms go unused.
+    fn rebuild(&self) -> AxiomStore {
+        let mut store = self.base.clone();
+        store.grow_roles(self.num_roles);
+        store.grow(self.store.num_concepts());
+        for &(sub, sup) in &self.role_subsumptions {
+            store.add_role_subsumption(sub, sup);
+        }
+        for &(first, second, sup) in &self.role_chains {
+            store.add_role_chain(first, second, sup);
+        }
+        for &role in &self.reflexive_roles {
+            store.add_reflexive(role);
+        }
+        for added in &self.added {
+            Normalizer::new(&mut store, added.fresh.start as usize).add_axiom(&added.axiom);
+        }
+        store
+    }
+
+    /// Empties the contexts that may have used a removed axiom, returning
+    /// them.
+    fn retract(&mut self) -> Vec<ConceptId> {
+        if self.removed.is_empty() {
+            return Vec::new();
+        }
+        let num_concepts = self.store.num_concepts();
+        let mut removed = vec![false; num_concepts];
+        for added in self.removed.drain(..) {
+            for c in premises(&added.axiom).into_iter().chain(added.fresh) {
+                removed[c as usize] = true;
+            }
+        }
+
+        let mut dirty = vec![false; self.contexts.len()];
+        let mut queue: Vec<ConceptId> = Vec::new();
+        for ctx in &self.contexts {
+            if ctx.super_set.iter().any(|&c| removed[c as usize]) {
+                dirty[ctx.id as usize] = true;
+                queue.push(ctx.id);
+            }
+        }
+        let mut emptied = Vec::new();
+        while let Some(c) = queue.pop() {
+            emptied.push(c);
+            for preds in &self.contexts[c as usize].pred_map {
+                for &p in preds {
+                    if !dirty[p as usize] {
+                        dirty[p as usize] = true;
+                        queue.push(p);
+                    }
+                }
+            }
+        }
+
+        for &c in &emptied {
+            let links = core::mem::take(&mut self.contexts[c as usize].link_map);
+            for (r, targets) in links.iter().enumerate() {
+                for &d in targets.iter().filter(|&&d| !dirty[d as usize]) {
+                    self.contexts[d as usize].pred_map[r].retain(|&p| p != c);
+                }
+            }
+            self.contexts[c as usize] = Context::new(c, self.num_roles);
+        }
+        emptied
+    }
+
+    /// Brings the contexts up to date with every axiom added or removed so
+    /// far.
+    pub fn resaturate(&mut self) {
+        self.taxonomy = None;
+        if !self.removed.is_empty() {
+            self.store = self.rebuild();
+        }
+        let num_concepts = self.store.num_concepts();
+        if self.restart {
+            self.contexts = saturate(&self.store, num_concepts, self.num_roles);
+            self.touched.clear();
+            self.removed.clear();
+            self.restart = false;
+            return;
+        }
+
+        let emptied = self.retract();
+        if emptied.len() * 2 > self.contexts.len() {
+            // Rederiving most of the contexts costs more than starting over.
+            self.contexts = saturate(&self.store, num_concepts, self.num_roles);
+            self.touched.clear();
+            return;
+        }
+        let from = self.contexts.len();
+        let mut touched = vec![false; num_concepts];
+        for c in self.touched.drain(..) {
+            touched[c as usize] = true;
+        }
+        let mut revisit: Vec<(ConceptId, ConceptId)> = Vec::new();
+        for ctx in &self.contexts {
+            let held = ctx.super_set.iter().filter(|&&c| touched[c as usize]);
+            revisit.extend(held.map(|&c| (ctx.id, c)));
+        }
+
+        let num_roles = self.num_roles;
+        let mut contexts = core::mem::take(&mut self.contexts);
+        contexts.extend((from..num_concepts).map(|c| Context::new(c as ConceptId, num_roles)));
+        let seeds = emptied.into_iter().chain(from as ConceptId..num_concepts as ConceptId);
+        self.contexts = resume(&self.store, contexts, num_roles, seeds, revisit, |_| {});
+    }
+
+    /// Whether edits are waiting for [`resaturate`](Self::resaturate).
+    fn is_stale(&self) -> bool {
+        self.restart
+            || !self.touched.is_empty()
+            || !self.removed.is_empty()
+            || self.contexts.len() < self.store.num_concepts()
+    }
+
+    fn up_to_date(&mut self) {
+        if self.is_stale() {
+            self.resaturate();
+        }
+    }
+
+    fn is_named(&self, c: ConceptId) -> bool {
+        self.named.get(c as usize).copied().unwrap_or(false)
+    }
+
+    fn classified(&mut self) -> &Taxonomy {
+        self.up_to_date();
+        if self.taxonomy.is_none() {
+            let mut named = self.named.clone();
+            named.resize(self.contexts.len(), false);
+            self.taxonomy = Some(Taxonomy::among(&self.contexts, &named));
+        }
+        self.taxonomy.as_ref().unwrap()
+    }
+
+    /// Whether `c` can have instances, i.e. does not entail BOTTOM.
+    pub fn is_satisfiable(&mut self, c: ConceptId) -> bool {
+        self.up_to_date();
+        self.satisfiable(c)
+    }
+
+    /// Named concepts other than `c` equivalent to it, in ascending order;
+    /// for an unsatisfiable `c`, BOTTOM and every other unsatisfiable one.
+    pub fn equivalent_classes(&mut self, c: ConceptId) -> Vec<ConceptId> {
+        self.up_to_date();
+        let contexts = &self.contexts;
+        let unsat = |d: usize| contexts[d].super_set.contains(&BOTTOM);
+        let equivalent = |d: usize| match unsat(c as usize) {
+            true => unsat(d),
+            false => contexts[c as usize].super_set.contains(&(d as ConceptId))
+                && contexts[d].super_set.contains(&c),
+        };
+        (0..contexts.len())
+            .filter(|&d| d != c as usize && equivalent(d))
+            .map(|d| d as ConceptId)
+            .filter(|&d| self.is_named(d))
+            .collect()
+    }
+
+    /// Named concepts strictly above `c`, TOP included, in ascending order;
+    /// with `direct`, only the most specific of them and their equivalents.
+    /// An unsatisfiable `c` is below every satisfiable concept, directly
+    /// below those without satisfiable children.
+    pub fn super_classes(&mut self, c: ConceptId, direct: bool) -> Vec<ConceptId> {
+        let mut supers: Vec<ConceptId> = match (self.is_satisfiable(c), direct) {
+            (true, true) => {
+                let taxonomy = self.classified();
+                let parents = taxonomy.parents(c);
+                let groups = taxonomy.equivalences().iter().filter(|group| parents.contains(&group[0]));
+                parents.iter().chain(groups.flatten()).copied().collect()
+            }
+            (true, false) => self.contexts[c as usize].super_set.iter().copied().collect(),
+            (false, direct) => {
+                self.classified();
+                let taxonomy = self.taxonomy.as_ref().unwrap();
+                let below = |d: &ConceptId| taxonomy.children(taxonomy.representative(*d)).iter();
+                let leaf = |d: &ConceptId| !below(d).any(|&e| self.satisfiable(e));
+                (0..self.contexts.len() as ConceptId)
+                    .filter(|&d| self.satisfiable(d) && (!direct || leaf(&d)))
+                    .collect()
+            }
+        };
+        let equivalents = self.equivalent_classes(c);
+        supers.retain(|&d| d != c && self.is_named(d) && !equivalents.contains(&d));
+        supers.sort_unstable();
+        supers.dedup();
+        supers
+    }
+
+    /// Satisfiable named concepts strictly below `c`, in ascending order;
+    /// with `direct`, only the most general of them and their equivalents.
+    /// Unsatisfiable concepts are left out: they are the
+    /// [`equivalent_classes`](Self::equivalent_classes) of BOTTOM.
+    pub fn sub_classes(&mut self, c: ConceptId, direct: bool) -> Vec<ConceptId> {
+        if !self.is_satisfiable(c) {
+            return Vec::new();
+        }
+        let mut subs: Vec<ConceptId> = match direct {
+            true => {
+                let taxonomy = self.classified();
+                taxonomy.children(taxonomy.representative(c)).to_vec()
+            }
+            false => {
+                let below = |ctx: &&Context| ctx.super_set.contains(&c);
+                self.contexts.iter().filter(below).map(|ctx| ctx.id).collect()
+            }
+        };
+        let equivalents = self.equivalent_classes(c);
+        subs.retain(|&d| d != c && self.is_named(d) && self.satisfiable(d) && !equivalents.contains(&d));
+        subs.sort_unstable();
+        subs
+    }
+
+    /// [`is_satisfiable`](Self::is_satisfiable) as of the last saturation.
+    fn satisfiable(&self, c: ConceptId) -> bool {
+        !self.contexts[c as usize].super_set.contains(&BOTTOM)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Reasoner {
+    /// Brings the contexts up to date and writes them to `path` as a
+    /// [`crate::checkpoint`] of [`store`](Self::store), which is what a
+    /// later [`resume`](Self::resume) must be given.
+    pub fn checkpoint(&mut self, path: &Path) -> io::Result<()> {
+        self.resaturate();
+        crate::checkpoint::save_checkpoint(path, &self.store, &self.contexts, &[])
+    }
+
+    /// [`Reasoner::new`], going on from the checkpoint at `path` if there is
+    /// one and writing one there every `interval` until saturated, so the
+    /// same call after a preempted run finishes its work.
+    pub fn resume(store: AxiomStore, num_roles: usize, path: &Path, interval: Duration) -> io::Result<Self> {
+        let options = crate::kernel::SaturationOptions::default();
+        let num_concepts = store.num_concepts();
+        let contexts =
+            crate::checkpoint::saturate_resumable(&store, num_concepts, num_roles, &options, path, interval)?;
+        Ok(Self::from_contexts(store, num_roles, contexts))
+    }
+}
@@ -0,0 +1,205 @@
+//! Mapping of identifiers as written in an input (IRIs, alternative IDs,
+//! odd casing) to the canonical concept identifiers the reasoner interns.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Base IRI of OBO Foundry PURLs: `OBO_PURL` + `GO_0008150` is `GO:0008150`.
+pub const OBO_PURL: &str = "http://purl.obolibrary.org/obo/";
+
+pub const OWL: &str = "http://www.w3.org/2002/07/owl#";
+pub const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+pub const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+pub const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Maps an input token to its canonical identifier. Front-ends and the query
+/// language call this on every concept reference before interning it.
+pub trait IdResolver {
+    fn resolve<'a>(&self, token: &'a str) -> Cow<'a, str>;
+}
+
+impl<T: IdResolver + ?Sized> IdResolver for &T {
+    fn resolve<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        (**self).resolve(token)
+    }
+}
+
+/// Applies the first resolver, then the second to its result.
+impl<A: IdResolver, B: IdResolver> IdResolver for (A, B) {
+    fn resolve<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        match self.0.resolve(token) {
+            Cow::Borrowed(t) => self.1.resolve(t),
+            Cow::Owned(t) => Cow::Owned(self.1.resolve(&t).into_owned()),
+        }
+    }
+}
+
+/// Keeps every identifier as written.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Verbatim;
+
+impl IdResolver for Verbatim {
+    fn resolve<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(token)
+    }
+}
+
+/// Contracts IRIs to CURIEs using registered `prefix → base IRI` pairs
+/// (longest base wins), optionally falling back to the OBO PURL convention,
+/// and expands CURIEs back to IRIs the same way.
+///
+/// A [`ParseResult`](crate::obo::ParseResult) keeps one as the table of
+/// prefixes its documents declared, so names can be written and looked up
+/// in either form.
+#[derive(Clone, Debug, Default)]
+pub struct CurieMap {
+    /// `(base IRI, prefix)`, longest base first.
+    bases: Vec<(String, String)>,
+    obo_purls: bool,
+}
+
+impl CurieMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A map that contracts `http://purl.obolibrary.org/obo/PREFIX_LOCAL`.
+    pub fn obo() -> Self {
+        Self { bases: Vec::new(), obo_purls: true }
+    }
+
+    /// [`CurieMap::obo`] with the `owl`, `rdf`, `rdfs` and `xsd` prefixes.
+    pub fn standard() -> Self {
+        let mut map = Self::obo();
+        for (prefix, base) in [("owl", OWL), ("rdf", RDF), ("rdfs", RDFS), ("xsd", XSD)] {
+            map.add_prefix(prefix, base);
+        }
+        map
+    }
+
+    /// Binds `prefix` to `base`, replacing an earlier binding of `prefix`.
+    pub fn add_prefix(&mut self, prefix: &str, base: &str) {
+        self.bases.retain(|(_, p)| p != prefix);
+        self.bases.push((base.to_string(), prefix.to_string()));
+        self.bases.sort_by_key(|(base, _)| core::cmp::Reverse(base.len()));
+    }
+
+    /// Adds the bindings of `other` for prefixes `self` doesn't bind yet.
+    pub fn merge(&mut self, other: &CurieMap) {
+        for (base, prefix) in &other.bases {
+            if self.base(prefix).is_none() {
+                self.add_prefix(prefix, base);
+            }
+        }
+        self.obo_purls |= other.obo_purls;
+    }
+
+    pub fn base(&self, prefix: &str) -> Option<&str> {
+        self.bases.iter().find(|(_, p)| p == prefix).map(|(base, _)| base.as_str())
+    }
+
+    /// `(prefix, base IRI)` pairs, longest base first.
+    pub fn prefixes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.bases.iter().map(|(base, prefix)| (prefix.as_str(), base.as_str()))
+    }
+
+    /// The IRI of the CURIE `name`: its bound base and local part, or with
+    /// OBO PURLs, `GO:0008150` as `OBO_PURL` + `GO_0008150`. Anything else,
+    /// full IRIs included, is returned as is.
+    pub fn expand<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let Some((prefix, local)) = name.split_once(':') else {
+            return Cow::Borrowed(name);
+        };
+        if let Some(base) = self.base(prefix) {
+            return Cow::Owned(format!("{}{}", base, local));
+        }
+        let word = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric());
+        let obo_local = local.split(['_', '-', '.']).all(|part| word(part) || part.is_empty());
+        if self.obo_purls && word(prefix) && !local.is_empty() && obo_local {
+            return Cow::Owned(format!("{}{}_{}", OBO_PURL, prefix, local));
+        }
+        Cow::Borrowed(name)
+    }
+}
+
+impl IdResolver for CurieMap {
+    fn resolve<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        let iri = token.strip_prefix('<').and_then(|t| t.strip_suffix('>')).unwrap_or(token);
+        let purl = iri.strip_prefix(OBO_PURL).and_then(|l| l.split_once('_')).filter(|_| self.obo_purls);
+        for (base, prefix) in &self.bases {
+            // A prefix for all of `OBO_PURL` (`obo:`) loses to the convention.
+            if purl.is_some() && base.len() <= OBO_PURL.len() {
+                break;
+            }
+            if let Some(local) = iri.strip_prefix(base.as_str()) {
+                return Cow::Owned(format!("{}:{}", prefix, local));
+            }
+        }
+        if let Some((prefix, local)) = purl {
+            return Cow::Owned(format!("{}:{}", prefix, local));
+        }
+        Cow::Borrowed(token)
+    }
+}
+
+/// Normalizes the case of known CURIE prefixes (`go:0008150` → `GO:0008150`).
+/// Unregistered prefixes are left alone, so `owl:Thing` stays intact.
+#[derive(Clone, Debug, Default)]
+pub struct PrefixCase {
+    /// Canonical spellings.
+    prefixes: Vec<String>,
+}
+
+impl PrefixCase {
+    pub fn new<'p>(prefixes: impl IntoIterator<Item = &'p str>) -> Self {
+        Self { prefixes: prefixes.into_iter().map(str::to_string).collect() }
+    }
+}
+
+impl IdResolver for PrefixCase {
+    fn resolve<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        let Some((prefix, local)) = token.split_once(':') else {
+            return Cow::Borrowed(token);
+        };
+        match self.prefixes.iter().find(|p| p.eq_ignore_ascii_case(prefix)) {
+            Some(canonical) if canonical != prefix => Cow::Owned(format!("{}:{}", canonical, local)),
+            _ => Cow::Borrowed(token),
+        }
+    }
+}
+
+/// Replaces alternative IDs (OBO `alt_id:`) with their primary ID.
+#[derive(Clone, Debug, Default)]
+pub struct AltIds {
+    primary: BTreeMap<String, String>,
+}
+
+impl AltIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, alt: &str, primary: &str) {
+        self.primary.insert(alt.to_string(), primary.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.primary.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primary.is_empty()
+    }
+}
+
+impl IdResolver for AltIds {
+    fn resolve<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        match self.primary.get(token) {
+            Some(primary) => Cow::Owned(primary.clone()),
+            None => Cow::Borrowed(token),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Rules over existential restrictions, and BOTTOM travelling back along
+//! links.
+
+use alloc::vec::Vec;
+
+use super::{Conclusions, InferenceRule};
+use crate::kernel::{ConceptId, RoleId, Rule, BOTTOM};
+
+/// CR3: `d ∈ S(c)` and `d ⊑ ∃r.e` give the link `c -r-> e`.
+pub struct ExistentialIntro;
+
+impl InferenceRule for ExistentialIntro {
+    fn rule(&self) -> Rule {
+        Rule::CR3
+    }
+
+    #[inline(always)]
+    fn concept(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, d: ConceptId) {
+        let store = cx.store();
+        if let Some(fillers) = store.exist_right.get(d as usize) {
+            for &rf in fillers {
+                cx.derive_link(c, rf.role, rf.fill);
+            }
+        }
+    }
+}
+
+/// CR4: the link `c -r-> d`, `e ∈ S(d)` and `∃r.e ⊑ f` give `f ∈ S(c)`;
+/// backward from `e` entering S(d), forward from the link being made.
+pub struct ExistentialElim;
+
+impl InferenceRule for ExistentialElim {
+    fn rule(&self) -> Rule {
+        Rule::CR4
+    }
+
+    #[inline(always)]
+    fn concept(&self, cx: &mut Conclusions<'_, '_>, d: ConceptId, e: ConceptId) {
+        let store = cx.store();
+        for r in 0..cx.num_roles() {
+            if r >= store.exist_left.len() || store.exist_left[r].is_empty() {
+                continue;
+            }
+            let preds: Vec<ConceptId> = cx.context(d).pred_map[r].clone();
+            if preds.is_empty() {
+                continue;
+            }
+            if let Some(sups) = store.exist_left[r].get(&e) {
+                for &pred in &preds {
+                    for &f in sups {
+                        cx.derive(pred, f);
+                    }
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn link(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, r: RoleId, d: ConceptId) {
+        let store = cx.store();
+        let Some(by_filler) = store.exist_left.get(r as usize).filter(|m| !m.is_empty()) else {
+            return;
+        };
+        let supers: Vec<ConceptId> = cx.context(d).super_set.iter().copied().collect();
+        for e in supers {
+            if let Some(sups) = by_filler.get(&e) {
+                for &f in sups {
+                    cx.derive(c, f);
+                }
+            }
+        }
+    }
+}
+
+/// CR5: the link `c -r-> d` and `BOTTOM ∈ S(d)` give `BOTTOM ∈ S(c)`;
+/// backward to the links made before BOTTOM reached `d`, forward from a new
+/// link.
+pub struct BottomPropagation;
+
+impl InferenceRule for BottomPropagation {
+    fn rule(&self) -> Rule {
+        Rule::CR5
+    }
+
+    #[inline(always)]
+    fn concept(&self, cx: &mut Conclusions<'_, '_>, d: ConceptId, added: ConceptId) {
+        if added != BOTTOM || !cx.tracks_bottom() {
+            return;
+        }
+        for r in 0..cx.num_roles() {
+            for i in 0..cx.context(d).pred_map[r].len() {
+                let pred = cx.context(d).pred_map[r][i];
+                cx.derive(pred, BOTTOM);
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn link(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, _r: RoleId, d: ConceptId) {
+        if cx.tracks_bottom() && cx.context(d).super_set.contains(&BOTTOM) {
+            cx.derive(c, BOTTOM);
+        }
+    }
+}
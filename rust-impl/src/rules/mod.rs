@@ -0,0 +1,277 @@
+//! The completion rules as [`InferenceRule`]s, applied by the saturation
+//! engine in the order of a [`RuleSet`].
+//!
+//! A rule reacts to worklist items: a concept entering a super set
+//! ([`InferenceRule::concept`]) or a link being made
+//! ([`InferenceRule::link`]). Rules that need the whole state at once, like
+//! nominal merging, run when both worklists are empty
+//! ([`InferenceRule::idle`]). Conclusions go through [`Conclusions`], which
+//! queues each new fact and logs it under the rule's [`Rule`].
+//!
+//! [`RuleSet::standard`] is the EL++ calculus; its rules are called
+//! directly rather than through the trait object, which the hot loop cannot
+//! afford. Dropping a rule with [`RuleSet::without`] leaves the saturation
+//! incomplete, which is only useful to see what a rule costs.
+
+mod existential;
+mod nominals;
+mod roles;
+mod subsumption;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::kernel::{
+    AxiomStore, ConceptId, Context, Derivation, LinkItem, RoleId, Rule, TraceEvent, WorkItem, Worklist,
+};
+
+pub use existential::{BottomPropagation, ExistentialElim, ExistentialIntro};
+pub use nominals::NominalMerge;
+pub use roles::{Reflexivity, RoleChain, RoleIdentity, RoleInclusion};
+pub use subsumption::{Conjunction, ToldSubsumption};
+
+/// A completion rule, or several logged as the same [`Rule`]. Hooks it
+/// does not react to are left as the defaults, which do nothing.
+pub trait InferenceRule: Sync {
+    /// What the rule's derivations are logged as, and what
+    /// [`RuleSet::without`] drops it by.
+    fn rule(&self) -> Rule;
+
+    /// Applies the rule to `added` having entered the super set of `concept`.
+    fn concept(&self, _cx: &mut Conclusions<'_, '_>, _concept: ConceptId, _added: ConceptId) {}
+
+    /// Applies the rule to the link `source -role-> target` just made.
+    fn link(&self, _cx: &mut Conclusions<'_, '_>, _source: ConceptId, _role: RoleId, _target: ConceptId) {}
+
+    /// Applies the rule once both worklists are empty; true if that queued
+    /// anything.
+    fn idle(&self, _cx: &mut Conclusions<'_, '_>) -> bool {
+        false
+    }
+}
+
+/// The saturation state as a rule sees it: the axioms, the contexts so far,
+/// and the worklists its conclusions go onto.
+pub struct Conclusions<'s, 'e> {
+    pub(crate) store: &'s AxiomStore,
+    pub(crate) num_roles: usize,
+    pub(crate) track_bottom: bool,
+    pub(crate) contexts: &'e mut [Context],
+    pub(crate) worklist: &'e mut Worklist<WorkItem>,
+    pub(crate) link_worklist: &'e mut Worklist<LinkItem>,
+    pub(crate) log: &'e mut Option<Vec<Derivation>>,
+    /// New facts so far, by [`Rule`] discriminant.
+    pub(crate) derived: &'e mut [usize; Rule::ALL.len()],
+    pub(crate) rule: Rule,
+    pub(crate) premise: Option<TraceEvent>,
+}
+
+impl<'s> Conclusions<'s, '_> {
+    pub fn store(&self) -> &'s AxiomStore {
+        self.store
+    }
+
+    pub fn num_roles(&self) -> usize {
+        self.num_roles
+    }
+
+    /// Whether any axiom can derive BOTTOM; if not, rules about BOTTOM
+    /// have nothing to do.
+    pub fn tracks_bottom(&self) -> bool {
+        self.track_bottom
+    }
+
+    pub fn contexts(&self) -> &[Context] {
+        self.contexts
+    }
+
+    #[inline(always)]
+    pub fn context(&self, c: ConceptId) -> &Context {
+        &self.contexts[c as usize]
+    }
+
+    /// Logs what follows as derived from `premise` rather than from the
+    /// item being processed.
+    pub fn set_premise(&mut self, premise: TraceEvent) {
+        self.premise = Some(premise);
+    }
+
+    /// Adds `added` to the super set of `concept`; false if it was there.
+    #[inline(always)]
+    pub fn derive(&mut self, concept: ConceptId, added: ConceptId) -> bool {
+        if !self.contexts[concept as usize].super_set.insert(added) {
+            return false;
+        }
+        self.worklist.push(WorkItem { concept, added });
+        self.record(TraceEvent::Concept { concept, added });
+        true
+    }
+
+    /// Makes the link `source -role-> target`; false if it was there.
+    #[inline(always)]
+    pub fn derive_link(&mut self, source: ConceptId, role: RoleId, target: ConceptId) -> bool {
+        let targets = &mut self.contexts[source as usize].link_map[role as usize];
+        if targets.contains(&target) {
+            return false;
+        }
+        targets.push(target);
+        self.contexts[target as usize].pred_map[role as usize].push(source);
+        self.link_worklist.push(LinkItem { source, role, target });
+        self.record(TraceEvent::Link { source, role, target });
+        true
+    }
+
+    #[inline(always)]
+    fn record(&mut self, derived: TraceEvent) {
+        self.derived[self.rule as usize] += 1;
+        if let Some(log) = self.log {
+            log.push(Derivation { rule: self.rule, premise: self.premise, derived });
+        }
+    }
+}
+
+/// The rules a saturation applies: the standard ones not dropped, in a
+/// fixed order and called directly, then the added ones in the order they
+/// were added.
+#[derive(Clone, Default)]
+pub struct RuleSet {
+    /// Standard rules dropped, as bits indexed by [`Rule`] discriminant.
+    dropped: u32,
+    added: Vec<&'static dyn InferenceRule>,
+}
+
+impl RuleSet {
+    /// CR1–CR6 and CR10–CR12.
+    pub fn standard() -> Self {
+        Self::default()
+    }
+
+    /// No rules at all, to build a set up from.
+    pub fn empty() -> Self {
+        Self { dropped: u32::MAX, added: Vec::new() }
+    }
+
+    /// Adds `rule` after the rules already in the set.
+    pub fn with(mut self, rule: &'static dyn InferenceRule) -> Self {
+        self.added.push(rule);
+        self
+    }
+
+    /// Drops every rule logged as `rule`.
+    pub fn without(mut self, rule: Rule) -> Self {
+        self.dropped |= 1 << rule as u32;
+        self.added.retain(|r| r.rule() != rule);
+        self
+    }
+
+    #[inline(always)]
+    fn has(&self, rule: Rule) -> bool {
+        self.dropped & (1 << rule as u32) == 0
+    }
+
+    /// The rules in the set, each once.
+    pub fn rules(&self) -> Vec<Rule> {
+        let standard = STANDARD.iter().copied().filter(|&r| self.has(r));
+        let mut rules: Vec<Rule> = standard.chain(self.added.iter().map(|r| r.rule())).collect();
+        rules.sort_unstable();
+        rules.dedup();
+        rules
+    }
+
+    #[inline(always)]
+    pub(crate) fn concept(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, d: ConceptId) {
+        apply(self, cx, &ToldSubsumption, |rule, cx| rule.concept(cx, c, d));
+        apply(self, cx, &Conjunction, |rule, cx| rule.concept(cx, c, d));
+        apply(self, cx, &ExistentialIntro, |rule, cx| rule.concept(cx, c, d));
+        apply(self, cx, &Reflexivity, |rule, cx| rule.concept(cx, c, d));
+        apply(self, cx, &BottomPropagation, |rule, cx| rule.concept(cx, c, d));
+        apply(self, cx, &NominalMerge, |rule, cx| rule.concept(cx, c, d));
+        apply(self, cx, &ExistentialElim, |rule, cx| rule.concept(cx, c, d));
+    }
+
+    #[inline(always)]
+    pub(crate) fn has_added(&self) -> bool {
+        !self.added.is_empty()
+    }
+
+    /// The added rules' [`InferenceRule::concept`], after [`Self::concept`]
+    /// and with a [`Conclusions`] of their own, so the standard rules'
+    /// one never reaches a trait object and stays out of memory.
+    #[inline(never)]
+    pub(crate) fn added_concept(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, d: ConceptId) {
+        let premise = cx.premise;
+        for rule in &self.added {
+            cx.rule = rule.rule();
+            cx.premise = premise;
+            rule.concept(cx, c, d);
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn link(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, r: RoleId, d: ConceptId) {
+        apply(self, cx, &ExistentialElim, |rule, cx| rule.link(cx, c, r, d));
+        apply(self, cx, &BottomPropagation, |rule, cx| rule.link(cx, c, r, d));
+        apply(self, cx, &RoleInclusion, |rule, cx| rule.link(cx, c, r, d));
+        apply(self, cx, &RoleChain, |rule, cx| rule.link(cx, c, r, d));
+        apply(self, cx, &RoleIdentity, |rule, cx| rule.link(cx, c, r, d));
+    }
+
+    /// The added rules' [`InferenceRule::link`], as in
+    /// [`Self::added_concept`].
+    #[inline(never)]
+    pub(crate) fn added_link(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, r: RoleId, d: ConceptId) {
+        let premise = cx.premise;
+        for rule in &self.added {
+            cx.rule = rule.rule();
+            cx.premise = premise;
+            rule.link(cx, c, r, d);
+        }
+    }
+
+    pub(crate) fn idle(&self, cx: &mut Conclusions<'_, '_>) -> bool {
+        let mut derived = false;
+        apply(self, cx, &NominalMerge, |rule, cx| derived |= rule.idle(cx));
+        for rule in &self.added {
+            cx.rule = rule.rule();
+            cx.premise = None;
+            derived |= rule.idle(cx);
+        }
+        derived
+    }
+}
+
+/// The rules of [`RuleSet::standard`].
+const STANDARD: [Rule; 9] = [
+    Rule::CR1,
+    Rule::CR2,
+    Rule::CR3,
+    Rule::CR4,
+    Rule::CR5,
+    Rule::CR6,
+    Rule::CR10,
+    Rule::CR11,
+    Rule::CR12,
+];
+
+/// Runs `hook` of the standard `rule` unless `rules` dropped it; the
+/// premise is the item being processed, whatever an earlier rule set.
+#[inline(always)]
+fn apply<R: InferenceRule>(
+    rules: &RuleSet,
+    cx: &mut Conclusions<'_, '_>,
+    rule: &R,
+    hook: impl FnOnce(&R, &mut Conclusions<'_, '_>),
+) {
+    if rules.has(rule.rule()) {
+        let premise = cx.premise;
+        cx.rule = rule.rule();
+        hook(rule, cx);
+        cx.premise = premise;
+    }
+}
+
+impl fmt::Debug for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.rules().iter().map(|r| r.name())).finish()
+    }
+}
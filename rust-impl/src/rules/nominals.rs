@@ -0,0 +1,104 @@
+//! CR6, over nominals.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{Conclusions, InferenceRule};
+use crate::kernel::{ConceptId, Context, Rule, TraceEvent, BOTTOM, TOP};
+
+/// CR6: contexts holding the same nominal `{a}` describe the individual `a`
+/// whenever they are non-empty, so one gets what the other has derived.
+/// That reads every context at once, so it runs when the worklists are
+/// empty. An empty nominal leaves no model, which is recorded as TOP ⊑ ⊥ as
+/// soon as it is derived.
+pub struct NominalMerge;
+
+impl InferenceRule for NominalMerge {
+    fn rule(&self) -> Rule {
+        Rule::CR6
+    }
+
+    #[inline(always)]
+    fn concept(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, added: ConceptId) {
+        if added == BOTTOM
+            && cx.tracks_bottom()
+            && c != TOP
+            && cx.store().nominals.contains(&c)
+            && cx.contexts().len() > TOP as usize
+        {
+            cx.derive(TOP, BOTTOM);
+        }
+    }
+
+    /// Contexts reachable from a nominal are never empty and share with
+    /// every holder of `{a}`; any other context `C` only takes from those
+    /// reachable from itself, as `C` is assumed non-empty while saturating
+    /// it.
+    fn idle(&self, cx: &mut Conclusions<'_, '_>) -> bool {
+        let store = cx.store();
+        if store.nominals.is_empty() {
+            return false;
+        }
+        let contexts = cx.contexts();
+        let n = contexts.len();
+        let mut is_nominal = vec![false; n];
+        for &a in store.nominals.iter().filter(|&&a| (a as usize) < n) {
+            is_nominal[a as usize] = true;
+        }
+        let nominals_of = |ctx: &Context| -> Vec<ConceptId> {
+            ctx.super_set.iter().copied().filter(|&x| is_nominal[x as usize]).collect()
+        };
+        let reach = |contexts: &[Context], from: &mut dyn Iterator<Item = ConceptId>| {
+            let mut seen = vec![false; n];
+            let mut stack: Vec<ConceptId> = from.collect();
+            let mut out = Vec::new();
+            while let Some(c) = stack.pop() {
+                if core::mem::replace(&mut seen[c as usize], true) {
+                    continue;
+                }
+                out.push(c);
+                for targets in &contexts[c as usize].link_map {
+                    stack.extend(targets);
+                }
+            }
+            out
+        };
+
+        let mut holders: Vec<Vec<ConceptId>> = vec![Vec::new(); n];
+        for ctx in contexts.iter() {
+            for a in nominals_of(ctx) {
+                holders[a as usize].push(ctx.id);
+            }
+        }
+        let mut merges: Vec<(ConceptId, ConceptId)> = Vec::new();
+        let global = reach(contexts, &mut store.nominals.iter().copied().filter(|&a| (a as usize) < n));
+        let mut is_global = vec![false; n];
+        for &d in &global {
+            is_global[d as usize] = true;
+            for a in nominals_of(&contexts[d as usize]) {
+                merges.extend(holders[a as usize].iter().map(|&c| (c, d)));
+            }
+        }
+        for c in (0..n as ConceptId).filter(|&c| !is_global[c as usize]) {
+            let own = nominals_of(&contexts[c as usize]);
+            if own.is_empty() {
+                continue;
+            }
+            for d in reach(contexts, &mut core::iter::once(c)) {
+                if d != c && own.iter().any(|a| contexts[d as usize].super_set.contains(a)) {
+                    merges.push((c, d));
+                }
+            }
+        }
+
+        let mut derived = false;
+        for (c, d) in merges.into_iter().filter(|&(c, d)| c != d) {
+            let from: Vec<ConceptId> = cx.context(d).super_set.iter().copied().collect();
+            for x in from {
+                cx.set_premise(TraceEvent::Concept { concept: d, added: x });
+                derived |= cx.derive(c, x);
+            }
+        }
+        derived
+    }
+}
@@ -0,0 +1,122 @@
+//! Rules over role inclusions, chains and reflexive roles.
+
+use alloc::vec::Vec;
+
+use super::{Conclusions, InferenceRule};
+use crate::kernel::{ConceptId, RoleId, Rule};
+
+/// CR10: the link `c -r-> d` and `r ⊑ s` give `c -s-> d`.
+pub struct RoleInclusion;
+
+impl InferenceRule for RoleInclusion {
+    fn rule(&self) -> Rule {
+        Rule::CR10
+    }
+
+    #[inline(always)]
+    fn link(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, r: RoleId, d: ConceptId) {
+        let store = cx.store();
+        if let Some(sups) = store.role_sub_to_sups.get(r as usize) {
+            for &s in sups {
+                cx.derive_link(c, s, d);
+            }
+        }
+    }
+}
+
+/// CR11: the links `c -r-> d -s-> e` and `r ∘ s ⊑ t` give `c -t-> e`, with
+/// the new link as either half.
+pub struct RoleChain;
+
+impl InferenceRule for RoleChain {
+    fn rule(&self) -> Rule {
+        Rule::CR11
+    }
+
+    #[inline(always)]
+    fn link(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, r: RoleId, d: ConceptId) {
+        let store = cx.store();
+        let r = r as usize;
+        if r >= store.chains_by_first.len() {
+            return;
+        }
+        for &(s, t) in &store.chains_by_first[r] {
+            let targets: Vec<ConceptId> = cx.context(d).link_map[s as usize].clone();
+            for e in targets {
+                cx.derive_link(c, t, e);
+            }
+        }
+        for &(q, t) in &store.chains_by_second[r] {
+            let preds: Vec<ConceptId> = cx.context(c).pred_map[q as usize].clone();
+            for p in preds {
+                cx.derive_link(p, t, d);
+            }
+        }
+    }
+}
+
+/// CR11 for right identities `r ∘ s ⊑ r` and left identities `s ∘ r ⊑ r`,
+/// which keep one of the two links' roles and so need no lookup of `t`.
+pub struct RoleIdentity;
+
+impl InferenceRule for RoleIdentity {
+    fn rule(&self) -> Rule {
+        Rule::CR11
+    }
+
+    #[inline(always)]
+    fn link(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, r: RoleId, d: ConceptId) {
+        let store = cx.store();
+        let r = r as usize;
+        if r >= store.right_identities.len() {
+            return;
+        }
+        // r ∘ s ⊑ r: c -r-> d -s-> e
+        for &s in &store.right_identities[r] {
+            let targets: Vec<ConceptId> = cx.context(d).link_map[s as usize].clone();
+            for e in targets {
+                cx.derive_link(c, r as RoleId, e);
+            }
+        }
+        // q ∘ r ⊑ q: p -q-> c -r-> d
+        for &q in &store.right_identities_by_second[r] {
+            let preds: Vec<ConceptId> = cx.context(c).pred_map[q as usize].clone();
+            for p in preds {
+                cx.derive_link(p, q, d);
+            }
+        }
+        // s ∘ r ⊑ r: p -s-> c -r-> d
+        for &s in &store.left_identities[r] {
+            let preds: Vec<ConceptId> = cx.context(c).pred_map[s as usize].clone();
+            for p in preds {
+                cx.derive_link(p, r as RoleId, d);
+            }
+        }
+        // r ∘ t ⊑ t: c -r-> d -t-> e
+        for &t in &store.left_identities_by_first[r] {
+            let targets: Vec<ConceptId> = cx.context(d).link_map[t as usize].clone();
+            for e in targets {
+                cx.derive_link(c, t, e);
+            }
+        }
+    }
+}
+
+/// CR12: a reflexive role links each context to itself.
+pub struct Reflexivity;
+
+impl InferenceRule for Reflexivity {
+    fn rule(&self) -> Rule {
+        Rule::CR12
+    }
+
+    #[inline(always)]
+    fn concept(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, added: ConceptId) {
+        if added != c {
+            return;
+        }
+        for &r in &cx.store().reflexive_roles {
+            cx.derive_link(c, r, c);
+        }
+    }
+}
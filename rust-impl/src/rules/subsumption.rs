@@ -0,0 +1,47 @@
+//! Rules over told subsumptions and conjunctions.
+
+use super::{Conclusions, InferenceRule};
+use crate::kernel::{ConceptId, Rule};
+
+/// CR1: `d ∈ S(c)` and `d ⊑ e` give `e ∈ S(c)`.
+pub struct ToldSubsumption;
+
+impl InferenceRule for ToldSubsumption {
+    fn rule(&self) -> Rule {
+        Rule::CR1
+    }
+
+    #[inline(always)]
+    fn concept(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, d: ConceptId) {
+        let store = cx.store();
+        if let Some(sups) = store.sub_to_sups.get(d as usize) {
+            for &e in sups {
+                cx.derive(c, e);
+            }
+        }
+    }
+}
+
+/// CR2: `d, d2 ∈ S(c)` and `d ⊓ d2 ⊑ e` give `e ∈ S(c)`.
+pub struct Conjunction;
+
+impl InferenceRule for Conjunction {
+    fn rule(&self) -> Rule {
+        Rule::CR2
+    }
+
+    #[inline(always)]
+    fn concept(&self, cx: &mut Conclusions<'_, '_>, c: ConceptId, d: ConceptId) {
+        let store = cx.store();
+        let Some(conjunctions) = store.conj_index.get(d as usize) else {
+            return;
+        };
+        for (&d2, results) in conjunctions {
+            if cx.context(c).super_set.contains(&d2) {
+                for &e in results {
+                    cx.derive(c, e);
+                }
+            }
+        }
+    }
+}
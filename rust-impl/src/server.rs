@@ -0,0 +1,274 @@
+//! The reasoning service behind `el-reasoner serve` (see `openapi.yaml`).
+//!
+//! One ontology is classified up front and answers hierarchy queries until
+//! `POST /classify` replaces it. Plain HTTP/1.1 with `Connection: close`, as
+//! [`crate::client`] expects; requests are answered in turn on the
+//! accepting thread, so an upload being classified holds the queries
+//! behind it. With the `owl` feature, OWLlink requests are answered on
+//! `POST /` and `POST /owllink`; see [`crate::owl::owllink`].
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::api::{ApiError, ClassifySummary, ConceptList, ConceptRef, Satisfiability};
+use crate::input::{parse_document, InputFormat, SNIFF_LEN};
+use crate::json::Value;
+#[cfg(feature = "owl")]
+use crate::owl::owllink::OwlLink;
+use crate::obo::{build_axiom_store, ParseResult};
+use crate::resolve::IdResolver;
+use crate::{count_inferred_subsumptions, saturate, unsatisfiable_concepts, ConceptId, Taxonomy, BOTTOM};
+
+/// Request heads longer than this are rejected.
+const MAX_HEAD: usize = 64 * 1024;
+/// Request bodies longer than this are rejected, before any is read.
+const MAX_BODY: usize = 1 << 30;
+
+/// A classified ontology.
+struct Classified {
+    parsed: ParseResult,
+    taxonomy: Taxonomy,
+    unsatisfiable: Vec<ConceptId>,
+    inferred_subsumptions: usize,
+}
+
+impl Classified {
+    fn new(parsed: ParseResult) -> Self {
+        let n = parsed.concepts.len();
+        let store = build_axiom_store(&parsed);
+        let mut contexts = saturate(&store, store.num_concepts(), parsed.roles.len());
+        contexts.truncate(n);
+        Self {
+            taxonomy: Taxonomy::new(&contexts, n),
+            unsatisfiable: unsatisfiable_concepts(&contexts),
+            inferred_subsumptions: count_inferred_subsumptions(&contexts),
+            parsed,
+        }
+    }
+
+    fn concept_ref(&self, c: ConceptId) -> ConceptRef {
+        ConceptRef {
+            id: self.parsed.concepts[c as usize].clone(),
+            label: self.parsed.labels.get(&(c as usize)).cloned(),
+        }
+    }
+
+    /// `ids` as concept references, sorted by ID.
+    fn concept_refs(&self, ids: impl IntoIterator<Item = ConceptId>) -> Vec<ConceptRef> {
+        let mut refs: Vec<ConceptRef> =
+            ids.into_iter().filter(|&c| c != BOTTOM).map(|c| self.concept_ref(c)).collect();
+        refs.sort_by(|a, b| a.id.cmp(&b.id));
+        refs
+    }
+
+    fn summary(&self) -> ClassifySummary {
+        ClassifySummary {
+            concepts: self.parsed.concepts.len().saturating_sub(2),
+            inferred_subsumptions: self.inferred_subsumptions,
+            unsatisfiable: self.concept_refs(self.unsatisfiable.iter().copied()),
+        }
+    }
+}
+
+/// A status code and JSON body.
+pub type Response = (u16, Value);
+
+fn error(code: u16, message: impl Into<String>) -> Response {
+    (code, ApiError { error: message.into() }.to_json())
+}
+
+pub struct Service<'r> {
+    /// Applied to concept IDs in paths and to uploads, as to the startup input.
+    resolver: &'r dyn IdResolver,
+    classified: Classified,
+    #[cfg(feature = "owl")]
+    owllink: OwlLink,
+}
+
+impl<'r> Service<'r> {
+    /// Classifies `parsed`, which was read through `resolver`.
+    pub fn new(parsed: ParseResult, resolver: &'r dyn IdResolver) -> Self {
+        Self {
+            resolver,
+            classified: Classified::new(parsed),
+            #[cfg(feature = "owl")]
+            owllink: OwlLink::new(),
+        }
+    }
+
+    pub fn summary(&self) -> ClassifySummary {
+        self.classified.summary()
+    }
+
+    /// Answers requests on `listener` until accepting fails. A connection
+    /// that breaks mid-request is dropped.
+    pub fn serve(&mut self, listener: &TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let _ = self.handle(stream?);
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(&stream);
+        let request = match read_request(&mut reader)? {
+            Ok(request) => request,
+            Err((code, body)) => return write_response(&stream, code, "application/json", &body.to_string()),
+        };
+        #[cfg(feature = "owl")]
+        if request.method == "POST" && matches!(request.target.as_str(), "/" | "/owllink") {
+            let xml = self.owllink.respond(&request.body);
+            return write_response(&stream, 200, "application/xml", &xml);
+        }
+        let (code, body) = self.respond(&request.method, &request.target, &request.body);
+        write_response(&stream, code, "application/json", &body.to_string())
+    }
+
+    /// The response to `method target` with `body`, per `openapi.yaml`.
+    pub fn respond(&mut self, method: &str, target: &str, body: &[u8]) -> Response {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let direct = query.split('&').any(|pair| matches!(pair, "direct=true" | "direct=1"));
+        let (endpoint, id) = match path.trim_start_matches('/').split_once('/') {
+            Some((endpoint, id)) => (endpoint, Some(id)),
+            None => (path.trim_start_matches('/'), None),
+        };
+        match (method, endpoint, id) {
+            ("POST", "classify", None) => self.classify(body),
+            ("GET", "subclasses" | "superclasses" | "equivalents" | "satisfiable", Some(id)) => {
+                let Some(id) = percent_decode(id) else {
+                    return error(400, "malformed percent-encoding");
+                };
+                let classified = &self.classified;
+                let Some(&c) = classified.parsed.concept_idx.get(self.resolver.resolve(&id).as_ref()) else {
+                    return error(404, format!("unknown concept '{}'", id));
+                };
+                let c = c as ConceptId;
+                let taxonomy = &classified.taxonomy;
+                // Equivalents share the representative's place in the hierarchy.
+                let r = taxonomy.representative(c);
+                let concepts = match endpoint {
+                    "satisfiable" => {
+                        let satisfiable = !classified.unsatisfiable.contains(&c);
+                        let concept = classified.concept_ref(c);
+                        return (200, Satisfiability { concept, satisfiable }.to_json());
+                    }
+                    "subclasses" if direct => classified.concept_refs(taxonomy.children(r).iter().copied()),
+                    "subclasses" => classified.concept_refs(taxonomy.descendants(r)),
+                    "superclasses" if direct => classified.concept_refs(taxonomy.parents(r).iter().copied()),
+                    "superclasses" => classified.concept_refs(taxonomy.ancestors(r)),
+                    _ => {
+                        let class = taxonomy.equivalences().iter().find(|class| class.contains(&c));
+                        classified.concept_refs(class.into_iter().flatten().copied().filter(|&e| e != c))
+                    }
+                };
+                (200, ConceptList { concept: classified.concept_ref(c), concepts }.to_json())
+            }
+            (_, "classify" | "subclasses" | "superclasses" | "equivalents" | "satisfiable", _) => {
+                error(405, format!("{} is not allowed on {}", method, path))
+            }
+            _ => error(404, format!("no endpoint {}", path)),
+        }
+    }
+
+    /// Replaces the served ontology with the document in `body`, whose
+    /// format is sniffed (OBO when in doubt).
+    fn classify(&mut self, body: &[u8]) -> Response {
+        let head = &body[..body.len().min(SNIFF_LEN)];
+        let format = InputFormat::sniff(head).unwrap_or(InputFormat::Obo);
+        match parse_document(body, format, self.resolver, false) {
+            Ok((parsed, _)) => {
+                self.classified = Classified::new(parsed);
+                (200, self.summary().to_json())
+            }
+            Err(e) => error(400, e.to_string()),
+        }
+    }
+}
+
+struct Request {
+    method: String,
+    target: String,
+    body: Vec<u8>,
+}
+
+/// The next request, or the response to a malformed one.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Response>> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(Err(error(400, "malformed request line")));
+    };
+    let mut content_length = 0;
+    let mut head_len = request_line.len();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        head_len += reader.read_line(&mut line)?;
+        if head_len > MAX_HEAD {
+            return Ok(Err(error(431, "request head too large")));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(n) => content_length = n,
+                    Err(_) => return Ok(Err(error(400, "bad Content-Length"))),
+                }
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Ok(Err(error(413, "request body too large")));
+    }
+    let mut body = Vec::new();
+    reader.take(content_length as u64).read_to_end(&mut body)?;
+    if body.len() < content_length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Ok(Request { method: method.to_string(), target: target.to_string(), body }))
+}
+
+fn write_response(mut stream: &TcpStream, code: u16, content_type: &str, body: &str) -> io::Result<()> {
+    let reason = match code {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Content Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        reason,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Inverse of [`crate::client::percent_encode`]; `None` if a `%` isn't
+/// followed by two hex digits or the result isn't UTF-8.
+pub fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
@@ -0,0 +1,118 @@
+//! Semantic similarity between classified concepts, using intrinsic
+//! information content (IC from descendant counts, no annotation corpus).
+
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::kernel::{ConceptId, Context};
+use crate::lcs::LcsIndex;
+use crate::taxonomy::{descendant_counts, Taxonomy};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Measure {
+    /// IC of the most informative common ancestor.
+    Resnik,
+    /// Resnik normalized by the ICs of both concepts, in `[0, 1]`.
+    Lin,
+    /// Shared ancestors over all ancestors of either concept, in `[0, 1]`.
+    Jaccard,
+}
+
+impl Measure {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "resnik" => Some(Measure::Resnik),
+            "lin" => Some(Measure::Lin),
+            "jaccard" => Some(Measure::Jaccard),
+            _ => None,
+        }
+    }
+}
+
+pub struct Similarity {
+    index: LcsIndex,
+    /// Intrinsic IC per concept: `-ln((descendants + 1) / classified)`.
+    ic: Vec<f64>,
+}
+
+impl Similarity {
+    pub fn new(contexts: &[Context], taxonomy: &Taxonomy) -> Self {
+        let n = taxonomy.len();
+        let classified = n.saturating_sub(2).max(1) as f64;
+        let ic = descendant_counts(contexts, n)
+            .into_iter()
+            .map(|d| -((d as f64 + 1.0) / classified).min(1.0).ln())
+            .collect();
+        Self { index: LcsIndex::new(contexts, taxonomy), ic }
+    }
+
+    pub fn ic(&self, c: ConceptId) -> f64 {
+        self.ic[c as usize]
+    }
+
+    pub fn score(&self, measure: Measure, a: ConceptId, b: ConceptId) -> f64 {
+        let common = self.index.common_ancestors(a, b);
+        match measure {
+            Measure::Resnik => self.mica_ic(&common),
+            Measure::Lin => {
+                let total = self.ic(a) + self.ic(b);
+                if total == 0.0 {
+                    return if a == b { 1.0 } else { 0.0 };
+                }
+                2.0 * self.mica_ic(&common) / total
+            }
+            Measure::Jaccard => {
+                let union = self.index.ancestor_count(a) + self.index.ancestor_count(b)
+                    - common.len();
+                common.len() as f64 / union.max(1) as f64
+            }
+        }
+    }
+
+    fn mica_ic(&self, common: &[ConceptId]) -> f64 {
+        common.iter().map(|&c| self.ic(c)).fold(0.0, f64::max)
+    }
+
+    /// Row-major `terms × terms` scores. Rows are split into one contiguous
+    /// block per thread, so the result doesn't depend on `threads`.
+    pub fn matrix(&self, measure: Measure, terms: &[ConceptId], threads: NonZeroUsize) -> Vec<f64> {
+        let n = terms.len();
+        let mut scores = vec![0.0; n * n];
+        if n == 0 {
+            return scores;
+        }
+        let rows_per_thread = n.div_ceil(threads.get());
+        thread::scope(|scope| {
+            for (block, out) in scores.chunks_mut(rows_per_thread * n).enumerate() {
+                scope.spawn(move || {
+                    for (i, row) in out.chunks_mut(n).enumerate() {
+                        let a = terms[block * rows_per_thread + i];
+                        for (cell, &b) in row.iter_mut().zip(terms) {
+                            *cell = self.score(measure, a, b);
+                        }
+                    }
+                });
+            }
+        });
+        scores
+    }
+}
+
+/// Writes a square TSV matrix: a header row of term names, then one row per
+/// term starting with its name.
+pub fn write_matrix_tsv<W: Write>(out: &mut W, names: &[&str], scores: &[f64]) -> io::Result<()> {
+    write!(out, "term")?;
+    for name in names {
+        write!(out, "\t{}", name)?;
+    }
+    writeln!(out)?;
+    for (name, row) in names.iter().zip(scores.chunks(names.len().max(1))) {
+        write!(out, "{}", name)?;
+        for score in row {
+            write!(out, "\t{:.6}", score)?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()
+}
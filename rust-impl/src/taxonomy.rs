@@ -0,0 +1,474 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::kernel::{ConceptId, Context, BOTTOM, TOP};
+use crate::progress::{NoProgress, Phase, ProgressSink};
+
+/// Concepts between two progress reports.
+const PROGRESS_STEP: usize = 1 << 14;
+
+/// Groups of two or more satisfiable concepts among the first
+/// `num_concepts` that subsume each other. Each group is sorted, so its
+/// first member (the smallest ID) is the representative, and groups come in
+/// ascending order of representative.
+pub fn equivalence_classes(contexts: &[Context], num_concepts: usize) -> Vec<Vec<ConceptId>> {
+    equivalence_classes_among(contexts, &vec![true; num_concepts])
+}
+
+/// [`equivalence_classes`] of the concepts `c` with `named[c]` set, fresh
+/// concepts being numbered among them.
+pub fn equivalence_classes_among(contexts: &[Context], named: &[bool]) -> Vec<Vec<ConceptId>> {
+    let num_concepts = named.len();
+    let mut grouped = vec![false; num_concepts];
+    let mut classes = Vec::new();
+    for c in 2..num_concepts {
+        let supers = &contexts[c].super_set;
+        if grouped[c] || !named[c] || supers.contains(&BOTTOM) {
+            continue;
+        }
+        // An equivalent below c would already have grouped it.
+        let mut class: Vec<ConceptId> = supers
+            .iter()
+            .copied()
+            .filter(|&s| {
+                let s = s as usize;
+                s > c && s < num_concepts && named[s] && contexts[s].super_set.contains(&(c as ConceptId))
+            })
+            .collect();
+        if class.is_empty() {
+            continue;
+        }
+        class.push(c as ConceptId);
+        class.sort_unstable();
+        for &m in &class {
+            grouped[m as usize] = true;
+        }
+        classes.push(class);
+    }
+    classes
+}
+
+/// Direct parents of the first `num_concepts` concepts. Concepts numbered
+/// from `num_concepts` up (fresh concepts introduced by normalization) are
+/// never reported as parents.
+pub fn build_taxonomy(contexts: &[Context], num_concepts: usize) -> Vec<Vec<ConceptId>> {
+    let equivalences = equivalence_classes(contexts, num_concepts);
+    build_taxonomy_with(contexts, num_concepts, &equivalences)
+}
+
+/// [`build_taxonomy`] over precomputed [`equivalence_classes`]. The
+/// hierarchy is reduced between representatives only: every member of a
+/// class gets its representative's parents, and only representatives are
+/// parents of anything, so equivalent concepts never form a cycle.
+pub fn build_taxonomy_with(
+    contexts: &[Context],
+    num_concepts: usize,
+    equivalences: &[Vec<ConceptId>],
+) -> Vec<Vec<ConceptId>> {
+    build_taxonomy_with_progress(contexts, num_concepts, equivalences, &mut NoProgress)
+}
+
+/// [`build_taxonomy_with`], reporting [`Phase::Taxonomy`] progress to
+/// `progress`.
+pub fn build_taxonomy_with_progress(
+    contexts: &[Context],
+    num_concepts: usize,
+    equivalences: &[Vec<ConceptId>],
+    progress: &mut dyn ProgressSink,
+) -> Vec<Vec<ConceptId>> {
+    build_taxonomy_among(contexts, &vec![true; num_concepts], equivalences, progress)
+}
+
+/// [`build_taxonomy_with_progress`] over the concepts `c` with `named[c]`
+/// set; the others get no parents and are never reported as one.
+pub fn build_taxonomy_among(
+    contexts: &[Context],
+    named: &[bool],
+    equivalences: &[Vec<ConceptId>],
+    progress: &mut dyn ProgressSink,
+) -> Vec<Vec<ConceptId>> {
+    progress.on_phase(Phase::Taxonomy);
+    let num_concepts = named.len();
+    let reducer = Reducer::new(contexts, named, equivalences);
+    let mut covered = vec![0; num_concepts];
+    let mut direct_parents: Vec<Vec<ConceptId>> = vec![Vec::new(); num_concepts];
+    for (c, parents) in direct_parents.iter_mut().enumerate().skip(2) {
+        if c % PROGRESS_STEP == 0 {
+            progress.on_progress(c, num_concepts);
+        }
+        *parents = reducer.direct_parents(c as ConceptId, &mut covered);
+    }
+    reducer.share_parents(&mut direct_parents);
+    progress.on_progress(num_concepts, num_concepts);
+
+    direct_parents
+}
+
+/// The transitive reduction of one concept's super set at a time, so
+/// concepts can be split between threads.
+pub(crate) struct Reducer<'a> {
+    contexts: &'a [Context],
+    named: &'a [bool],
+    equivalences: &'a [Vec<ConceptId>],
+    representative: Vec<ConceptId>,
+}
+
+impl<'a> Reducer<'a> {
+    pub(crate) fn new(contexts: &'a [Context], named: &'a [bool], equivalences: &'a [Vec<ConceptId>]) -> Self {
+        let mut representative: Vec<ConceptId> = (0..named.len() as ConceptId).collect();
+        for class in equivalences {
+            for &m in class {
+                representative[m as usize] = class[0];
+            }
+        }
+        Self { contexts, named, equivalences, representative }
+    }
+
+    /// Direct parents of `c` if it is a named representative, else none
+    /// (see [`share_parents`](Self::share_parents)). `covered` is scratch
+    /// space of one entry per concept, holding no concept ID above 1 when
+    /// first passed in.
+    ///
+    /// A candidate below another has the larger super set, so sweeping the
+    /// candidates from the largest super set down, each one not yet covered
+    /// is direct and covers its own supers: O(k log k) for k candidates plus
+    /// the sizes of the direct parents' super sets, instead of k² lookups.
+    pub(crate) fn direct_parents(&self, c: ConceptId, covered: &mut [u32]) -> Vec<ConceptId> {
+        let num_concepts = self.named.len();
+        if self.representative[c as usize] != c || !self.named[c as usize] {
+            return Vec::new();
+        }
+        let supers = &self.contexts[c as usize].super_set;
+
+        let mut candidates: Vec<ConceptId> = Vec::with_capacity(supers.len());
+        let mut has_top = false;
+
+        for &s in supers {
+            match s {
+                TOP => { has_top = true; continue; }
+                BOTTOM => continue,
+                x if x == c || x as usize >= num_concepts || !self.named[x as usize] => continue,
+                // Equivalents of c, and of other candidates, are covered by
+                // their representative.
+                x if self.representative[x as usize] != x => continue,
+                _ => candidates.push(s),
+            }
+        }
+
+        let mut by_size = candidates.clone();
+        by_size.sort_unstable_by_key(|&b| core::cmp::Reverse(self.contexts[b as usize].super_set.len()));
+        for b in by_size {
+            if covered[b as usize] == c {
+                continue;
+            }
+            for &s in &self.contexts[b as usize].super_set {
+                if s != b && (s as usize) < num_concepts {
+                    covered[s as usize] = c;
+                }
+            }
+        }
+        let mut direct: Vec<ConceptId> = candidates.into_iter().filter(|&b| covered[b as usize] != c).collect();
+
+        if direct.is_empty() && has_top {
+            direct.push(TOP);
+        }
+        direct
+    }
+
+    /// Gives every member of an equivalence class its representative's
+    /// parents.
+    pub(crate) fn share_parents(&self, direct_parents: &mut [Vec<ConceptId>]) {
+        for class in self.equivalences {
+            let parents = direct_parents[class[0] as usize].clone();
+            for &m in &class[1..] {
+                direct_parents[m as usize] = parents.clone();
+            }
+        }
+    }
+}
+
+/// Shortest and longest `is_a` path length from TOP (depth 0).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepthRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+/// The reduced hierarchy with both directions indexed: parent lists per
+/// concept, and a CSR (offsets + flat array) children index built from them.
+#[derive(Clone, Debug, Default)]
+pub struct Taxonomy {
+    direct_parents: Vec<Vec<ConceptId>>,
+    /// See [`equivalence_classes`]; empty unless supplied.
+    equivalences: Vec<Vec<ConceptId>>,
+    /// `children[child_offsets[c]..child_offsets[c + 1]]` are the children of `c`.
+    child_offsets: Vec<usize>,
+    children: Vec<ConceptId>,
+}
+
+impl Taxonomy {
+    /// Classifies the first `num_concepts` concepts; see [`build_taxonomy`].
+    pub fn new(contexts: &[Context], num_concepts: usize) -> Self {
+        let equivalences = equivalence_classes(contexts, num_concepts);
+        let direct_parents = build_taxonomy_with(contexts, num_concepts, &equivalences);
+        Self::from_parents(direct_parents).with_equivalences(equivalences)
+    }
+
+    /// Classifies the concepts `c` with `named[c]` set, leaving out fresh
+    /// concepts numbered among them.
+    pub fn among(contexts: &[Context], named: &[bool]) -> Self {
+        let equivalences = equivalence_classes_among(contexts, named);
+        let direct_parents = build_taxonomy_among(contexts, named, &equivalences, &mut NoProgress);
+        Self::from_parents(direct_parents).with_equivalences(equivalences)
+    }
+
+    pub fn with_equivalences(mut self, equivalences: Vec<Vec<ConceptId>>) -> Self {
+        self.equivalences = equivalences;
+        self
+    }
+
+    pub fn from_parents(direct_parents: Vec<Vec<ConceptId>>) -> Self {
+        let n = direct_parents.len();
+        let mut child_offsets = vec![0usize; n + 1];
+        for parents in &direct_parents {
+            for &p in parents {
+                child_offsets[p as usize + 1] += 1;
+            }
+        }
+        for i in 0..n {
+            child_offsets[i + 1] += child_offsets[i];
+        }
+
+        let mut fill = child_offsets.clone();
+        let mut children = vec![0; child_offsets[n]];
+        for (c, parents) in direct_parents.iter().enumerate() {
+            for &p in parents {
+                children[fill[p as usize]] = c as ConceptId;
+                fill[p as usize] += 1;
+            }
+        }
+
+        Self { direct_parents, equivalences: Vec::new(), child_offsets, children }
+    }
+
+    /// Number of concepts covered (including TOP and BOTTOM).
+    pub fn len(&self) -> usize {
+        self.direct_parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.direct_parents.is_empty()
+    }
+
+    pub fn parents(&self, c: ConceptId) -> &[ConceptId] {
+        &self.direct_parents[c as usize]
+    }
+
+    /// Direct children of `c`, in ascending concept order.
+    pub fn children(&self, c: ConceptId) -> &[ConceptId] {
+        let c = c as usize;
+        &self.children[self.child_offsets[c]..self.child_offsets[c + 1]]
+    }
+
+    /// Every concept reachable from `c` through `next`, `c` excluded, in
+    /// ascending order.
+    fn reachable<'a>(
+        &'a self,
+        c: ConceptId,
+        next: impl Fn(ConceptId) -> &'a [ConceptId],
+    ) -> Vec<ConceptId> {
+        let mut seen = vec![false; self.len()];
+        seen[c as usize] = true;
+        let mut stack = vec![c];
+        let mut out = Vec::new();
+        while let Some(x) = stack.pop() {
+            for &y in next(x) {
+                if !core::mem::replace(&mut seen[y as usize], true) {
+                    out.push(y);
+                    stack.push(y);
+                }
+            }
+        }
+        out.sort_unstable();
+        out
+    }
+
+    /// Every concept above `c` in the hierarchy, TOP included.
+    pub fn ancestors(&self, c: ConceptId) -> Vec<ConceptId> {
+        self.reachable(c, |x| self.parents(x))
+    }
+
+    /// Every concept below `c` in the hierarchy.
+    pub fn descendants(&self, c: ConceptId) -> Vec<ConceptId> {
+        self.reachable(c, |x| self.children(x))
+    }
+
+    /// The most specific concepts above both `a` and `b` (each counting as
+    /// above itself), from the hierarchy alone; see
+    /// [`LcsIndex`](crate::lcs::LcsIndex) for repeated queries.
+    pub fn lowest_common_ancestors(&self, a: ConceptId, b: ConceptId) -> Vec<ConceptId> {
+        let mut common = self.ancestors(a);
+        common.push(a);
+        common.sort_unstable();
+        let mut of_b = self.ancestors(b);
+        of_b.push(b);
+        of_b.sort_unstable();
+        common.retain(|c| of_b.binary_search(c).is_ok());
+        // Ancestors of a common ancestor are common too, so one that is a
+        // direct parent of another is not the lowest.
+        let mut above = vec![false; self.len()];
+        for &c in &common {
+            for &p in self.parents(c) {
+                above[p as usize] = true;
+            }
+        }
+        common.retain(|&c| !above[c as usize]);
+        common
+    }
+
+    /// Groups of equivalent concepts, representative first.
+    pub fn equivalences(&self) -> &[Vec<ConceptId>] {
+        &self.equivalences
+    }
+
+    /// The representative of `c`'s equivalence group, which stands for the
+    /// group in the hierarchy; `c` when it has no equivalents.
+    pub fn representative(&self, c: ConceptId) -> ConceptId {
+        self.equivalences.iter().find(|class| class.contains(&c)).map_or(c, |class| class[0])
+    }
+
+    /// Concepts directly under TOP.
+    pub fn roots(&self) -> &[ConceptId] {
+        self.children(TOP)
+    }
+
+    /// Classified concepts without inferred children, in ascending order.
+    pub fn leaves(&self) -> impl Iterator<Item = ConceptId> + '_ {
+        (2..self.len() as ConceptId)
+            .filter(|&c| self.children(c).is_empty() && !self.parents(c).is_empty())
+    }
+
+    /// Every concept, parents before children: reverse DFS postorder over the
+    /// children index, so TOP comes first. Cycles (parent lists built other
+    /// than by [`build_taxonomy`]) are broken arbitrarily.
+    pub fn topological_order(&self) -> Vec<ConceptId> {
+        let n = self.len();
+        let mut visited = vec![false; n];
+        let mut order = Vec::with_capacity(n);
+        let mut stack: Vec<(ConceptId, usize)> = Vec::new();
+
+        // Any start order yields a valid ordering; descending ids make TOP
+        // the last to finish and therefore the first in the result.
+        for start in (0..n as ConceptId).rev() {
+            if visited[start as usize] {
+                continue;
+            }
+            visited[start as usize] = true;
+            stack.push((start, 0));
+            while let Some(top) = stack.last_mut() {
+                let (c, next) = *top;
+                match self.children(c).get(next) {
+                    Some(&child) => {
+                        top.1 += 1;
+                        if !visited[child as usize] {
+                            visited[child as usize] = true;
+                            stack.push((child, 0));
+                        }
+                    }
+                    None => {
+                        order.push(c);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        order.reverse();
+        order
+    }
+
+    /// Min/max depth of every concept over the inferred hierarchy; roots are
+    /// at depth 1. Concepts without parents (TOP, BOTTOM) are at depth 0.
+    pub fn depths(&self) -> Vec<DepthRange> {
+        let mut depths = vec![DepthRange::default(); self.len()];
+        let mut done = vec![false; self.len()];
+        for c in self.topological_order() {
+            let mut range: Option<DepthRange> = None;
+            // Parents not yet done are equivalents in the same cycle.
+            for &p in self.parents(c).iter().filter(|&&p| done[p as usize]) {
+                let d = depths[p as usize];
+                range = Some(match range {
+                    None => DepthRange { min: d.min + 1, max: d.max + 1 },
+                    Some(r) => DepthRange { min: r.min.min(d.min + 1), max: r.max.max(d.max + 1) },
+                });
+            }
+            depths[c as usize] = range.unwrap_or_default();
+            done[c as usize] = true;
+        }
+        depths
+    }
+
+    /// Parent lists indexed by concept, as taken by the output writers.
+    pub fn direct_parents(&self) -> &[Vec<ConceptId>] {
+        &self.direct_parents
+    }
+}
+
+/// Number of concepts strictly below each of the first `num_concepts`
+/// concepts, read off the closure so a descendant reachable along several
+/// paths of the DAG is counted once. Equivalent concepts don't count as each
+/// other's descendants.
+pub fn descendant_counts(contexts: &[Context], num_concepts: usize) -> Vec<u32> {
+    let mut counts = vec![0u32; num_concepts];
+    for d in 2..num_concepts {
+        let did = d as ConceptId;
+        for &a in &contexts[d].super_set {
+            let ai = a as usize;
+            if a == did || a == BOTTOM || ai >= num_concepts {
+                continue;
+            }
+            if a != TOP && contexts[ai].super_set.contains(&did) {
+                continue;
+            }
+            counts[ai] += 1;
+        }
+    }
+    counts
+}
+
+/// Puts every parent list in ascending concept order, so the taxonomy no
+/// longer depends on the order saturation happened to fill the super sets.
+pub fn sort_direct_parents(direct_parents: &mut [Vec<ConceptId>]) {
+    for parents in direct_parents {
+        parents.sort_unstable();
+    }
+}
+
+/// Concepts other than BOTTOM whose super set contains BOTTOM; pass only the
+/// named prefix of `contexts` to leave fresh normalization concepts out.
+pub fn unsatisfiable_concepts(contexts: &[Context]) -> Vec<ConceptId> {
+    (2..contexts.len())
+        .filter(|&c| contexts[c].super_set.contains(&BOTTOM))
+        .map(|c| c as ConceptId)
+        .collect()
+}
+
+/// Whether the saturated ontology has a model, i.e. TOP is satisfiable. A
+/// nominal that derives BOTTOM puts it into TOP as well, so this covers
+/// both ways an EL ontology can be inconsistent.
+pub fn is_consistent(contexts: &[Context]) -> bool {
+    contexts.get(TOP as usize).is_none_or(|top| !top.super_set.contains(&BOTTOM))
+}
+
+/// Inferred subsumptions between the concepts in `contexts`; pass only the
+/// named prefix to leave fresh normalization concepts out of the count.
+pub fn count_inferred_subsumptions(contexts: &[Context]) -> usize {
+    let n = contexts.len();
+    contexts.iter()
+        .skip(2)
+        .map(|c| {
+            let fresh = c.super_set.iter().filter(|&&s| s as usize >= n).count();
+            c.super_set.len().saturating_sub(2 + fresh)
+        })
+        .sum()
+}
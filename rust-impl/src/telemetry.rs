@@ -0,0 +1,196 @@
+//! Run statistics collected through `tracing`. The pipeline runs each phase
+//! in a span named after it (`parse`, `normalize`, `saturate`, `reduce`) and
+//! the saturation kernel reports the facts each rule derived as events with
+//! `rule` and `derived` fields. A [`Recorder`] installed as the subscriber
+//! sums both up, so runs over different ontology versions or machines can be
+//! compared from its [`Recorder::to_json`] output.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+use crate::json::Value;
+
+#[derive(Default)]
+struct State {
+    /// Every span so far; span `i` has ID `i + 1`.
+    spans: Vec<Span>,
+    /// Total time in closed spans, by name, in the order first closed.
+    phases: Vec<(&'static str, Duration)>,
+    /// Facts derived, by rule, in the order first reported.
+    rules: Vec<(String, u64)>,
+}
+
+impl State {
+    fn span(&mut self, id: &Id) -> Option<&mut Span> {
+        self.spans.get_mut((id.into_u64() - 1) as usize)
+    }
+}
+
+struct Span {
+    name: &'static str,
+    entered: Option<Instant>,
+    elapsed: Duration,
+    /// Handles to the span; it closes when the last one goes.
+    refs: usize,
+}
+
+/// A `tracing` subscriber timing spans and counting rule derivations.
+/// Clones share what they record, so one can be installed and another kept
+/// to read it back.
+#[derive(Clone, Default)]
+pub struct Recorder {
+    state: Arc<Mutex<State>>,
+    /// Print the messages of INFO and more severe events to stderr.
+    echo: bool,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prints event messages at INFO and above to stderr as they come.
+    pub fn with_echo(mut self, echo: bool) -> Self {
+        self.echo = echo;
+        self
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Total time spent in closed spans named `name`.
+    pub fn phase(&self, name: &str) -> Duration {
+        let state = self.state();
+        state.phases.iter().filter(|(n, _)| *n == name).map(|&(_, d)| d).sum()
+    }
+
+    /// Time in closed spans, by span name, in the order first closed.
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        self.state().phases.clone()
+    }
+
+    /// Facts derived by each rule that derived any.
+    pub fn rule_counts(&self) -> Vec<(String, u64)> {
+        self.state().rules.clone()
+    }
+
+    /// `{"phases_ms": {...}, "rules": {...}}`.
+    pub fn to_json(&self) -> Value {
+        let state = self.state();
+        let phases = state
+            .phases
+            .iter()
+            .map(|&(name, d)| (name.to_string(), Value::from(d.as_secs_f64() * 1000.0)))
+            .collect();
+        let rules = state.rules.iter().map(|(rule, n)| (rule.clone(), Value::Number(*n as f64))).collect();
+        Value::Object(vec![
+            ("phases_ms".to_string(), Value::Object(phases)),
+            ("rules".to_string(), Value::Object(rules)),
+        ])
+    }
+}
+
+/// The fields of an event the recorder looks at.
+#[derive(Default)]
+struct Fields {
+    message: Option<String>,
+    rule: Option<String>,
+    derived: Option<u64>,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "rule" {
+            self.rule = Some(value.to_string());
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "derived" {
+            self.derived = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{:?}", value));
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let mut state = self.state();
+        let name = attrs.metadata().name();
+        state.spans.push(Span { name, entered: None, elapsed: Duration::ZERO, refs: 1 });
+        Id::from_u64(state.spans.len() as u64)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        if let (Some(rule), Some(derived)) = (fields.rule, fields.derived) {
+            let mut state = self.state();
+            match state.rules.iter_mut().find(|(r, _)| *r == rule) {
+                Some((_, n)) => *n += derived,
+                None => state.rules.push((rule, derived)),
+            }
+        } else if let (true, Some(message)) = (self.echo, fields.message) {
+            if *event.metadata().level() <= Level::INFO {
+                eprintln!("{}", message);
+            }
+        }
+    }
+
+    fn enter(&self, id: &Id) {
+        if let Some(span) = self.state().span(id) {
+            span.entered.get_or_insert_with(Instant::now);
+        }
+    }
+
+    fn exit(&self, id: &Id) {
+        if let Some(span) = self.state().span(id) {
+            if let Some(entered) = span.entered.take() {
+                span.elapsed += entered.elapsed();
+            }
+        }
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Some(span) = self.state().span(id) {
+            span.refs += 1;
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let mut state = self.state();
+        let Some(span) = state.span(&id) else {
+            return false;
+        };
+        span.refs -= 1;
+        if span.refs > 0 {
+            return false;
+        }
+        let (name, elapsed) = (span.name, span.elapsed);
+        match state.phases.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, total)) => *total += elapsed,
+            None => state.phases.push((name, elapsed)),
+        }
+        true
+    }
+}
@@ -0,0 +1,76 @@
+//! On-disk saturation traces, so a run that exposes a completeness bug can be
+//! recorded on the user's machine and replayed item-for-item elsewhere.
+//!
+//! Layout: the magic `ELTRACE1`, then one record per processed worklist item:
+//! a tag byte (`0` concept, `1` link) followed by its ids as little-endian
+//! `u32`s. A trace is one total order, whichever engine recorded it, and is
+//! always replayed sequentially.
+
+use std::io::{self, Read, Write};
+
+use crate::kernel::TraceEvent;
+
+const MAGIC: &[u8; 8] = b"ELTRACE1";
+const TAG_CONCEPT: u8 = 0;
+const TAG_LINK: u8 = 1;
+
+pub fn write_trace<W: Write>(events: &[TraceEvent], mut out: W) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    for event in events {
+        match *event {
+            TraceEvent::Concept { concept, added } => {
+                out.write_all(&[TAG_CONCEPT])?;
+                out.write_all(&concept.to_le_bytes())?;
+                out.write_all(&added.to_le_bytes())?;
+            }
+            TraceEvent::Link { source, role, target } => {
+                out.write_all(&[TAG_LINK])?;
+                out.write_all(&source.to_le_bytes())?;
+                out.write_all(&role.to_le_bytes())?;
+                out.write_all(&target.to_le_bytes())?;
+            }
+        }
+    }
+    out.flush()
+}
+
+pub fn read_trace<R: Read>(mut input: R) -> io::Result<Vec<TraceEvent>> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a saturation trace".to_string()));
+    }
+
+    let mut events = Vec::new();
+    let mut tag = [0u8; 1];
+    loop {
+        match input.read_exact(&mut tag) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(events),
+            Err(e) => return Err(e),
+        }
+        let event = match tag[0] {
+            TAG_CONCEPT => TraceEvent::Concept {
+                concept: read_u32(&mut input)?,
+                added: read_u32(&mut input)?,
+            },
+            TAG_LINK => TraceEvent::Link {
+                source: read_u32(&mut input)?,
+                role: read_u32(&mut input)?,
+                target: read_u32(&mut input)?,
+            },
+            t => return Err(invalid(format!("unknown record tag {} in record {}", t, events.len()))),
+        };
+        events.push(event);
+    }
+}
+
+fn read_u32<R: Read>(input: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,182 @@
+//! Conformance against a reference classification, such as ELK's or
+//! HermiT's output: the subsumptions the reference states and this engine
+//! does not entail (missing), and the direct subsumptions, equivalences and
+//! unsatisfiable classes of this engine the reference does not entail
+//! (spurious).
+//!
+//! A reference is a list of `sub ⊑ sup` pairs by name, read from a TSV file
+//! (`sub<TAB>sup` per line, `#` comments) or taken from the told named
+//! subsumptions and equivalences of a parsed ontology. Names are looked up
+//! in the verified ontology, so IDs, IRIs and labels all work; pairs naming
+//! a class it lacks are set aside as unknown.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::changes::TermRef;
+use crate::normalize::{ClassAxiom, ClassExpr};
+use crate::obo::ParseResult;
+use crate::{ConceptId, Context, Taxonomy, BOTTOM, TOP};
+
+/// A `sub ⊑ sup` pair by name.
+type NamedPair = (String, String);
+
+/// Reads `sub<TAB>sup` lines; blank lines and `#` comments are skipped.
+pub fn read_reference_tsv<R: BufRead>(input: R) -> io::Result<Vec<NamedPair>> {
+    let mut pairs = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((sub, sup)) = line.split_once('\t') else {
+            let message = format!("line {}: expected 'sub<TAB>sup'", i + 1);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        };
+        pairs.push((sub.trim().to_string(), sup.trim().to_string()));
+    }
+    Ok(pairs)
+}
+
+/// The told subsumptions between named classes of `reference`, an
+/// equivalence counting both ways; what a reasoner writes out as its
+/// inferred hierarchy.
+pub fn reference_pairs(reference: &ParseResult) -> Vec<NamedPair> {
+    let mut pairs: Vec<(usize, usize)> = reference.subsumptions.clone();
+    for axiom in &reference.class_axioms {
+        match axiom {
+            ClassAxiom::SubClassOf(ClassExpr::Named(a), ClassExpr::Named(b)) => {
+                pairs.push((*a as usize, *b as usize));
+            }
+            ClassAxiom::Equivalent(ClassExpr::Named(a), ClassExpr::Named(b)) => {
+                pairs.push((*a as usize, *b as usize));
+                pairs.push((*b as usize, *a as usize));
+            }
+            _ => {}
+        }
+    }
+    for d in &reference.definitions {
+        if let ([genus], []) = (d.genus.as_slice(), d.differentia.as_slice()) {
+            pairs.push((d.term, *genus));
+            pairs.push((*genus, d.term));
+        }
+    }
+    let name = |c: usize| reference.concepts[c].clone();
+    pairs
+        .into_iter()
+        .filter(|&(a, b)| !reference.concepts[a].is_empty() && !reference.concepts[b].is_empty())
+        .map(|(a, b)| (name(a), name(b)))
+        .collect()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VerifyReport {
+    /// Reference subsumptions this engine does not entail.
+    pub missing: Vec<(TermRef, TermRef)>,
+    /// Subsumptions of this engine the reference does not entail; an
+    /// unsatisfiable class shows as subsumed by owl:Nothing.
+    pub spurious: Vec<(TermRef, TermRef)>,
+    /// Reference names not found in the verified ontology.
+    pub unknown: Vec<String>,
+    /// Reference pairs compared.
+    pub checked: usize,
+}
+
+impl VerifyReport {
+    /// Whether the two classifications agree.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.spurious.is_empty()
+    }
+}
+
+/// Compares the classification of `parsed` (its saturated `contexts` and
+/// `taxonomy`) with `reference`, whose names `lookup` finds in `parsed`.
+pub fn verify(
+    parsed: &ParseResult,
+    contexts: &[Context],
+    taxonomy: &Taxonomy,
+    reference: &[NamedPair],
+    lookup: impl Fn(&str) -> Option<ConceptId>,
+) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    let mut edges: HashMap<ConceptId, Vec<ConceptId>> = HashMap::new();
+    for (sub, sup) in reference {
+        let (Some(a), Some(b)) = (lookup(sub), lookup(sup)) else {
+            for name in [sub, sup].into_iter().filter(|n| lookup(n).is_none()) {
+                report.unknown.push(name.clone());
+            }
+            continue;
+        };
+        report.checked += 1;
+        edges.entry(a).or_default().push(b);
+        let supers = &contexts[a as usize].super_set;
+        if a != b && b != TOP && !supers.contains(&b) && !supers.contains(&BOTTOM) {
+            report.missing.push((term(parsed, a), term(parsed, b)));
+        }
+    }
+    report.unknown.sort_unstable();
+    report.unknown.dedup();
+
+    // Whether the reference entails `a ⊑ b`, through its own pairs.
+    let entailed = |a: ConceptId, b: ConceptId| {
+        let mut seen = HashSet::from([a]);
+        let mut stack = vec![a];
+        while let Some(x) = stack.pop() {
+            if x == b || x == BOTTOM {
+                return true;
+            }
+            for &y in edges.get(&x).into_iter().flatten() {
+                if seen.insert(y) {
+                    stack.push(y);
+                }
+            }
+        }
+        false
+    };
+    let named = |c: ConceptId| (c as usize) < parsed.concepts.len() && !parsed.concepts[c as usize].is_empty();
+    let mut ours: Vec<(ConceptId, ConceptId)> = Vec::new();
+    for c in (BOTTOM + 1..taxonomy.len() as ConceptId).filter(|&c| named(c)) {
+        if contexts[c as usize].super_set.contains(&BOTTOM) {
+            ours.push((c, BOTTOM));
+            continue;
+        }
+        ours.extend(taxonomy.parents(c).iter().filter(|&&p| p != TOP && named(p)).map(|&p| (c, p)));
+    }
+    for class in taxonomy.equivalences() {
+        for &a in class.iter().filter(|&&a| named(a)) {
+            ours.extend(class.iter().filter(|&&b| b != a && named(b)).map(|&b| (a, b)));
+        }
+    }
+    for (a, b) in ours {
+        if !entailed(a, b) {
+            report.spurious.push((term(parsed, a), term(parsed, b)));
+        }
+    }
+    report
+}
+
+fn term(parsed: &ParseResult, c: ConceptId) -> TermRef {
+    let c = c as usize;
+    TermRef { id: parsed.concepts[c].clone(), label: parsed.labels.get(&c).cloned() }
+}
+
+/// Markdown, in the style of the `changes` report.
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Compared {} reference subsumptions.", self.checked)?;
+        let sections = [("Missing entailments", &self.missing), ("Spurious entailments", &self.spurious)];
+        for (title, pairs) in sections {
+            writeln!(f, "\n## {} ({})\n", title, pairs.len())?;
+            for (sub, sup) in pairs {
+                writeln!(f, "- {} ⊑ {}", sub, sup)?;
+            }
+        }
+        writeln!(f, "\n## Unknown reference terms ({})\n", self.unknown.len())?;
+        for name in &self.unknown {
+            writeln!(f, "- {}", name)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! An I/O-free entry point for WebAssembly hosts such as ontology
+//! browsers: a document goes in as bytes and the taxonomy comes out as the
+//! JSON of `--format json`. The `el-reasoner-wasm` cdylib in `wasm/`
+//! exports the functions below; `wasm/el_reasoner.js` wraps them for
+//! JavaScript, copying documents in and results out through [`el_alloc`]ed
+//! linear memory.
+
+use crate::input::{parse_document, InputFormat, SNIFF_LEN};
+use crate::json::Value;
+use crate::output::{write_classification, ClassificationView, JsonWriter};
+use crate::resolve::Verbatim;
+use crate::{unsatisfiable_concepts, ConceptId, ReasonerError, Taxonomy};
+
+/// Classifies the document in `bytes`, sniffing its format when `format`
+/// is `None`. Equivalence classes, unsatisfiable concepts and the number
+/// of skipped lines follow the concepts, as in the CLI's summary.
+pub fn classify_json(bytes: &[u8], format: Option<InputFormat>) -> Result<String, ReasonerError> {
+    let head = &bytes[..bytes.len().min(SNIFF_LEN)];
+    let format = format.or_else(|| InputFormat::sniff(head)).unwrap_or(InputFormat::Obo);
+    let (parsed, warnings) = parse_document(bytes, format, &Verbatim, false)?;
+    let n = parsed.concepts.len();
+    let store = crate::obo::build_axiom_store(&parsed);
+    let mut contexts = crate::saturate(&store, store.num_concepts(), parsed.roles.len());
+    contexts.truncate(n);
+    let taxonomy = Taxonomy::new(&contexts, n);
+
+    let names = |ids: &[ConceptId]| {
+        Value::Array(ids.iter().map(|&c| Value::from(parsed.concepts[c as usize].as_str())).collect())
+    };
+    let equivalences = taxonomy.equivalences().iter().map(|class| names(class)).collect();
+    let summary = vec![
+        ("equivalences".to_string(), Value::Array(equivalences)),
+        ("unsatisfiable".to_string(), names(&unsatisfiable_concepts(&contexts))),
+        ("warnings".to_string(), Value::from(warnings.len())),
+    ];
+    let view = ClassificationView {
+        concepts: &parsed.concepts,
+        direct_parents: taxonomy.direct_parents(),
+        depths: None,
+        labels: Some(&parsed.labels),
+    };
+    let mut out = Vec::new();
+    write_classification(&mut JsonWriter::with_summary(summary), &view, &mut out)?;
+    Ok(String::from_utf8(out).expect("JSON output is UTF-8"))
+}
+
+/// The outcome of [`el_classify_json`]: JSON on success, a message otherwise.
+pub struct Response {
+    ok: bool,
+    body: Vec<u8>,
+}
+
+/// `len` bytes for the host to fill, freed with [`el_free`].
+#[no_mangle]
+pub extern "C" fn el_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// # Safety
+/// `ptr` and `len` are those of one [`el_alloc`] call, not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn el_free(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Vec::from_raw_parts(ptr, 0, len));
+    }
+}
+
+/// Classifies the `len` bytes at `ptr`; `format` (`format_len` bytes) is an
+/// input format name as for `--input-format`, or empty to sniff it.
+///
+/// # Safety
+/// Both ranges are readable.
+#[no_mangle]
+pub unsafe extern "C" fn el_classify_json(
+    ptr: *const u8,
+    len: usize,
+    format: *const u8,
+    format_len: usize,
+) -> *mut Response {
+    let bytes = std::slice::from_raw_parts(ptr, len);
+    let name = std::str::from_utf8(std::slice::from_raw_parts(format, format_len)).unwrap_or("?");
+    let format = match name {
+        "" => Ok(None),
+        _ => InputFormat::from_name(name).map(Some).ok_or(format!("unknown input format '{}'", name)),
+    };
+    let result = format.and_then(|format| classify_json(bytes, format).map_err(|e| e.to_string()));
+    let response = match result {
+        Ok(json) => Response { ok: true, body: json.into_bytes() },
+        Err(message) => Response { ok: false, body: message.into_bytes() },
+    };
+    Box::into_raw(Box::new(response))
+}
+
+/// 1 if the response holds JSON, 0 if an error message.
+///
+/// # Safety
+/// `response` comes from [`el_classify_json`].
+#[no_mangle]
+pub unsafe extern "C" fn el_response_ok(response: *const Response) -> i32 {
+    (*response).ok as i32
+}
+
+/// # Safety
+/// `response` comes from [`el_classify_json`].
+#[no_mangle]
+pub unsafe extern "C" fn el_response_ptr(response: *const Response) -> *const u8 {
+    (*response).body.as_ptr()
+}
+
+/// # Safety
+/// `response` comes from [`el_classify_json`].
+#[no_mangle]
+pub unsafe extern "C" fn el_response_len(response: *const Response) -> usize {
+    (*response).body.len()
+}
+
+/// # Safety
+/// `response` comes from [`el_classify_json`] and isn't used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn el_response_free(response: *mut Response) {
+    if !response.is_null() {
+        drop(Box::from_raw(response));
+    }
+}
@@ -0,0 +1,54 @@
+// Loads the el-reasoner WebAssembly module and classifies documents held in
+// memory, e.g. a File read in the browser:
+//
+//   const reasoner = await load(fetch("el_reasoner.wasm"));
+//   const { concepts, equivalences, unsatisfiable } =
+//       reasoner.classify(await file.text());
+//
+// `concepts` has one {id, label, parents} entry per named concept, as the
+// CLI's `--format json`.
+
+export async function load(source) {
+  const response = await source;
+  const { instance } = response instanceof Response
+    ? await WebAssembly.instantiateStreaming(response)
+    : await WebAssembly.instantiate(response);
+  return new Reasoner(instance.exports);
+}
+
+export class Reasoner {
+  constructor(exports) {
+    this.exports = exports;
+  }
+
+  // `document` is a string or Uint8Array; `format` an --input-format name
+  // ("obo", "ofn", "rdfxml", "ttl"), sniffed from the content when omitted.
+  classify(document, format = "") {
+    const encoder = new TextEncoder();
+    const bytes = typeof document === "string" ? encoder.encode(document) : document;
+    const name = encoder.encode(format);
+    const e = this.exports;
+    const input = this.copyIn(bytes);
+    const formatName = this.copyIn(name);
+    const response = e.el_classify_json(input, bytes.length, formatName, name.length);
+    e.el_free(input, bytes.length);
+    e.el_free(formatName, name.length);
+    try {
+      const body = new Uint8Array(
+        e.memory.buffer, e.el_response_ptr(response), e.el_response_len(response));
+      const text = new TextDecoder().decode(body);
+      if (!e.el_response_ok(response)) {
+        throw new Error(text);
+      }
+      return JSON.parse(text);
+    } finally {
+      e.el_response_free(response);
+    }
+  }
+
+  copyIn(bytes) {
+    const ptr = this.exports.el_alloc(bytes.length);
+    new Uint8Array(this.exports.memory.buffer, ptr, bytes.length).set(bytes);
+    return ptr;
+  }
+}